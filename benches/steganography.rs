@@ -0,0 +1,62 @@
+//! # 核心隐写算法基准测试
+//!
+//! 使用 criterion 测量 `hide_bytes` 与 `recover_bytes` 在内存缓冲区上的吞吐量，
+//! 覆盖小 (1 KB)、中 (100 KB) 与大 (10 MB) 三种载荷，不涉及任何文件 I/O
+//!
+//! 运行方式：`cargo bench --bench steganography`
+
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use lsb_hide::constants::BYTES_PER_CHAR;
+use lsb_hide::steganography::{header_len, hide_bytes, recover_bytes};
+use rand::{RngCore, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use std::hint::black_box;
+
+/// 参与测试的载荷大小 (字节) 及其显示名称
+const PAYLOAD_SIZES: [(usize, &str); 3] = [
+    (1024, "1KB"),
+    (100 * 1024, "100KB"),
+    (10 * 1024 * 1024, "10MB"),
+];
+
+/// 生成固定种子的伪随机载荷，以及恰好能容纳它的像素缓冲区
+fn fixture(size: usize) -> (Vec<u8>, Vec<u8>) {
+    let mut rng = ChaCha20Rng::seed_from_u64(0x4C53_4248);
+    let mut payload = vec![0u8; size];
+    rng.fill_bytes(&mut payload);
+    let mut pixels = vec![0u8; header_len(size as u64, 0) + size * BYTES_PER_CHAR];
+    rng.fill_bytes(&mut pixels);
+    (payload, pixels)
+}
+
+/// 测量将载荷隐藏到像素缓冲区中的耗时
+fn bench_hide(c: &mut Criterion) {
+    let mut group = c.benchmark_group("hide_bytes");
+    group.sample_size(10);
+    for (size, name) in PAYLOAD_SIZES {
+        let (payload, mut pixels) = fixture(size);
+        group.throughput(Throughput::Bytes(size as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(name), &payload, |b, payload| {
+            b.iter(|| hide_bytes(black_box(&mut pixels), black_box(payload)).unwrap());
+        });
+    }
+    group.finish();
+}
+
+/// 测量从像素缓冲区中恢复载荷的耗时
+fn bench_recover(c: &mut Criterion) {
+    let mut group = c.benchmark_group("recover_bytes");
+    group.sample_size(10);
+    for (size, name) in PAYLOAD_SIZES {
+        let (payload, mut pixels) = fixture(size);
+        hide_bytes(&mut pixels, &payload).unwrap();
+        group.throughput(Throughput::Bytes(size as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(name), &pixels, |b, pixels| {
+            b.iter(|| recover_bytes(black_box(pixels)).unwrap());
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_hide, bench_recover);
+criterion_main!(benches);
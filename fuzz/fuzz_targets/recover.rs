@@ -0,0 +1,27 @@
+//! # `recover` 模糊测试目标
+//!
+//! 以任意字节作为像素缓冲区，以任意的起始索引、字节数与位深度调用 `recover`，
+//! 只允许返回 `Ok` 或 `Err`，不允许 panic 或整数溢出；成功时再用 `modify` 写回并确认往返一致
+//!
+//! 运行方式：`cargo +nightly fuzz run recover fuzz/corpus/recover`
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use lsb_hide::steganography::{modify, recover};
+
+fuzz_target!(|input: (usize, usize, u8, Vec<u8>)| {
+    let (dix, size, bits, pix) = input;
+    let Ok(value) = recover(&pix, dix, size, bits) else {
+        return;
+    };
+
+    // 成功时恢复出的值不会超出 `size * bits` 位
+    let used = size * bits as usize;
+    assert!(used >= 64 || value >> used == 0);
+
+    // 写回同一个值不会改变像素，且参数同样有效
+    let mut copy = pix.clone();
+    modify(value, &mut copy, dix, size, bits).expect("modify rejected arguments recover accepted");
+    assert_eq!(copy, pix);
+});
@@ -0,0 +1,297 @@
+//! # 隐写分析模块
+//!
+//! 实现经典的“值对” (pairs of values) 卡方检验，用于粗略估计像素字节中是否嵌入了 LSB 隐写数据
+//!
+//! 将最低有效位替换为近似随机的数据后，只相差最低位的两个值 (`2k` 与 `2k + 1`) 出现的次数会趋于相等，
+//! 而自然图像中它们通常相差较大。检验统计量越小，两者越接近，嵌入数据的可能性越高
+//! 该检验仅供教学参考，并不严格：随机噪声图像同样可能得到较高的分数
+//!
+//! 另外提供逐字节比较原始图像与隐写图像的 [`diff`]，用于量化嵌入对图像的影响，
+//! 以及提取单个位平面的 [`bit_plane`]，用于直接观察嵌入位置的分布
+
+/// 参与检验的值对的最小期望频数，低于该值的值对不满足卡方近似的前提，会被忽略
+const MIN_EXPECTED: f64 = 5.0;
+
+/// 一次卡方检验的结果
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChiSquareReport {
+    /// 卡方统计量
+    pub statistic: f64,
+
+    /// 自由度，即参与检验的值对数减一
+    pub degrees_of_freedom: usize,
+
+    /// 含有隐藏数据的概率估计 (0.0 到 1.0)，即卡方分布的上尾概率
+    pub probability: f64,
+}
+
+/// 对像素字节 (`pixels`) 进行值对卡方检验
+///
+/// 参与检验的值对少于两个 (例如图像几乎是纯色) 时无法做出判断，概率报告为 0
+pub fn chi_square(pixels: &[u8]) -> ChiSquareReport {
+    let mut histogram = [0u64; 256];
+    for &byte in pixels {
+        histogram[byte as usize] += 1;
+    }
+
+    // 嵌入后每一对值的频数都应接近两者的平均值
+    let (statistic, pairs) = histogram
+        .chunks_exact(2)
+        .map(|pair| (pair[0] as f64, (pair[0] + pair[1]) as f64 / 2.0))
+        .filter(|&(_, expected)| expected >= MIN_EXPECTED)
+        .fold((0.0, 0usize), |(sum, pairs), (observed, expected)| {
+            (sum + (observed - expected).powi(2) / expected, pairs + 1)
+        });
+
+    let degrees_of_freedom = pairs.saturating_sub(1);
+    let probability = if degrees_of_freedom == 0 {
+        0.0
+    } else {
+        upper_incomplete_gamma(degrees_of_freedom as f64 / 2.0, statistic / 2.0)
+    };
+
+    ChiSquareReport {
+        statistic,
+        degrees_of_freedom,
+        probability,
+    }
+}
+
+/// 使用 Lanczos 近似计算 `ln Γ(x)` (`x > 0`)
+fn ln_gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 9] = [
+        0.999_999_999_999_809_9,
+        676.520_368_121_885_1,
+        -1_259.139_216_722_402_8,
+        771.323_428_777_653_1,
+        -176.615_029_162_140_6,
+        12.507_343_278_686_905,
+        -0.138_571_095_265_720_12,
+        9.984_369_578_019_572e-6,
+        1.505_632_735_149_311_6e-7,
+    ];
+    let x = x - 1.0;
+    let t = x + 7.5;
+    let series = COEFFICIENTS[1..]
+        .iter()
+        .enumerate()
+        .fold(COEFFICIENTS[0], |acc, (i, &c)| {
+            acc + c / (x + i as f64 + 1.0)
+        });
+    0.5 * (2.0 * std::f64::consts::PI).ln() + (x + 0.5) * t.ln() - t + series.ln()
+}
+
+/// 计算正则化的上不完全伽马函数 `Q(a, x) = Γ(a, x) / Γ(a)`
+///
+/// `x < a + 1` 时使用级数展开求 `P(a, x)` 再取补，否则使用连分式直接求 `Q(a, x)`
+fn upper_incomplete_gamma(a: f64, x: f64) -> f64 {
+    const MAX_ITERATIONS: usize = 1000;
+    const EPSILON: f64 = 1e-14;
+    const TINY: f64 = 1e-300;
+
+    if x <= 0.0 {
+        return 1.0;
+    }
+    let prefactor = (-x + a * x.ln() - ln_gamma(a)).exp();
+
+    if x < a + 1.0 {
+        let mut term = 1.0 / a;
+        let mut sum = term;
+        for n in 1..MAX_ITERATIONS {
+            term *= x / (a + n as f64);
+            sum += term;
+            if term.abs() < sum.abs() * EPSILON {
+                break;
+            }
+        }
+        return (1.0 - sum * prefactor).clamp(0.0, 1.0);
+    }
+
+    // Lentz 算法求连分式
+    let mut b = x + 1.0 - a;
+    let mut c = 1.0 / TINY;
+    let mut d = 1.0 / b;
+    let mut h = d;
+    for n in 1..MAX_ITERATIONS {
+        let an = -(n as f64) * (n as f64 - a);
+        b += 2.0;
+        d = an * d + b;
+        if d.abs() < TINY {
+            d = TINY;
+        }
+        c = b + an / c;
+        if c.abs() < TINY {
+            c = TINY;
+        }
+        d = 1.0 / d;
+        let delta = d * c;
+        h *= delta;
+        if (delta - 1.0).abs() < EPSILON {
+            break;
+        }
+    }
+    (prefactor * h).clamp(0.0, 1.0)
+}
+
+/// 原始图像与隐写图像逐字节比较的结果
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DiffReport {
+    /// 参与比较的像素字节数
+    pub bytes: usize,
+
+    /// 取值不同的像素字节数
+    pub changed_bytes: usize,
+
+    /// 最低有效位不同的像素字节数
+    pub changed_lsbs: usize,
+
+    /// 单个像素字节的最大差值
+    pub max_delta: u8,
+
+    /// 均方误差
+    pub mse: f64,
+
+    /// 峰值信噪比 (dB)，两者完全相同时为正无穷
+    pub psnr: f64,
+}
+
+/// 逐字节比较原始像素字节 (`cover`) 与隐写后的像素字节 (`stego`)，只比较两者共同的前缀
+pub fn diff(cover: &[u8], stego: &[u8]) -> DiffReport {
+    let mut report = DiffReport {
+        bytes: cover.len().min(stego.len()),
+        changed_bytes: 0,
+        changed_lsbs: 0,
+        max_delta: 0,
+        mse: 0.0,
+        psnr: f64::INFINITY,
+    };
+    let mut squared = 0u64;
+    for (&cover, &stego) in cover.iter().zip(stego) {
+        let delta = cover.abs_diff(stego);
+        report.changed_bytes += usize::from(delta != 0);
+        report.changed_lsbs += usize::from((cover ^ stego) & 1 != 0);
+        report.max_delta = report.max_delta.max(delta);
+        squared += u64::from(delta) * u64::from(delta);
+    }
+
+    if report.bytes > 0 && squared > 0 {
+        report.mse = squared as f64 / report.bytes as f64;
+        report.psnr = 10.0 * (f64::from(u8::MAX).powi(2) / report.mse).log10();
+    }
+    report
+}
+
+/// 将两者每个字节的差值乘以 `factor` (超出 255 时取 255)，得到便于肉眼观察的差异图像字节
+pub fn amplify_diff(cover: &[u8], stego: &[u8], factor: u8) -> Vec<u8> {
+    cover
+        .iter()
+        .zip(stego)
+        .map(|(&cover, &stego)| cover.abs_diff(stego).saturating_mul(factor))
+        .collect()
+}
+
+/// 取出每个像素字节中第 `plane` 位 (0 为最低有效位)，放大为 0 或 255，得到便于肉眼观察的位平面图像字节
+///
+/// 自然图像的最低有效位近似随机噪声，顺序嵌入的载荷 (尤其是头部与文本) 会在其中形成成片的规则条纹，
+/// 打散嵌入则把改动均匀地分散到整幅图像中
+pub fn bit_plane(pixels: &[u8], plane: u8) -> Vec<u8> {
+    pixels
+        .iter()
+        .map(|&byte| if byte >> plane & 1 == 1 { u8::MAX } else { 0 })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::DEFAULT_BITS;
+    use crate::steganography::{hide_bytes, max_payload_bytes};
+    use rand::{RngCore, SeedableRng};
+    use rand_chacha::ChaCha20Rng;
+
+    /// 测试不完全伽马函数与已知的闭式结果一致
+    #[test]
+    fn test_upper_incomplete_gamma() {
+        // Q(1, x) = e^-x
+        for x in [0.1, 1.0, 2.5, 10.0] {
+            assert!((upper_incomplete_gamma(1.0, x) - (-x).exp()).abs() < 1e-10);
+        }
+        assert!((ln_gamma(5.0) - 24f64.ln()).abs() < 1e-10);
+        assert_eq!(upper_incomplete_gamma(3.0, 0.0), 1.0);
+    }
+
+    /// 测试嵌入大量数据后的图像比干净的渐变图像得到更高的分数
+    #[test]
+    fn test_embedded_payload_scores_higher() {
+        // 步长为 2 的渐变中只出现偶数值，每一对值的频数都相差悬殊
+        let clean: Vec<u8> = (0..64 * 64).map(|i| (i % 128 * 2) as u8).collect();
+        let clean_report = chi_square(&clean);
+        assert!(clean_report.degrees_of_freedom > 0);
+        assert!(clean_report.probability < 0.01);
+
+        let mut payload = vec![0u8; max_payload_bytes(clean.len(), DEFAULT_BITS as usize)];
+        ChaCha20Rng::seed_from_u64(33).fill_bytes(&mut payload);
+        let mut stego = clean.clone();
+        hide_bytes(&mut stego, &payload).expect("Failed to hide payload.");
+
+        let stego_report = chi_square(&stego);
+        assert!(
+            stego_report.probability > clean_report.probability,
+            "{:?} should score higher than {:?}",
+            stego_report,
+            clean_report
+        );
+        assert!(stego_report.probability > 0.5);
+    }
+
+    /// 测试几乎纯色的图像无法做出判断
+    #[test]
+    fn test_flat_image_reports_zero() {
+        let report = chi_square(&[7u8; 1000]);
+        assert_eq!(report.degrees_of_freedom, 0);
+        assert_eq!(report.probability, 0.0);
+    }
+
+    /// 测试图像与自身比较时没有差异，与隐写后的图像比较时差值不超过所用的位深度
+    #[test]
+    fn test_diff_reports_embedding_impact() {
+        let mut cover = vec![0u8; 64 * 64];
+        ChaCha20Rng::seed_from_u64(87).fill_bytes(&mut cover);
+
+        let same = diff(&cover, &cover);
+        assert_eq!(same.bytes, cover.len());
+        assert_eq!(
+            (same.changed_bytes, same.changed_lsbs, same.max_delta),
+            (0, 0, 0)
+        );
+        assert_eq!(same.mse, 0.0);
+        assert!(same.psnr.is_infinite());
+
+        let mut payload = vec![0u8; 500];
+        ChaCha20Rng::seed_from_u64(88).fill_bytes(&mut payload);
+        let mut stego = cover.clone();
+        hide_bytes(&mut stego, &payload).expect("Failed to hide payload.");
+
+        let report = diff(&cover, &stego);
+        assert!(report.changed_bytes > 0);
+        assert!(report.changed_lsbs <= report.changed_bytes);
+        assert!(report.max_delta < 1 << DEFAULT_BITS);
+        assert!(report.psnr.is_finite() && report.psnr > 40.0);
+
+        let amplified = amplify_diff(&cover, &stego, 64);
+        assert_eq!(
+            amplified.iter().filter(|&&byte| byte != 0).count(),
+            report.changed_bytes
+        );
+        assert!(amplified.iter().all(|&byte| byte % 64 == 0));
+    }
+
+    /// 测试位平面只取出所选的一位并放大为 0 或 255
+    #[test]
+    fn test_bit_plane() {
+        let pixels = [0b0000_0000, 0b0000_0001, 0b1000_0010, 0b1111_1111];
+        assert_eq!(bit_plane(&pixels, 0), [0, 255, 0, 255]);
+        assert_eq!(bit_plane(&pixels, 1), [0, 0, 255, 255]);
+        assert_eq!(bit_plane(&pixels, 7), [0, 0, 255, 255]);
+    }
+}
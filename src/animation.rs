@@ -0,0 +1,189 @@
+//! # 动画图像模块
+//!
+//! 解码与重新编码 APNG (动画 PNG)，供 `--frame` 在多帧图像的某一帧中隐藏或恢复数据
+//! 每一帧都按完整画布解码为 RGBA 图像；重新编码时每一帧都覆盖整个画布，因此显示效果与原动画一致
+
+use anyhow::Context;
+use image::codecs::png::PngDecoder;
+use image::{AnimationDecoder, Frame, RgbaImage};
+use std::io::Cursor;
+
+/// 已解码的 APNG 动画
+pub struct Animation {
+    /// 按播放顺序排列的各帧，每一帧都是完整画布大小
+    frames: Vec<Frame>,
+
+    /// 动画的播放次数，0 表示无限循环
+    num_plays: u32,
+}
+
+impl Animation {
+    /// 解码 APNG 文件的原始字节
+    ///
+    /// # Errors
+    ///
+    /// * 如果 `bytes` 不是 PNG 图像，或是不含动画的普通 PNG，将返回错误
+    /// * 如果任意一帧无法解码，将返回错误
+    pub fn decode(bytes: &[u8]) -> anyhow::Result<Self> {
+        let decoder =
+            PngDecoder::new(Cursor::new(bytes)).context("Failed to decode the PNG image.")?;
+        anyhow::ensure!(
+            decoder.is_apng()?,
+            "The image is not an animated PNG (APNG)."
+        );
+
+        // 播放次数不会出现在解码出的帧中，单独从动画控制块读取
+        let num_plays = png::Decoder::new(Cursor::new(bytes))
+            .read_info()
+            .ok()
+            .and_then(|reader| reader.info().animation_control)
+            .map_or(0, |control| control.num_plays);
+
+        let frames = decoder
+            .apng()?
+            .into_frames()
+            .collect_frames()
+            .context("Failed to decode the APNG frames.")?;
+        anyhow::ensure!(!frames.is_empty(), "The animated PNG has no frames.");
+        Ok(Self { frames, num_plays })
+    }
+
+    /// 由各帧与播放次数构造动画，所有帧必须大小相同
+    pub fn new(frames: Vec<Frame>, num_plays: u32) -> Self {
+        Self { frames, num_plays }
+    }
+
+    /// 动画的帧数
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// 动画是否不含任何帧
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// 第 `index` 帧 (从 0 开始) 的完整画布
+    ///
+    /// # Errors
+    ///
+    /// 如果 `index` 超出帧数，将返回错误
+    pub fn frame(&self, index: usize) -> anyhow::Result<&RgbaImage> {
+        self.frames.get(index).map(Frame::buffer).with_context(|| {
+            format!(
+                "Frame {} is out of range (the animation has {} frames).",
+                index,
+                self.frames.len()
+            )
+        })
+    }
+
+    /// 将第 `index` 帧的画布替换为 `buffer`，帧的显示时长保持不变
+    ///
+    /// # Errors
+    ///
+    /// 如果 `index` 超出帧数，或 `buffer` 的尺寸与原画布不同，将返回错误
+    pub fn set_frame(&mut self, index: usize, buffer: RgbaImage) -> anyhow::Result<()> {
+        let dimensions = self.frame(index)?.dimensions();
+        anyhow::ensure!(
+            buffer.dimensions() == dimensions,
+            "Frame {} must stay {}x{} pixels.",
+            index,
+            dimensions.0,
+            dimensions.1
+        );
+        let frame = &mut self.frames[index];
+        *frame = Frame::from_parts(buffer, 0, 0, frame.delay());
+        Ok(())
+    }
+
+    /// 将动画编码为 APNG 文件的原始字节
+    ///
+    /// 每一帧都以 RGBA 完整画布写出，并直接替换上一帧 (不混合、不清除)，逐像素保留解码得到的画面
+    ///
+    /// # Errors
+    ///
+    /// 如果编码失败，将返回错误
+    pub fn encode(&self) -> anyhow::Result<Vec<u8>> {
+        let (width, height) = self.frame(0)?.dimensions();
+        let mut output = Vec::new();
+        let mut encoder = png::Encoder::new(&mut output, width, height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_animated(self.frames.len() as u32, self.num_plays)?;
+        let mut writer = encoder.write_header()?;
+
+        for frame in &self.frames {
+            // 以毫秒为单位记录显示时长，超出 `u16` 范围时取最大值
+            let (numerator, denominator) = frame.delay().numer_denom_ms();
+            let millis = (numerator as u64 / denominator.max(1) as u64).min(u16::MAX as u64);
+            writer.set_frame_delay(millis as u16, 1000)?;
+            writer.set_blend_op(png::BlendOp::Source)?;
+            writer.set_dispose_op(png::DisposeOp::None)?;
+            writer.write_image_data(frame.buffer().as_raw())?;
+        }
+        writer.finish()?;
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Delay, Rgba};
+
+    /// 构造一个每帧颜色不同的两帧动画
+    fn two_frames() -> Animation {
+        let frames = [Rgba([255, 0, 0, 255]), Rgba([0, 0, 255, 128])]
+            .into_iter()
+            .map(|color| {
+                Frame::from_parts(
+                    RgbaImage::from_pixel(4, 3, color),
+                    0,
+                    0,
+                    Delay::from_numer_denom_ms(250, 1),
+                )
+            })
+            .collect();
+        Animation::new(frames, 3)
+    }
+
+    /// 测试编码后再解码得到相同的帧、显示时长与播放次数
+    #[test]
+    fn test_encode_decode_round_trip() {
+        let animation = two_frames();
+        let decoded = Animation::decode(&animation.encode().unwrap()).unwrap();
+
+        assert_eq!(decoded.len(), 2);
+        assert_eq!(decoded.num_plays, 3);
+        for index in 0..2 {
+            assert_eq!(
+                decoded.frame(index).unwrap(),
+                animation.frame(index).unwrap()
+            );
+            assert_eq!(decoded.frames[index].delay().numer_denom_ms(), (250, 1));
+        }
+        assert!(decoded.frame(2).is_err());
+    }
+
+    /// 测试替换帧时尺寸必须一致，普通 PNG 不会被当作动画
+    #[test]
+    fn test_set_frame_and_still_png() {
+        let mut animation = two_frames();
+        assert!(animation.set_frame(1, RgbaImage::new(2, 2)).is_err());
+        animation
+            .set_frame(1, RgbaImage::from_pixel(4, 3, Rgba([1, 2, 3, 4])))
+            .unwrap();
+        assert_eq!(
+            animation.frame(1).unwrap().get_pixel(0, 0),
+            &Rgba([1, 2, 3, 4])
+        );
+
+        let mut still = Cursor::new(Vec::new());
+        RgbaImage::new(4, 3)
+            .write_to(&mut still, image::ImageFormat::Png)
+            .unwrap();
+        let err = Animation::decode(still.get_ref()).err().unwrap();
+        assert!(err.to_string().contains("not an animated PNG"));
+    }
+}
@@ -0,0 +1,167 @@
+//! # 多文件归档模块
+//!
+//! 将多个文件打包为一个简单的类 TAR 容器，以便在一张图像中同时隐藏它们
+//!
+//! 容器布局 (所有整数均为小端序)：
+//! 1. 条目数量：`u32`
+//! 2. 每个条目依次为：文件名长度 `u16`、UTF-8 文件名、数据长度 `u64`、文件数据
+
+use anyhow::Context;
+
+/// 归档中的一个文件
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    /// 不含目录部分的文件名
+    pub name: String,
+
+    /// 文件的完整内容
+    pub data: Vec<u8>,
+}
+
+/// 判断文件名是否只包含单个路径组成部分，可以安全地与输出目录拼接
+///
+/// 防止恢复时通过 `..` 或绝对路径写到输出目录之外
+pub fn is_plain_name(name: &str) -> bool {
+    !name.is_empty() && name != "." && name != ".." && !name.contains(['/', '\\', '\0'])
+}
+
+/// 校验条目的文件名只包含单个路径组成部分，且长度可以记录在归档中
+fn ensure_plain_name(name: &str) -> anyhow::Result<()> {
+    anyhow::ensure!(
+        is_plain_name(name) && name.len() <= u16::MAX as usize,
+        "Invalid file name in archive: {:?}",
+        name
+    );
+    Ok(())
+}
+
+/// 将多个文件打包为一个归档
+///
+/// # Errors
+///
+/// * 如果某个文件名为空、包含路径分隔符或过长，将返回错误
+/// * 如果存在重复的文件名，将返回错误
+/// * 如果条目数量超过 `u32::MAX`，将返回错误
+pub fn pack(entries: &[Entry]) -> anyhow::Result<Vec<u8>> {
+    let count = u32::try_from(entries.len()).context("Too many files to archive.")?;
+    let mut archive = count.to_le_bytes().to_vec();
+
+    for (i, entry) in entries.iter().enumerate() {
+        ensure_plain_name(&entry.name)?;
+        anyhow::ensure!(
+            entries[..i].iter().all(|other| other.name != entry.name),
+            "Duplicate file name in archive: {}",
+            entry.name
+        );
+
+        archive.extend_from_slice(&(entry.name.len() as u16).to_le_bytes());
+        archive.extend_from_slice(entry.name.as_bytes());
+        archive.extend_from_slice(&(entry.data.len() as u64).to_le_bytes());
+        archive.extend_from_slice(&entry.data);
+    }
+
+    Ok(archive)
+}
+
+/// 从归档数据中依次读取指定长度的字节
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    /// 读取接下来的 `len` 个字节
+    fn take(&mut self, len: usize) -> anyhow::Result<&'a [u8]> {
+        anyhow::ensure!(len <= self.data.len(), "Archive is truncated or corrupted.");
+        let (head, rest) = self.data.split_at(len);
+        self.data = rest;
+        Ok(head)
+    }
+
+    /// 读取接下来的 `N` 个字节作为定长数组
+    fn take_array<const N: usize>(&mut self) -> anyhow::Result<[u8; N]> {
+        Ok(self.take(N)?.try_into()?)
+    }
+}
+
+/// 将归档解包为文件列表
+///
+/// # Errors
+///
+/// * 如果归档被截断、包含多余数据或长度字段无效，将返回错误
+/// * 如果某个文件名不是有效的 UTF-8 或包含路径分隔符，将返回错误
+pub fn unpack(archive: &[u8]) -> anyhow::Result<Vec<Entry>> {
+    let mut reader = Reader { data: archive };
+    let count = u32::from_le_bytes(reader.take_array()?);
+
+    // 不按条目数量预分配，避免损坏的数量字段导致巨大的内存申请
+    let mut entries = Vec::new();
+    for _ in 0..count {
+        let name_len = u16::from_le_bytes(reader.take_array()?) as usize;
+        let name = std::str::from_utf8(reader.take(name_len)?)
+            .context("File name in archive is not valid UTF-8.")?
+            .to_string();
+        ensure_plain_name(&name)?;
+
+        let data_len = usize::try_from(u64::from_le_bytes(reader.take_array()?))
+            .context("Archive entry is too large.")?;
+        let data = reader.take(data_len)?.to_vec();
+
+        entries.push(Entry { name, data });
+    }
+
+    anyhow::ensure!(
+        reader.data.is_empty(),
+        "Archive contains unexpected trailing data."
+    );
+
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 测试多个文件 (包括空文件和二进制文件) 能够原样打包并解包
+    #[test]
+    fn test_pack_and_unpack_round_trip() {
+        let entries = vec![
+            Entry {
+                name: "a.txt".to_string(),
+                data: b"hello".to_vec(),
+            },
+            Entry {
+                name: "empty".to_string(),
+                data: Vec::new(),
+            },
+            Entry {
+                name: "数据.bin".to_string(),
+                data: (0..=255).collect(),
+            },
+        ];
+
+        let archive = pack(&entries).expect("Failed to pack entries.");
+        assert_eq!(unpack(&archive).expect("Failed to unpack."), entries);
+
+        assert!(unpack(&[]).is_err());
+        assert!(unpack(&archive[..archive.len() - 1]).is_err());
+        assert!(unpack(&[archive.as_slice(), b"x"].concat()).is_err());
+    }
+
+    /// 测试危险或重复的文件名会被拒绝
+    #[test]
+    fn test_rejects_unsafe_and_duplicate_names() {
+        for name in ["", ".", "..", "../escape", "dir/file", "C:\\file"] {
+            let entry = Entry {
+                name: name.to_string(),
+                data: Vec::new(),
+            };
+            assert!(pack(&[entry]).is_err(), "{:?} should be rejected", name);
+        }
+
+        let entry = Entry {
+            name: "same".to_string(),
+            data: Vec::new(),
+        };
+        assert!(pack(&[entry.clone(), entry]).is_err());
+    }
+}
@@ -0,0 +1,126 @@
+//! # 颜色通道选择模块
+//!
+//! 默认情况下载荷会写入每个像素的全部通道字节 (包括 alpha)
+//! 本模块允许只使用部分通道 (例如仅蓝色通道) 承载数据，其余通道的字节保持不变
+//!
+//! 通道选择以位掩码表示：第 `i` 位代表每个像素中的第 `i` 个通道字节 (R、G、B、A 依次为 0 到 3)
+
+use std::fmt;
+use std::str::FromStr;
+
+/// 通道名称，顺序与像素中通道字节的排列顺序一致
+const CHANNEL_NAMES: [char; 4] = ['r', 'g', 'b', 'a'];
+
+/// 用于承载载荷的颜色通道集合
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Channels(u8);
+
+impl Channels {
+    /// 全部通道 (RGBA)
+    ///
+    /// 对于 RGB 图像，不存在的 alpha 通道会被自动忽略
+    pub const ALL: Channels = Channels(0b1111);
+
+    /// 返回记录在隐写头部中的通道掩码
+    pub fn id(self) -> u8 {
+        self.0
+    }
+
+    /// 根据隐写头部中的通道掩码还原通道集合
+    ///
+    /// # Errors
+    ///
+    /// 如果掩码为空或包含未知通道，将返回错误
+    pub fn from_id(id: u8) -> anyhow::Result<Self> {
+        anyhow::ensure!(
+            id != 0 && id & !Self::ALL.0 == 0,
+            "Unknown channel selection: {:#06b}",
+            id
+        );
+        Ok(Channels(id))
+    }
+
+    /// 判断第 `channel` 个通道字节是否被选中
+    pub fn contains(self, channel: usize) -> bool {
+        channel < CHANNEL_NAMES.len() && self.0 & (1 << channel) != 0
+    }
+
+    /// 判断在每个像素有 `stride` 个通道字节的图像中，全部通道是否都被选中
+    ///
+    /// 此时无需逐字节过滤，所有像素字节都可用于隐写
+    pub fn covers(self, stride: usize) -> bool {
+        (0..stride).all(|channel| self.contains(channel))
+    }
+
+    /// 判断像素缓冲区中的第 `index` 个字节是否属于被选中的通道
+    pub fn selects(self, index: usize, stride: usize) -> bool {
+        self.covers(stride) || self.contains(index % stride)
+    }
+}
+
+impl Default for Channels {
+    fn default() -> Self {
+        Self::ALL
+    }
+}
+
+impl FromStr for Channels {
+    type Err = anyhow::Error;
+
+    /// 解析由 `r`、`g`、`b`、`a` 组成的通道列表，例如 `rgb`、`rg` 或 `b`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut mask = 0u8;
+        for c in s.chars() {
+            let channel = CHANNEL_NAMES
+                .iter()
+                .position(|&name| name == c.to_ascii_lowercase())
+                .ok_or_else(|| {
+                    anyhow::anyhow!("Unknown channel '{}' (expected any of r, g, b, a).", c)
+                })?;
+            anyhow::ensure!(
+                mask & (1 << channel) == 0,
+                "Channel '{}' is listed more than once.",
+                c
+            );
+            mask |= 1 << channel;
+        }
+        anyhow::ensure!(mask != 0, "At least one channel must be selected.");
+        Ok(Channels(mask))
+    }
+}
+
+impl fmt::Display for Channels {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        CHANNEL_NAMES
+            .iter()
+            .enumerate()
+            .filter(|&(channel, _)| self.contains(channel))
+            .try_for_each(|(_, name)| write!(f, "{}", name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 测试通道列表的解析、显示与头部编号往返
+    #[test]
+    fn test_parse_display_and_id_round_trip() {
+        let blue: Channels = "b".parse().expect("Failed to parse channels.");
+        assert!(blue.contains(2));
+        assert!(!blue.contains(0));
+        assert_eq!(blue.to_string(), "b");
+
+        let rgb: Channels = "BGR".parse().expect("Failed to parse channels.");
+        assert_eq!(rgb.to_string(), "rgb");
+        assert!(rgb.covers(3));
+        assert!(!rgb.covers(4));
+        assert_eq!(Channels::from_id(rgb.id()).unwrap(), rgb);
+
+        assert!("".parse::<Channels>().is_err());
+        assert!("rx".parse::<Channels>().is_err());
+        assert!("rr".parse::<Channels>().is_err());
+        assert!(Channels::from_id(0).is_err());
+        assert!(Channels::from_id(0b1_0000).is_err());
+    }
+}
@@ -3,7 +3,20 @@
 //! 使用 `clap` 定义了程序的命令行结构，包括子命令和参数
 //! 所有用户通过命令行与程序交互的入口点都在此模块中定义
 
-use clap::Parser;
+use crate::channels::Channels;
+use crate::compression::Compression;
+use crate::constants::{
+    DEFAULT_BITS, DEFAULT_DIFF_AMPLIFY, LENGTH_BITS_CHOICES, MAX_BITS, MAX_PLANE, MAX_REDUNDANCY,
+    MAX_SPACING,
+};
+use crate::i18n::Lang;
+use crate::region::Region;
+use crate::steganography::Endian;
+use crate::text_encoding;
+use base64::prelude::*;
+use clap::{ArgGroup, Parser, ValueEnum};
+use image::ImageFormat;
+use std::fmt;
 use std::path::PathBuf;
 
 /// 一款基于 LSB (最低有效位) 隐写术的命令行工具，用于在无损格式图像 (如 PNG, BMP) 中隐藏或恢复文本
@@ -16,9 +29,32 @@ use std::path::PathBuf;
 pub struct Cli {
     #[command(subcommand)]
     pub command: Commands,
+
+    /// 以 JSON 对象代替彩色的提示信息输出结果，便于脚本和 CI 解析
+    #[arg(long, global = true)]
+    pub json: bool,
+
+    /// 成功时不输出任何提示信息，只通过退出码报告结果；错误仍会输出到标准错误
+    ///
+    /// 与 `--json` 同时使用时仍输出 JSON 结果
+    #[arg(short, long, global = true)]
+    pub quiet: bool,
+
+    /// 并行读写载荷时使用的最大线程数
+    ///
+    /// 0 (默认) 使用 rayon 的默认值，即逻辑 CPU 的数量；1 完全按顺序执行。结果与线程数无关
+    #[arg(long, global = true, default_value_t = 0)]
+    pub threads: usize,
+
+    /// 提示与错误信息使用的语言
+    ///
+    /// \[可选\] 如果未提供，`LANG` 环境变量以 `zh` 开头 (如 `zh_CN.UTF-8`) 时使用中文，否则使用英文
+    #[arg(long, global = true, value_enum)]
+    pub lang: Option<Lang>,
 }
 
-/// 可用的子命令：hide (隐藏) 和 recover (恢复)
+/// 可用的子命令：hide (隐藏)、recover (恢复)、capacity (容量查询)、info (检查)、verify (校验)、analyze (隐写分析)、
+/// clean (清除) 和 list-formats (列出支持的格式)
 #[derive(Parser, Debug)]
 pub enum Commands {
     /// 在无损格式图像 (如 PNG, BMP) 中隐藏文本文件内容
@@ -26,18 +62,64 @@ pub enum Commands {
 
     /// 从经过隐写的图像中恢复隐藏的文本
     Recover(RecoverArgs),
+
+    /// 查询图像最多能隐藏多少字节的数据
+    Capacity(CapacityArgs),
+
+    /// 检查图像是否包含隐藏的数据，并报告头部记录的信息 (不恢复载荷)
+    Info(InfoArgs),
+
+    /// 从图像中恢复载荷并与预期的文件逐字节比较，不一致时以非零状态码退出
+    Verify(VerifyArgs),
+
+    /// 对图像进行值对卡方检验，估计其中含有 LSB 隐写数据的概率 (不依赖 lsb_hide 的头部)
+    Analyze(AnalyzeArgs),
+
+    /// 逐字节比较原始图像与隐写后的图像，报告改动的字节数与 PSNR，并可写出放大后的差异图像
+    Diff(DiffArgs),
+
+    /// 将图像每个像素字节的某一位 (默认为最低有效位) 放大为黑白两色写出为图像，用于观察嵌入位置的分布
+    Preview(PreviewArgs),
+
+    /// 清零或随机化每个像素字节的最低有效位，销毁其中可能隐藏的任何数据
+    Clean(CleanArgs),
+
+    /// 列出可以读取的图像格式，以及可以安全写出隐写结果的无损格式
+    ListFormats(ListFormatsArgs),
 }
 
 /// 'hide' 命令所需的参数
 #[derive(Parser, Debug)]
+#[command(group(ArgGroup::new("scatter_key").args(["password", "seed_file"])))]
 pub struct HideArgs {
     /// 用于隐写的输入图像文件路径 (如 PNG, BMP)
-    #[arg(short, long)]
-    pub image: PathBuf,
+    ///
+    /// 未提供时必须使用 `--image-dir` 从目录中挑选
+    #[arg(short, long, required_unless_present = "image_dir")]
+    pub image: Option<PathBuf>,
+
+    /// 从目录中自动挑选封面图像：在容量足以容纳载荷的图像中选择容量最小的一张
+    ///
+    /// 目录中无法解码的文件会被忽略。不能与 `--image`、`--in-place`、`--frame` 或 `--raw-bmp` 同时使用
+    #[arg(long, conflicts_with_all = ["image", "in_place", "frame", "raw_bmp"])]
+    pub image_dir: Option<PathBuf>,
 
     /// 要隐藏的文本内容的文件路径
-    #[arg(short, long)]
-    pub text: PathBuf,
+    ///
+    /// \[可选\] 如果未提供或为 "-"，将从标准输入读取载荷；可以重复指定多次，
+    /// 此时所有文件会被打包为一个归档一起隐藏
+    #[arg(
+        short,
+        long,
+        long_help = "要隐藏的文本内容的文件路径\n[可选] 如果未提供或为 \"-\"，将从标准输入读取载荷\n可以重复指定多次，此时所有文件会被打包为一个归档一起隐藏"
+    )]
+    pub text: Vec<PathBuf>,
+
+    /// 直接在命令行中提供要隐藏的短消息，以其 UTF-8 字节作为载荷
+    ///
+    /// 不能与 `--text` 同时使用
+    #[arg(short, long, conflicts_with = "text")]
+    pub message: Option<String>,
 
     /// 隐写完成后，保存结果图像的输出路径
     ///
@@ -49,13 +131,482 @@ pub struct HideArgs {
     )]
     pub dest: Option<PathBuf>,
 
+    /// 原地隐写：将结果直接写回 `--image` 指定的原始图像，而不是另存为新文件
+    ///
+    /// 原始图像会被覆盖且无法还原，因此必须同时提供 `--force` 确认
+    #[arg(long, conflicts_with = "dest")]
+    pub in_place: bool,
+
+    /// 追加模式：`--image` 已隐藏了载荷，把新载荷接在原载荷之后，而不是覆盖它
+    ///
+    /// 只改写头部中的长度与校验和并写入新增的字节；位深度、通道等参数沿用原载荷头部的记录。
+    /// 原载荷必须未经加密、压缩或白化，且不是归档、分段或以 `--delimited`、`--redundancy` 隐藏的载荷；
+    /// 原载荷以 `--scatter`、`--edge-adaptive` 或 `--endian` 隐藏时需要同样指定
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "image_dir", "split", "chunk_size", "compress", "password", "xor_key", "delimited",
+            "auto_bits", "length_backup", "length_bits", "provenance", "redundancy", "bits",
+            "plane", "channels", "spacing", "input_encoding"
+        ]
+    )]
+    pub append: bool,
+
+    /// 不写入隐写头部，只从 `--offset` 处按默认位深度隐藏载荷的原始字节
+    ///
+    /// 图像中没有魔数、长度等任何头部结构；偏移量、长度与校验和写入边车文件 (参见 `--meta`)，恢复时使用 `recover --meta`。
+    /// 不能与改变载荷格式或嵌入位置的选项同时使用
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "image_dir", "split", "append", "chunk_size", "compress", "password", "xor_key",
+            "delimited", "auto_bits", "length_backup", "length_bits", "provenance", "redundancy",
+            "bits", "plane", "channels", "spacing", "scatter", "skip_alpha", "alpha_threshold",
+            "edge_adaptive", "endian", "raw_bmp", "input_encoding"
+        ]
+    )]
+    pub no_length_header: bool,
+
+    /// `--no-length-header` 写出的边车文件路径
+    ///
+    /// \[可选\] 如果未提供，将默认保存到 "{输出路径}.meta"
+    #[arg(long, value_name = "PATH", requires = "no_length_header")]
+    pub meta: Option<PathBuf>,
+
     /// 强制写入到输出路径
     #[arg(long)]
     pub force: bool,
+
+    /// 输出路径所在的目录不存在时自动创建
+    #[arg(long)]
+    pub mkdir: bool,
+
+    /// 试运行：完成容量检查与全部隐写操作，但不写出结果图像
+    ///
+    /// 不会检查输出文件是否已存在，适合批量验证大量图像与文本的组合
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// 显式指定结果图像的编码格式，而不是根据输出路径的扩展名推断
+    ///
+    /// 指定后输出路径可以使用任意扩展名 (或没有扩展名)，且不会再因扩展名被拒绝
+    #[arg(long, value_enum)]
+    pub output_format: Option<OutputFormat>,
+
+    /// 即使输出路径不是已知的无损格式 (如 JPEG) 也强制写入
+    ///
+    /// 有损压缩会破坏隐藏的数据，写出的图像通常无法再恢复
+    #[arg(long)]
+    pub force_lossy: bool,
+
+    /// 将原始图像中的文本、EXIF 等元数据块复制到输出图像中，使输出文件更接近原始图像
+    ///
+    /// 仅支持 PNG：原始图像与输出图像都必须是 PNG
+    #[arg(long)]
+    pub preserve_metadata: bool,
+
+    /// 直接在 BMP 文件的原始字节中隐写，从 `BMP_HEADER_SIZE` 字节的文件头之后开始写入
+    ///
+    /// 不经过解码与重新编码，文件头与行填充等文件结构逐字节保留，适合要求逐字节可比对的场景。
+    /// 输入必须是像素数据紧跟在 54 字节文件头之后的未压缩 BMP，输出也必须是 BMP；恢复时需要指定相同的 `--raw-bmp`
+    #[arg(long, conflicts_with_all = ["frame", "preserve_metadata", "output_format", "skip_alpha", "alpha_threshold"])]
+    pub raw_bmp: bool,
+
+    /// 以二进制模式处理载荷，跳过所有面向文本的处理
+    #[arg(long)]
+    pub binary: bool,
+
+    /// 文本载荷的原始编码 (如 "gbk"、"shift_jis"、"latin1")，用于隐藏非 UTF-8 的文本文件
+    ///
+    /// 载荷按原始字节隐藏，编码记录在头部中，恢复时默认转换为 UTF-8 (参见 recover 的 `--output-encoding`)。
+    /// 载荷必须是该编码的有效文本，且不能是多文件归档
+    #[arg(
+        long,
+        value_name = "LABEL",
+        value_parser = text_encoding::parse_label,
+        conflicts_with_all = ["binary", "chunk_size"]
+    )]
+    pub input_encoding: Option<&'static encoding_rs::Encoding>,
+
+    /// 严格模式：载荷为空时报错，而不是只打印警告
+    #[arg(long)]
+    pub strict: bool,
+
+    /// 在头部中记录隐藏时间与工具版本，可通过 `info` 或 `recover --verbose` 查看
+    ///
+    /// 默认不记录，以免泄露图像何时被处理过
+    #[arg(long)]
+    pub provenance: bool,
+
+    /// 每个像素字节使用的最低有效位数 (1-4)
+    ///
+    /// 位数越多容量越大，但对图像的视觉影响也越明显。位深度会被记录在头部中，恢复时无需再次指定
+    #[arg(
+        long,
+        default_value_t = DEFAULT_BITS,
+        value_parser = clap::value_parser!(u8).range(1..=MAX_BITS as i64)
+    )]
+    pub bits: u8,
+
+    /// 载荷在 `--bits` 位深度下放不下时，自动逐位增加位深度 (至多 4 位) 直到放得下
+    ///
+    /// 实际使用的位深度同样记录在头部中；4 位仍然放不下时报告 4 位下所需与可用的字节数
+    #[arg(long)]
+    pub auto_bits: bool,
+
+    /// 载荷写入的位平面，即数据窗口的最低位在每个像素字节中的位置 (0-7)
+    ///
+    /// 默认为 0，即最低有效位；例如 `--plane 2` 时使用第 2、3 位，最低两位保持不变。
+    /// 位平面与位深度之和不能超过 8。位平面会被记录在头部中，恢复时无需再次指定
+    #[arg(
+        long,
+        default_value_t = 0,
+        value_parser = clap::value_parser!(u8).range(0..=MAX_PLANE as i64)
+    )]
+    pub plane: u8,
+
+    /// 用于加密载荷的口令
+    ///
+    /// \[可选\] 提供后将使用 AES-256-GCM 加密载荷 (密钥由 Argon2 从口令派生)
+    #[arg(short, long)]
+    pub password: Option<String>,
+
+    /// 按口令派生的伪随机排列分散嵌入位置，而不是顺序写入
+    ///
+    /// 需要同时提供 `--password` 或 `--seed-file`，恢复时使用相同的口令或种子文件即可
+    #[arg(long, requires = "scatter_key")]
+    pub scatter: bool,
+
+    /// 从文件读取打散排列的种子材料，代替由 `--password` 派生种子
+    ///
+    /// 需要同时提供 `--scatter`，恢复时提供相同的文件即可，密钥不会出现在命令行与 shell 历史中；
+    /// 不能与 `--password` 同时使用
+    #[arg(
+        long,
+        value_name = "PATH",
+        requires = "scatter",
+        conflicts_with = "password"
+    )]
+    pub seed_file: Option<PathBuf>,
+
+    /// 用于白化载荷的密钥
+    ///
+    /// \[可选\] 提供后载荷会与由密钥派生的 ChaCha20 密钥流逐字节异或，使最低有效位中不再直接出现明文。
+    /// 比 `--password` 的加密更轻量，但不提供认证，密钥错误时只会恢复出不同的字节；不能与 `--password` 同时使用
+    #[arg(long, value_name = "KEY", conflicts_with = "password")]
+    pub xor_key: Option<String>,
+
+    /// 隐藏前压缩载荷所使用的算法
+    ///
+    /// 如果压缩后反而更大，将自动以原始数据保存。算法会被记录在头部中，恢复时自动解压
+    #[arg(long, value_enum, default_value_t = Compression::None)]
+    pub compress: Compression,
+
+    /// 承载载荷的颜色通道，由 r、g、b、a 组合而成 (例如 rgb、rg、b)
+    ///
+    /// 未选中通道的像素字节保持不变，RGB 图像会忽略 a。所选通道会被记录在头部中，恢复时无需再次指定
+    #[arg(long, default_value_t = Channels::ALL)]
+    pub channels: Channels,
+
+    /// 对于带 alpha 通道的图像 (RGBA 或 LA)，只在颜色字节中隐藏数据，alpha 通道保持不变
+    ///
+    /// 恢复时会自动识别，无需再次指定
+    #[arg(long)]
+    pub skip_alpha: bool,
+
+    /// 对于带 alpha 通道的图像，跳过 alpha 低于该阈值的像素 (隐含 `--skip-alpha`)
+    ///
+    /// 近乎透明的像素的颜色不携带视觉信息，预乘 alpha 的图像重新合成时还可能改变它们。
+    /// alpha 通道从不被修改，阈值不会记录在头部中，恢复时必须指定相同的 `--alpha-threshold`
+    #[arg(long)]
+    pub alpha_threshold: Option<u8>,
+
+    /// 对于动画 PNG (APNG)，只在第 N 帧 (从 0 开始) 中隐藏数据，其余帧原样保留
+    ///
+    /// 输出仍是 APNG，每一帧都以完整画布的 RGBA 写出。帧序号不会记录在头部中，恢复时必须指定相同的 `--frame`
+    #[arg(long)]
+    pub frame: Option<usize>,
+
+    /// 只在图像中左上角为 (X, Y)、宽 W、高 H 像素的矩形区域中隐藏数据，区域外的像素保持不变
+    ///
+    /// 适合把载荷限制在纹理丰富的背景等区域中；区域内的像素按行依次使用，`--offset` 从区域的左上角开始计算。
+    /// 区域不会记录在图像中，恢复时必须指定相同的 `--region`
+    #[arg(
+        long,
+        value_name = "X,Y,W,H",
+        conflicts_with_all = ["skip_alpha", "alpha_threshold", "raw_bmp", "split", "no_length_header"]
+    )]
+    pub region: Option<Region>,
+
+    /// 开始隐写前跳过的像素数，头部与载荷都从该位置之后写入
+    ///
+    /// 前 N 个像素 (例如缩略图区域) 将保持不变。偏移量不会记录在头部中，恢复时必须指定相同的 `--offset`
+    #[arg(long, default_value_t = 0)]
+    pub offset: usize,
+
+    /// 以定长方式记录载荷长度时使用的位数 (16、32 或 64)
+    ///
+    /// 默认使用变长编码，总是得到最短的头部；指定后载荷长度不能超出该位数所能表示的范围。
+    /// 所选位数会被记录在头部中，恢复时无需再次指定
+    #[arg(long, value_parser = parse_length_bits)]
+    pub length_bits: Option<u8>,
+
+    /// 不记录载荷长度，而是在载荷前后写入起始与结束标记，恢复时扫描到结束标记为止
+    ///
+    /// 载荷中与标记冲突的字节会被转义，因此任意数据都能安全隐藏；载荷被截断时恢复会报错。
+    /// 不能与 `--scatter`、`--length-bits` 或 `--redundancy` 同时使用
+    #[arg(long, conflicts_with_all = ["scatter", "length_bits"])]
+    pub delimited: bool,
+
+    /// 在图像末尾额外写入一份载荷长度的备份
+    ///
+    /// 头部中的长度字段损坏时，恢复仍可按备份的长度提取载荷；两份长度不一致时 recover 会报告损坏。
+    /// 备份占用图像末尾的 48 个像素字节，不能与 `--delimited` 同时使用
+    #[arg(long, conflicts_with = "delimited")]
+    pub length_backup: bool,
+
+    /// 不把载荷文件整个读入内存，而是每次读取 `BYTES` 个字节，边读边隐藏
+    ///
+    /// 先分块扫描一遍得到载荷的长度与校验和，再从头分块读取并写入像素，适合数百 MB 的大载荷；图像本身仍需完整载入内存。
+    /// 只能隐藏单个载荷文件，不能与压缩、加密、白化、`--message`、`--delimited`、`--auto-bits` 或 `--length-backup` 同时使用
+    #[arg(
+        long,
+        value_name = "BYTES",
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..),
+        conflicts_with_all = ["message", "compress", "password", "xor_key", "delimited", "auto_bits", "length_backup"]
+    )]
+    pub chunk_size: Option<usize>,
+
+    /// 载荷放不进一张图像时，依次填满 `--image-dir` 中的多张图像，每张隐藏一段
+    ///
+    /// `--dest` 为输出目录 (默认为 "doctored_{目录名}")，其中写出编号的 PNG 图像 "part_001.png" 等以及清单 "manifest.json"。
+    /// 每一段的序号与总段数记录在该图像的载荷中，恢复时使用 `recover --split` 指向保存这些图像的目录
+    #[arg(
+        long,
+        requires = "image_dir",
+        conflicts_with_all = ["chunk_size", "skip_alpha", "alpha_threshold", "edge_adaptive", "preserve_metadata", "output_format"]
+    )]
+    pub split: bool,
+
+    /// 只在纹理丰富 (边缘、噪点等高梯度) 区域的像素字节中隐藏载荷，平滑区域保持不变
+    ///
+    /// 平滑区域中的最低有效位变化更容易被察觉；可用容量随图像纹理减少。
+    /// 该选项不会记录在头部中，恢复时必须同样指定 `--edge-adaptive`；不能与 `--delimited` 同时使用
+    #[arg(long, conflicts_with = "delimited")]
+    pub edge_adaptive: bool,
+
+    /// 载荷每个字节拆分为多组位后写入像素字节的顺序："le" 先写最低的一组位，"be" 先写最高的一组位
+    ///
+    /// 头部字段始终按小端序写入；该选项不会记录在头部中，恢复时必须指定相同的 `--endian`
+    #[arg(long, value_enum, default_value_t = Endian::Little)]
+    pub endian: Endian,
+
+    /// 载荷只使用所选通道中每 `N` 个像素字节中的第一个 (1-32)，其余像素字节保持不变
+    ///
+    /// 把改动分散到更大的区域，降低局部最低有效位异常的密度；可用容量按间隔等比例减少。
+    /// 间隔记录在头部中，恢复时无需再次指定
+    #[arg(
+        long = "stride",
+        value_name = "N",
+        default_value_t = 1,
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..=MAX_SPACING as u64)
+    )]
+    pub spacing: usize,
+
+    /// 载荷重复写入的份数 (不超过 9 的奇数)
+    ///
+    /// 恢复时对各份副本逐位多数表决，少数副本所在区域损坏时仍能恢复出正确内容；
+    /// 可用容量按份数等比例减少。份数记录在头部中，恢复时无需再次指定
+    #[arg(long, default_value_t = 1, value_parser = parse_redundancy)]
+    pub redundancy: u8,
+
+    /// 载荷最多允许占用的容量比例 (大于 0 且不超过 1)
+    ///
+    /// 例如 `0.1` 表示载荷超过可用容量的 10% 时报错。占用的比例越低，隐写越难被检测，默认为 1 (不限制)
+    #[arg(long, default_value_t = 1.0, value_parser = parse_capacity_fraction)]
+    pub max_capacity_fraction: f64,
+
+    /// 隐藏完成后打印载荷大小、容量使用率及被修改的字节区域等统计信息
+    #[arg(short, long)]
+    pub verbose: bool,
+
+    /// 以 JSON 格式输出结果，由全局的 `--json` 标志设置
+    #[arg(skip)]
+    pub json: bool,
+
+    /// 成功时不输出提示信息，由全局的 `--quiet` 标志设置
+    #[arg(skip)]
+    pub quiet: bool,
+
+    /// 输出文件已存在时是否可以在终端中询问用户，由 `main` 根据标准输入与标准输出是否为终端设置
+    #[arg(skip)]
+    pub interactive: bool,
+}
+
+impl Default for HideArgs {
+    /// 与命令行默认值保持一致的参数，主要供库调用方和测试使用
+    fn default() -> Self {
+        Self {
+            image: None,
+            image_dir: None,
+            text: Vec::new(),
+            message: None,
+            dest: None,
+            in_place: false,
+            append: false,
+            no_length_header: false,
+            meta: None,
+            force: false,
+            mkdir: false,
+            dry_run: false,
+            output_format: None,
+            force_lossy: false,
+            preserve_metadata: false,
+            raw_bmp: false,
+            binary: false,
+            input_encoding: None,
+            strict: false,
+            provenance: false,
+            bits: DEFAULT_BITS,
+            auto_bits: false,
+            plane: 0,
+            password: None,
+            scatter: false,
+            seed_file: None,
+            xor_key: None,
+            compress: Compression::None,
+            channels: Channels::ALL,
+            skip_alpha: false,
+            alpha_threshold: None,
+            frame: None,
+            region: None,
+            offset: 0,
+            length_bits: None,
+            delimited: false,
+            length_backup: false,
+            chunk_size: None,
+            split: false,
+            edge_adaptive: false,
+            endian: Endian::Little,
+            spacing: 1,
+            redundancy: 1,
+            max_capacity_fraction: 1.0,
+            verbose: false,
+            json: false,
+            quiet: false,
+            interactive: false,
+        }
+    }
+}
+
+/// 解析 `--length-bits`，只接受 `LENGTH_BITS_CHOICES` 中的位数
+fn parse_length_bits(value: &str) -> Result<u8, String> {
+    value
+        .parse()
+        .ok()
+        .filter(|bits| LENGTH_BITS_CHOICES.contains(bits))
+        .ok_or_else(|| format!("expected one of {:?}, got '{}'", LENGTH_BITS_CHOICES, value))
+}
+
+/// 解析 `--redundancy`，只接受不超过 `MAX_REDUNDANCY` 的奇数，使多数表决不会出现平局
+fn parse_redundancy(value: &str) -> Result<u8, String> {
+    value
+        .parse()
+        .ok()
+        .filter(|copies: &u8| copies % 2 == 1 && *copies <= MAX_REDUNDANCY)
+        .ok_or_else(|| {
+            format!(
+                "expected an odd number from 1 to {}, got '{}'",
+                MAX_REDUNDANCY, value
+            )
+        })
+}
+
+/// 解析 `--max-capacity-fraction`，要求取值在 `(0, 1]` 范围内
+fn parse_capacity_fraction(value: &str) -> Result<f64, String> {
+    let fraction: f64 = value
+        .parse()
+        .map_err(|_| format!("'{}' is not a number", value))?;
+    if fraction > 0.0 && fraction <= 1.0 {
+        Ok(fraction)
+    } else {
+        Err(format!("{} is not in the range (0, 1]", value))
+    }
+}
+
+/// `--output-format` 可选的无损图像格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// PNG
+    Png,
+
+    /// BMP
+    Bmp,
+
+    /// TIFF
+    Tiff,
+}
+
+impl From<OutputFormat> for ImageFormat {
+    fn from(format: OutputFormat) -> Self {
+        match format {
+            OutputFormat::Png => ImageFormat::Png,
+            OutputFormat::Bmp => ImageFormat::Bmp,
+            OutputFormat::Tiff => ImageFormat::Tiff,
+        }
+    }
+}
+
+/// `recover --encoding` 可选的输出编码
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Encoding {
+    /// 原样写出恢复的字节
+    #[default]
+    Raw,
+
+    /// 标准 Base64 (RFC 4648)，带填充
+    Base64,
+
+    /// 小写十六进制
+    Hex,
+}
+
+impl Encoding {
+    /// 按所选编码转换恢复的字节；非原始编码的结果以换行符结尾，便于直接输出到终端
+    pub fn encode(self, data: &[u8]) -> Vec<u8> {
+        let mut encoded = match self {
+            Encoding::Raw => return data.to_vec(),
+            Encoding::Base64 => BASE64_STANDARD.encode(data),
+            Encoding::Hex => data.iter().map(|byte| format!("{:02x}", byte)).collect(),
+        };
+        encoded.push('\n');
+        encoded.into_bytes()
+    }
+
+    /// 默认输出文件使用的扩展名，原样写出时为 `None`
+    pub fn extension(self) -> Option<&'static str> {
+        match self {
+            Encoding::Raw => None,
+            Encoding::Base64 => Some("b64"),
+            Encoding::Hex => Some("hex"),
+        }
+    }
+}
+
+impl fmt::Display for Encoding {
+    /// 显示与命令行参数 `--encoding` 相同的编码名称
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Encoding::Raw => "raw",
+            Encoding::Base64 => "base64",
+            Encoding::Hex => "hex",
+        };
+        f.write_str(name)
+    }
 }
 
 /// 'recover' 命令所需的参数
-#[derive(Parser, Debug)]
+#[derive(Parser, Debug, Default)]
 pub struct RecoverArgs {
     /// 已隐藏文本数据的图像文件路径
     #[arg(short, long)]
@@ -64,14 +615,453 @@ pub struct RecoverArgs {
     /// 恢复文本后，保存文本内容的输出路径
     ///
     /// \[可选\] 如果未提供，将默认保存到 "recovered_{原始文件名}.txt"
+    /// (二进制模式下为 "recovered_{原始文件名}.bin"；头部记录了原始载荷的文件名时沿用其扩展名)；
+    /// 为 "-" 时写入标准输出。
+    /// 如果隐藏的是多文件归档，则为解包的目标目录，默认为 "recovered_{原始文件名}"
     #[arg(
         short,
         long,
-        long_help = "恢复文本后，保存文本内容的输出路径\n[可选] 如果未提供，将默认保存到 \"recovered_{原始文件名}.txt\""
+        long_help = "恢复文本后，保存文本内容的输出路径\n[可选] 如果未提供，将默认保存到 \"recovered_{原始文件名}.txt\" (二进制模式下为 \"recovered_{原始文件名}.bin\")\n头部记录了原始载荷的文件名时沿用其扩展名\n为 \"-\" 时写入标准输出\n如果隐藏的是多文件归档，则为解包的目标目录，默认为 \"recovered_{原始文件名}\""
     )]
     pub text: Option<PathBuf>,
 
     /// 强制写入到输出路径
     #[arg(long)]
     pub force: bool,
+
+    /// 输出文件所在的目录不存在时自动创建
+    #[arg(long)]
+    pub mkdir: bool,
+
+    /// 以二进制模式恢复载荷，原样写出恢复的字节
+    #[arg(long)]
+    pub binary: bool,
+
+    /// 未指定 `--text` 时默认输出文件名的模板，默认为 "recovered_{stem}.{ext}"
+    ///
+    /// `{stem}` 替换为原始图像的文件名 (不带扩展名)，`{ext}` 替换为按编码、内嵌文件名或二进制模式选择的扩展名。
+    /// 多文件归档解包到目录中，此时模板中的 `.{ext}` 会被去掉
+    #[arg(long)]
+    pub name_template: Option<String>,
+
+    /// 写出恢复的字节前使用的编码
+    ///
+    /// `base64` 与 `hex` 会把任意字节转换为可打印文本，适合写入标准输出或终端；默认原样写出
+    #[arg(long, value_enum, default_value_t = Encoding::Raw)]
+    pub encoding: Encoding,
+
+    /// 恢复的文本载荷转换为的编码 (如 "gbk"、"shift_jis"、"latin1")，在 `--encoding` 之前进行
+    ///
+    /// \[可选\] 头部记录了 hide 的 `--input-encoding` 时默认转换为 UTF-8；未记录时文本载荷视为 UTF-8。
+    /// 文本中有目标编码无法表示的字符时报错；二进制载荷与 `--binary` 模式下不转换
+    #[arg(long, value_name = "LABEL", value_parser = text_encoding::parse_label, conflicts_with = "binary")]
+    pub output_encoding: Option<&'static encoding_rs::Encoding>,
+
+    /// 将恢复的文本复制到系统剪贴板，需要以 `clipboard` 特性编译
+    ///
+    /// 未指定 `--text` 时只复制到剪贴板而不写出文件，否则同时写出文件；载荷必须是有效的 UTF-8 文本
+    #[arg(long)]
+    pub clipboard: bool,
+
+    /// 用于解密载荷的口令
+    ///
+    /// \[可选\] 仅当隐藏时使用了口令加密才需要提供
+    #[arg(short, long)]
+    pub password: Option<String>,
+
+    /// 用于定位打散载荷的种子文件
+    ///
+    /// \[可选\] 仅当隐藏时使用了 `--seed-file` 才需要提供，必须与隐藏时的文件内容相同；不能与 `--password` 同时使用
+    #[arg(long, value_name = "PATH", conflicts_with = "password")]
+    pub seed_file: Option<PathBuf>,
+
+    /// 用于还原白化载荷的密钥
+    ///
+    /// \[可选\] 仅当隐藏时使用了 `--xor-key` 才需要提供，必须与隐藏时相同
+    #[arg(long, value_name = "KEY")]
+    pub xor_key: Option<String>,
+
+    /// 隐藏时使用的像素偏移量，必须与 hide 的 `--offset` 相同
+    #[arg(long, default_value_t = 0)]
+    pub offset: usize,
+
+    /// 隐藏时使用的 alpha 阈值，必须与 hide 的 `--alpha-threshold` 相同
+    #[arg(long)]
+    pub alpha_threshold: Option<u8>,
+
+    /// 隐藏数据的动画帧序号，必须与 hide 的 `--frame` 相同
+    #[arg(long)]
+    pub frame: Option<usize>,
+
+    /// 隐藏数据的矩形区域 (X,Y,W,H)，必须与 hide 的 `--region` 相同
+    #[arg(long, value_name = "X,Y,W,H", conflicts_with_all = ["raw_bmp", "split", "alpha_threshold"])]
+    pub region: Option<Region>,
+
+    /// 直接从 BMP 文件的原始字节中恢复，必须与 hide 的 `--raw-bmp` 相同
+    #[arg(long, conflicts_with_all = ["frame", "alpha_threshold"])]
+    pub raw_bmp: bool,
+
+    /// 预期位置没有找到头部时，继续向后扫描有限的字节数查找魔数
+    ///
+    /// 用于恢复被其他无损工具重新保存后整体错位了几个字节的载荷，最多扫描 `RESYNC_SCAN_BYTES` 个字节
+    #[arg(long)]
+    pub resync: bool,
+
+    /// 最多提取的载荷字节数
+    ///
+    /// \[可选\] 头部声称的载荷长度超过该值时拒绝提取，防止精心构造的图像声称巨大的长度；
+    /// 如果未提供，上限为图像的实际容量
+    #[arg(long, value_name = "BYTES")]
+    pub payload_limit: Option<u64>,
+
+    /// 载荷损坏时写出已恢复的部分并给出警告，而不是放弃全部数据
+    ///
+    /// 遇到第一个无效字节时停止提取，写出之前成功恢复的字节；全部字节都能解码但未通过完整性校验时照常写出。
+    /// 加密或压缩的载荷损坏后通常无法解密或解压，仍会报错
+    #[arg(long)]
+    pub best_effort: bool,
+
+    /// 从 `hide --split` 写出的多张图像中恢复载荷：`--image` 为保存这些图像的目录
+    ///
+    /// 目录中的每张图像都会被读取，按段头中的序号拼接各段；缺少某一段时报错并列出缺少的序号
+    #[arg(long, conflicts_with_all = ["raw_bmp", "frame", "resync", "best_effort", "no_header"])]
+    pub split: bool,
+
+    /// 载荷只隐藏在纹理丰富的像素字节中，必须与 hide 的 `--edge-adaptive` 相同
+    #[arg(long)]
+    pub edge_adaptive: bool,
+
+    /// 载荷各组位的顺序，必须与 hide 的 `--endian` 相同
+    #[arg(long, value_enum, default_value_t = Endian::Little)]
+    pub endian: Endian,
+
+    /// 图像不含隐写头部，跳过魔数与长度的读取，直接从 `--offset` 处按默认位深度提取 `--length` 个字节
+    ///
+    /// 用于恢复其他工具或旧版本写入的、没有记录长度的载荷，恢复出的字节原样写出
+    #[arg(long, requires = "length", conflicts_with_all = ["resync", "password", "seed_file", "xor_key", "alpha_threshold", "edge_adaptive", "endian", "best_effort", "output_encoding"])]
+    pub no_header: bool,
+
+    /// 按 `hide --no-length-header` 写出的边车文件提取无头部的载荷
+    ///
+    /// 偏移量与长度都取自边车文件，提取出的字节必须与其中记录的校验和一致，之后原样写出
+    #[arg(
+        long,
+        value_name = "PATH",
+        conflicts_with_all = [
+            "no_header", "offset", "region", "split", "raw_bmp", "resync", "password", "seed_file",
+            "xor_key", "alpha_threshold", "edge_adaptive", "endian", "best_effort", "output_encoding"
+        ]
+    )]
+    pub meta: Option<PathBuf>,
+
+    /// 与 `--no-header` 一起使用时要提取的载荷字节数，为 0 时视为图像中没有载荷
+    #[arg(long, value_name = "N", requires = "no_header")]
+    pub length: Option<u64>,
+
+    /// 恢复完成后打印恢复的载荷长度及提取结束的字节偏移量
+    #[arg(short, long)]
+    pub verbose: bool,
+
+    /// 以 JSON 格式输出结果，由全局的 `--json` 标志设置
+    #[arg(skip)]
+    pub json: bool,
+
+    /// 成功时不输出提示信息，由全局的 `--quiet` 标志设置
+    #[arg(skip)]
+    pub quiet: bool,
+
+    /// 输出文件已存在时是否可以在终端中询问用户，由 `main` 根据标准输入与标准输出是否为终端设置
+    #[arg(skip)]
+    pub interactive: bool,
+}
+
+/// 'capacity' 命令所需的参数
+#[derive(Parser, Debug, Default)]
+pub struct CapacityArgs {
+    /// 要查询容量的图像文件路径
+    #[arg(short, long)]
+    pub image: PathBuf,
+
+    /// 以 JSON 格式输出结果，由全局的 `--json` 标志设置
+    #[arg(skip)]
+    pub json: bool,
+}
+
+/// 'info' 命令所需的参数
+#[derive(Parser, Debug, Default)]
+pub struct InfoArgs {
+    /// 要检查的图像文件路径
+    #[arg(short, long)]
+    pub image: PathBuf,
+
+    /// 隐藏时使用的像素偏移量，必须与 hide 的 `--offset` 相同
+    #[arg(long, default_value_t = 0)]
+    pub offset: usize,
+
+    /// 隐藏时使用的 alpha 阈值，必须与 hide 的 `--alpha-threshold` 相同
+    #[arg(long)]
+    pub alpha_threshold: Option<u8>,
+
+    /// 隐藏数据的动画帧序号，必须与 hide 的 `--frame` 相同
+    #[arg(long)]
+    pub frame: Option<usize>,
+
+    /// 隐藏数据的矩形区域 (X,Y,W,H)，必须与 hide 的 `--region` 相同
+    #[arg(long, value_name = "X,Y,W,H", conflicts_with = "alpha_threshold")]
+    pub region: Option<Region>,
+
+    /// 以 JSON 格式输出结果，由全局的 `--json` 标志设置
+    #[arg(skip)]
+    pub json: bool,
+}
+
+/// 'verify' 命令所需的参数
+#[derive(Parser, Debug, Default)]
+pub struct VerifyArgs {
+    /// 要校验的经过隐写的图像文件路径
+    #[arg(short, long)]
+    pub image: PathBuf,
+
+    /// 预期载荷的文件路径，恢复出的载荷必须与其内容逐字节相同
+    #[arg(short, long)]
+    pub text: PathBuf,
+
+    /// 用于解密载荷的口令
+    ///
+    /// \[可选\] 仅当隐藏时使用了口令加密才需要提供
+    #[arg(short, long)]
+    pub password: Option<String>,
+
+    /// 用于定位打散载荷的种子文件
+    ///
+    /// \[可选\] 仅当隐藏时使用了 `--seed-file` 才需要提供，必须与隐藏时的文件内容相同；不能与 `--password` 同时使用
+    #[arg(long, value_name = "PATH", conflicts_with = "password")]
+    pub seed_file: Option<PathBuf>,
+
+    /// 用于还原白化载荷的密钥
+    ///
+    /// \[可选\] 仅当隐藏时使用了 `--xor-key` 才需要提供，必须与隐藏时相同
+    #[arg(long, value_name = "KEY")]
+    pub xor_key: Option<String>,
+
+    /// 隐藏时使用的像素偏移量，必须与 hide 的 `--offset` 相同
+    #[arg(long, default_value_t = 0)]
+    pub offset: usize,
+
+    /// 隐藏时使用的 alpha 阈值，必须与 hide 的 `--alpha-threshold` 相同
+    #[arg(long)]
+    pub alpha_threshold: Option<u8>,
+
+    /// 隐藏数据的动画帧序号，必须与 hide 的 `--frame` 相同
+    #[arg(long)]
+    pub frame: Option<usize>,
+
+    /// 隐藏数据的矩形区域 (X,Y,W,H)，必须与 hide 的 `--region` 相同
+    #[arg(long, value_name = "X,Y,W,H", conflicts_with = "alpha_threshold")]
+    pub region: Option<Region>,
+
+    /// 以 JSON 格式输出结果，由全局的 `--json` 标志设置
+    #[arg(skip)]
+    pub json: bool,
+
+    /// 成功时不输出提示信息，由全局的 `--quiet` 标志设置
+    #[arg(skip)]
+    pub quiet: bool,
+}
+
+/// 'analyze' 命令所需的参数
+#[derive(Parser, Debug, Default)]
+pub struct AnalyzeArgs {
+    /// 要分析的图像文件路径
+    #[arg(short, long)]
+    pub image: PathBuf,
+
+    /// 以 JSON 格式输出结果，由全局的 `--json` 标志设置
+    #[arg(skip)]
+    pub json: bool,
+}
+
+/// 'diff' 命令所需的参数
+#[derive(Parser, Debug)]
+pub struct DiffArgs {
+    /// 原始 (隐写前) 图像文件路径
+    #[arg(short, long)]
+    pub cover: PathBuf,
+
+    /// 隐写后的图像文件路径，尺寸与颜色类型必须与原始图像相同
+    #[arg(short, long)]
+    pub stego: PathBuf,
+
+    /// 写出差异图像的路径，每个字节为两者差值的 `--amplify` 倍，alpha 通道保持不透明
+    ///
+    /// \[可选\] 如果未提供，只报告统计结果
+    #[arg(short, long)]
+    pub dest: Option<PathBuf>,
+
+    /// 差异图像中每个字节差值的放大倍数，超出 255 时取 255
+    #[arg(
+        long,
+        default_value_t = DEFAULT_DIFF_AMPLIFY,
+        value_parser = clap::value_parser!(u8).range(1..)
+    )]
+    pub amplify: u8,
+
+    /// 强制写入到差异图像的输出路径
+    #[arg(long)]
+    pub force: bool,
+
+    /// 以 JSON 格式输出结果，由全局的 `--json` 标志设置
+    #[arg(skip)]
+    pub json: bool,
+
+    /// 成功时不输出提示信息，由全局的 `--quiet` 标志设置
+    #[arg(skip)]
+    pub quiet: bool,
+
+    /// 输出文件已存在时是否可以在终端中询问用户，由 `main` 根据标准输入与标准输出是否为终端设置
+    #[arg(skip)]
+    pub interactive: bool,
+}
+
+impl Default for DiffArgs {
+    /// 与命令行默认值保持一致的参数，主要供库调用方和测试使用
+    fn default() -> Self {
+        Self {
+            cover: PathBuf::new(),
+            stego: PathBuf::new(),
+            dest: None,
+            amplify: DEFAULT_DIFF_AMPLIFY,
+            force: false,
+            json: false,
+            quiet: false,
+            interactive: false,
+        }
+    }
+}
+
+/// 'preview' 命令所需的参数
+#[derive(Parser, Debug, Default)]
+pub struct PreviewArgs {
+    /// 要预览的图像文件路径
+    #[arg(short, long)]
+    pub image: PathBuf,
+
+    /// 写出位平面图像的路径，每个像素字节的所选位为 1 时为 255，否则为 0，alpha 通道保持不透明
+    ///
+    /// \[可选\] 如果未提供，将默认保存到 "lsb_{原始文件名}.png"
+    #[arg(
+        short,
+        long,
+        long_help = "写出位平面图像的路径，每个像素字节的所选位为 1 时为 255，否则为 0，alpha 通道保持不透明\n[可选] 如果未提供，将默认保存到 \"lsb_{原始文件名}.png\""
+    )]
+    pub dest: Option<PathBuf>,
+
+    /// 要预览的位平面 (0-7)，0 为最低有效位；以 `hide --plane` 隐藏的载荷需要指定相同的位
+    #[arg(long, default_value_t = 0, value_parser = clap::value_parser!(u8).range(0..=MAX_PLANE as i64))]
+    pub plane: u8,
+
+    /// 强制写入到位平面图像的输出路径
+    #[arg(long)]
+    pub force: bool,
+
+    /// 以 JSON 格式输出结果，由全局的 `--json` 标志设置
+    #[arg(skip)]
+    pub json: bool,
+
+    /// 成功时不输出提示信息，由全局的 `--quiet` 标志设置
+    #[arg(skip)]
+    pub quiet: bool,
+
+    /// 输出文件已存在时是否可以在终端中询问用户，由 `main` 根据标准输入与标准输出是否为终端设置
+    #[arg(skip)]
+    pub interactive: bool,
+}
+
+/// 'list-formats' 命令所需的参数
+#[derive(Parser, Debug, Default)]
+pub struct ListFormatsArgs {
+    /// 以 JSON 格式输出结果，由全局的 `--json` 标志设置
+    #[arg(skip)]
+    pub json: bool,
+}
+
+/// 'clean' 命令所需的参数
+#[derive(Parser, Debug)]
+pub struct CleanArgs {
+    /// 要清除的图像文件路径
+    #[arg(short, long)]
+    pub image: PathBuf,
+
+    /// 保存清除后图像的输出路径
+    ///
+    /// \[可选\] 如果未提供，将默认保存到 "cleaned_{原始文件名}"
+    #[arg(short, long)]
+    pub dest: Option<PathBuf>,
+
+    /// 强制写入到输出路径
+    #[arg(long)]
+    pub force: bool,
+
+    /// 最低有效位的处理方式
+    #[arg(long, value_enum, default_value_t = CleanMode::Random)]
+    pub mode: CleanMode,
+
+    /// 每个像素字节清除的最低位数 (1 到 4)，默认与隐藏时的默认位深度相同
+    #[arg(
+        short,
+        long,
+        default_value_t = DEFAULT_BITS,
+        value_parser = clap::value_parser!(u8).range(1..=MAX_BITS as i64)
+    )]
+    pub bits: u8,
+
+    /// 以 JSON 格式输出结果，由全局的 `--json` 标志设置
+    #[arg(skip)]
+    pub json: bool,
+
+    /// 成功时不输出提示信息，由全局的 `--quiet` 标志设置
+    #[arg(skip)]
+    pub quiet: bool,
+
+    /// 输出文件已存在时是否可以在终端中询问用户，由 `main` 根据标准输入与标准输出是否为终端设置
+    #[arg(skip)]
+    pub interactive: bool,
+}
+
+impl Default for CleanArgs {
+    /// 与命令行默认值保持一致的参数，主要供库调用方和测试使用
+    fn default() -> Self {
+        Self {
+            image: PathBuf::new(),
+            dest: None,
+            force: false,
+            mode: CleanMode::Random,
+            bits: DEFAULT_BITS,
+            json: false,
+            quiet: false,
+            interactive: false,
+        }
+    }
+}
+
+/// `clean --mode` 可选的最低有效位处理方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum CleanMode {
+    /// 替换为随机位，图像统计特征与普通照片相近
+    #[default]
+    Random,
+
+    /// 全部清零，得到可重复的干净基线
+    Zero,
+}
+
+impl fmt::Display for CleanMode {
+    /// 显示与命令行参数 `--mode` 相同的名称
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            CleanMode::Random => "random",
+            CleanMode::Zero => "zero",
+        };
+        f.write_str(name)
+    }
 }
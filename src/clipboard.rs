@@ -0,0 +1,106 @@
+//! # 剪贴板模块
+//!
+//! 供 `recover --clipboard` 将恢复的文本复制到系统剪贴板
+//! 系统剪贴板只在启用 `clipboard` 特性时可用；剪贴板后端抽象为 [`Clipboard`] trait，便于在测试中替换
+
+/// 可以写入文本的剪贴板后端
+pub trait Clipboard {
+    /// 用 `text` 替换剪贴板中的内容
+    ///
+    /// # Errors
+    ///
+    /// 如果剪贴板无法访问或写入失败，将返回错误
+    fn set_text(&mut self, text: &str) -> anyhow::Result<()>;
+}
+
+/// 将恢复的字节作为文本复制到 `clipboard` 中
+///
+/// # Errors
+///
+/// * 如果 `bytes` 不是有效的 UTF-8 文本，将返回错误，剪贴板保持不变
+/// * 如果写入剪贴板失败，将返回错误
+pub fn copy_text(clipboard: &mut dyn Clipboard, bytes: &[u8]) -> anyhow::Result<()> {
+    let text = std::str::from_utf8(bytes).map_err(|err| {
+        anyhow::anyhow!(
+            "The recovered payload is not valid UTF-8 text ({}), so it cannot be copied to the clipboard.\nUse --text to write it to a file instead.",
+            err
+        )
+    })?;
+    clipboard.set_text(text)
+}
+
+/// 基于 `arboard` 的系统剪贴板
+#[cfg(feature = "clipboard")]
+struct SystemClipboard(arboard::Clipboard);
+
+#[cfg(feature = "clipboard")]
+impl Clipboard for SystemClipboard {
+    fn set_text(&mut self, text: &str) -> anyhow::Result<()> {
+        use anyhow::Context;
+
+        self.0
+            .set_text(text)
+            .context("Failed to copy the recovered text to the clipboard.")
+    }
+}
+
+/// 打开系统剪贴板
+///
+/// # Errors
+///
+/// 如果没有可用的剪贴板 (例如没有图形会话)，将返回错误
+#[cfg(feature = "clipboard")]
+pub fn system() -> anyhow::Result<Box<dyn Clipboard>> {
+    use anyhow::Context;
+
+    let clipboard = arboard::Clipboard::new().context("Failed to access the system clipboard.")?;
+    Ok(Box::new(SystemClipboard(clipboard)))
+}
+
+/// 打开系统剪贴板
+///
+/// # Errors
+///
+/// 程序编译时没有启用 `clipboard` 特性，总是返回错误
+#[cfg(not(feature = "clipboard"))]
+pub fn system() -> anyhow::Result<Box<dyn Clipboard>> {
+    anyhow::bail!(
+        "Clipboard support is not enabled in this build.\nRebuild lsb_hide with `--features clipboard`, or use --text to write the recovered text to a file."
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 记录最后一次写入内容的模拟剪贴板
+    #[derive(Default)]
+    struct MockClipboard(Option<String>);
+
+    impl Clipboard for MockClipboard {
+        fn set_text(&mut self, text: &str) -> anyhow::Result<()> {
+            self.0 = Some(text.to_string());
+            Ok(())
+        }
+    }
+
+    /// 测试 UTF-8 文本被复制到剪贴板，而无效的 UTF-8 会报错且不修改剪贴板
+    #[test]
+    fn test_copy_text() {
+        let mut clipboard = MockClipboard::default();
+        copy_text(&mut clipboard, "hunter2 🔑".as_bytes()).unwrap();
+        assert_eq!(clipboard.0.as_deref(), Some("hunter2 🔑"));
+
+        let err = copy_text(&mut clipboard, &[0xff, 0xfe]).unwrap_err();
+        assert!(err.to_string().contains("not valid UTF-8"), "{}", err);
+        assert_eq!(clipboard.0.as_deref(), Some("hunter2 🔑"));
+    }
+
+    /// 测试没有启用 `clipboard` 特性时，打开系统剪贴板会给出重新编译的提示
+    #[cfg(not(feature = "clipboard"))]
+    #[test]
+    fn test_system_clipboard_requires_feature() {
+        let err = system().err().unwrap();
+        assert!(err.to_string().contains("--features clipboard"), "{}", err);
+    }
+}
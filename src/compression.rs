@@ -0,0 +1,176 @@
+//! # 载荷压缩模块
+//!
+//! 在隐藏之前压缩载荷以节省图像容量，恢复之后再解压
+//! 所选算法的编号会记录在隐写头部中，恢复时据此选择解压方式
+
+use anyhow::Context;
+use clap::ValueEnum;
+use flate2::Compression as GzipLevel;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use std::fmt;
+use std::io::{Read, Write};
+
+/// zstd 压缩级别
+///
+/// 载荷通常较小，较高的级别几乎不会带来可察觉的耗时
+const ZSTD_LEVEL: i32 = 19;
+
+/// 支持的载荷压缩算法
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum Compression {
+    /// 不压缩
+    #[default]
+    None,
+
+    /// gzip (DEFLATE) 压缩
+    Gzip,
+
+    /// zstd 压缩
+    Zstd,
+}
+
+impl Compression {
+    /// 返回记录在隐写头部中的算法编号
+    pub fn id(self) -> u8 {
+        match self {
+            Compression::None => 0,
+            Compression::Gzip => 1,
+            Compression::Zstd => 2,
+        }
+    }
+
+    /// 根据隐写头部中的算法编号还原压缩算法
+    ///
+    /// # Errors
+    ///
+    /// 如果编号未知，将返回错误
+    pub fn from_id(id: u8) -> anyhow::Result<Self> {
+        match id {
+            0 => Ok(Compression::None),
+            1 => Ok(Compression::Gzip),
+            2 => Ok(Compression::Zstd),
+            _ => anyhow::bail!("Unknown compression algorithm id: {}", id),
+        }
+    }
+
+    /// 使用该算法压缩数据
+    ///
+    /// # Errors
+    ///
+    /// 如果底层压缩器失败，将返回错误
+    pub fn compress(self, data: &[u8]) -> anyhow::Result<Vec<u8>> {
+        match self {
+            Compression::None => Ok(data.to_vec()),
+            Compression::Gzip => {
+                let mut encoder = GzEncoder::new(Vec::new(), GzipLevel::best());
+                encoder
+                    .write_all(data)
+                    .and_then(|_| encoder.finish())
+                    .context("Failed to compress the payload with gzip.")
+            }
+            Compression::Zstd => zstd::encode_all(data, ZSTD_LEVEL)
+                .context("Failed to compress the payload with zstd."),
+        }
+    }
+
+    /// 使用该算法解压数据
+    ///
+    /// # Errors
+    ///
+    /// 如果数据不是该算法的有效压缩流，将返回错误
+    pub fn decompress(self, data: &[u8]) -> anyhow::Result<Vec<u8>> {
+        match self {
+            Compression::None => Ok(data.to_vec()),
+            Compression::Gzip => {
+                let mut output = Vec::new();
+                GzDecoder::new(data)
+                    .read_to_end(&mut output)
+                    .context("Failed to decompress the gzip payload.")?;
+                Ok(output)
+            }
+            Compression::Zstd => {
+                zstd::decode_all(data).context("Failed to decompress the zstd payload.")
+            }
+        }
+    }
+}
+
+impl fmt::Display for Compression {
+    /// 显示与命令行参数 `--compress` 相同的算法名称
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Compression::None => "none",
+            Compression::Gzip => "gzip",
+            Compression::Zstd => "zstd",
+        };
+        f.write_str(name)
+    }
+}
+
+/// 使用指定算法压缩载荷，但仅在确实更小时才采用压缩结果
+///
+/// 对于已经无法再压缩的数据 (例如加密数据或压缩包)，压缩反而会增大体积，
+/// 此时返回原始数据并将算法记为 `Compression::None`
+///
+/// # Returns
+///
+/// 返回实际采用的算法以及对应的数据
+///
+/// # Errors
+///
+/// 如果底层压缩器失败，将返回错误
+pub fn compress_if_smaller(
+    algorithm: Compression,
+    data: &[u8],
+) -> anyhow::Result<(Compression, Vec<u8>)> {
+    if algorithm == Compression::None {
+        return Ok((Compression::None, data.to_vec()));
+    }
+
+    let compressed = algorithm.compress(data)?;
+    if compressed.len() < data.len() {
+        Ok((algorithm, compressed))
+    } else {
+        Ok((Compression::None, data.to_vec()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::RngCore;
+
+    /// 测试每种算法都能无损往返，且算法编号可以互相转换
+    #[test]
+    fn test_compress_round_trip_all_algorithms() {
+        let data = "Compress me! 压缩我！".repeat(100).into_bytes();
+
+        for algorithm in [Compression::None, Compression::Gzip, Compression::Zstd] {
+            let compressed = algorithm.compress(&data).expect("Failed to compress.");
+            let decompressed = algorithm
+                .decompress(&compressed)
+                .expect("Failed to decompress.");
+            assert_eq!(data, decompressed, "Round trip failed for {:?}.", algorithm);
+            assert_eq!(Compression::from_id(algorithm.id()).unwrap(), algorithm);
+        }
+
+        assert!(Compression::from_id(42).is_err());
+    }
+
+    /// 测试不可压缩的数据会以原样保存
+    #[test]
+    fn test_compress_if_smaller_keeps_incompressible_data() {
+        let mut random = vec![0u8; 1024];
+        rand::rng().fill_bytes(&mut random);
+
+        let (algorithm, stored) = compress_if_smaller(Compression::Zstd, &random).unwrap();
+        assert_eq!(algorithm, Compression::None);
+        assert_eq!(stored, random);
+
+        let repetitive = vec![b'a'; 1024];
+        let (algorithm, stored) = compress_if_smaller(Compression::Gzip, &repetitive).unwrap();
+        assert_eq!(algorithm, Compression::Gzip);
+        assert!(stored.len() < repetitive.len());
+    }
+}
@@ -2,24 +2,333 @@
 //!
 //! 定义了整个应用程序中使用的硬编码常量，以提高代码的可读性和可维护性
 
-/// 用于存储隐藏文本长度的字节数
+/// 默认位深度下，以定长方式存储一个完整 `u64` 所需的像素字节数
 ///
-/// 使用 32 个像素字节来存储长度，允许隐藏非常大的文本文件
+/// 头部中的载荷长度改用变长编码 (参见 `LENGTH_PREFIX_HIDING_BYTES`)，此常量仅作为定长存储的参考
 pub const LENGTH_HIDING_BYTES: usize = 32;
 
-/// 用于存储单个字符（1 字节）所需的像素字节数
+/// 写入隐写数据开头的魔数，用于识别由 lsb_hide 隐藏的载荷
+pub const MAGIC: [u8; 4] = *b"LSBH";
+
+/// 用于存储魔数的像素字节数
+///
+/// 魔数以默认位深度写入，每个魔数字节占用 `BYTES_PER_CHAR` 个像素字节
+pub const MAGIC_HIDING_BYTES: usize = MAGIC.len() * BYTES_PER_CHAR;
+
+/// `recover` 未指定 `--text` 时默认输出文件名的模板，参见 `--name-template`
+pub const DEFAULT_NAME_TEMPLATE: &str = "recovered_{stem}.{ext}";
+
+/// `recover --resync` 在预期位置之后最多向后扫描的像素字节数
+///
+/// 每个候选位置都要解码一次魔数，限制扫描范围可以避免在不含载荷的大图像上耗费过长时间
+pub const RESYNC_SCAN_BYTES: usize = 4096;
+
+/// 图像中未找到魔数时报告的错误信息
+pub const NO_PAYLOAD_MESSAGE: &str = "This image does not contain an lsb_hide payload";
+
+/// 隐藏或恢复的载荷为空时报告的信息
+///
+/// hide 默认只给出警告，指定 `--strict` 时作为错误拒绝
+pub const EMPTY_PAYLOAD_MESSAGE: &str = "The payload is empty";
+
+/// 当前的隐写格式版本
+///
+/// 头部布局或字段含义发生不兼容的变化时递增，旧版本的程序会拒绝无法理解的新格式，而不是读出错误的数据
+/// 版本 1 中，每个载荷字节占用的像素字节数完全由位深度决定 (参见 `bytes_per_char`)
+pub const FORMAT_VERSION: u8 = 1;
+
+/// 用于存储格式版本的字节数
+///
+/// 版本以默认位深度写入，占用 4 个像素字节 (8 bits)
+pub const VERSION_HIDING_BYTES: usize = 4;
+
+/// 格式版本字段在像素缓冲区中的起始偏移量 (紧随魔数之后)
+pub const VERSION_OFFSET: usize = MAGIC_HIDING_BYTES;
+
+/// 用于存储载荷位深度的字节数
+///
+/// 位深度以默认位深度 (`DEFAULT_BITS`) 写入，占用 4 个像素字节 (8 bits)
+pub const BITS_HIDING_BYTES: usize = 4;
+
+/// 位深度字段在像素缓冲区中的起始偏移量 (紧随格式版本字段之后)
+pub const BITS_OFFSET: usize = VERSION_OFFSET + VERSION_HIDING_BYTES;
+
+/// 用于存储载荷标志位的字节数
+///
+/// 标志位以默认位深度写入，占用 4 个像素字节 (8 bits)
+pub const FLAGS_HIDING_BYTES: usize = 4;
+
+/// 标志位字段在像素缓冲区中的起始偏移量 (紧随位深度字段之后)
+pub const FLAGS_OFFSET: usize = BITS_OFFSET + BITS_HIDING_BYTES;
+
+/// 标志位：载荷已使用口令加密
+pub const FLAG_ENCRYPTED: u8 = 0b0000_0001;
+
+/// 标志位：载荷按口令派生的伪随机排列分散在图像中
+pub const FLAG_SCATTERED: u8 = 0b0000_0010;
+
+/// 标志位：载荷是由多个文件打包而成的归档，恢复时需要解包到目录中
+pub const FLAG_ARCHIVE: u8 = 0b0000_0100;
+
+/// 标志位：头部在文件名之后记录了来源信息 (隐藏时的 Unix 时间戳与工具版本)
+///
+/// 来源信息是可选的，未设置该标志时不占用任何容量
+pub const FLAG_PROVENANCE: u8 = 0b0000_1000;
+
+/// 标志位：载荷长度以 `--length-bits` 指定的定长小端序编码记录，而不是 LEB128 变长编码
+pub const FLAG_FIXED_LENGTH: u8 = 0b0001_0000;
+
+/// 标志位：载荷重复写入了多份，头部记录了份数，恢复时逐位多数表决
+pub const FLAG_REDUNDANT: u8 = 0b0010_0000;
+
+/// 标志位：头部在冗余份数之后记录了 1 个字节的载荷类型标签 (文本、二进制、归档或已加密)
+///
+/// 恢复时据此选择默认的扩展名，并决定是否校验载荷为 UTF-8 文本
+pub const FLAG_TYPED: u8 = 0b0100_0000;
+
+/// 标志位：载荷不记录长度，而是以 `DELIMITER_START` 开始、以 `DELIMITER_END` 结束，恢复时扫描到结束标记为止
+///
+/// 头部的长度编码字节数记录为 0；载荷中的 `DELIMITER_ESCAPE` 字节会被转义，因此结束标记不会出现在载荷内部
+pub const FLAG_DELIMITED: u8 = 0b1000_0000;
+
+/// 分隔模式中的转义字节：载荷中的该字节写为 `DELIMITER_ESCAPE, DELIMITER_ESCAPED`，
+/// 标记则由它与一个不同的第二字节组成
+pub const DELIMITER_ESCAPE: u8 = 0xDB;
+
+/// 分隔模式中跟在 `DELIMITER_ESCAPE` 之后、表示一个字面量 `DELIMITER_ESCAPE` 字节的第二字节
+pub const DELIMITER_ESCAPED: u8 = 0xDD;
+
+/// 分隔模式中紧跟在头部之后的起始标记
+pub const DELIMITER_START: [u8; 2] = [DELIMITER_ESCAPE, 0xDE];
+
+/// 分隔模式中紧跟在载荷之后的结束标记
+pub const DELIMITER_END: [u8; 2] = [DELIMITER_ESCAPE, 0xDC];
+
+/// `--redundancy` 允许的最大份数
+pub const MAX_REDUNDANCY: u8 = 9;
+
+/// 长度备份的标记，写在像素缓冲区末尾的长度备份开头，用于识别备份是否存在
+///
+/// 标志位已全部占用，因此备份不在头部中登记，而是由该标记自我标识
+pub const LENGTH_BACKUP_MAGIC: [u8; 4] = *b"LSBL";
+
+/// 长度备份 (标记 + 小端序 `u64` 载荷长度) 在像素缓冲区末尾占用的像素字节数
+///
+/// 以默认位深度写入，每个字节占用 `BYTES_PER_CHAR` 个像素字节
+pub const LENGTH_BACKUP_BYTES: usize = (LENGTH_BACKUP_MAGIC.len() + 8) * BYTES_PER_CHAR;
+
+/// `--edge-adaptive` 判定像素字节处于纹理丰富区域的梯度阈值
+///
+/// 梯度为该字节与同一通道左右相邻像素字节之差的绝对值之和，只比较隐藏时不会被修改的高位；
+/// 低于该值的平滑区域不承载载荷，因为那里的最低有效位变化更容易被察觉
+pub const EDGE_GRADIENT_THRESHOLD: u16 = 32;
+
+/// `--length-bits` 支持的定长长度字段位数
+pub const LENGTH_BITS_CHOICES: [u8; 3] = [16, 32, 64];
+
+/// 来源信息中 Unix 时间戳 (秒) 占用的字节数，按小端序记录
+pub const TIMESTAMP_LEN: usize = 8;
+
+/// 用于存储载荷压缩算法编号的字节数
+///
+/// 编号以默认位深度写入，占用 4 个像素字节 (8 bits)：低 `TEXT_ENCODING_SHIFT` 位为压缩算法编号，`0` 表示未压缩；
+/// 其余高位记录文本载荷的原始编码编号 (参见 `text_encoding` 模块)，`0` 表示未记录
+pub const COMPRESSION_HIDING_BYTES: usize = 4;
+
+/// 压缩算法字段中文本编码编号所在的最低位；压缩算法编号只占用其下的 2 位
+pub const TEXT_ENCODING_SHIFT: u8 = 2;
+
+/// 压缩算法字段在像素缓冲区中的起始偏移量 (紧随标志位字段之后)
+pub const COMPRESSION_OFFSET: usize = FLAGS_OFFSET + FLAGS_HIDING_BYTES;
+
+/// 用于存储载荷通道掩码的字节数
+///
+/// 掩码以默认位深度写入，占用 4 个像素字节 (8 bits)，第 `i` 位代表每个像素中的第 `i` 个通道字节
+pub const CHANNELS_HIDING_BYTES: usize = 4;
+
+/// 通道掩码字段在像素缓冲区中的起始偏移量 (紧随压缩算法字段之后)
+pub const CHANNELS_OFFSET: usize = COMPRESSION_OFFSET + COMPRESSION_HIDING_BYTES;
+
+/// 用于存储载荷位平面的字节数
+///
+/// 位平面以默认位深度写入，占用 4 个像素字节 (8 bits)：低 `SPACING_SHIFT` 位表示载荷使用的最低一位在像素字节中的位置，
+/// 其余高位记录载荷像素字节的间隔减 1 (参见 `MAX_SPACING`)
+pub const PLANE_HIDING_BYTES: usize = 4;
+
+/// 位平面字段中间隔所在的最低位；位平面本身不超过 `MAX_PLANE`，只占用其下的 3 位
+pub const SPACING_SHIFT: u8 = 3;
+
+/// 载荷像素字节的最大间隔
+///
+/// 间隔为 `n` 时载荷只使用所选通道中每 `n` 个像素字节中的第一个，其余字节保持不变；
+/// 间隔减 1 记录在位平面字段的高 5 位中，因此最大为 32
+pub const MAX_SPACING: usize = 1 << (8 - SPACING_SHIFT);
+
+/// 位平面字段在像素缓冲区中的起始偏移量 (紧随通道掩码字段之后)
+pub const PLANE_OFFSET: usize = CHANNELS_OFFSET + CHANNELS_HIDING_BYTES;
+
+/// 用于存储载荷校验和的字节数
+///
+/// 校验和是对图像中实际隐藏的载荷字节 (即压缩、加密之后的数据) 计算的 CRC32 (IEEE) 值，
+/// 以默认位深度按小端序写入，占用 16 个像素字节 (32 bits)
+pub const CHECKSUM_HIDING_BYTES: usize = 16;
+
+/// 校验和字段在像素缓冲区中的起始偏移量 (紧随位平面字段之后)
+pub const CHECKSUM_OFFSET: usize = PLANE_OFFSET + PLANE_HIDING_BYTES;
+
+/// 恢复出的载荷与头部记录的校验和不一致时报告的错误信息
+pub const CHECKSUM_MISMATCH_MESSAGE: &str =
+    "Integrity check failed: recovered data is corrupted (CRC mismatch)";
+
+/// 长度字段在像素缓冲区中的起始偏移量 (紧随校验和字段之后)
+///
+/// 长度字段是头部的最后一个字段，其大小随载荷长度变化：
+/// 1. `LENGTH_PREFIX_HIDING_BYTES` 个字节：以默认位深度记录长度编码的字节数 (1 到 10)
+/// 2. 随后每个字节占用 `BYTES_PER_CHAR` 个像素字节：以默认位深度记录的 LEB128 变长编码长度
+pub const LENGTH_OFFSET: usize = CHECKSUM_OFFSET + CHECKSUM_HIDING_BYTES;
+
+/// 用于存储长度编码字节数的像素字节数
+///
+/// 以默认位深度写入，占用 2 个像素字节 (4 bits)，足以表示最多 10 个字节的 `u64` 变长编码
+pub const LENGTH_PREFIX_HIDING_BYTES: usize = 2;
+
+/// 用于存储文件名字节数的像素字节数
+///
+/// 文件名字段紧随长度字段之后：先以默认位深度用一个字节记录文件名的字节数 (0 表示未知)，
+/// 随后每个文件名字节占用 `BYTES_PER_CHAR` 个像素字节
+pub const NAME_LENGTH_HIDING_BYTES: usize = BYTES_PER_CHAR;
+
+/// 头部中可记录的文件名的最大字节数
+pub const MAX_NAME_LEN: usize = u8::MAX as usize;
+
+/// 隐写头部 (魔数 + 格式版本 + 位深度 + 标志位 + 压缩算法 + 通道掩码 + 位平面 + 校验和 + 长度 + 文件名) 占用的最小像素字节数
+///
+/// 即载荷长度不超过 127 字节、长度编码只占 1 个字节且不记录文件名时的头部大小
+/// 载荷或文件名更长时头部随之增长，载荷内容总是紧随完整的头部之后写入
+pub const HEADER_BYTES: usize =
+    LENGTH_OFFSET + LENGTH_PREFIX_HIDING_BYTES + BYTES_PER_CHAR + NAME_LENGTH_HIDING_BYTES;
+
+/// 有损图像格式的文件扩展名 (小写)
+///
+/// 有损压缩会破坏像素的最低有效位，写入这些格式后隐藏的数据将无法恢复
+/// `image` 库只以无损方式编码 WebP，因此 `webp` 不在此列
+pub const LOSSY_EXTENSIONS: [&str; 4] = ["jpg", "jpeg", "jfif", "avif"];
+
+/// 已知的无损图像格式的文件扩展名 (小写)
+///
+/// 隐写结果默认只允许写入这些格式，其余格式需要显式指定 `--force-lossy`
+pub const LOSSLESS_EXTENSIONS: [&str; 7] = ["png", "bmp", "tif", "tiff", "webp", "tga", "qoi"];
+
+/// 默认情况下每个像素字节使用的最低有效位数
+///
+/// 头部字段始终使用该位深度，以便恢复时能够先读出载荷实际使用的位深度
+pub const DEFAULT_BITS: u8 = 2;
+
+/// 每个像素字节最多可使用的最低有效位数
+///
+/// 位数越多容量越大，但对图像的视觉影响也越明显
+pub const MAX_BITS: u8 = 4;
+
+/// 载荷可使用的最高位平面
+///
+/// 位平面 `p` 表示使用像素字节的第 `p` 位到第 `p + bits - 1` 位，`0` 即传统的最低有效位
+/// 实际可用的最高位平面还受位深度限制，数据窗口不能超出一个字节的 8 bits
+pub const MAX_PLANE: u8 = 7;
+
+/// 默认位深度下，用于存储单个字符（1 字节）所需的像素字节数
 ///
 /// 由于每个像素字节的 LSB 只能存储 2 bits，因此需要 4 个像素字节来存储一个完整的 8-bit 字符
 pub const BYTES_PER_CHAR: usize = 4;
 
-/// 位掩码，用于从像素字节中提取最低的两位 (LSB)
+/// 默认位深度下的位掩码，用于从像素字节中提取最低的两位 (LSB)
 ///
 /// 二进制表示为 `0b0000_0011`
 /// 在恢复数据时，通过与此掩码进行“与”操作来获取隐藏的数据位
 pub const LSB_MASK: u8 = 0x03;
 
-/// 位掩码，用于清除像素字节的最低两位，以便写入新的 LSB 数据
+/// 默认位深度下的位掩码，用于清除像素字节的最低两位，以便写入新的 LSB 数据
 ///
 /// 二进制表示为 `0b1111_1100`
 /// 在隐藏数据时，先与此掩码进行“与”操作，以确保 LSB 区域为零
 pub const DATA_MASK: u8 = 0xFC;
+
+/// 载荷达到此字节数时，hide 与 recover 才在终端中显示进度条
+///
+/// 较小的载荷瞬间即可完成，显示进度条只会造成闪烁
+pub const PROGRESS_THRESHOLD_BYTES: usize = 1024 * 1024;
+
+/// 隐写过程中报告进度的间隔 (载荷字节数)，避免逐字节回调带来的开销
+pub const PROGRESS_STEP_BYTES: usize = 64 * 1024;
+
+/// 设置为 `1` 时，即使标准输出不是终端也启用进度条，主要供测试使用
+pub const PROGRESS_ENV_VAR: &str = "LSB_HIDE_PROGRESS";
+
+/// `hide --split` 在输出目录中写出的清单文件名，记录每一段对应的输出图像、封面图像与字节数
+pub const SPLIT_MANIFEST_NAME: &str = "manifest.json";
+
+/// `hide --no-length-header` 写出的边车文件的扩展名，追加在输出图像的完整文件名之后
+///
+/// 边车文件记录无头部载荷的偏移量、长度与校验和，供 `recover --meta` 使用
+pub const SIDECAR_EXTENSION: &str = "meta";
+
+/// diff 写出的差异图像中，每个字节差值默认的放大倍数
+///
+/// 默认位深度下差值最大为 3，放大后约为 192，肉眼即可分辨
+pub const DEFAULT_DIFF_AMPLIFY: u8 = 64;
+
+/// hide 写出的图像与原始图像的文件大小相差超过该比例时发出警告
+///
+/// 例如由 BMP 转换为 PNG 时文件大小会明显变化，对比原图即可发现隐写图像
+pub const SIZE_CHANGE_WARNING_RATIO: f64 = 0.25;
+
+/// PNG 文件签名，之后紧跟 IHDR 数据块
+pub const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n'];
+
+/// PNG 文件中 IHDR 数据块的颜色类型字段的字节偏移量
+///
+/// 签名 (8) + 块长度 (4) + 块类型 (4) + 宽度 (4) + 高度 (4) + 位深度 (1)
+pub const PNG_COLOR_TYPE_OFFSET: usize = 25;
+
+/// PNG 中表示索引颜色 (调色板) 图像的颜色类型编号
+pub const PNG_INDEXED_COLOR_TYPE: u8 = 3;
+
+/// BMP 文件签名
+pub const BMP_SIGNATURE: [u8; 2] = *b"BM";
+
+/// 最常见的 BMP 文件头的字节数：14 字节的文件头加上 40 字节的 BITMAPINFOHEADER
+///
+/// `--raw-bmp` 从这里开始直接在文件字节中隐写，之前的字节原样保留
+pub const BMP_HEADER_SIZE: usize = 54;
+
+/// BMP 文件头中像素数据起始偏移量 (bfOffBits) 字段的字节偏移量
+pub const BMP_PIXEL_OFFSET_FIELD: usize = 10;
+
+/// BMP 信息头中压缩方式 (biCompression) 字段的字节偏移量，0 表示未压缩 (BI_RGB)
+pub const BMP_COMPRESSION_FIELD: usize = 30;
+
+/// `--preserve-metadata` 从原始 PNG 复制到输出图像的辅助数据块类型
+///
+/// 包括文本 (tEXt、zTXt、iTXt)、EXIF (eXIf)、修改时间 (tIME)、物理尺寸 (pHYs) 以及色彩管理 (iCCP、sRGB、gAMA、cHRM)；
+/// 这些数据块都可以出现在 IHDR 之后、图像数据之前，且不影响像素数据
+pub const PRESERVED_PNG_CHUNKS: [[u8; 4]; 10] = [
+    *b"tEXt", *b"zTXt", *b"iTXt", *b"eXIf", *b"tIME", *b"pHYs", *b"iCCP", *b"sRGB", *b"gAMA",
+    *b"cHRM",
+];
+
+/// 进程成功退出时的退出码
+pub const EXIT_SUCCESS: i32 = 0;
+
+/// 不属于以下任何一类的失败 (包括命令行参数错误) 的退出码
+pub const EXIT_FAILURE: i32 = 1;
+
+/// 图像空间不足以容纳载荷时的退出码
+pub const EXIT_NOT_ENOUGH_SPACE: i32 = 2;
+
+/// 图像中没有 lsb_hide 载荷 (魔数不匹配) 时的退出码
+pub const EXIT_NO_PAYLOAD: i32 = 3;
+
+/// 读写文件或标准输入输出失败时的退出码
+pub const EXIT_IO_ERROR: i32 = 4;
+
+/// 载荷存在但已损坏 (完整性校验失败、长度无效等) 时的退出码
+pub const EXIT_CORRUPTED: i32 = 5;
@@ -0,0 +1,136 @@
+//! # 载荷加密模块
+//!
+//! 使用 AES-256-GCM 对载荷进行认证加密，密钥由用户口令经 Argon2 派生
+//! 加密结果的布局为：`盐 (SALT_LEN) | 随机数 (NONCE_LEN) | 密文 + 认证标签`，
+//! 可整体作为载荷隐藏到图像中
+//!
+//! AES-GCM 的认证标签由 `aes-gcm` 在解密时以常数时间校验，恢复路径中没有其他需要比较的认证标签
+//! (参见 `test_decrypt_checks_authentication_tag`)
+
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use anyhow::Context;
+use argon2::Argon2;
+use rand::RngCore;
+
+/// Argon2 密钥派生所用盐的字节数
+pub const SALT_LEN: usize = 16;
+
+/// AES-GCM 随机数 (Nonce) 的字节数
+pub const NONCE_LEN: usize = 12;
+
+/// AES-GCM 认证标签的字节数
+pub const TAG_LEN: usize = 16;
+
+/// 解密失败时报告的错误信息
+///
+/// 口令错误与数据损坏在认证加密下无法区分，因此统一报告
+pub const DECRYPTION_FAILED_MESSAGE: &str = "Decryption failed: wrong password or corrupted data";
+
+/// 使用 Argon2 从口令 (`password`) 和盐 (`salt`) 派生 256 位 AES 密钥
+fn derive_key(password: &str, salt: &[u8]) -> anyhow::Result<Key<Aes256Gcm>> {
+    let mut key = Key::<Aes256Gcm>::default();
+    Argon2::default()
+        .hash_password_into(password.as_bytes(), salt, &mut key)
+        .map_err(|e| anyhow::anyhow!("Failed to derive encryption key: {}", e))?;
+    Ok(key)
+}
+
+/// 使用口令 (`password`) 加密载荷 (`plaintext`)
+///
+/// 每次调用都会生成新的随机盐和随机数，因此相同的输入会得到不同的输出
+///
+/// # Returns
+///
+/// 成功时返回 `盐 | 随机数 | 密文 + 认证标签`
+///
+/// # Errors
+///
+/// 如果密钥派生或加密失败，将返回错误
+pub fn encrypt(plaintext: &[u8], password: &str) -> anyhow::Result<Vec<u8>> {
+    let mut salt = [0u8; SALT_LEN];
+    let mut nonce = [0u8; NONCE_LEN];
+    rand::rng().fill_bytes(&mut salt);
+    rand::rng().fill_bytes(&mut nonce);
+
+    let cipher = Aes256Gcm::new(&derive_key(password, &salt)?);
+    let ciphertext = cipher
+        .encrypt(&Nonce::from(nonce), plaintext)
+        .map_err(|_| anyhow::anyhow!("Failed to encrypt the payload."))?;
+
+    let mut output = Vec::with_capacity(SALT_LEN + NONCE_LEN + ciphertext.len());
+    output.extend_from_slice(&salt);
+    output.extend_from_slice(&nonce);
+    output.extend_from_slice(&ciphertext);
+    Ok(output)
+}
+
+/// 使用口令 (`password`) 解密由 `encrypt` 生成的数据 (`data`)
+///
+/// # Errors
+///
+/// * 如果数据短于盐、随机数和认证标签的总长度，将返回错误
+/// * 如果口令错误或数据被篡改导致认证失败，将返回 `DECRYPTION_FAILED_MESSAGE`
+pub fn decrypt(data: &[u8], password: &str) -> anyhow::Result<Vec<u8>> {
+    anyhow::ensure!(
+        data.len() >= SALT_LEN + NONCE_LEN + TAG_LEN,
+        "Encrypted payload is too short ({} bytes).",
+        data.len()
+    );
+
+    let (salt, rest) = data.split_at(SALT_LEN);
+    let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+    let nonce: [u8; NONCE_LEN] = nonce.try_into().context("Invalid nonce length.")?;
+
+    let cipher = Aes256Gcm::new(&derive_key(password, salt)?);
+    cipher
+        .decrypt(&Nonce::from(nonce), ciphertext)
+        .map_err(|_| anyhow::anyhow!(DECRYPTION_FAILED_MESSAGE))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 测试使用正确口令可以解密出原始数据
+    #[test]
+    fn test_encrypt_and_decrypt_round_trip() {
+        let plaintext = b"Top secret message. \xe7\xa7\x98\xe5\xaf\x86";
+        let encrypted = encrypt(plaintext, "correct horse").expect("Failed to encrypt.");
+
+        assert_eq!(
+            encrypted.len(),
+            SALT_LEN + NONCE_LEN + plaintext.len() + TAG_LEN
+        );
+        assert_ne!(
+            &encrypted[SALT_LEN + NONCE_LEN..][..plaintext.len()],
+            plaintext
+        );
+
+        let decrypted = decrypt(&encrypted, "correct horse").expect("Failed to decrypt.");
+        assert_eq!(plaintext.as_slice(), decrypted.as_slice());
+    }
+
+    /// 测试认证标签正确时解密成功，标签被篡改时解密失败
+    #[test]
+    fn test_decrypt_checks_authentication_tag() {
+        let mut encrypted = encrypt(b"payload", "right").expect("Failed to encrypt.");
+        assert_eq!(decrypt(&encrypted, "right").unwrap(), b"payload");
+
+        let last = encrypted.len() - 1;
+        encrypted[last] ^= 1;
+        let err = decrypt(&encrypted, "right").expect_err("A wrong tag should fail.");
+        assert_eq!(err.to_string(), DECRYPTION_FAILED_MESSAGE);
+    }
+
+    /// 测试使用错误口令或截断数据时返回明确的错误
+    #[test]
+    fn test_decrypt_wrong_password_and_truncated_data() {
+        let encrypted = encrypt(b"payload", "right").expect("Failed to encrypt.");
+
+        let err = decrypt(&encrypted, "wrong").expect_err("Wrong password should fail.");
+        assert_eq!(err.to_string(), DECRYPTION_FAILED_MESSAGE);
+
+        assert!(decrypt(&encrypted[..SALT_LEN + NONCE_LEN], "right").is_err());
+    }
+}
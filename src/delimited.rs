@@ -0,0 +1,144 @@
+//! # 分隔编码模块
+//!
+//! 为 `--delimited` 模式编码载荷：起始标记、转义后的载荷与结束标记依次排列，恢复时无需预先知道载荷长度
+//! 转义方式类似 SLIP：载荷中的每个 `DELIMITER_ESCAPE` 字节都写为 `DELIMITER_ESCAPE, DELIMITER_ESCAPED`，
+//! 因此转义后的载荷中 `DELIMITER_ESCAPE` 之后总是 `DELIMITER_ESCAPED`，不会与起始或结束标记混淆
+
+use crate::constants::{DELIMITER_END, DELIMITER_ESCAPE, DELIMITER_ESCAPED, DELIMITER_START};
+
+/// 计算 `payload` 编码后 (包括两个标记) 占用的字节数
+pub fn encoded_len(payload: &[u8]) -> usize {
+    let escapes = payload
+        .iter()
+        .filter(|&&byte| byte == DELIMITER_ESCAPE)
+        .count();
+    DELIMITER_START.len() + payload.len() + escapes + DELIMITER_END.len()
+}
+
+/// 计算 `len` 个字节的载荷编码后最多占用的字节数，即每个字节都需要转义时的长度
+pub fn max_encoded_len(len: u64) -> u64 {
+    len.saturating_mul(2)
+        .saturating_add((DELIMITER_START.len() + DELIMITER_END.len()) as u64)
+}
+
+/// 将 `payload` 编码为 起始标记 + 转义后的载荷 + 结束标记
+pub fn encode(payload: &[u8]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(encoded_len(payload));
+    bytes.extend_from_slice(&DELIMITER_START);
+    for &byte in payload {
+        bytes.push(byte);
+        if byte == DELIMITER_ESCAPE {
+            bytes.push(DELIMITER_ESCAPED);
+        }
+    }
+    bytes.extend_from_slice(&DELIMITER_END);
+    bytes
+}
+
+/// 从逐字节读取的序列 (`bytes`) 中解码载荷，读到结束标记即停止，不会继续消耗之后的字节
+///
+/// 返回解码出的载荷以及从序列中消耗的字节数 (包括两个标记)
+///
+/// # Errors
+///
+/// * 如果序列不以起始标记开头，或转义字节之后跟着未知的字节，将返回错误
+/// * 如果序列在结束标记之前结束，说明载荷被截断，将返回错误
+/// * 如果读取某个字节失败，将原样返回该错误
+pub fn decode(
+    mut bytes: impl Iterator<Item = anyhow::Result<u8>>,
+) -> anyhow::Result<(Vec<u8>, usize)> {
+    let mut next = || bytes.next().transpose();
+
+    let start = [next()?, next()?];
+    anyhow::ensure!(
+        start == DELIMITER_START.map(Some),
+        "The delimited payload does not begin with the start marker; the data appears to be corrupted."
+    );
+
+    let mut payload = Vec::new();
+    let mut consumed = DELIMITER_START.len();
+    loop {
+        let Some(byte) = next()? else {
+            anyhow::bail!(
+                "No end marker found after {} payload bytes; the delimited payload appears to be truncated.",
+                payload.len()
+            );
+        };
+        consumed += 1;
+        if byte != DELIMITER_ESCAPE {
+            payload.push(byte);
+            continue;
+        }
+
+        let escaped = next()?;
+        consumed += 1;
+        match escaped {
+            Some(DELIMITER_ESCAPED) => payload.push(DELIMITER_ESCAPE),
+            Some(second) if [DELIMITER_ESCAPE, second] == DELIMITER_END => {
+                return Ok((payload, consumed));
+            }
+            Some(second) => anyhow::bail!(
+                "Invalid escape sequence {:#04x} {:#04x} at byte {} of the delimited payload.",
+                DELIMITER_ESCAPE,
+                second,
+                consumed - 2
+            ),
+            None => anyhow::bail!(
+                "No end marker found after {} payload bytes; the delimited payload appears to be truncated.",
+                payload.len()
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 将字节切片转换为 `decode` 所需的逐字节序列
+    fn stream(bytes: &[u8]) -> impl Iterator<Item = anyhow::Result<u8>> + '_ {
+        bytes.iter().map(|&byte| Ok(byte))
+    }
+
+    /// 测试普通载荷、空载荷以及载荷中恰好出现标记字节时都能往返，且解码在结束标记处停止
+    #[test]
+    fn test_round_trip_with_marker_bytes_in_payload() {
+        let tricky = [
+            b"plain".to_vec(),
+            Vec::new(),
+            DELIMITER_END.to_vec(),
+            DELIMITER_START.to_vec(),
+            vec![DELIMITER_ESCAPE, DELIMITER_ESCAPED, DELIMITER_ESCAPE],
+            [b"a".as_slice(), &DELIMITER_END, b"b"].concat(),
+        ];
+        for payload in tricky {
+            let mut encoded = encode(&payload);
+            assert_eq!(encoded.len(), encoded_len(&payload));
+            let len = encoded.len();
+            encoded.extend_from_slice(b"trailing pixels");
+
+            let (decoded, consumed) = decode(stream(&encoded)).unwrap();
+            assert_eq!(decoded, payload);
+            assert_eq!(consumed, len);
+        }
+    }
+
+    /// 测试截断、缺少起始标记与未知的转义序列都会报错
+    #[test]
+    fn test_decode_rejects_invalid_input() {
+        let encoded = encode(b"truncated");
+        let err = decode(stream(&encoded[..encoded.len() - 1])).unwrap_err();
+        assert!(err.to_string().contains("truncated"), "{}", err);
+
+        let err = decode(stream(b"no start marker")).unwrap_err();
+        assert!(err.to_string().contains("start marker"), "{}", err);
+
+        let bad = [&DELIMITER_START[..], &[DELIMITER_ESCAPE, 0x00]].concat();
+        let err = decode(stream(&bad)).unwrap_err();
+        assert!(
+            err.to_string().contains("Invalid escape sequence"),
+            "{}",
+            err
+        );
+    }
+}
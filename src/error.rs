@@ -0,0 +1,258 @@
+//! # 错误类型模块
+//!
+//! 定义了核心隐写算法可能返回的结构化错误，便于库调用方按错误种类进行匹配
+//! 命令处理层仍然使用 `anyhow`，`StegError` 可以通过 `?` 自动转换，
+//! 也可以通过 `anyhow::Error::downcast_ref::<StegError>()` 取回
+//!
+//! 命令行程序按 [`exit_code`] 将错误映射为进程退出码，供脚本区分失败的种类：
+//!
+//! | 退出码 | 含义 |
+//! | --- | --- |
+//! | 0 | 成功 |
+//! | 1 | 其他失败，包括命令行参数错误、口令错误等 |
+//! | 2 | 图像空间不足 (`NotEnoughSpace`、`PayloadTooLarge`) |
+//! | 3 | 图像中没有载荷 (`MagicMismatch`) |
+//! | 4 | 读写文件或标准输入输出失败 (错误链中的 `std::io::Error`) |
+//! | 5 | 载荷已损坏 (`ChecksumMismatch`、`ByteOverflow`、`LengthExceedsCapacity`、`LengthCopiesDisagree`) |
+
+use crate::constants::{
+    CHECKSUM_MISMATCH_MESSAGE, EXIT_CORRUPTED, EXIT_FAILURE, EXIT_IO_ERROR, EXIT_NO_PAYLOAD,
+    EXIT_NOT_ENOUGH_SPACE, MAX_BITS, NO_PAYLOAD_MESSAGE,
+};
+use std::io;
+use thiserror::Error;
+
+/// 隐写算法的错误种类
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum StegError {
+    /// 隐写或恢复区域超出了像素缓冲区的边界
+    #[error("Steganography region out of bounds.\nstart: {start}, end: {end}, len: {len}")]
+    OutOfBounds {
+        /// 区域的起始索引
+        start: usize,
+        /// 区域的结束索引 (不含)
+        end: usize,
+        /// 像素缓冲区的长度
+        len: usize,
+    },
+
+    /// 计算区域结束索引时发生整数溢出
+    #[error("Integer overflow when calculating end index.\ndix: {dix}, size: {size}")]
+    Overflow {
+        /// 区域的起始索引
+        dix: usize,
+        /// 区域的字节数
+        size: usize,
+    },
+
+    /// 请求读写的位数超过了一个 `u64` 能容纳的 64 bits
+    #[error("Steganography size limit exceeded: {requested} bits (max 64 bits for a u64 value).")]
+    SizeLimitExceeded {
+        /// 请求读写的总位数
+        requested: usize,
+    },
+
+    /// 每个像素字节使用的位数不在支持的范围内
+    #[error("Unsupported bit depth: {0} (expected 1 to {MAX_BITS}).")]
+    UnsupportedBits(u8),
+
+    /// 位平面与位深度组合后的数据窗口超出了一个像素字节的 8 bits
+    #[error(
+        "Unsupported bit plane: {plane} ({bits} bits starting at bit {plane} do not fit in a byte)."
+    )]
+    UnsupportedPlane {
+        /// 数据窗口的最低位在像素字节中的位置
+        plane: u8,
+        /// 每个像素字节使用的位数
+        bits: u8,
+    },
+
+    /// 自定义的位掩码不一致：两个掩码必须互补，且数据窗口必须是连续的若干位
+    #[error(
+        "Invalid mask configuration: lsb_mask {lsb_mask:#010b} and data_mask {data_mask:#010b} must be complementary, and lsb_mask must be a single run of bits."
+    )]
+    InvalidMask {
+        /// 承载数据的位
+        lsb_mask: u8,
+        /// 保持不变的位
+        data_mask: u8,
+    },
+
+    /// 像素缓冲区比最小的隐写头部还要小，无法隐藏任何数据
+    #[error(
+        "Image too small to hold even the length header ({len} pixel bytes, at least {required} required)."
+    )]
+    ImageTooSmall {
+        /// 像素缓冲区的长度
+        len: usize,
+        /// 最小的隐写头部占用的像素字节数
+        required: usize,
+    },
+
+    /// 从像素字节中恢复的单个载荷字节超出了 `u8` 的范围，说明数据已损坏
+    ///
+    /// 当 8 不能被位深度整除时 (例如 3 bits/字节 时每个字节占用 9 bits)，多出的高位在正常数据中总是 0
+    #[error(
+        "Recovered value {value} at index {index} does not fit in a byte; the data appears to be corrupted."
+    )]
+    ByteOverflow {
+        /// 恢复出的原始值
+        value: u64,
+        /// 该字节在所恢复序列中的索引
+        index: usize,
+    },
+
+    /// 像素缓冲区没有足够的空间容纳头部和全部载荷
+    #[error(
+        "Not enough space in the image to hide the text. \nRequired: {required}, Available: {available}"
+    )]
+    NotEnoughSpace {
+        /// 载荷所需的像素字节数
+        required: usize,
+        /// 头部之后可用的像素字节数
+        available: usize,
+    },
+
+    /// 载荷所需的像素字节数超出了 `usize` 的范围 (例如 32 位平台上的超大载荷)，无法进行容量检查
+    #[error(
+        "The payload is too large: {len} bytes overflow the required pixel byte count on this platform."
+    )]
+    PayloadTooLarge {
+        /// 载荷的字节数
+        len: usize,
+    },
+
+    /// 头部记录的载荷长度超过了图像实际能够容纳的字节数，通常意味着头部已损坏
+    #[error("Recovered length {length} exceeds image capacity {capacity}.")]
+    LengthExceedsCapacity {
+        /// 头部记录的载荷字节数
+        length: u64,
+        /// 图像在该头部之后最多能容纳的载荷字节数
+        capacity: usize,
+    },
+
+    /// 头部记录的载荷长度 (或分隔模式下实际读到的载荷) 超过了调用方指定的上限 (`--payload-limit`)
+    #[error("Declared payload length exceeds --payload-limit ({limit} bytes).")]
+    PayloadLimitExceeded {
+        /// 允许提取的最大载荷字节数
+        limit: u64,
+    },
+
+    /// 头部记录的载荷长度与图像末尾的长度备份不一致，且两者都无法通过完整性校验
+    #[error(
+        "The payload length copies disagree (header: {header}, backup: {backup}) and neither passes the integrity check; the data is corrupted."
+    )]
+    LengthCopiesDisagree {
+        /// 头部记录的载荷字节数
+        header: u64,
+        /// 图像末尾的长度备份记录的载荷字节数
+        backup: u64,
+    },
+
+    /// 头部记录的格式版本无法被当前程序识别，通常由更新版本的 lsb_hide 写入
+    #[error("Unsupported lsb_hide format version {0}.")]
+    UnsupportedVersion(u8),
+
+    /// 像素缓冲区开头没有 lsb_hide 的魔数，即不包含隐藏的载荷
+    #[error("{NO_PAYLOAD_MESSAGE}")]
+    MagicMismatch,
+
+    /// 恢复出的载荷与头部记录的校验和不一致，说明图像被重新编码或部分损坏
+    #[error("{CHECKSUM_MISMATCH_MESSAGE}")]
+    ChecksumMismatch,
+}
+
+/// [`StegError`] 的种类，不携带任何附加数据
+///
+/// `StegError` 本身只包含整数字段，构造时不会分配内存，错误信息也只在显示时才格式化；
+/// `ErrorCode` 进一步省去了这些字段，只占一个字节且实现了 `Copy`，适合在紧密循环中逐字节调用
+/// [`try_modify`](crate::steganography::try_modify) 等函数、或需要稳定数字编号的调用方 (例如 FFI)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum ErrorCode {
+    /// 参见 [`StegError::OutOfBounds`]
+    OutOfBounds = 1,
+    /// 参见 [`StegError::Overflow`]
+    Overflow = 2,
+    /// 参见 [`StegError::SizeLimitExceeded`]
+    SizeLimitExceeded = 3,
+    /// 参见 [`StegError::UnsupportedBits`]
+    UnsupportedBits = 4,
+    /// 参见 [`StegError::UnsupportedPlane`]
+    UnsupportedPlane = 5,
+    /// 参见 [`StegError::ImageTooSmall`]
+    ImageTooSmall = 6,
+    /// 参见 [`StegError::ByteOverflow`]
+    ByteOverflow = 7,
+    /// 参见 [`StegError::NotEnoughSpace`]
+    NotEnoughSpace = 8,
+    /// 参见 [`StegError::PayloadTooLarge`]
+    PayloadTooLarge = 9,
+    /// 参见 [`StegError::LengthExceedsCapacity`]
+    LengthExceedsCapacity = 10,
+    /// 参见 [`StegError::PayloadLimitExceeded`]
+    PayloadLimitExceeded = 11,
+    /// 参见 [`StegError::LengthCopiesDisagree`]
+    LengthCopiesDisagree = 12,
+    /// 参见 [`StegError::UnsupportedVersion`]
+    UnsupportedVersion = 13,
+    /// 参见 [`StegError::MagicMismatch`]
+    MagicMismatch = 14,
+    /// 参见 [`StegError::ChecksumMismatch`]
+    ChecksumMismatch = 15,
+    /// 参见 [`StegError::InvalidMask`]
+    InvalidMask = 16,
+}
+
+impl StegError {
+    /// 该错误的种类，丢弃附加数据
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::OutOfBounds { .. } => ErrorCode::OutOfBounds,
+            Self::Overflow { .. } => ErrorCode::Overflow,
+            Self::SizeLimitExceeded { .. } => ErrorCode::SizeLimitExceeded,
+            Self::UnsupportedBits(_) => ErrorCode::UnsupportedBits,
+            Self::UnsupportedPlane { .. } => ErrorCode::UnsupportedPlane,
+            Self::ImageTooSmall { .. } => ErrorCode::ImageTooSmall,
+            Self::ByteOverflow { .. } => ErrorCode::ByteOverflow,
+            Self::NotEnoughSpace { .. } => ErrorCode::NotEnoughSpace,
+            Self::PayloadTooLarge { .. } => ErrorCode::PayloadTooLarge,
+            Self::LengthExceedsCapacity { .. } => ErrorCode::LengthExceedsCapacity,
+            Self::PayloadLimitExceeded { .. } => ErrorCode::PayloadLimitExceeded,
+            Self::LengthCopiesDisagree { .. } => ErrorCode::LengthCopiesDisagree,
+            Self::UnsupportedVersion(_) => ErrorCode::UnsupportedVersion,
+            Self::MagicMismatch => ErrorCode::MagicMismatch,
+            Self::ChecksumMismatch => ErrorCode::ChecksumMismatch,
+            Self::InvalidMask { .. } => ErrorCode::InvalidMask,
+        }
+    }
+
+    /// 该错误对应的进程退出码，映射关系参见模块文档
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Self::NotEnoughSpace { .. } | Self::PayloadTooLarge { .. } => EXIT_NOT_ENOUGH_SPACE,
+            Self::MagicMismatch => EXIT_NO_PAYLOAD,
+            Self::ChecksumMismatch
+            | Self::ByteOverflow { .. }
+            | Self::LengthExceedsCapacity { .. }
+            | Self::LengthCopiesDisagree { .. } => EXIT_CORRUPTED,
+            _ => EXIT_FAILURE,
+        }
+    }
+}
+
+/// 将命令处理层返回的错误映射为进程退出码，映射关系参见模块文档
+///
+/// 沿错误链查找：最先出现的 `StegError` 决定退出码，其次是 `std::io::Error`，都没有时为 `EXIT_FAILURE`
+pub fn exit_code(err: &anyhow::Error) -> i32 {
+    if let Some(err) = err
+        .chain()
+        .find_map(|cause| cause.downcast_ref::<StegError>())
+    {
+        return err.exit_code();
+    }
+    if err.chain().any(|cause| cause.is::<io::Error>()) {
+        return EXIT_IO_ERROR;
+    }
+    EXIT_FAILURE
+}
@@ -1,220 +1,3364 @@
 //! # 命令处理逻辑模块
 //!
-//! 包含处理 `hide` 和 `recover` 子命令的高级业务逻辑
+//! 包含处理 `hide`、`recover`、`capacity`、`info`、`verify`、`analyze`、`diff`、`clean` 和 `list-formats` 子命令的高级业务逻辑
 //! 本模块负责协调文件 I/O、调用核心隐写算法以及向用户报告结果
+//! 另外提供完全在内存中处理已编码图像文件的 [`hide_in_image_bytes`] 与 [`recover_from_image_bytes`]，供库调用方使用
 
-use crate::cli::{HideArgs, RecoverArgs};
-use crate::constants::{BYTES_PER_CHAR, LENGTH_HIDING_BYTES};
-use crate::steganography::{modify, recover};
+use crate::animation::Animation;
+use crate::cli::{
+    AnalyzeArgs, CapacityArgs, CleanArgs, CleanMode, DiffArgs, Encoding, HideArgs, InfoArgs,
+    ListFormatsArgs, PreviewArgs, RecoverArgs, VerifyArgs,
+};
+use crate::constants::{
+    BMP_COMPRESSION_FIELD, BMP_HEADER_SIZE, BMP_PIXEL_OFFSET_FIELD, BMP_SIGNATURE, DEFAULT_BITS,
+    DEFAULT_NAME_TEMPLATE, FLAG_ARCHIVE, FLAG_ENCRYPTED, LOSSLESS_EXTENSIONS, LOSSY_EXTENSIONS,
+    MAX_BITS, MAX_NAME_LEN, PNG_COLOR_TYPE_OFFSET, PNG_INDEXED_COLOR_TYPE, PNG_SIGNATURE,
+    RESYNC_SCAN_BYTES, SIZE_CHANGE_WARNING_RATIO, SPLIT_MANIFEST_NAME,
+};
+use crate::error::StegError;
+use crate::i18n::{Message, tr};
+use crate::progress::Progress;
+use crate::region::Region;
+use crate::scatter::SEED_LEN;
+use crate::segment::{self, Segment};
+use crate::sidecar::Sidecar;
+use crate::steganography::{
+    EmbedStats, Header, HideOptions, PayloadType, Provenance, RecoverOptions, StreamSummary,
+    append_bytes, ensure_length_fits, ensure_payload_limit, find_magic, has_magic, hide_bytes_with,
+    hide_bytes_with_progress, hide_capacity, hide_headerless, hide_stream_with_progress,
+    max_payload_bytes, read_header, read_length_backup, recover_headerless, recover_payload,
+    recover_payload_with_progress, recover_payload_with_stats, scan_stream,
+};
+use crate::style;
+use crate::{
+    analysis, archive, clipboard, compression, crypto, metadata, remote, scatter, text_encoding,
+    whiten,
+};
 use anyhow::Context;
-use colored::Colorize;
-use image::{DynamicImage, GenericImageView, ImageBuffer, Rgb, Rgba};
+use image::{
+    DynamicImage, GenericImageView, ImageBuffer, ImageFormat, ImageReader, Luma, LumaA, Rgb, Rgba,
+};
+use rand::RngCore;
+use serde_json::json;
 use std::fs;
+use std::io::{self, BufRead, Cursor, Read, Seek, Write};
+use std::path::{Path, PathBuf};
+
+/// 判断路径是否为代表标准输入/输出的 "-"
+fn is_stdio(path: &Path) -> bool {
+    path.as_os_str() == "-"
+}
+
+/// 读取 `--image` 指定的图像
+///
+/// 支持本地路径与 `file://` URL；启用 `remote` 特性时还支持 `http(s)://` URL，图像会被下载到内存中解码
+/// 本地图像的格式根据文件内容识别，因此以 `--output-format` 写出的任意扩展名的文件也能读取
+fn open_image(path: &Path) -> anyhow::Result<DynamicImage> {
+    read_image_bytes(path).and_then(|bytes| decode_image(path, &bytes))
+}
+
+/// 读取 `--image` 指定的图像文件的原始字节，远程图像会先被下载
+fn read_image_bytes(path: &Path) -> anyhow::Result<Vec<u8>> {
+    let bytes = match remote::remote_url(path) {
+        Some(url) => remote::fetch(url),
+        None => fs::read(remote::local_path(path)).map_err(anyhow::Error::from),
+    };
+    bytes.with_context(|| {
+        tr(
+            Message::UnreadableImage,
+            &[&style::error(path.to_string_lossy())],
+        )
+    })
+}
+
+/// 解码由 `read_image_bytes` 读取的图像字节
+///
+/// 格式优先根据文件内容识别，无法识别时 (如 TGA) 再根据扩展名判断
+///
+/// # Errors
+///
+/// * 如果文件内容与扩展名都无法确定图像格式 (例如误把文本文件当作 `--image`)，将返回列出支持格式的错误
+/// * 如果图像数据无法解码，将返回错误
+fn decode_image(path: &Path, bytes: &[u8]) -> anyhow::Result<DynamicImage> {
+    let mut reader = ImageReader::new(Cursor::new(bytes));
+    if let Ok(format) = ImageFormat::from_path(remote::local_path(path)) {
+        reader.set_format(format);
+    }
+    let reader = reader.with_guessed_format()?;
+    anyhow::ensure!(
+        reader.format().is_some(),
+        tr(
+            Message::UnrecognizedFormat,
+            &[
+                &style::error(path.to_string_lossy()),
+                &LOSSLESS_EXTENSIONS.join(", ")
+            ]
+        )
+    );
+    reader
+        .decode()
+        .map_err(anyhow::Error::from)
+        .with_context(|| {
+            tr(
+                Message::UnreadableImage,
+                &[&style::error(path.to_string_lossy())],
+            )
+        })
+}
+
+/// 将 `path` 的原始字节解码为 APNG 动画，供 `--frame` 使用
+///
+/// # Errors
+///
+/// 如果图像不是动画 PNG (例如普通 PNG 或其他格式)，将返回说明 `--frame` 只支持 APNG 的错误
+fn decode_animation(path: &Path, bytes: &[u8]) -> anyhow::Result<Animation> {
+    Animation::decode(bytes).with_context(|| {
+        tr(
+            Message::FrameRequiresApng,
+            &[&style::error(path.to_string_lossy())],
+        )
+    })
+}
+
+/// 判断图像字节是否为索引颜色 (调色板) PNG
+///
+/// `image` 库解码时会把调色板展开为 RGB(A)，解码后的图像无法再区分，因此直接检查 IHDR 中的颜色类型
+fn is_indexed_png(bytes: &[u8]) -> bool {
+    bytes.starts_with(&PNG_SIGNATURE)
+        && bytes.get(PNG_COLOR_TYPE_OFFSET) == Some(&PNG_INDEXED_COLOR_TYPE)
+}
+
+/// 检查 `bytes` 是否是像素数据紧跟在 `BMP_HEADER_SIZE` 字节文件头之后的未压缩 BMP，供 `--raw-bmp` 直接在文件字节中隐写
+///
+/// 调色板、位域掩码或更大的信息头都会把像素数据推后，在这些字节中隐写会破坏图像，因此一律拒绝
+fn ensure_raw_bmp(path: &Path, bytes: &[u8]) -> anyhow::Result<()> {
+    let field = |offset: usize| {
+        bytes
+            .get(offset..offset + 4)
+            .and_then(|field| field.try_into().ok())
+            .map(u32::from_le_bytes)
+    };
+    anyhow::ensure!(
+        bytes.starts_with(&BMP_SIGNATURE)
+            && bytes.len() > BMP_HEADER_SIZE
+            && field(BMP_PIXEL_OFFSET_FIELD) == Some(BMP_HEADER_SIZE as u32)
+            && field(BMP_COMPRESSION_FIELD) == Some(0),
+        tr(
+            Message::RawBmpLayout,
+            &[&style::error(path.to_string_lossy()), &BMP_HEADER_SIZE]
+        )
+    );
+    Ok(())
+}
+
+/// 在输入图像所在的目录中生成名为 `name` 的输出路径
+///
+/// 远程图像没有本地目录，输出到当前工作目录
+fn sibling_path(image: &Path, name: String) -> PathBuf {
+    match remote::remote_url(image) {
+        Some(_) => PathBuf::from(name),
+        None => remote::local_path(image).with_file_name(name),
+    }
+}
+
+/// 由口令或种子文件派生打散排列的种子，两者都未提供时返回 `None`
+///
+/// 种子文件的全部字节都作为密钥材料，与口令使用相同的派生方式
+///
+/// # Errors
+///
+/// 如果无法读取种子文件，或文件为空，将返回错误
+fn scatter_seed(
+    password: Option<&str>,
+    seed_file: Option<&Path>,
+) -> anyhow::Result<Option<[u8; SEED_LEN]>> {
+    if let Some(path) = seed_file {
+        let material = fs::read(path).with_context(|| {
+            tr(
+                Message::UnreadableSeedFile,
+                &[&style::error(path.to_string_lossy())],
+            )
+        })?;
+        anyhow::ensure!(
+            !material.is_empty(),
+            tr(
+                Message::EmptySeedFile,
+                &[&style::error(path.to_string_lossy())]
+            )
+        );
+        return Ok(Some(scatter::seed_from_bytes(&material)));
+    }
+    Ok(password.map(|password| scatter::seed_from_bytes(password.as_bytes())))
+}
+
+/// 读取要隐藏的载荷
+///
+/// 如果未提供路径或路径为 "-"，则以二进制安全的方式从标准输入读取全部数据，
+/// 否则读取指定文件的内容
+fn read_payload(path: Option<&Path>) -> anyhow::Result<Vec<u8>> {
+    match path {
+        Some(path) if !is_stdio(path) => fs::read(path).with_context(|| {
+            tr(
+                Message::UnreadableText,
+                &[&style::error(path.to_string_lossy())],
+            )
+        }),
+        _ => {
+            let mut buffer = Vec::new();
+            io::stdin()
+                .read_to_end(&mut buffer)
+                .with_context(|| tr(Message::UnreadableStdin, &[]))?;
+            Ok(buffer)
+        }
+    }
+}
+
+/// 读取要隐藏的全部载荷，并返回载荷是否为多文件归档
+///
+/// 未提供文件时从标准输入读取，提供单个文件时原样读取，
+/// 提供多个文件时将它们按文件名打包为一个归档
+fn read_payloads(paths: &[PathBuf]) -> anyhow::Result<(Vec<u8>, bool)> {
+    match paths {
+        [] => Ok((read_payload(None)?, false)),
+        [path] => Ok((read_payload(Some(path))?, false)),
+        _ => {
+            let entries = paths
+                .iter()
+                .map(|path| {
+                    anyhow::ensure!(!is_stdio(path), tr(Message::StdinWithFiles, &[]));
+                    let name = path
+                        .file_name()
+                        .and_then(|name| name.to_str())
+                        .with_context(|| {
+                            tr(
+                                Message::UnknownFileName,
+                                &[&style::error(path.to_string_lossy())],
+                            )
+                        })?;
+                    Ok(archive::Entry {
+                        name: name.to_string(),
+                        data: read_payload(Some(path))?,
+                    })
+                })
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            Ok((archive::pack(&entries)?, true))
+        }
+    }
+}
+
+/// `--chunk-size` 分块读取的载荷文件及其预先扫描的概要
+struct PayloadStream {
+    file: fs::File,
+    summary: StreamSummary,
+    chunk_size: usize,
+}
+
+/// 打开要分块隐藏的单个载荷文件，并分块扫描出其长度、校验和与是否为文本
+///
+/// # Errors
+///
+/// 如果没有恰好提供一个载荷文件 (标准输入无法读取两遍)，或无法读取该文件，将返回错误
+fn open_payload_stream(paths: &[PathBuf], chunk_size: usize) -> anyhow::Result<PayloadStream> {
+    let path = match paths {
+        [path] if !is_stdio(path) => path,
+        _ => anyhow::bail!(tr(Message::ChunkSizeNeedsFile, &[])),
+    };
+    let unreadable = || {
+        tr(
+            Message::UnreadableText,
+            &[&style::error(path.to_string_lossy())],
+        )
+    };
+    let mut file = fs::File::open(path).with_context(unreadable)?;
+    let summary = scan_stream(&mut file, chunk_size).with_context(unreadable)?;
+    Ok(PayloadStream {
+        file,
+        summary,
+        chunk_size,
+    })
+}
+
+/// 检查输出路径 (`path`) 是否可以写入：文件不存在、指定了 `--force`，或用户在终端中确认覆盖
+///
+/// 非交互环境 (`interactive` 为假) 中不会询问，文件已存在时直接返回错误
+fn ensure_overwritable(path: &Path, force: bool, interactive: bool) -> anyhow::Result<()> {
+    if force || !path.exists() {
+        return Ok(());
+    }
+    let confirmed = interactive
+        && confirm_overwrite(path, &mut io::stdin().lock(), &mut io::stderr())
+            .with_context(|| tr(Message::ConfirmationFailed, &[]))?;
+    anyhow::ensure!(
+        confirmed,
+        tr(
+            Message::OutputExists,
+            &[&style::warning(path.to_string_lossy())]
+        )
+    );
+    Ok(())
+}
+
+/// 在 `output` 上询问是否覆盖已存在的 `path`，并从 `input` 读取一行回答
+///
+/// 只有 `y` 或 `yes` (不区分大小写) 视为确认，空行或输入结束都视为拒绝
+fn confirm_overwrite(
+    path: &Path,
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+) -> io::Result<bool> {
+    write!(
+        output,
+        "{}",
+        tr(
+            Message::OverwritePrompt,
+            &[&style::warning(path.to_string_lossy())]
+        )
+    )?;
+    output.flush()?;
+    let mut answer = String::new();
+    input.read_line(&mut answer)?;
+    Ok(matches!(
+        answer.trim().to_ascii_lowercase().as_str(),
+        "y" | "yes"
+    ))
+}
+
+/// `recover --name-template` 指定的默认输出文件名模板
+struct NameTemplate<'a>(&'a str);
+
+impl<'a> NameTemplate<'a> {
+    /// 使用 `template`，未指定时使用 `DEFAULT_NAME_TEMPLATE`
+    ///
+    /// # Errors
+    ///
+    /// 如果模板不包含 `{stem}`，不同图像恢复出的文件会互相覆盖，将返回错误
+    fn new(template: Option<&'a str>) -> anyhow::Result<Self> {
+        let template = template.unwrap_or(DEFAULT_NAME_TEMPLATE);
+        anyhow::ensure!(
+            template.contains("{stem}"),
+            tr(Message::InvalidNameTemplate, &[&template])
+        );
+        Ok(Self(template))
+    }
+
+    /// 将 `{stem}` 替换为 `stem`、`{ext}` 替换为 `extension`；`extension` 为 `None` 时 (归档目录) 去掉 `.{ext}`
+    ///
+    /// 先替换扩展名再替换文件名，文件名中字面的 `{ext}` 不会被展开
+    fn expand(&self, stem: &str, extension: Option<&str>) -> String {
+        let name = match extension {
+            Some(ext) => self.0.replace("{ext}", ext),
+            None => self.0.replace(".{ext}", "").replace("{ext}", ""),
+        };
+        name.replace("{stem}", stem)
+    }
+}
+
+/// 将归档中的文件解包到目录 (`dir`) 中
+///
+/// 在写入任何文件之前先检查所有目标文件，避免只解包了一部分就因文件已存在而失败
+fn extract_archive(dir: &Path, entries: &[archive::Entry], force: bool) -> anyhow::Result<()> {
+    if let Some(existing) = entries
+        .iter()
+        .map(|entry| dir.join(&entry.name))
+        .find(|path| path.exists() && !force)
+    {
+        anyhow::bail!(tr(
+            Message::OutputExists,
+            &[&style::warning(existing.to_string_lossy())]
+        ));
+    }
+
+    fs::create_dir_all(dir).with_context(|| {
+        tr(
+            Message::CreateDirFailed,
+            &[&style::error(dir.to_string_lossy())],
+        )
+    })?;
+
+    entries.iter().try_for_each(|entry| {
+        let path = dir.join(&entry.name);
+        fs::write(&path, &entry.data).with_context(|| {
+            tr(
+                Message::WriteFileFailed,
+                &[&style::error(path.to_string_lossy())],
+            )
+        })
+    })
+}
+
+/// 确保输出路径 (`path`) 所在的目录存在
+///
+/// 目录不存在时，`mkdir` 为真则创建它，否则返回明确的错误，而不是留给写入时的底层 I/O 错误
+fn ensure_parent_dir(path: &Path, mkdir: bool) -> anyhow::Result<()> {
+    let Some(parent) = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    else {
+        return Ok(());
+    };
+    if parent.is_dir() {
+        return Ok(());
+    }
+    anyhow::ensure!(
+        mkdir,
+        tr(
+            Message::MissingDirectory,
+            &[&style::warning(parent.to_string_lossy())]
+        )
+    );
+    fs::create_dir_all(parent).with_context(|| {
+        tr(
+            Message::CreateDirFailed,
+            &[&style::error(parent.to_string_lossy())],
+        )
+    })
+}
+
+/// 校验输出路径的扩展名属于已知的无损图像格式 (PNG、BMP、TIFF、WebP、TGA、QOI)
+///
+/// 集中定义输出格式的策略，而不是依赖 `image::save` 按扩展名静默地选择编码器
+///
+/// # Errors
+///
+/// * 如果扩展名属于有损格式 (如 JPEG)，将返回错误
+/// * 如果路径没有扩展名，或扩展名不属于已知的无损格式，将返回错误
+pub fn validate_lossless_format(path: &Path) -> anyhow::Result<()> {
+    let ext = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(str::to_ascii_lowercase)
+        .unwrap_or_default();
+
+    if LOSSLESS_EXTENSIONS.contains(&ext.as_str()) {
+        return Ok(());
+    }
+
+    // 有损格式会破坏最低有效位，拒绝在写出后才发现数据已无法恢复
+    anyhow::ensure!(
+        !LOSSY_EXTENSIONS.contains(&ext.as_str()),
+        tr(
+            Message::LossyOutput,
+            &[&style::warning(path.to_string_lossy())]
+        )
+    );
+    anyhow::bail!(tr(
+        Message::UnsupportedOutput,
+        &[
+            &style::warning(path.to_string_lossy()),
+            &LOSSLESS_EXTENSIONS.join(", ")
+        ]
+    ))
+}
+
+/// 判断图像格式是否为已知的无损格式，即其扩展名属于 `LOSSLESS_EXTENSIONS`
+fn is_lossless_format(format: ImageFormat) -> bool {
+    format
+        .extensions_str()
+        .iter()
+        .any(|ext| LOSSLESS_EXTENSIONS.contains(ext))
+}
+
+/// 当前构建 (按启用的 `image` 特性) 能够解码的全部图像格式
+pub fn readable_formats() -> Vec<ImageFormat> {
+    ImageFormat::all()
+        .filter(|format| format.reading_enabled())
+        .collect()
+}
+
+/// 当前构建能够编码、且不会破坏最低有效位的无损输出格式
+///
+/// 有损格式 (如 JPEG) 即使可以写出，隐藏的数据也无法恢复，因此不在其中
+pub fn safe_output_formats() -> Vec<ImageFormat> {
+    ImageFormat::all()
+        .filter(|&format| format.writing_enabled() && is_lossless_format(format))
+        .collect()
+}
+
+/// 计算跳过前 `pixels` 个像素后，在每像素 `stride` 个字节、总长 `len` 的字节流中的起始偏移量
+fn pixel_offset(pixels: usize, stride: usize, len: usize) -> anyhow::Result<usize> {
+    pixels
+        .checked_mul(stride)
+        .filter(|&start| start <= len)
+        .with_context(|| tr(Message::OffsetOutOfRange, &[&pixels, &(len / stride)]))
+}
+
+/// 各种颜色类型的名称，按每个像素的通道字节数 (1 到 4) 排列
+const COLOR_TYPE_NAMES: [&str; 4] = ["L", "LA", "RGB", "RGBA"];
+
+/// 判断每个像素有 `stride` 个通道字节的图像是否带有 alpha 通道 (LA 或 RGBA)
+fn has_alpha(stride: usize) -> bool {
+    stride == 2 || stride == 4
+}
+
+/// 将图像拆解为 8 位像素字节流，并返回每个像素的通道字节数
+///
+/// 灰度 (L、LA)、RGB 与 RGBA 图像直接使用其原始字节，写出时颜色类型与位深度保持不变；
+/// 16 位图像使用每个样本的低字节，高字节由 [`high_bytes`] 单独保存，写出时原样拼回
+///
+/// # Errors
+///
+/// 如果图像是浮点颜色类型，隐写前必须先转换为 8 位或 16 位，将返回错误而不是静默转换
+fn into_pixel_bytes(img: DynamicImage) -> anyhow::Result<(Vec<u8>, usize)> {
+    match img {
+        DynamicImage::ImageLuma8(luma) => Ok((luma.into_raw(), 1)),
+        DynamicImage::ImageLumaA8(luma_alpha) => Ok((luma_alpha.into_raw(), 2)),
+        DynamicImage::ImageRgb8(rgb) => Ok((rgb.into_raw(), 3)),
+        DynamicImage::ImageRgba8(rgba) => Ok((rgba.into_raw(), 4)),
+        DynamicImage::ImageLuma16(luma) => Ok((low_bytes(luma.as_raw()), 1)),
+        DynamicImage::ImageLumaA16(luma_alpha) => Ok((low_bytes(luma_alpha.as_raw()), 2)),
+        DynamicImage::ImageRgb16(rgb) => Ok((low_bytes(rgb.as_raw()), 3)),
+        DynamicImage::ImageRgba16(rgba) => Ok((low_bytes(rgba.as_raw()), 4)),
+        other => anyhow::bail!(tr(
+            Message::UnsupportedColorType,
+            &[&format!("{:?}", other.color())]
+        )),
+    }
+}
+
+/// 取出每个 16 位样本的低字节，隐写只修改这一部分
+fn low_bytes(samples: &[u16]) -> Vec<u8> {
+    samples.iter().map(|&sample| sample as u8).collect()
+}
+
+/// 16 位图像中每个样本的高字节，8 位图像返回 `None`
+///
+/// 必须在 [`into_pixel_bytes`] 消耗图像之前调用，随后交给 [`from_pixel_bytes`] 恢复原始位深度
+fn high_bytes(img: &DynamicImage) -> Option<Vec<u8>> {
+    let samples: &[u16] = match img {
+        DynamicImage::ImageLuma16(luma) => luma.as_raw(),
+        DynamicImage::ImageLumaA16(luma_alpha) => luma_alpha.as_raw(),
+        DynamicImage::ImageRgb16(rgb) => rgb.as_raw(),
+        DynamicImage::ImageRgba16(rgba) => rgba.as_raw(),
+        _ => return None,
+    };
+    Some(samples.iter().map(|&sample| (sample >> 8) as u8).collect())
+}
+
+/// 由修改后的像素字节流重建图像，颜色类型由每个像素的通道字节数 (`stride`) 决定
+///
+/// 提供了 `high` 时，每个字节作为低字节与对应的高字节拼成 16 位样本，重建出 16 位图像
+///
+/// # Errors
+///
+/// * 如果 `bytes` 或 `high` 的长度不等于 `width * height * stride`，将返回报告预期与实际字节数的错误
+/// * 如果无法创建图像缓冲区，将返回错误
+fn from_pixel_bytes(
+    width: u32,
+    height: u32,
+    bytes: Vec<u8>,
+    stride: usize,
+    high: Option<Vec<u8>>,
+) -> anyhow::Result<DynamicImage> {
+    // 字节流总是由本模块按图像尺寸产生，长度不符说明通道处理有误
+    // 在这里报告具体的字节数，而不是等 `from_raw` 返回 `None`；高字节的长度同样要核对，否则拼接时会被静默截断
+    let color_type = match high {
+        Some(_) => format!("{}16", COLOR_TYPE_NAMES[stride - 1]),
+        None => COLOR_TYPE_NAMES[stride - 1].to_string(),
+    };
+    let expected = u64::from(width) * u64::from(height) * stride as u64;
+    for len in [Some(bytes.len()), high.as_ref().map(Vec::len)]
+        .into_iter()
+        .flatten()
+    {
+        anyhow::ensure!(
+            len as u64 == expected,
+            tr(
+                Message::PixelBufferMismatch,
+                &[&len, &width, &height, &color_type, &expected]
+            )
+        );
+    }
+
+    if let Some(high) = high {
+        let samples: Vec<u16> = high
+            .iter()
+            .zip(&bytes)
+            .map(|(&high, &low)| u16::from_be_bytes([high, low]))
+            .collect();
+        let img = match stride {
+            1 => ImageBuffer::<Luma<u16>, _>::from_raw(width, height, samples)
+                .map(DynamicImage::ImageLuma16),
+            2 => ImageBuffer::<LumaA<u16>, _>::from_raw(width, height, samples)
+                .map(DynamicImage::ImageLumaA16),
+            3 => ImageBuffer::<Rgb<u16>, _>::from_raw(width, height, samples)
+                .map(DynamicImage::ImageRgb16),
+            _ => ImageBuffer::<Rgba<u16>, _>::from_raw(width, height, samples)
+                .map(DynamicImage::ImageRgba16),
+        };
+        return img.with_context(|| tr(Message::ImageBufferFailed, &[&color_type]));
+    }
+
+    let img = match stride {
+        1 => {
+            ImageBuffer::<Luma<u8>, _>::from_raw(width, height, bytes).map(DynamicImage::ImageLuma8)
+        }
+        2 => ImageBuffer::<LumaA<u8>, _>::from_raw(width, height, bytes)
+            .map(DynamicImage::ImageLumaA8),
+        3 => ImageBuffer::<Rgb<u8>, _>::from_raw(width, height, bytes).map(DynamicImage::ImageRgb8),
+        _ => {
+            ImageBuffer::<Rgba<u8>, _>::from_raw(width, height, bytes).map(DynamicImage::ImageRgba8)
+        }
+    };
+    img.with_context(|| tr(Message::ImageBufferFailed, &[&color_type]))
+}
+
+/// 列出每像素 `stride` 个字节的字节流中 alpha 不低于 `threshold` 的像素序号
+///
+/// 阈值为 0 时包含全部像素。alpha 通道从不被修改，因此恢复时由相同的阈值总能重建出相同的序列
+fn opaque_pixels(pixels: &[u8], stride: usize, threshold: u8) -> Vec<usize> {
+    pixels
+        .chunks_exact(stride)
+        .enumerate()
+        .filter(|(_, pixel)| pixel[stride - 1] >= threshold)
+        .map(|(index, _)| index)
+        .collect()
+}
+
+/// 计算跳过前 `pixels` 个像素后，在只含 `opaque` 中像素颜色字节的字节流中的起始偏移量
+fn color_offset(
+    pixels: usize,
+    stride: usize,
+    len: usize,
+    opaque: &[usize],
+) -> anyhow::Result<usize> {
+    pixel_offset(pixels, stride, len)?;
+    Ok(opaque.partition_point(|&pixel| pixel < pixels) * (stride - 1))
+}
+
+/// 将只含 `opaque` 中像素颜色字节的字节流中的区域 `start..end` 换算为完整字节流 (每像素 `stride` 个字节) 中的对应区域
+fn color_range_to_full(
+    start: usize,
+    end: usize,
+    stride: usize,
+    opaque: &[usize],
+) -> (usize, usize) {
+    let colors = stride - 1;
+    let to_full = |index: usize| opaque[index / colors] * stride + index % colors;
+    (to_full(start), to_full(end.saturating_sub(1)) + 1)
+}
+
+/// 从每像素 `stride` 个字节的字节流中抽出 `opaque` 中像素的颜色字节，跳过每个像素最后的 alpha 通道
+fn strip_alpha(pixels: &[u8], stride: usize, opaque: &[usize]) -> Vec<u8> {
+    opaque
+        .iter()
+        .flat_map(|&pixel| &pixels[pixel * stride..(pixel + 1) * stride - 1])
+        .copied()
+        .collect()
+}
+
+/// 将颜色字节写回每像素 `stride` 个字节的字节流中 `opaque` 所列的像素，每个像素的 alpha 通道保持不变
+fn restore_alpha(pixels: &mut [u8], colors: &[u8], stride: usize, opaque: &[usize]) {
+    opaque
+        .iter()
+        .zip(colors.chunks_exact(stride - 1))
+        .for_each(|(&pixel, color)| {
+            pixels[pixel * stride..(pixel + 1) * stride - 1].copy_from_slice(color)
+        });
+}
+
+/// 从图像中读取、用于定位隐写数据的像素字节流
+struct StegoPixels {
+    /// 像素字节流，数据以 `--skip-alpha` 方式隐藏时已去除 alpha 通道
+    bytes: Vec<u8>,
+
+    /// 每个像素包含的通道字节数
+    stride: usize,
+
+    /// 跳过 `--offset` 个像素之后，头部在字节流中的起始偏移量
+    start: usize,
+
+    /// 数据是否以 `--skip-alpha` 方式隐藏
+    skipped_alpha: bool,
+
+    /// 以 `--skip-alpha` 方式隐藏时，参与隐写 (alpha 不低于 `--alpha-threshold`) 的像素序号
+    opaque: Vec<usize>,
+
+    /// 以 `--resync` 扫描时，魔数相对预期位置向后错开的字节数 (已计入 `start`)
+    shift: usize,
+}
+
+/// 读取图像，并定位跳过 `offset` 个像素之后的隐写头部；指定 `region` 时只在该区域内的像素字节中定位
+///
+/// 使用 `--skip-alpha` 隐藏的数据只存在于颜色字节中，魔数不会出现在带 alpha 通道的完整字节流里，
+/// 此时改为在去除 alpha 通道的字节流中定位；是否真的存在魔数由调用方检查
+///
+/// 指定了 `alpha_threshold` 时，数据必然以 `--skip-alpha` 方式隐藏，直接跳过 alpha 低于阈值的像素后定位
+///
+/// `resync` 大于 0 时，预期位置没有魔数则继续向后扫描至多 `resync` 个字节
+///
+/// # Errors
+///
+/// * 如果无法读取图像文件，或图像是浮点颜色类型，将返回错误
+/// * 如果偏移量超出图像的像素数，将返回错误
+/// * 如果指定了 `alpha_threshold`，但图像没有 alpha 通道，将返回错误
+fn open_stego_pixels(
+    path: &Path,
+    offset: usize,
+    resync: usize,
+    alpha_threshold: Option<u8>,
+    frame: Option<usize>,
+    region: Option<Region>,
+) -> anyhow::Result<StegoPixels> {
+    let (bytes, stride) = open_pixel_bytes(path, frame, region)?;
+    anyhow::ensure!(
+        alpha_threshold.is_none() || has_alpha(stride),
+        tr(
+            Message::AlphaThresholdNeedsAlpha,
+            &[&style::error(path.to_string_lossy())]
+        )
+    );
+    locate_stego_pixels(bytes, stride, offset, resync, alpha_threshold)
+}
+
+/// 读取图像 (指定 `frame` 时为动画的该帧)，按原始颜色类型转换为字节流，并返回每个像素的通道字节数
+///
+/// 指定 `region` 时只返回该区域内按行收集的像素字节
+fn open_pixel_bytes(
+    path: &Path,
+    frame: Option<usize>,
+    region: Option<Region>,
+) -> anyhow::Result<(Vec<u8>, usize)> {
+    let img = match frame {
+        Some(index) => {
+            let bytes = read_image_bytes(path)?;
+            DynamicImage::ImageRgba8(decode_animation(path, &bytes)?.frame(index)?.clone())
+        }
+        None => open_image(path)?,
+    };
+    let (width, height) = img.dimensions();
+    let (bytes, stride) = into_pixel_bytes(img)?;
+    match region {
+        Some(region) => {
+            region.ensure_within(width, height)?;
+            Ok((region.gather(&bytes, width, stride), stride))
+        }
+        None => Ok((bytes, stride)),
+    }
+}
+
+/// 读取不含隐写头部的图像，载荷从跳过 `offset` 个像素之后的位置开始，占用包括 alpha 在内的全部通道字节
+///
+/// # Errors
+///
+/// 参见 [`open_stego_pixels`]
+fn open_headerless_pixels(
+    path: &Path,
+    offset: usize,
+    frame: Option<usize>,
+    region: Option<Region>,
+) -> anyhow::Result<StegoPixels> {
+    let (bytes, stride) = open_pixel_bytes(path, frame, region)?;
+    let start = pixel_offset(offset, stride, bytes.len())?;
+    Ok(StegoPixels {
+        bytes,
+        stride,
+        start,
+        skipped_alpha: false,
+        opaque: Vec::new(),
+        shift: 0,
+    })
+}
+
+/// 以原始文件字节读取 `--raw-bmp` 写出的 BMP，在文件头之后的字节中定位跳过 `offset` 个字节之后的隐写头部
+///
+/// 原始字节不区分像素与通道 (包括行填充)，每个字节都视为一个单通道像素
+///
+/// # Errors
+///
+/// 如果无法读取图像文件，或文件不是像素数据紧跟在文件头之后的未压缩 BMP，将返回错误
+fn open_raw_bmp_pixels(path: &Path, offset: usize, resync: usize) -> anyhow::Result<StegoPixels> {
+    let mut bytes = read_image_bytes(path)?;
+    ensure_raw_bmp(path, &bytes)?;
+    let bytes = bytes.split_off(BMP_HEADER_SIZE);
+    locate_stego_pixels(bytes, 1, offset, resync, None)
+}
+
+/// 在每像素 `stride` 个字节的像素字节流 (`bytes`) 中定位跳过 `offset` 个像素之后的隐写头部，参见 [`open_stego_pixels`]
+fn locate_stego_pixels(
+    bytes: Vec<u8>,
+    stride: usize,
+    offset: usize,
+    resync: usize,
+    alpha_threshold: Option<u8>,
+) -> anyhow::Result<StegoPixels> {
+    let start = pixel_offset(offset, stride, bytes.len())?;
+    let found = match alpha_threshold {
+        Some(_) => None,
+        None => find_magic(&bytes[start..], resync),
+    };
+
+    if has_alpha(stride) && found.is_none() {
+        let opaque = opaque_pixels(&bytes, stride, alpha_threshold.unwrap_or(0));
+        let start = color_offset(offset, stride, bytes.len(), &opaque)?;
+        let bytes = strip_alpha(&bytes, stride, &opaque);
+        let shift = find_magic(&bytes[start..], resync).unwrap_or(0);
+        return Ok(StegoPixels {
+            bytes,
+            stride: stride - 1,
+            start: start + shift,
+            skipped_alpha: true,
+            opaque,
+            shift,
+        });
+    }
+
+    let shift = found.unwrap_or(0);
+    Ok(StegoPixels {
+        bytes,
+        stride,
+        start: start + shift,
+        skipped_alpha: false,
+        opaque: Vec::new(),
+        shift,
+    })
+}
 
 /// 处理 'Hide' 命令的执行逻辑
 ///
-/// 负责读取图像和文本文件、检查隐写空间是否足够、调用隐写核心函数隐藏长度和字符，
-/// 最后将结果写入目标图像文件
+/// 负责读取图像和文本文件 (多个文件时打包为归档)、按需压缩并加密载荷、调用 `hide_bytes_with` 检查隐写空间并隐藏长度和字符，
+/// 最后将结果写入目标图像文件
+///
+/// # Arguments
+///
+/// * `args` - 包含输入/输出路径的 `HideArgs` 结构体
+///
+/// # Errors
+///
+/// 如果发生以下任一情况，将返回错误：
+/// * 写入路径文件已存在，且没有 `--force` 或 `--dry-run` 标识，用户也没有在终端中确认覆盖
+/// * 写入路径所在的目录不存在，且没有 `--mkdir` 标识
+/// * 指定了 `--in-place` 但没有 `--force` 确认，或原始图像是远程 URL
+/// * 指定了 `--image-dir` 但无法读取该目录，或其中没有任何图像放得下载荷 (`--split` 时为所有图像合计也放不下)
+/// * 写入路径不是已知的无损图像格式 (如 JPEG)，且没有 `--force-lossy` 或 `--output-format` 标识
+/// * 无法读取输入的图像或文本文件 (或标准输入)
+/// * 图像是浮点颜色类型，需要先转换为 8 位或 16 位灰度、RGB 或 RGBA
+/// * 提供了口令但加密失败
+/// * 图像文件没有足够的空间来隐藏文本
+/// * 核心隐写函数 (`hide_bytes_with`) 在执行过程中失败
+/// * 无法写入到目标图像文件
+pub fn handle_hide(args: HideArgs) -> anyhow::Result<()> {
+    // 指定 `--chunk-size` 时不把载荷文件读入内存，只预先分块扫描出长度与校验和，隐藏时再从头分块读取
+    // 载荷先于图像读取：使用 `--image-dir` 时需要根据载荷的长度挑选封面图像
+    let mut stream = args
+        .chunk_size
+        .map(|chunk_size| open_payload_stream(&args.text, chunk_size))
+        .transpose()?;
+    let (text, is_archive) = match (&args.message, &stream) {
+        (_, Some(_)) => (Vec::new(), false),
+        (Some(message), None) => (message.as_bytes().to_vec(), false),
+        (None, None) => read_payloads(&args.text)?,
+    };
+    let (text_len, is_text) = match &stream {
+        Some(stream) => (stream.summary.len, stream.summary.utf8),
+        None => (text.len() as u64, std::str::from_utf8(&text).is_ok()),
+    };
+
+    // 指定了原始编码的文本按原始字节隐藏，只在头部中记录编码，恢复时再转换
+    if let Some(encoding) = args.input_encoding {
+        anyhow::ensure!(!is_archive, tr(Message::InputEncodingArchive, &[]));
+        anyhow::ensure!(
+            text_encoding::is_valid(&text, encoding),
+            tr(
+                Message::InvalidInputEncoding,
+                &[&style::error(encoding.name())]
+            )
+        );
+    }
+    let is_text = is_text || args.input_encoding.is_some();
+
+    // 空载荷虽然可以隐藏，但通常意味着输入有误
+    if text_len == 0 {
+        let empty = tr(Message::EmptyPayload, &[]);
+        anyhow::ensure!(!args.strict, tr(Message::EmptyPayloadStrict, &[&empty]));
+        eprintln!(
+            "{} {}",
+            style::warning(tr(Message::Warning, &[])),
+            tr(Message::EmptyHideWarning, &[&empty])
+        );
+    }
+
+    // 单个文件时记录其文件名，供恢复时选择默认的输出路径；归档中已包含各自的文件名
+    let name = match args.text.as_slice() {
+        [path] if !is_stdio(path) => path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .filter(|name| name.len() <= MAX_NAME_LEN)
+            .unwrap_or_default()
+            .to_string(),
+        _ => String::new(),
+    };
+
+    // 如果提供了口令，先对载荷进行加密，并在头部中标记
+    // 打散模式下，嵌入位置的排列种子同样由口令派生
+    // 每像素的通道字节数要等读取图像后才能确定
+    let mut options = HideOptions {
+        bits: args.bits,
+        plane: args.plane,
+        channels: args.channels,
+        scatter_seed: if args.scatter {
+            scatter_seed(args.password.as_deref(), args.seed_file.as_deref())?
+        } else {
+            None
+        },
+        name,
+        provenance: args.provenance.then(Provenance::now),
+        length_bits: args.length_bits,
+        redundancy: args.redundancy,
+        delimited: args.delimited,
+        auto_bits: args.auto_bits,
+        length_backup: args.length_backup,
+        spacing: args.spacing,
+        edge_adaptive: args.edge_adaptive,
+        endian: args.endian,
+        text_encoding: args.input_encoding,
+        ..Default::default()
+    };
+    if is_archive {
+        options.flags |= FLAG_ARCHIVE;
+    }
+
+    // 类型标签描述明文；加密或白化时只记录 `Encrypted` 或 `Whitened`，不透露明文是否为文本
+    // 标志位已全部占用，白化只能由类型标签记录，因此它优先于归档 (归档另有 `FLAG_ARCHIVE` 标记)
+    options.payload_type = Some(if args.xor_key.is_some() {
+        PayloadType::Whitened
+    } else if is_archive {
+        PayloadType::Archive
+    } else if args.password.is_some() {
+        PayloadType::Encrypted
+    } else if args.binary || !is_text {
+        PayloadType::Binary
+    } else {
+        PayloadType::Text
+    });
+
+    // 先压缩再加密：密文几乎不可压缩，反过来做没有意义
+    let (compression, compressed) = compression::compress_if_smaller(args.compress, &text)?;
+    options.compression = compression;
+
+    // 加密与白化不能同时使用，白化同样在压缩之后进行
+    let payload = match (&args.password, &args.xor_key) {
+        (Some(password), _) => {
+            options.flags |= FLAG_ENCRYPTED;
+            crypto::encrypt(&compressed, password)?
+        }
+        (None, Some(key)) => whiten::apply(&compressed, key),
+        (None, None) => compressed,
+    };
+    let total = stream
+        .as_ref()
+        .map_or(payload.len(), |stream| stream.summary.len as usize);
+
+    // 指定 `--split` 时把载荷拆分到 `--image-dir` 中的多张图像，写出编号的结果图像与清单
+    if args.split {
+        return hide_segments(&args, &payload, options);
+    }
+
+    // 未指定 `--image` 时，从 `--image-dir` 中挑选容量足够且最小的封面图像
+    let image = match (args.image.clone(), &args.image_dir) {
+        (Some(image), _) => image,
+        (None, Some(dir)) => {
+            let image = select_cover(dir, total, &options, &args)?;
+            if !args.json && !args.quiet {
+                println!(
+                    "{}",
+                    tr(
+                        Message::CoverSelected,
+                        &[&style::success(image.to_string_lossy())]
+                    )
+                );
+            }
+            image
+        }
+        (None, None) => anyhow::bail!(tr(Message::MissingCoverImage, &[])),
+    };
+
+    // 原地隐写时覆盖原始图像，这一操作无法撤销，必须显式确认
+    if args.in_place {
+        anyhow::ensure!(
+            remote::remote_url(&image).is_none(),
+            tr(
+                Message::RemoteInPlace,
+                &[&style::warning(image.to_string_lossy())]
+            )
+        );
+        anyhow::ensure!(
+            args.force || args.dry_run,
+            tr(
+                Message::InPlaceNeedsForce,
+                &[&style::warning(image.to_string_lossy())]
+            )
+        );
+    }
+
+    // 先读取源图像：输入不是图像时，报告这一点比报告由它派生的默认输出路径更有帮助
+    // 调色板 PNG 会被展开为真彩色后写出，编码与文件大小都会明显改变，因此直接拒绝而不是静默转换
+    let mut image_bytes = read_image_bytes(&image)?;
+    let input_len = image_bytes.len() as u64;
+    anyhow::ensure!(
+        !is_indexed_png(&image_bytes),
+        tr(
+            Message::IndexedPng,
+            &[&style::error(image.to_string_lossy())]
+        )
+    );
+
+    // 指定 `--frame` 时只在动画的这一帧中隐藏数据，其余帧在写出时原样保留
+    let mut animation = match args.frame {
+        Some(_) => Some(decode_animation(&image, &image_bytes)?),
+        None => None,
+    };
+    let img = match (&animation, args.frame) {
+        (Some(animation), Some(index)) => DynamicImage::ImageRgba8(animation.frame(index)?.clone()),
+        _ => decode_image(&image, &image_bytes)?,
+    };
+
+    // 如果用户没有提供输出路径，则动态生成一个默认路径；原地隐写时即为原始图像
+    let dest_path = args.dest.unwrap_or_else(|| {
+        if args.in_place {
+            return remote::local_path(&image);
+        }
+        let original_filename = image
+            .file_name()
+            .and_then(|s| s.to_str())
+            .unwrap_or("output");
+        let new_filename = format!("doctored_{}", original_filename);
+        sibling_path(&image, new_filename)
+    });
+
+    // 只有无损格式能保留最低有效位，其余格式需要显式确认
+    // 显式指定的输出格式都是无损的，此时扩展名无关紧要
+    if !args.force_lossy && args.output_format.is_none() {
+        validate_lossless_format(&dest_path)?;
+    }
+
+    // 动画只能重新编码为 APNG，元数据块也只能在 PNG 之间复制
+    let format = match args.output_format {
+        Some(format) => Some(format.into()),
+        None => ImageFormat::from_path(&dest_path).ok(),
+    };
+    if args.preserve_metadata {
+        anyhow::ensure!(
+            image_bytes.starts_with(&PNG_SIGNATURE) && format == Some(ImageFormat::Png),
+            tr(
+                Message::PreserveMetadataPng,
+                &[&style::warning(dest_path.to_string_lossy())]
+            )
+        );
+    }
+    if args.raw_bmp {
+        anyhow::ensure!(
+            format == Some(ImageFormat::Bmp),
+            tr(
+                Message::RawBmpOutput,
+                &[&style::warning(dest_path.to_string_lossy())]
+            )
+        );
+    }
+    if animation.is_some() {
+        anyhow::ensure!(
+            format == Some(ImageFormat::Png),
+            tr(
+                Message::FrameOutputPng,
+                &[&style::warning(dest_path.to_string_lossy())]
+            )
+        );
+    }
+
+    // 无头部隐写时在结果图像旁另外写出边车文件，记录恢复所需的偏移量、长度与校验和
+    let meta_path = args.no_length_header.then(|| {
+        args.meta
+            .clone()
+            .unwrap_or_else(|| Sidecar::path_for(&dest_path))
+    });
+
+    // 在写入前检查文件是否存在，防止意外覆盖；试运行不会写入，因此无需检查
+    if !args.dry_run {
+        for path in [Some(&dest_path), meta_path.as_ref()].into_iter().flatten() {
+            ensure_overwritable(path, args.force, args.interactive)?;
+        }
+    }
+
+    // 目标目录不存在时尽早报错；指定了 `--mkdir` 时推迟到真正写出前再创建，试运行不会创建
+    if !args.mkdir {
+        ensure_parent_dir(&dest_path, false)?;
+    }
+
+    let (width, height) = img.dimensions();
+
+    // 按原始颜色类型将图像转换为字节流，并记录每个像素的通道字节数；16 位图像另存高字节
+    // 8 位图像直接取出解码得到的缓冲区，隐写与重建图像都在这一个缓冲区上进行，不会再复制整幅像素
+    // 指定 `--raw-bmp` 时直接使用文件头之后的原始文件字节，每个字节视为一个单通道像素：
+    // 就地移去文件头后复用读入的文件缓冲区，`image_bytes` 只保留文件头，解码出的图像也不再需要
+    let high = high_bytes(&img);
+    let (mut picture_bytes, stride) = if args.raw_bmp {
+        ensure_raw_bmp(&image, &image_bytes)?;
+        drop(img);
+        let mut pixels = std::mem::take(&mut image_bytes);
+        image_bytes = pixels.drain(..BMP_HEADER_SIZE).collect();
+        (pixels, 1)
+    } else {
+        into_pixel_bytes(img)?
+    };
+
+    // 原始文件字节此后只用于复制元数据块，其余情况下立即释放，峰值内存不再包含整个编码后的文件
+    if !args.preserve_metadata && !args.raw_bmp {
+        image_bytes = Vec::new();
+    }
+    anyhow::ensure!(
+        args.alpha_threshold.is_none() || has_alpha(stride),
+        tr(
+            Message::AlphaThresholdNeedsAlpha,
+            &[&style::error(image.to_string_lossy())]
+        )
+    );
+
+    options.stride = stride;
+
+    // 检查空间并按指定参数隐藏头部与载荷内容
+    // 跳过 alpha 时，头部与载荷都只写入颜色字节，可用容量也随之减少
+    // 头部与载荷都从跳过 `--offset` 个像素之后的位置开始写入
+    // 较大的载荷在终端中显示进度条
+    let progress = Progress::new(total, "Hiding", !args.json && !args.quiet);
+    let report = |done: usize| progress.set(done);
+    let mut embed = |pixels: &mut [u8], options: &HideOptions| match &mut stream {
+        Some(stream) => {
+            stream.file.rewind()?;
+            hide_stream_with_progress(
+                pixels,
+                &mut stream.file,
+                &stream.summary,
+                options,
+                stream.chunk_size,
+                &report,
+            )
+        }
+        // 追加模式下载荷参数沿用已有的头部，只需提供无法记录在头部中的参数
+        None if args.append => append_bytes(
+            pixels,
+            &payload,
+            &RecoverOptions {
+                stride: options.stride,
+                scatter_seed: options.scatter_seed,
+                edge_adaptive: options.edge_adaptive,
+                endian: options.endian,
+                ..Default::default()
+            },
+        ),
+        None if args.no_length_header => hide_headerless(pixels, &payload),
+        None => hide_bytes_with_progress(pixels, &payload, options, &report),
+    };
+    // 指定了 alpha 阈值时还会跳过 alpha 低于阈值的像素
+    let (start, end, stats) = if has_alpha(stride)
+        && (args.skip_alpha || args.alpha_threshold.is_some())
+    {
+        options.stride = stride - 1;
+        let opaque = opaque_pixels(&picture_bytes, stride, args.alpha_threshold.unwrap_or(0));
+        let start = color_offset(args.offset, stride, picture_bytes.len(), &opaque)?;
+        let mut color_bytes = strip_alpha(&picture_bytes, stride, &opaque);
+        let stats = embed(&mut color_bytes[start..], &options)?;
+        restore_alpha(&mut picture_bytes, &color_bytes, stride, &opaque);
+
+        // 将颜色字节流中的偏移量换算回完整字节流中的偏移量
+        let (start, end) = color_range_to_full(start, start + stats.end_offset, stride, &opaque);
+        (start, end, stats)
+    } else if let Some(region) = args.region {
+        // 指定区域时只在区域内的像素字节中隐写，`--offset` 从区域的左上角开始计算
+        region.ensure_within(width, height)?;
+        let mut region_bytes = region.gather(&picture_bytes, width, stride);
+        let start = pixel_offset(args.offset, stride, region_bytes.len())?;
+        let stats = embed(&mut region_bytes[start..], &options)?;
+        region.scatter(&mut picture_bytes, &region_bytes, width, stride);
+
+        // 将区域字节流中的偏移量换算回完整字节流中的偏移量
+        let end = start + stats.end_offset;
+        let to_full = |index: usize| region.full_offset(index, width, stride);
+        (to_full(start), to_full(end.saturating_sub(1)) + 1, stats)
+    } else {
+        let start = pixel_offset(args.offset, options.stride, picture_bytes.len())?;
+        let stats = embed(&mut picture_bytes[start..], &options)?;
+        (start, start + stats.end_offset, stats)
+    };
+    progress.finish();
+
+    // 所有修改都还只在内存中，超出允许的容量比例时直接报错，不写出任何文件
+    anyhow::ensure!(
+        stats.usage_fraction() <= args.max_capacity_fraction,
+        tr(
+            Message::CapacityFractionExceeded,
+            &[
+                &format!("{:.4}", stats.usage_fraction()),
+                &stats.payload_len,
+                &stats.capacity,
+                &args.max_capacity_fraction
+            ]
+        )
+    );
+
+    // 按原始颜色类型与位深度从修改后的字节创建 DynamicImage
+    // `--raw-bmp` 不重新编码，而是将原始文件头与修改后的字节直接拼接写出
+    let output_img = if args.raw_bmp {
+        None
+    } else {
+        let bytes = std::mem::take(&mut picture_bytes);
+        Some(from_pixel_bytes(width, height, bytes, stride, high)?)
+    };
+
+    // 试运行时，所有隐写操作都已在内存中完成，只跳过写出文件
+    if !args.dry_run {
+        ensure_parent_dir(&dest_path, args.mkdir)?;
+        let saved = match (output_img, &mut animation, args.frame) {
+            (None, _, _) => fs::File::create(&dest_path)
+                .and_then(|mut file| {
+                    file.write_all(&image_bytes)?;
+                    file.write_all(&picture_bytes)
+                })
+                .map_err(anyhow::Error::from),
+            (Some(output_img), Some(animation), Some(index)) => {
+                animation.set_frame(index, output_img.into_rgba8())?;
+                encode_output(&image_bytes, animation.encode()?, args.preserve_metadata)
+                    .and_then(|bytes| fs::write(&dest_path, bytes).map_err(anyhow::Error::from))
+            }
+            (Some(output_img), _, _) if args.preserve_metadata => {
+                let mut encoded = Cursor::new(Vec::new());
+                output_img
+                    .write_to(&mut encoded, ImageFormat::Png)
+                    .map_err(anyhow::Error::from)
+                    .and_then(|_| encode_output(&image_bytes, encoded.into_inner(), true))
+                    .and_then(|bytes| fs::write(&dest_path, bytes).map_err(anyhow::Error::from))
+            }
+            (Some(output_img), _, _) => match args.output_format {
+                Some(format) => output_img.save_with_format(&dest_path, format.into()),
+                None => output_img.save(&dest_path),
+            }
+            .map_err(anyhow::Error::from),
+        };
+        saved.with_context(|| {
+            tr(
+                Message::WriteImageFailed,
+                &[&style::error(dest_path.to_string_lossy())],
+            )
+        })?;
+
+        // 文件大小明显变化 (例如转换了格式) 时，对比原图即可发现隐写图像
+        let output_len = fs::metadata(&dest_path)?.len();
+        if let Some(change) = size_change(input_len, output_len)
+            && !args.quiet
+        {
+            eprintln!(
+                "{} {}",
+                style::warning(tr(Message::Warning, &[])),
+                tr(
+                    Message::SizeChangeWarning,
+                    &[&output_len, &input_len, &format!("{:+.0}", change * 100.0)]
+                )
+            );
+        }
+
+        if let Some(meta_path) = &meta_path {
+            let sidecar = Sidecar {
+                offset: args.offset,
+                length: stats.payload_len as u64,
+                checksum: crc32fast::hash(&payload),
+            };
+            fs::write(meta_path, sidecar.encode()).with_context(|| {
+                tr(
+                    Message::WriteTextFailed,
+                    &[&style::error(meta_path.to_string_lossy())],
+                )
+            })?;
+        }
+    }
+
+    if args.json {
+        println!(
+            "{}",
+            json!({
+                "status": "ok",
+                "image": image.to_string_lossy(),
+                "dest": dest_path.to_string_lossy(),
+                "meta": meta_path.as_ref().map(|path| path.to_string_lossy()),
+                "payload_bytes": stats.payload_len,
+                "capacity_bytes": stats.capacity,
+                "bits": stats.bits,
+                "dry_run": args.dry_run,
+            })
+        );
+        return Ok(());
+    }
+
+    if args.quiet {
+        return Ok(());
+    }
+
+    if args.dry_run {
+        println!(
+            "{}",
+            tr(
+                Message::HideDryRun,
+                &[&style::success(dest_path.to_string_lossy())]
+            )
+        );
+    } else {
+        println!(
+            "{}",
+            tr(
+                Message::HideDone,
+                &[&style::success(dest_path.to_string_lossy())]
+            )
+        );
+        if let Some(meta_path) = &meta_path {
+            println!(
+                "{}",
+                tr(
+                    Message::SidecarWritten,
+                    &[&style::success(meta_path.to_string_lossy())]
+                )
+            );
+        }
+    }
+
+    // 自动提高了位深度时总是提示，对图像的视觉影响会比预期更明显
+    // 追加模式沿用已有头部中的位深度，与 `--bits` 的默认值不同并不意味着提高了位深度
+    if args.auto_bits && stats.bits != args.bits {
+        println!(
+            "{}",
+            tr(Message::AutoBitsRaised, &[&args.bits, &stats.bits])
+        );
+    }
+
+    if args.verbose {
+        println!("{}", tr(Message::PayloadSize, &[&stats.payload_len]));
+        println!("{}", tr(Message::TotalCapacity, &[&stats.capacity]));
+        println!(
+            "{}",
+            tr(
+                Message::CapacityUsed,
+                &[&format!("{:.2}", stats.usage_percent())]
+            )
+        );
+        println!("{}", tr(Message::ModifiedRange, &[&start, &end]));
+    }
+
+    Ok(())
+}
+
+/// 在 `dir` 中挑选容量足以容纳 `payload_len` 个载荷字节、且容量最小的封面图像，供 `--image-dir` 使用
+///
+/// 容量相同时选择路径排序靠前的一张，使结果与目录的遍历顺序无关
+///
+/// # Errors
+///
+/// * 如果无法读取目录，将返回错误
+/// * 如果目录中没有任何图像放得下载荷，将返回错误
+fn select_cover(
+    dir: &Path,
+    payload_len: usize,
+    options: &HideOptions,
+    args: &HideArgs,
+) -> anyhow::Result<PathBuf> {
+    cover_capacities(dir, options, args)?
+        .into_iter()
+        .filter(|(_, capacity)| *capacity >= payload_len)
+        .min_by_key(|(_, capacity)| *capacity)
+        .map(|(path, _)| path)
+        .with_context(|| {
+            tr(
+                Message::NoCoverFits,
+                &[&style::error(dir.to_string_lossy()), &payload_len],
+            )
+        })
+}
+
+/// 按路径排序列出 `dir` 中的所有文件 (不含子目录)
+///
+/// # Errors
+///
+/// 如果无法读取目录，将返回错误
+fn dir_files(dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let mut paths = fs::read_dir(dir)
+        .and_then(|entries| {
+            entries
+                .map(|entry| entry.map(|entry| entry.path()))
+                .collect::<io::Result<Vec<_>>>()
+        })
+        .with_context(|| {
+            tr(
+                Message::UnreadableImageDir,
+                &[&style::error(dir.to_string_lossy())],
+            )
+        })?;
+    paths.retain(|path| path.is_file());
+    paths.sort();
+    Ok(paths)
+}
+
+/// 按路径顺序列出 `dir` 中可以作为封面的图像及其容量
+///
+/// 每张图像的容量按 `options` 以及 `args` 中的跳过 alpha、像素偏移量与区域计算；无法解码的文件、调色板 PNG 以及放不下 `--region` 的图像会被忽略
+///
+/// # Errors
+///
+/// 如果无法读取目录，将返回错误
+fn cover_capacities(
+    dir: &Path,
+    options: &HideOptions,
+    args: &HideArgs,
+) -> anyhow::Result<Vec<(PathBuf, usize)>> {
+    let mut covers = Vec::new();
+    for path in dir_files(dir)? {
+        let Ok(bytes) = fs::read(&path) else {
+            continue;
+        };
+        if is_indexed_png(&bytes) {
+            continue;
+        }
+        let Ok(img) = decode_image(&path, &bytes) else {
+            continue;
+        };
+        let (width, height) = img.dimensions();
+        let Ok((pixels, stride)) = into_pixel_bytes(img) else {
+            continue;
+        };
+
+        // 指定 `--region` 时只有区域内的像素可用，区域超出边界的图像无法使用
+        let len = match args.region {
+            Some(region) if region.ensure_within(width, height).is_err() => continue,
+            Some(region) => region.width as usize * region.height as usize * stride,
+            None => pixels.len(),
+        };
+        let capacity = cover_capacity(len, stride, options, args);
+        covers.push((path, capacity));
+    }
+    Ok(covers)
+}
+
+/// 计算 `len` 个像素字节 (每像素 `stride` 个通道字节) 在 hide 的参数下最多可隐藏的载荷字节数
+///
+/// 自动提高位深度时按 `MAX_BITS` 计算，跳过 alpha 时只计入颜色字节 (按 alpha 阈值跳过的像素无法预先得知，同样只扣除 alpha 字节)
+fn cover_capacity(len: usize, stride: usize, options: &HideOptions, args: &HideArgs) -> usize {
+    let mut options = HideOptions {
+        stride,
+        bits: if options.auto_bits {
+            MAX_BITS
+        } else {
+            options.bits
+        },
+        ..options.clone()
+    };
+    let mut len = len.saturating_sub(args.offset.saturating_mul(stride));
+    if has_alpha(stride) && (args.skip_alpha || args.alpha_threshold.is_some()) {
+        len = len / stride * (stride - 1);
+        options.stride = stride - 1;
+    }
+    hide_capacity(len, &options)
+}
+
+/// 处理 `hide --split`：按路径顺序依次填满 `--image-dir` 中的图像，每张隐藏载荷的一段
+///
+/// 每一段都带有段头 (完整载荷的类型标签、序号、总段数与校验和)，头部中的类型标签为 `PayloadType::Segment`；
+/// 结果以 "part_001.png" 等文件名写入 `--dest` 目录，并附带记录各段来源的 `SPLIT_MANIFEST_NAME` 清单
+///
+/// # Errors
+///
+/// * 如果无法读取图像目录，或所有图像的容量之和放不下载荷，将返回错误
+/// * 如果输出文件已存在且没有 `--force`，用户也没有在终端中确认覆盖，将返回错误
+/// * 如果任意一段隐藏失败，或无法写出结果图像与清单，将返回错误
+fn hide_segments(args: &HideArgs, payload: &[u8], mut options: HideOptions) -> anyhow::Result<()> {
+    let dir = args
+        .image_dir
+        .as_deref()
+        .with_context(|| tr(Message::MissingCoverImage, &[]))?;
+    let dest_dir = args.dest.clone().unwrap_or_else(|| {
+        let dir_name = dir.file_name().and_then(|s| s.to_str()).unwrap_or("covers");
+        sibling_path(dir, format!("doctored_{}", dir_name))
+    });
+
+    // 段头中记录完整载荷的类型标签，图像头部只标记这是其中一段
+    let payload_type = options.payload_type.replace(PayloadType::Segment);
+    let covers = cover_capacities(dir, &options, args)?;
+    let capacities: Vec<usize> = covers.iter().map(|(_, capacity)| *capacity).collect();
+    let parts = segment::plan(payload.len(), &capacities).with_context(|| {
+        tr(
+            Message::NoCoverFits,
+            &[&style::error(dir.to_string_lossy()), &payload.len()],
+        )
+    })?;
+    let outputs: Vec<PathBuf> = (1..=parts.len())
+        .map(|number| dest_dir.join(format!("part_{:03}.png", number)))
+        .collect();
+    let manifest_path = dest_dir.join(SPLIT_MANIFEST_NAME);
+
+    // 先确认所有输出都可以写出，避免只写出一部分图像后才失败
+    if !args.dry_run {
+        for path in outputs.iter().chain([&manifest_path]) {
+            ensure_overwritable(path, args.force, args.interactive)?;
+        }
+        fs::create_dir_all(&dest_dir).with_context(|| {
+            tr(
+                Message::WriteImageFailed,
+                &[&style::error(dest_dir.to_string_lossy())],
+            )
+        })?;
+    }
+
+    let checksum = crc32fast::hash(payload);
+    let mut offset = 0;
+    let mut entries = Vec::new();
+    for (index, ((image, size), output)) in parts.into_iter().zip(&outputs).enumerate() {
+        let cover = &covers[image].0;
+        let segment = Segment {
+            payload_type,
+            index: index as u16,
+            count: outputs.len() as u16,
+            checksum,
+            data: payload[offset..offset + size].to_vec(),
+        };
+        offset += size;
+
+        let img = open_image(cover)?;
+        let (width, height) = img.dimensions();
+        let high = high_bytes(&img);
+        let (mut pixels, stride) = into_pixel_bytes(img)?;
+        options.stride = stride;
+        let start = pixel_offset(args.offset, stride, pixels.len())?;
+        hide_bytes_with(&mut pixels[start..], &segment.encode(), &options).with_context(|| {
+            tr(
+                Message::HideSegmentFailed,
+                &[&(index + 1), &style::error(cover.to_string_lossy())],
+            )
+        })?;
+
+        if !args.dry_run {
+            from_pixel_bytes(width, height, pixels, stride, high)?
+                .save_with_format(output, ImageFormat::Png)
+                .with_context(|| {
+                    tr(
+                        Message::WriteImageFailed,
+                        &[&style::error(output.to_string_lossy())],
+                    )
+                })?;
+        }
+        entries.push(json!({
+            "index": index + 1,
+            "file": output.file_name().map(|name| name.to_string_lossy()),
+            "cover": cover.to_string_lossy(),
+            "bytes": size,
+        }));
+    }
+
+    let manifest = json!({
+        "payload_bytes": payload.len(),
+        "checksum": format!("{:08x}", checksum),
+        "segments": entries,
+    });
+    if !args.dry_run {
+        fs::write(&manifest_path, format!("{:#}\n", manifest)).with_context(|| {
+            tr(
+                Message::WriteTextFailed,
+                &[&style::error(manifest_path.to_string_lossy())],
+            )
+        })?;
+    }
+
+    if args.json {
+        println!(
+            "{}",
+            json!({
+                "status": "ok",
+                "dest": dest_dir.to_string_lossy(),
+                "payload_bytes": payload.len(),
+                "segments": outputs.len(),
+                "dry_run": args.dry_run,
+            })
+        );
+        return Ok(());
+    }
+
+    if !args.quiet {
+        println!(
+            "{}",
+            tr(
+                Message::SplitDone,
+                &[&outputs.len(), &style::success(dest_dir.to_string_lossy())]
+            )
+        );
+    }
+
+    Ok(())
+}
+
+/// 计算输出文件相对原始图像的大小变化比例，只有变化超过 `SIZE_CHANGE_WARNING_RATIO` 时才返回
+fn size_change(input_len: u64, output_len: u64) -> Option<f64> {
+    let change = (output_len as f64 - input_len as f64) / input_len.max(1) as f64;
+    (change.abs() > SIZE_CHANGE_WARNING_RATIO).then_some(change)
+}
+
+/// 返回要写出的 PNG 文件字节，`preserve_metadata` 为真时先复制原始图像 (`source`) 中的元数据块
+fn encode_output(source: &[u8], png: Vec<u8>, preserve_metadata: bool) -> anyhow::Result<Vec<u8>> {
+    if preserve_metadata {
+        metadata::copy_png_metadata(source, &png)
+    } else {
+        Ok(png)
+    }
+}
+
+/// 按头部的加密标志解密恢复出的载荷，未加密时原样返回
+///
+/// # Errors
+///
+/// 如果载荷已加密但没有提供口令，或口令错误导致解密失败，将返回错误
+fn decrypt_payload(
+    header: &Header,
+    payload: Vec<u8>,
+    password: Option<&str>,
+) -> anyhow::Result<Vec<u8>> {
+    if !header.is_encrypted() {
+        return Ok(payload);
+    }
+    let password = password.with_context(|| tr(Message::PasswordRequired, &[]))?;
+    crypto::decrypt(&payload, password)
+}
+
+/// 按头部的类型标签还原白化的载荷，未白化时原样返回
+///
+/// 白化不提供认证，密钥错误时只会得到不同的字节
+///
+/// # Errors
+///
+/// 如果载荷已白化但没有提供密钥，将返回错误
+fn unwhiten_payload(
+    header: &Header,
+    payload: Vec<u8>,
+    xor_key: Option<&str>,
+) -> anyhow::Result<Vec<u8>> {
+    if !header.is_whitened() {
+        return Ok(payload);
+    }
+    let key = xor_key.with_context(|| tr(Message::XorKeyRequired, &[]))?;
+    Ok(whiten::apply(&payload, key))
+}
+
+/// 判断恢复的载荷是否需要转换编码，返回载荷的原始编码与目标编码
+///
+/// 头部记录了原始编码时默认转换为 UTF-8；未记录时只有标记为文本的载荷视为 UTF-8，且仅在指定了 `output` 时转换。
+/// 二进制、归档或未标记的载荷，以及指定了 `--binary` 时都原样处理
+fn text_transcoding(
+    header: &Header,
+    output: Option<&'static encoding_rs::Encoding>,
+    binary: bool,
+) -> Option<(
+    &'static encoding_rs::Encoding,
+    &'static encoding_rs::Encoding,
+)> {
+    if binary {
+        return None;
+    }
+    match (header.text_encoding, output) {
+        (Some(source), output) => Some((source, output.unwrap_or(encoding_rs::UTF_8))),
+        (None, Some(output)) if header.payload_type == Some(PayloadType::Text) => {
+            Some((encoding_rs::UTF_8, output))
+        }
+        (None, _) => None,
+    }
+}
+
+/// 判断恢复的载荷是否应当按 UTF-8 文本校验
+///
+/// 只有头部标记为文本的载荷才会校验；二进制、归档或未标记的载荷，以及指定了 `--binary` 时都原样处理
+fn should_validate_utf8(payload_type: Option<PayloadType>, binary: bool) -> bool {
+    !binary && payload_type == Some(PayloadType::Text)
+}
+
+/// 将恢复的字节按 `encoding` 转换后复制到系统剪贴板
+///
+/// 编码输出末尾的换行只是为了在终端中显示，不会被复制
+///
+/// # Errors
+///
+/// * 如果程序编译时没有启用 `clipboard` 特性，或无法访问系统剪贴板，将返回错误
+/// * 如果转换后的内容不是有效的 UTF-8 文本，将返回错误
+fn copy_to_clipboard(text: &[u8], encoding: Encoding) -> anyhow::Result<()> {
+    let encoded = encoding.encode(text);
+    let copied = match encoding {
+        Encoding::Raw => &encoded[..],
+        _ => encoded.strip_suffix(b"\n").unwrap_or(&encoded),
+    };
+    clipboard::copy_text(clipboard::system()?.as_mut(), copied)
+}
+
+/// 处理 'Recover' 命令的执行逻辑
+///
+/// 负责读取经过隐写的图像文件、调用 `recover_payload` 获取文本长度和每个字符 (必要时解密)，
+/// 最后将恢复的文本内容写入目标文本文件；如果隐藏的是多文件归档，则解包到目标目录中
+///
+/// # Arguments
+///
+/// * `args` - 包含输入/输出路径及二进制模式标识的 `RecoverArgs` 结构体
+///
+/// # Errors
+///
+/// 如果发生以下任一情况，将返回错误：
+/// * 写入路径文件已存在，且没有 `--force` 标志，用户也没有在终端中确认覆盖
+/// * 输出文件所在的目录不存在，且没有 `--mkdir` 标志
+/// * 无法读取输入的图像文件
+/// * 图像是浮点颜色类型 (如 32 位浮点 TIFF)
+/// * 图像中不包含 lsb_hide 的魔数，即没有隐藏的载荷
+/// * 核心恢复函数 (`recover_payload`) 在执行过程中失败；指定 `--best-effort` 时，载荷内容损坏只会给出警告
+/// * 图像只包含 `hide --split` 拆分出的一段；或指定了 `--split`，但目录中没有载荷段、缺少某些段或各段不属于同一个载荷
+/// * 载荷已加密但未提供口令，或口令错误导致解密失败
+/// * 载荷已白化但未提供 `--xor-key`
+/// * 载荷是多文件归档，但归档已损坏，或输出路径为标准输出、指定了 `--clipboard`
+/// * 指定了 `--clipboard`，但载荷不是有效的 UTF-8 文本，或无法访问系统剪贴板
+/// * 无法写入到目标文本文件 (或标准输出、归档的目标目录)
+pub fn handle_recover(args: RecoverArgs) -> anyhow::Result<()> {
+    // 默认输出路径以原始图像的文件名 (不带扩展名) 为基础
+    let original_filename = args
+        .image
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("output");
+    let default_output = |name: String| sibling_path(&args.image, name);
+    let name_template = NameTemplate::new(args.name_template.as_deref())?;
+
+    // 指定 `--split` 时 `--image` 是保存分段图像的目录，逐一恢复各段后按序号拼接
+    let RecoveredPayload {
+        header,
+        payload,
+        stats,
+        end,
+        shift,
+    } = if args.split {
+        recover_segments(&args)?
+    } else {
+        recover_image(&args)?
+    };
+
+    // 两份长度不一致时即使恢复成功也要报告，提示图像已部分损坏
+    if let Some((header_length, backup_length)) = stats.length_mismatch {
+        eprintln!(
+            "{} {}",
+            style::warning(tr(Message::Warning, &[])),
+            tr(
+                Message::LengthCopiesDisagreeWarning,
+                &[&header_length, &backup_length]
+            )
+        );
+    }
+
+    // 尽力恢复时载荷已损坏，照常写出已恢复的部分，并报告丢弃或可能出错的范围
+    if let Some(expected) = stats.damaged {
+        let warning = if (payload.len() as u64) < expected {
+            tr(Message::PayloadTruncated, &[&payload.len(), &expected])
+        } else {
+            tr(Message::PayloadCorrupt, &[&payload.len()])
+        };
+        eprintln!("{} {}", style::warning(tr(Message::Warning, &[])), warning);
+    }
+
+    // 如果载荷已加密，则必须提供口令才能解密
+    let decrypted = decrypt_payload(&header, payload, args.password.as_deref())?;
+    let decrypted = unwhiten_payload(&header, decrypted, args.xor_key.as_deref())?;
+
+    // 按头部记录的算法解压
+    let text = header.compression.decompress(&decrypted)?;
+
+    // 多文件归档解包到目录中，而不是写出单个文件
+    if header.is_archive() {
+        anyhow::ensure!(
+            args.encoding == Encoding::Raw,
+            tr(Message::ArchiveEncoding, &[&args.encoding])
+        );
+        let dir = args
+            .text
+            .clone()
+            .unwrap_or_else(|| default_output(name_template.expand(original_filename, None)));
+        anyhow::ensure!(!is_stdio(&dir), tr(Message::ArchiveToStdout, &[]));
+        anyhow::ensure!(!args.clipboard, tr(Message::ArchiveToClipboard, &[]));
+
+        let entries = archive::unpack(&text)?;
+        extract_archive(&dir, &entries, args.force)?;
+
+        if args.json {
+            println!(
+                "{}",
+                json!({
+                    "status": "ok",
+                    "output": dir.to_string_lossy(),
+                    "recovered_bytes": entries.iter().map(|entry| entry.data.len()).sum::<usize>(),
+                    "files": entries.iter().map(|entry| &entry.name).collect::<Vec<_>>(),
+                })
+            );
+            return Ok(());
+        }
+
+        if args.quiet {
+            return Ok(());
+        }
+
+        println!(
+            "{}",
+            tr(
+                Message::ArchiveRecovered,
+                &[&entries.len(), &style::success(dir.to_string_lossy())]
+            )
+        );
+
+        if args.verbose {
+            println!("{}", tr(Message::RecoveredLength, &[&stats.payload_len]));
+            println!("{}", tr(Message::ExtractionEnd, &[&end]));
+            if shift > 0 {
+                println!("{}", tr(Message::Resynchronized, &[&shift]));
+            }
+            if let Some(provenance) = describe_provenance(&header) {
+                println!("{}", provenance);
+            }
+        }
+
+        return Ok(());
+    }
+
+    // 如果用户没有提供输出路径，则动态生成一个默认路径。
+    // 编码输出使用对应编码的扩展名 (如 `.b64`)；头部记录了原始文件名时沿用其扩展名 (如 `.pdf`)；
+    // 否则二进制模式下使用 `.bin` 扩展名，避免将任意字节误标为文本
+    let text_path = args.text.clone().unwrap_or_else(|| {
+        let embedded_extension = Path::new(&header.name)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .filter(|ext| archive::is_plain_name(ext));
+        let extension = match (args.encoding.extension(), embedded_extension) {
+            (Some(ext), _) | (None, Some(ext)) => ext,
+            (None, None) if args.binary || header.payload_type == Some(PayloadType::Binary) => {
+                "bin"
+            }
+            (None, None) => "txt",
+        };
+        default_output(name_template.expand(original_filename, Some(extension)))
+    });
+
+    if text.is_empty() {
+        eprintln!(
+            "{} {}",
+            style::warning(tr(Message::Warning, &[])),
+            tr(
+                Message::EmptyRecoverWarning,
+                &[&tr(Message::EmptyPayload, &[])]
+            )
+        );
+    }
+
+    // 转换编码时已校验过原始文本，转换后的结果也可能不是 UTF-8，因此不再重复校验
+    let transcoding = text_transcoding(&header, args.output_encoding, args.binary);
+    let text = match transcoding {
+        Some((source, target)) => text_encoding::transcode(&text, source, target)?,
+        None => text,
+    };
+
+    // 标记为文本的载荷解码失败，说明数据可能已损坏或被错误解密
+    if transcoding.is_none()
+        && should_validate_utf8(header.payload_type, args.binary)
+        && std::str::from_utf8(&text).is_err()
+    {
+        eprintln!(
+            "{} {}",
+            style::warning(tr(Message::Warning, &[])),
+            tr(Message::InvalidUtf8Warning, &[])
+        );
+    }
+
+    // 只指定 `--clipboard` 时复制到剪贴板而不写出文件
+    if args.clipboard && args.text.is_none() {
+        copy_to_clipboard(&text, args.encoding)?;
+
+        if args.json {
+            println!(
+                "{}",
+                json!({
+                    "status": "ok",
+                    "output": "clipboard",
+                    "recovered_bytes": text.len(),
+                    "encoding": args.encoding.to_string(),
+                })
+            );
+            return Ok(());
+        }
+
+        if args.quiet {
+            return Ok(());
+        }
+
+        println!(
+            "{}",
+            tr(
+                Message::CopiedToClipboard,
+                &[&style::success(tr(Message::Clipboard, &[]))]
+            )
+        );
+
+        if args.verbose {
+            println!("{}", tr(Message::RecoveredLength, &[&stats.payload_len]));
+            println!("{}", tr(Message::ExtractionEnd, &[&end]));
+        }
+
+        return Ok(());
+    }
+
+    // 在写入前检查文件是否存在，防止意外覆盖
+    if !is_stdio(&text_path) {
+        ensure_overwritable(&text_path, args.force, args.interactive)?;
+        ensure_parent_dir(&text_path, args.mkdir)?;
+    }
+
+    if args.clipboard {
+        copy_to_clipboard(&text, args.encoding)?;
+    }
+
+    let summary = json!({
+        "status": "ok",
+        "output": text_path.to_string_lossy(),
+        "recovered_bytes": text.len(),
+        "encoding": args.encoding.to_string(),
+    });
+
+    // 按 `--encoding` 转换输出，原样写出时不做任何改动
+    let text = args.encoding.encode(&text);
+
+    // 输出到标准输出时，成功信息改写到标准错误，避免污染管道中的数据
+    if is_stdio(&text_path) {
+        let mut stdout = io::stdout().lock();
+        stdout
+            .write_all(&text)
+            .and_then(|_| stdout.flush())
+            .with_context(|| tr(Message::StdoutWriteFailed, &[]))?;
+
+        if args.json {
+            eprintln!("{}", summary);
+            return Ok(());
+        }
+
+        if args.quiet {
+            return Ok(());
+        }
+
+        eprintln!(
+            "{}",
+            tr(
+                Message::WrittenToStdout,
+                &[&style::success(tr(Message::StandardOutput, &[]))]
+            )
+        );
+
+        if args.verbose {
+            eprintln!("{}", tr(Message::RecoveredLength, &[&stats.payload_len]));
+            eprintln!("{}", tr(Message::ExtractionEnd, &[&end]));
+            if shift > 0 {
+                eprintln!("{}", tr(Message::Resynchronized, &[&shift]));
+            }
+            if let Some(provenance) = describe_provenance(&header) {
+                eprintln!("{}", provenance);
+            }
+        }
+    } else {
+        fs::write(&text_path, text).with_context(|| {
+            tr(
+                Message::WriteTextFailed,
+                &[&style::error(text_path.to_string_lossy())],
+            )
+        })?;
+
+        if args.json {
+            println!("{}", summary);
+            return Ok(());
+        }
+
+        if args.quiet {
+            return Ok(());
+        }
+
+        println!(
+            "{}",
+            tr(
+                Message::RecoverDone,
+                &[&style::success(text_path.to_string_lossy())]
+            )
+        );
+
+        if args.verbose {
+            println!("{}", tr(Message::RecoveredLength, &[&stats.payload_len]));
+            println!("{}", tr(Message::ExtractionEnd, &[&end]));
+            if shift > 0 {
+                println!("{}", tr(Message::Resynchronized, &[&shift]));
+            }
+            if let Some(provenance) = describe_provenance(&header) {
+                println!("{}", provenance);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// 从图像中恢复出、尚未解密与解压的载荷
+struct RecoveredPayload {
+    /// 载荷的隐写头部
+    header: Header,
+
+    /// 载荷的原始字节
+    payload: Vec<u8>,
+
+    /// 提取过程的统计信息
+    stats: EmbedStats,
+
+    /// 载荷在原始图像字节流中的结束偏移量，供 `--verbose` 报告
+    end: usize,
+
+    /// 以 `--resync` 扫描时，魔数相对预期位置向后错开的字节数
+    shift: usize,
+}
+
+/// 读取 `hide --no-length-header` 写出的边车文件
+///
+/// # Errors
+///
+/// 如果无法读取文件，或文件内容不是有效的边车信息，将返回错误
+fn read_sidecar(path: &Path) -> anyhow::Result<Sidecar> {
+    fs::read_to_string(path)
+        .map_err(anyhow::Error::from)
+        .and_then(|text| Sidecar::decode(&text))
+        .with_context(|| {
+            tr(
+                Message::UnreadableSidecar,
+                &[&style::error(path.to_string_lossy())],
+            )
+        })
+}
+
+/// 按 `args` 从单张图像中恢复载荷，供 `handle_recover` 使用
+///
+/// # Errors
+///
+/// * 如果无法读取图像，或图像中没有魔数 (且未指定 `--no-header`)，将返回错误
+/// * 如果头部无效或载荷无法完整恢复，将返回错误
+/// * 如果图像只包含 `--split` 拆分出的一段载荷，将返回提示使用 `--split` 的错误
+fn recover_image(args: &RecoverArgs) -> anyhow::Result<RecoveredPayload> {
+    // 读取图像文件，头部从跳过 `--offset` 个像素之后的位置开始
+    // 指定 `--resync` 时，如果该位置没有魔数，再向后扫描有限的字节数
+    // 指定 `--no-header` 时图像中没有魔数可供定位，载荷直接从该位置开始
+    // 指定 `--meta` 时同样没有头部，偏移量与长度取自 `hide --no-length-header` 写出的边车文件
+    let resync = if args.resync { RESYNC_SCAN_BYTES } else { 0 };
+    let sidecar = args.meta.as_deref().map(read_sidecar).transpose()?;
+    let no_header = args.no_header || sidecar.is_some();
+    let offset = sidecar.map_or(args.offset, |sidecar| sidecar.offset);
+    let StegoPixels {
+        bytes: picture_bytes,
+        stride,
+        start,
+        skipped_alpha,
+        opaque,
+        shift,
+    } = if args.raw_bmp {
+        open_raw_bmp_pixels(&args.image, offset, resync)?
+    } else if no_header {
+        open_headerless_pixels(&args.image, offset, args.frame, args.region)?
+    } else {
+        open_stego_pixels(
+            &args.image,
+            offset,
+            resync,
+            args.alpha_threshold,
+            args.frame,
+            args.region,
+        )?
+    };
+
+    // 指定 `--no-header` 时不读取头部，直接从起始位置提取 `--length` 个字节
+    // 边车文件还记录了校验和，提取出的字节必须与之一致
+    let (header, payload, stats) = if no_header {
+        let length = match sidecar {
+            Some(sidecar) => sidecar.length,
+            None => args
+                .length
+                .with_context(|| tr(Message::NoHeaderLength, &[]))?,
+        };
+        recover_headerless(&picture_bytes[start..], length, args.payload_limit)
+            .and_then(|recovered| match sidecar {
+                Some(sidecar) if recovered.0.checksum != sidecar.checksum => {
+                    Err(StegError::ChecksumMismatch)
+                }
+                _ => Ok(recovered),
+            })
+            .with_context(|| {
+                tr(
+                    Message::RecoverFailed,
+                    &[&style::error(args.image.to_string_lossy())],
+                )
+            })?
+    } else {
+        // 先校验魔数，避免将普通图像中的随机数据误读为巨大的长度
+        if !has_magic(&picture_bytes[start..]) {
+            return Err(StegError::MagicMismatch.into());
+        }
+
+        // 恢复隐藏文本的长度及内容
+        // 如果载荷是打散嵌入的，需要由口令重建相同的位置排列
+        let options = RecoverOptions {
+            stride,
+            scatter_seed: scatter_seed(args.password.as_deref(), args.seed_file.as_deref())?,
+            payload_limit: args.payload_limit,
+            edge_adaptive: args.edge_adaptive,
+            endian: args.endian,
+            best_effort: args.best_effort,
+        };
+
+        // 头部记录的长度超出图像容量时立即报错，既不显示进度条也不开始提取
+        // 较大的载荷在终端中显示进度条；头部本身无效时由下面的恢复过程报告错误
+        let total = match read_header(&picture_bytes[start..]) {
+            // 头部与末尾备份记录的长度不一致时，由恢复过程判断哪一份有效
+            Ok(header)
+                if read_length_backup(&picture_bytes[start..])
+                    .is_some_and(|length| length != header.length) =>
+            {
+                0
+            }
+            Ok(header) => ensure_payload_limit(header.length, args.payload_limit)
+                .and_then(|_| ensure_length_fits(&header, picture_bytes.len() - start, stride))
+                .with_context(|| {
+                    tr(
+                        Message::RecoverFailed,
+                        &[&style::error(args.image.to_string_lossy())],
+                    )
+                })?,
+            Err(_) => 0,
+        };
+        let progress = Progress::new(total, "Recovering", !args.json && !args.quiet);
+        let (header, payload, stats) =
+            recover_payload_with_progress(&picture_bytes[start..], &options, &|done| {
+                progress.set(done)
+            })
+            .with_context(|| {
+                tr(
+                    Message::RecoverFailed,
+                    &[&style::error(args.image.to_string_lossy())],
+                )
+            })?;
+        progress.finish();
+        (header, payload, stats)
+    };
+
+    // 换算为原始图像字节流中的偏移量，供 `--verbose` 报告；指定 `--region` 时为区域字节流中的偏移量
+    let end = if skipped_alpha {
+        color_range_to_full(start, start + stats.end_offset, stride + 1, &opaque).1
+    } else {
+        start + stats.end_offset
+    };
+
+    // 单独一段载荷无法解密或解压，提示恢复整组图像
+    if header.payload_type == Some(PayloadType::Segment) {
+        let segment = Segment::decode(&payload)?;
+        anyhow::bail!(tr(
+            Message::SegmentNeedsSplit,
+            &[&(segment.index + 1), &segment.count]
+        ));
+    }
+
+    Ok(RecoveredPayload {
+        header,
+        payload,
+        stats,
+        end,
+        shift,
+    })
+}
+
+/// 处理 `recover --split`：读取 `--image` 目录中的每张图像，恢复其中的载荷段并按序号拼接
+///
+/// 无法解码、不含魔数或不是载荷段的文件 (例如 hide 写出的清单) 会被忽略；拼接后的载荷沿用第一段的头部
+/// (压缩算法、加密标志等)，类型标签还原为段头中记录的完整载荷类型
+///
+/// # Errors
+///
+/// * 如果无法读取目录，或目录中没有任何载荷段，将返回错误
+/// * 如果某一段无法恢复，或各段不属于同一个载荷、缺少某些段，将返回错误
+fn recover_segments(args: &RecoverArgs) -> anyhow::Result<RecoveredPayload> {
+    let seed = scatter_seed(args.password.as_deref(), args.seed_file.as_deref())?;
+    let mut segments = Vec::new();
+    let mut first = None;
+    for path in dir_files(&args.image)? {
+        let Ok(pixels) = open_stego_pixels(&path, args.offset, 0, args.alpha_threshold, None, None)
+        else {
+            continue;
+        };
+        let bytes = &pixels.bytes[pixels.start..];
+        if !has_magic(bytes) {
+            continue;
+        }
+        let options = RecoverOptions {
+            stride: pixels.stride,
+            scatter_seed: seed,
+            payload_limit: args.payload_limit,
+            edge_adaptive: args.edge_adaptive,
+            endian: args.endian,
+            best_effort: false,
+        };
+        let (header, payload, stats) =
+            recover_payload_with_stats(bytes, &options).with_context(|| {
+                tr(
+                    Message::RecoverFailed,
+                    &[&style::error(path.to_string_lossy())],
+                )
+            })?;
+        if header.payload_type != Some(PayloadType::Segment) {
+            continue;
+        }
+        segments.push(Segment::decode(&payload).with_context(|| {
+            tr(
+                Message::RecoverFailed,
+                &[&style::error(path.to_string_lossy())],
+            )
+        })?);
+        first.get_or_insert((header, stats));
+    }
+
+    let Some((header, stats)) = first else {
+        anyhow::bail!(tr(
+            Message::NoSegmentsFound,
+            &[&style::error(args.image.to_string_lossy())]
+        ));
+    };
+    let (payload, payload_type) = segment::join(segments)?;
+    Ok(RecoveredPayload {
+        header: Header {
+            length: payload.len() as u64,
+            payload_type,
+            ..header
+        },
+        stats: EmbedStats {
+            payload_len: payload.len(),
+            ..stats
+        },
+        end: stats.end_offset,
+        shift: 0,
+        payload,
+    })
+}
+
+/// 处理 'Capacity' 命令的执行逻辑
+///
+/// 读取图像并计算其在默认位深度下最多能隐藏的载荷字节数，同时报告图像尺寸和通道数
+/// 对于带 alpha 通道的图像，会分别报告包含与不包含 alpha 通道时的容量
 ///
 /// # Arguments
 ///
-/// * `args` - 包含输入/输出路径的 `HideArgs` 结构体
+/// * `args` - 包含输入图像路径的 `CapacityArgs` 结构体
 ///
 /// # Errors
 ///
-/// 如果发生以下任一情况，将返回错误：
-/// * 写入路径文件已存在，且没有 `--force` 标识
-/// * 无法读取输入的图像或文本文件
-/// * 图像文件没有足够的空间来隐藏文本
-/// * 核心隐写函数 (`modify`) 在执行过程中失败
-/// * 无法写入到目标图像文件
-pub fn handle_hide(args: HideArgs) -> anyhow::Result<()> {
-    // 如果用户没有提供输出路径，则动态生成一个默认路径
-    let dest_path = args.dest.unwrap_or_else(|| {
-        let original_path = &args.image;
-        let original_filename = original_path
-            .file_name()
-            .and_then(|s| s.to_str())
-            .unwrap_or("output");
-        let new_filename = format!("doctored_{}", original_filename);
-        original_path.with_file_name(new_filename)
-    });
+/// 如果无法读取输入的图像文件，或图像是浮点颜色类型 (如 32 位浮点 TIFF)，将返回错误
+pub fn handle_capacity(args: CapacityArgs) -> anyhow::Result<()> {
+    // 读取图像文件
+    let img = open_image(&args.image)?;
 
-    // 在写入前检查文件是否存在，防止意外覆盖
-    anyhow::ensure!(
-        !dest_path.exists() || args.force,
-        "Output file already exists: {}.\nUse --force to overwrite.",
-        dest_path.to_string_lossy().yellow().bold()
-    );
+    let (width, height) = img.dimensions();
+
+    // 与 hide/recover 保持一致，按原始颜色类型计算每个像素的通道字节数
+    let (_, channels) = into_pixel_bytes(img)?;
+    let pixel_count = width as usize * height as usize;
+
+    // 计算每个像素有 `stride` 个通道字节时可隐藏的最大载荷字节数
+    let max_payload =
+        |stride: usize| max_payload_bytes(pixel_count * stride, DEFAULT_BITS as usize);
 
-    // 读取源图像
-    let img = image::open(&args.image).with_context(|| {
-        format!(
-            "Unable to read image file: {}",
-            args.image.to_string_lossy().red().bold()
+    if args.json {
+        println!(
+            "{}",
+            json!({
+                "status": "ok",
+                "image": args.image.to_string_lossy(),
+                "width": width,
+                "height": height,
+                "channels": channels,
+                "capacity_bytes": max_payload(channels),
+            })
+        );
+        return Ok(());
+    }
+
+    println!(
+        "{}",
+        tr(
+            Message::CapacityImage,
+            &[
+                &style::success(args.image.to_string_lossy()),
+                &width,
+                &height,
+                &channels
+            ]
         )
-    })?;
+    );
 
-    let (width, height) = img.dimensions();
+    if has_alpha(channels) {
+        println!(
+            "{}",
+            tr(
+                Message::MaxPayloadWithAlpha,
+                &[
+                    &COLOR_TYPE_NAMES[channels - 1],
+                    &style::success(max_payload(channels).to_string())
+                ]
+            )
+        );
+        println!(
+            "{}",
+            tr(
+                Message::MaxPayloadWithoutAlpha,
+                &[
+                    &COLOR_TYPE_NAMES[channels - 2],
+                    &style::success(max_payload(channels - 1).to_string())
+                ]
+            )
+        );
+    } else {
+        println!(
+            "{}",
+            tr(
+                Message::MaxPayload,
+                &[&style::success(max_payload(channels).to_string())]
+            )
+        );
+    }
 
-    // 将图像转换为字节流，判断并记录原始颜色格式（RGB/RGBA）
-    let (mut picture_bytes, is_rgba) = match img {
-        DynamicImage::ImageRgba8(rgba) => (rgba.into_raw(), true),
-        _ => (img.into_rgb8().into_raw(), false),
-    };
+    Ok(())
+}
 
-    let text = fs::read(&args.text).with_context(|| {
-        format!(
-            "Unable to read text file: {}",
-            args.text.to_string_lossy().red().bold()
+/// 处理 'Info' 命令的执行逻辑
+///
+/// 只读地检查图像是否包含 lsb_hide 载荷；如果包含，则报告头部记录的载荷长度、位深度、
+/// 是否加密以及压缩算法等信息，不会恢复或写出载荷本身
+///
+/// # Arguments
+///
+/// * `args` - 包含输入图像路径及像素偏移量的 `InfoArgs` 结构体
+///
+/// # Errors
+///
+/// * 如果无法读取输入的图像文件，或偏移量超出图像的像素数，将返回错误
+/// * 如果图像包含魔数但头部已损坏，将返回错误
+pub fn handle_info(args: InfoArgs) -> anyhow::Result<()> {
+    let pixels = open_stego_pixels(
+        &args.image,
+        args.offset,
+        0,
+        args.alpha_threshold,
+        args.frame,
+        args.region,
+    )?;
+    let picture_bytes = &pixels.bytes[pixels.start..];
+
+    if !has_magic(picture_bytes) {
+        if args.json {
+            println!(
+                "{}",
+                json!({
+                    "status": "ok",
+                    "image": args.image.to_string_lossy(),
+                    "payload_detected": false,
+                })
+            );
+        } else {
+            println!(
+                "{}",
+                tr(
+                    Message::NoPayloadDetected,
+                    &[&style::warning(args.image.to_string_lossy())]
+                )
+            );
+        }
+        return Ok(());
+    }
+
+    let header = read_header(picture_bytes).with_context(|| {
+        tr(
+            Message::CorruptedHeader,
+            &[&style::error(args.image.to_string_lossy())],
         )
     })?;
 
-    // 检查图像是否有足够的空间来隐藏文本
-    let required_space = text.len() * BYTES_PER_CHAR;
-    let available_space = picture_bytes.len().saturating_sub(LENGTH_HIDING_BYTES);
+    if args.json {
+        println!(
+            "{}",
+            json!({
+                "status": "ok",
+                "image": args.image.to_string_lossy(),
+                "payload_detected": true,
+                "payload_bytes": (!header.is_delimited()).then_some(header.length),
+                "delimited": header.is_delimited(),
+                "version": header.version,
+                "bits": header.bits,
+                "plane": header.plane,
+                "stride": header.spacing,
+                "encrypted": header.is_encrypted(),
+                "compression": header.compression.to_string(),
+                "channels": header.channels.to_string(),
+                "scattered": header.is_scattered(),
+                "archive": header.is_archive(),
+                "skip_alpha": pixels.skipped_alpha,
+                "name": header.name,
+                "length_bits": header.length_bits,
+                "redundancy": header.redundancy,
+                "payload_type": header.payload_type.map(|payload_type| payload_type.to_string()),
+                "text_encoding": header.text_encoding.map(encoding_rs::Encoding::name),
+                "timestamp": header.provenance.as_ref().map(|provenance| provenance.timestamp),
+                "tool_version": header.provenance.as_ref().map(|provenance| &provenance.tool_version),
+            })
+        );
+        return Ok(());
+    }
 
-    anyhow::ensure!(
-        available_space >= required_space,
-        "Not enough space in the image to hide the text. \nRequired: {}, Available: {}",
-        required_space.to_string().red().bold(),
-        available_space.to_string().green().bold()
+    let yes_no = |value: bool| tr(if value { Message::Yes } else { Message::No }, &[]);
+    println!(
+        "{}",
+        tr(
+            Message::PayloadDetected,
+            &[&style::success(args.image.to_string_lossy())]
+        )
+    );
+    // 分隔模式的头部不记录长度，只有恢复载荷时才能得知
+    if header.is_delimited() {
+        println!("{}", tr(Message::PayloadSizeUnknown, &[]));
+        println!("{}", tr(Message::LengthFieldDelimited, &[]));
+    } else {
+        println!("{}", tr(Message::PayloadSize, &[&header.length]));
+        match header.length_bits {
+            Some(bits) => println!("{}", tr(Message::LengthFieldFixed, &[&bits])),
+            None => println!("{}", tr(Message::LengthFieldVariable, &[])),
+        }
+    }
+    println!("{}", tr(Message::FormatVersion, &[&header.version]));
+    println!("{}", tr(Message::BitDepth, &[&header.bits]));
+    println!("{}", tr(Message::BitPlane, &[&header.plane]));
+    println!("{}", tr(Message::ByteStride, &[&header.spacing]));
+    println!(
+        "{}",
+        tr(Message::Encrypted, &[&yes_no(header.is_encrypted())])
     );
+    println!("{}", tr(Message::Compression, &[&header.compression]));
+    println!("{}", tr(Message::Channels, &[&header.channels]));
+    println!(
+        "{}",
+        tr(Message::Scattered, &[&yes_no(header.is_scattered())])
+    );
+    println!("{}", tr(Message::Archive, &[&yes_no(header.is_archive())]));
+    println!("{}", tr(Message::Redundancy, &[&header.redundancy]));
+    let payload_type = match header.payload_type {
+        Some(payload_type) => payload_type.to_string(),
+        None => tr(Message::Unknown, &[]),
+    };
+    println!("{}", tr(Message::PayloadType, &[&payload_type]));
+    if let Some(encoding) = header.text_encoding {
+        println!("{}", tr(Message::TextEncoding, &[&encoding.name()]));
+    }
+    println!(
+        "{}",
+        tr(Message::AlphaSkipped, &[&yes_no(pixels.skipped_alpha)])
+    );
+    if !header.name.is_empty() {
+        println!("{}", tr(Message::FileName, &[&header.name]));
+    }
+    if let Some(provenance) = describe_provenance(&header) {
+        println!("{}", provenance);
+    }
+
+    Ok(())
+}
 
-    // 隐藏文本长度
-    let text_len = text.len() as u64;
-    modify(text_len, &mut picture_bytes, 0, LENGTH_HIDING_BYTES).with_context(|| {
-        format!(
-            "Failed to hide the text length: {}",
-            text_len.to_string().red().bold()
+/// 将头部中记录的来源信息格式化为一行说明，未记录时返回 `None`
+fn describe_provenance(header: &Header) -> Option<String> {
+    header.provenance.as_ref().map(|provenance| {
+        tr(
+            Message::EmbeddedBy,
+            &[&provenance.tool_version, &provenance.timestamp],
+        )
+    })
+}
+
+/// 处理 'Verify' 命令的执行逻辑
+///
+/// 与 `recover` 相同地从图像中恢复载荷 (必要时解密并解压)，再与 `--text` 指定的预期文件逐字节比较，
+/// 不会写出任何文件，适合在 CI 中确认图像仍然携带预期的载荷
+///
+/// # Arguments
+///
+/// * `args` - 包含图像路径、预期载荷文件路径及恢复参数的 `VerifyArgs` 结构体
+///
+/// # Errors
+///
+/// * 如果无法读取预期的载荷文件或输入的图像文件，将返回错误
+/// * 如果图像中不包含 lsb_hide 载荷，或载荷无法恢复、解密，将返回错误
+/// * 如果载荷是多文件归档，将返回错误
+/// * 如果恢复出的载荷与预期文件不一致，将返回附带差异摘要的错误
+pub fn handle_verify(args: VerifyArgs) -> anyhow::Result<()> {
+    let expected = fs::read(&args.text).with_context(|| {
+        tr(
+            Message::UnreadableExpected,
+            &[&style::error(args.text.to_string_lossy())],
         )
     })?;
 
-    // 逐字节隐藏文本内容
-    text.iter().enumerate().try_for_each(|(i, &char_byte)| {
-        let offset = LENGTH_HIDING_BYTES + BYTES_PER_CHAR * i;
-        modify(char_byte as u64, &mut picture_bytes, offset, BYTES_PER_CHAR).with_context(|| {
-            let char_info = std::str::from_utf8(&[char_byte])
-                .map(ToString::to_string)
-                .unwrap_or_else(|_| format!("byte value {}", char_byte));
-            format!(
-                "Failed to hide character {} (at index {}).",
-                char_info.red().bold(),
-                i.to_string().green()
-            )
-        })
+    let pixels = open_stego_pixels(
+        &args.image,
+        args.offset,
+        0,
+        args.alpha_threshold,
+        args.frame,
+        args.region,
+    )?;
+    let picture_bytes = &pixels.bytes[pixels.start..];
+    if !has_magic(picture_bytes) {
+        return Err(StegError::MagicMismatch.into());
+    }
+
+    let options = RecoverOptions {
+        stride: pixels.stride,
+        scatter_seed: scatter_seed(args.password.as_deref(), args.seed_file.as_deref())?,
+        ..Default::default()
+    };
+    let (header, payload) = recover_payload(picture_bytes, &options).with_context(|| {
+        tr(
+            Message::RecoverFailed,
+            &[&style::error(args.image.to_string_lossy())],
+        )
     })?;
+    anyhow::ensure!(!header.is_archive(), tr(Message::VerifyArchive, &[]));
+    let decrypted = decrypt_payload(&header, payload, args.password.as_deref())?;
+    let decrypted = unwhiten_payload(&header, decrypted, args.xor_key.as_deref())?;
+    let recovered = header.compression.decompress(&decrypted)?;
+
+    if let Some(mismatch) = describe_mismatch(&expected, &recovered) {
+        anyhow::bail!(tr(
+            Message::VerifyMismatch,
+            &[
+                &style::error(args.image.to_string_lossy()),
+                &style::warning(args.text.to_string_lossy()),
+                &mismatch
+            ]
+        ));
+    }
+
+    if args.json {
+        println!(
+            "{}",
+            json!({
+                "status": "ok",
+                "image": args.image.to_string_lossy(),
+                "expected": args.text.to_string_lossy(),
+                "matched_bytes": recovered.len(),
+            })
+        );
+        return Ok(());
+    }
+
+    if args.quiet {
+        return Ok(());
+    }
+
+    println!(
+        "{}",
+        tr(
+            Message::VerifyMatch,
+            &[
+                &style::success(args.image.to_string_lossy()),
+                &style::success(args.text.to_string_lossy()),
+                &recovered.len()
+            ]
+        )
+    );
+
+    Ok(())
+}
+
+/// 比较预期的载荷与恢复出的载荷，一致时返回 `None`，否则返回差异摘要
+///
+/// 摘要包含两者的字节数、第一个不同字节的偏移量以及不同字节的总数 (长度之差也计入)
+fn describe_mismatch(expected: &[u8], recovered: &[u8]) -> Option<String> {
+    if expected == recovered {
+        return None;
+    }
+    let common = expected.len().min(recovered.len());
+    let differing = expected
+        .iter()
+        .zip(recovered)
+        .filter(|(expected, recovered)| expected != recovered)
+        .count()
+        + expected.len().abs_diff(recovered.len());
+    let first = expected
+        .iter()
+        .zip(recovered)
+        .position(|(expected, recovered)| expected != recovered)
+        .unwrap_or(common);
+    Some(tr(
+        Message::MismatchSummary,
+        &[&expected.len(), &recovered.len(), &first, &differing],
+    ))
+}
 
-    // 根据原始颜色格式（RGB/RGBA），从修改后的字节创建 DynamicImage
-    let output_img = if is_rgba {
-        ImageBuffer::<Rgba<u8>, Vec<u8>>::from_raw(width, height, picture_bytes)
-            .context("Failed to create RGBA image buffer from modified bytes.")
-            .map(DynamicImage::ImageRgba8)
+/// 处理 'Analyze' 命令的执行逻辑
+///
+/// 对图像的颜色字节进行值对卡方检验，报告检验统计量以及含有 LSB 隐写数据的概率估计
+/// alpha 通道通常是恒定值，会干扰检验，因此不参与统计
+///
+/// # Arguments
+///
+/// * `args` - 包含输入图像路径的 `AnalyzeArgs` 结构体
+///
+/// # Errors
+///
+/// 如果无法读取输入的图像文件，或图像是浮点颜色类型 (如 32 位浮点 TIFF)，将返回错误
+pub fn handle_analyze(args: AnalyzeArgs) -> anyhow::Result<()> {
+    let img = open_image(&args.image)?;
+    let (bytes, stride) = into_pixel_bytes(img)?;
+    let color_bytes = if has_alpha(stride) {
+        strip_alpha(&bytes, stride, &opaque_pixels(&bytes, stride, 0))
     } else {
-        ImageBuffer::<Rgb<u8>, Vec<u8>>::from_raw(width, height, picture_bytes)
-            .context("Failed to create RGB image buffer from modified bytes.")
-            .map(DynamicImage::ImageRgb8)
-    }?;
+        bytes
+    };
 
-    output_img.save(&dest_path).with_context(|| {
-        format!(
-            "Unable to write to target image file: {}",
-            dest_path.to_string_lossy().red().bold()
+    let report = analysis::chi_square(&color_bytes);
+
+    if args.json {
+        println!(
+            "{}",
+            json!({
+                "status": "ok",
+                "image": args.image.to_string_lossy(),
+                "chi_square": report.statistic,
+                "degrees_of_freedom": report.degrees_of_freedom,
+                "probability": report.probability,
+            })
+        );
+        return Ok(());
+    }
+
+    let percent = format!("{:.2}%", report.probability * 100.0);
+    println!(
+        "{}",
+        tr(
+            Message::ImageLabel,
+            &[&style::success(args.image.to_string_lossy())]
         )
-    })?;
+    );
+    println!(
+        "{}",
+        tr(
+            Message::ChiSquare,
+            &[
+                &format!("{:.2}", report.statistic),
+                &report.degrees_of_freedom
+            ]
+        )
+    );
+    let probability = if report.probability >= 0.5 {
+        style::warning(percent)
+    } else {
+        style::success(percent)
+    };
+    println!("{}", tr(Message::HiddenProbability, &[&probability]));
+
+    Ok(())
+}
+
+/// 处理 'Diff' 命令的执行逻辑
+///
+/// 逐字节比较原始图像与隐写后的图像，报告改动的字节数、最低有效位被翻转的字节数、最大差值与 PSNR，
+/// 并可按 `--amplify` 倍放大差值后写出差异图像。16 位图像只比较 hide 会修改的低字节
+///
+/// # Arguments
+///
+/// * `args` - 包含两张图像路径及差异图像输出路径的 `DiffArgs` 结构体
+///
+/// # Errors
+///
+/// * 如果无法读取任一图像文件，或图像是浮点颜色类型，将返回错误
+/// * 如果两张图像的尺寸或颜色类型不同，将返回错误
+/// * 如果差异图像的输出路径已存在且没有 `--force` 标志，或无法写出差异图像，将返回错误
+pub fn handle_diff(args: DiffArgs) -> anyhow::Result<()> {
+    if let Some(dest_path) = &args.dest {
+        ensure_overwritable(dest_path, args.force, args.interactive)?;
+        ensure_parent_dir(dest_path, false)?;
+    }
+
+    let cover = open_image(&args.cover)?;
+    let stego = open_image(&args.stego)?;
+    let (width, height) = cover.dimensions();
+    let (cover_bytes, stride) = into_pixel_bytes(cover)?;
+    let stego_dimensions = stego.dimensions();
+    let (stego_bytes, stego_stride) = into_pixel_bytes(stego)?;
+    anyhow::ensure!(
+        (width, height, stride) == (stego_dimensions.0, stego_dimensions.1, stego_stride),
+        tr(
+            Message::DiffShapeMismatch,
+            &[
+                &style::error(args.cover.to_string_lossy()),
+                &width,
+                &height,
+                &stride,
+                &style::error(args.stego.to_string_lossy()),
+                &stego_dimensions.0,
+                &stego_dimensions.1,
+                &stego_stride
+            ]
+        )
+    );
+
+    let report = analysis::diff(&cover_bytes, &stego_bytes);
+
+    // 差异图像保持原始的通道布局，alpha 通道置为不透明，以免差值为 0 的像素完全透明
+    if let Some(dest_path) = &args.dest {
+        let mut amplified = analysis::amplify_diff(&cover_bytes, &stego_bytes, args.amplify);
+        if has_alpha(stride) {
+            for pixel in amplified.chunks_exact_mut(stride) {
+                pixel[stride - 1] = u8::MAX;
+            }
+        }
+        from_pixel_bytes(width, height, amplified, stride, None)?
+            .save(dest_path)
+            .with_context(|| {
+                tr(
+                    Message::WriteImageFailed,
+                    &[&style::error(dest_path.to_string_lossy())],
+                )
+            })?;
+    }
+
+    if args.json {
+        println!(
+            "{}",
+            json!({
+                "status": "ok",
+                "cover": args.cover.to_string_lossy(),
+                "stego": args.stego.to_string_lossy(),
+                "bytes": report.bytes,
+                "changed_bytes": report.changed_bytes,
+                "changed_lsbs": report.changed_lsbs,
+                "max_delta": report.max_delta,
+                "mse": report.mse,
+                "psnr": report.psnr.is_finite().then_some(report.psnr),
+                "dest": args.dest.as_ref().map(|dest| dest.to_string_lossy()),
+            })
+        );
+        return Ok(());
+    }
 
+    if args.quiet {
+        return Ok(());
+    }
+
+    println!(
+        "{}",
+        tr(
+            Message::DiffCover,
+            &[&style::success(args.cover.to_string_lossy())]
+        )
+    );
+    println!(
+        "{}",
+        tr(
+            Message::DiffStego,
+            &[&style::success(args.stego.to_string_lossy())]
+        )
+    );
+    let percent = format!(
+        "{:.2}%",
+        report.changed_bytes as f64 * 100.0 / report.bytes.max(1) as f64
+    );
     println!(
-        "The text has been successfully hidden and saved: {}",
-        dest_path.to_string_lossy().green().bold()
+        "{}",
+        tr(
+            Message::DiffChangedBytes,
+            &[&report.changed_bytes, &report.bytes, &percent]
+        )
+    );
+    println!("{}", tr(Message::DiffChangedLsbs, &[&report.changed_lsbs]));
+    println!("{}", tr(Message::DiffMaxDelta, &[&report.max_delta]));
+    println!(
+        "{}",
+        tr(Message::DiffPsnr, &[&format!("{:.2}", report.psnr)])
     );
+    if let Some(dest_path) = &args.dest {
+        println!(
+            "{}",
+            tr(
+                Message::DiffImageSaved,
+                &[&style::success(dest_path.to_string_lossy())]
+            )
+        );
+    }
 
     Ok(())
 }
 
-/// 处理 'Recover' 命令的执行逻辑
+/// 处理 'Preview' 命令的执行逻辑
 ///
-/// 负责读取经过隐写的图像文件、调用恢复核心函数获取文本长度和每个字符，
-/// 最后将恢复的文本内容写入目标文本文件
+/// 取出每个像素字节中 `--plane` 所选的一位并放大为 0 或 255，按原始的通道布局写出为图像，
+/// 使顺序嵌入形成的规则条纹与打散嵌入的均匀噪声都能直接看出。16 位图像只预览 hide 会修改的低字节
 ///
 /// # Arguments
 ///
-/// * `args` - 包含输入/输出路径的 `RecoverArgs` 结构体
+/// * `args` - 包含图像路径、输出路径与位平面的 `PreviewArgs` 结构体
 ///
 /// # Errors
 ///
-/// 如果发生以下任一情况，将返回错误：
-/// * 写入路径文件已存在，且没有 `--force` 标志
-/// * 无法读取输入的图像文件
-/// * 核心恢复函数 (`recover`) 在执行过程中失败
-/// * 无法写入到目标文本文件
-pub fn handle_recover(args: RecoverArgs) -> anyhow::Result<()> {
-    // 如果用户没有提供输出路径，则动态生成一个默认路径。
-    let text_path = args.text.unwrap_or_else(|| {
-        let original_path = &args.image;
-        let original_filename = original_path
-            .file_stem() // 获取不带扩展名的文件名
+/// * 如果无法读取图像文件，或图像是浮点颜色类型，将返回错误
+/// * 如果输出路径已存在且没有 `--force` 标志，或无法写出位平面图像，将返回错误
+pub fn handle_preview(args: PreviewArgs) -> anyhow::Result<()> {
+    let dest_path = args.dest.clone().unwrap_or_else(|| {
+        let stem = args
+            .image
+            .file_stem()
             .and_then(|s| s.to_str())
             .unwrap_or("output");
-        let new_filename = format!("recovered_{}.txt", original_filename);
-        original_path.with_file_name(new_filename)
+        sibling_path(&args.image, format!("lsb_{}.png", stem))
     });
+    ensure_overwritable(&dest_path, args.force, args.interactive)?;
+    ensure_parent_dir(&dest_path, false)?;
 
-    // 在写入前检查文件是否存在，防止意外覆盖
-    anyhow::ensure!(
-        !text_path.exists() || args.force,
-        "Output file already exists: {}.\nUse --force to overwrite.",
-        text_path.to_string_lossy().yellow().bold()
-    );
+    let img = open_image(&args.image)?;
+    let (width, height) = img.dimensions();
+    let (pixels, stride) = into_pixel_bytes(img)?;
 
-    // 读取图像文件
-    let img = image::open(&args.image).with_context(|| {
-        format!(
-            "Unable to read image file: {}",
-            args.image.to_string_lossy().red().bold()
-        )
-    })?;
+    // 与差异图像相同，alpha 通道置为不透明，以免所选位为 0 的像素完全透明
+    let mut plane = analysis::bit_plane(&pixels, args.plane);
+    let set_bits = plane.iter().filter(|&&byte| byte != 0).count();
+    if has_alpha(stride) {
+        for pixel in plane.chunks_exact_mut(stride) {
+            pixel[stride - 1] = u8::MAX;
+        }
+    }
+    from_pixel_bytes(width, height, plane, stride, None)?
+        .save(&dest_path)
+        .with_context(|| {
+            tr(
+                Message::WriteImageFailed,
+                &[&style::error(dest_path.to_string_lossy())],
+            )
+        })?;
 
-    // 根据原始颜色格式（RGB/RGBA），将图像转换为字节流
-    let picture_bytes = match img {
-        DynamicImage::ImageRgba8(rgba) => rgba.into_raw(),
-        _ => img.into_rgb8().into_raw(),
-    };
+    if args.json {
+        println!(
+            "{}",
+            json!({
+                "status": "ok",
+                "image": args.image.to_string_lossy(),
+                "dest": dest_path.to_string_lossy(),
+                "plane": args.plane,
+                "bytes": pixels.len(),
+                "set_bits": set_bits,
+            })
+        );
+        return Ok(());
+    }
 
-    // 恢复隐藏文本的长度
-    let text_len = recover(&picture_bytes, 0, LENGTH_HIDING_BYTES).with_context(|| {
-        format!(
-            "Failed to recover text length from image file: {}",
-            args.image.to_string_lossy().red().bold()
+    if args.quiet {
+        return Ok(());
+    }
+
+    let percent = format!(
+        "{:.2}%",
+        set_bits as f64 * 100.0 / pixels.len().max(1) as f64
+    );
+    println!(
+        "{}",
+        tr(
+            Message::PreviewSetBits,
+            &[&set_bits, &pixels.len(), &percent]
         )
-    })?;
+    );
+    println!(
+        "{}",
+        tr(
+            Message::PreviewSaved,
+            &[&args.plane, &style::success(dest_path.to_string_lossy())]
+        )
+    );
 
-    // 根据恢复的长度，逐字节恢复文本内容
-    let text: Vec<u8> = (0..text_len as usize)
-        .map(|i| {
-            let offset = LENGTH_HIDING_BYTES + BYTES_PER_CHAR * i;
-            recover(&picture_bytes, offset, BYTES_PER_CHAR)
-                .map(|value| value as u8)
-                .with_context(|| {
-                    format!(
-                        "Failed to recover character at index {}. \nThe data at offset {} appears to be corrupted or invalid.",
-                        i.to_string().red().bold(),
-                        offset.to_string().red().bold()
-                    )
+    Ok(())
+}
+
+/// 处理 'ListFormats' 命令的执行逻辑
+///
+/// 分别列出可以读取的图像格式与可以安全写出隐写结果的无损格式，以及各自对应的扩展名
+///
+/// # Arguments
+///
+/// * `args` - 包含输出方式的 `ListFormatsArgs` 结构体
+///
+/// # Errors
+///
+/// 该命令不会失败，返回 `Result` 只是为了与其他子命令保持一致
+pub fn handle_list_formats(args: ListFormatsArgs) -> anyhow::Result<()> {
+    let read = readable_formats();
+    let write = safe_output_formats();
+
+    if args.json {
+        let describe = |formats: &[ImageFormat]| {
+            formats
+                .iter()
+                .map(|format| {
+                    json!({
+                        "format": format!("{:?}", format),
+                        "extensions": format.extensions_str(),
+                    })
                 })
-        })
-        .collect::<anyhow::Result<Vec<u8>>>()?;
+                .collect::<Vec<_>>()
+        };
+        println!(
+            "{}",
+            json!({
+                "status": "ok",
+                "read": describe(&read),
+                "write": describe(&write),
+            })
+        );
+        return Ok(());
+    }
 
-    fs::write(&text_path, text).with_context(|| {
-        format!(
-            "Unable to write to target text file: {}",
-            text_path.to_string_lossy().red().bold()
-        )
-    })?;
+    let print = |formats: &[ImageFormat]| {
+        for format in formats {
+            println!(
+                "  {:<6} {}",
+                style::success(format!("{:?}", format)),
+                format.extensions_str().join(", ")
+            );
+        }
+    };
+    println!("{}", tr(Message::ReadableFormats, &[]));
+    print(&read);
+    println!("{}", tr(Message::SafeOutputFormats, &[]));
+    print(&write);
+
+    Ok(())
+}
+
+/// 处理 'Clean' 命令的执行逻辑
+///
+/// 读取图像，将每个像素字节 (包括 alpha 通道) 的最低 `--bits` 位清零或替换为随机位，再写出到目标图像文件
+/// 图像中以不超过该位深度隐藏的任何数据都会被销毁，而图像在视觉上保持不变
+///
+/// # Arguments
+///
+/// * `args` - 包含输入/输出路径及处理方式的 `CleanArgs` 结构体
+///
+/// # Errors
+///
+/// 如果发生以下任一情况，将返回错误：
+/// * 写入路径不是已知的无损图像格式 (如 JPEG)
+/// * 写入路径文件已存在，且没有 `--force` 标志，用户也没有在终端中确认覆盖
+/// * 无法读取输入的图像文件，或图像是浮点颜色类型 (如 32 位浮点 TIFF)
+/// * 无法写入到目标图像文件
+pub fn handle_clean(args: CleanArgs) -> anyhow::Result<()> {
+    let dest_path = args.dest.unwrap_or_else(|| {
+        let original_filename = args
+            .image
+            .file_name()
+            .and_then(|s| s.to_str())
+            .unwrap_or("output");
+        sibling_path(&args.image, format!("cleaned_{}", original_filename))
+    });
+
+    // 有损格式写出的像素字节不受控制，与 hide 一样只允许无损格式
+    validate_lossless_format(&dest_path)?;
+    ensure_overwritable(&dest_path, args.force, args.interactive)?;
+
+    let img = open_image(&args.image)?;
+    let (width, height) = img.dimensions();
+    let high = high_bytes(&img);
+    let (mut picture_bytes, stride) = into_pixel_bytes(img)?;
+    clean_low_bits(&mut picture_bytes, args.bits, args.mode);
+
+    from_pixel_bytes(width, height, picture_bytes, stride, high)?
+        .save(&dest_path)
+        .with_context(|| {
+            tr(
+                Message::WriteImageFailed,
+                &[&style::error(dest_path.to_string_lossy())],
+            )
+        })?;
+
+    if args.json {
+        println!(
+            "{}",
+            json!({
+                "status": "ok",
+                "dest": dest_path.to_string_lossy(),
+                "mode": args.mode.to_string(),
+                "bits": args.bits,
+            })
+        );
+        return Ok(());
+    }
+
+    if args.quiet {
+        return Ok(());
+    }
 
     println!(
-        "The text has been successfully recovered and saved: {}",
-        text_path.to_string_lossy().green().bold()
+        "{}",
+        tr(
+            Message::CleanDone,
+            &[
+                &args.bits,
+                &args.mode,
+                &style::success(dest_path.to_string_lossy())
+            ]
+        )
     );
 
     Ok(())
 }
+
+/// 将每个像素字节的最低 `bits` 位按 `mode` 清零或替换为随机位，其余高位保持不变
+fn clean_low_bits(pixels: &mut [u8], bits: u8, mode: CleanMode) {
+    let mask = ((1u16 << bits) - 1) as u8;
+    let mut noise = vec![0u8; pixels.len()];
+    if mode == CleanMode::Random {
+        rand::rng().fill_bytes(&mut noise);
+    }
+    pixels
+        .iter_mut()
+        .zip(noise)
+        .for_each(|(byte, noise)| *byte = (*byte & !mask) | (noise & mask));
+}
+
+/// 在内存中的图像文件 (`input`) 里隐藏载荷 (`payload`)，并返回以 `format` 编码的结果图像文件
+///
+/// 与 `hide` 子命令的默认设置相同：不加密、不压缩，使用全部通道和默认位深度，输出图像保持原始的颜色类型。
+/// 全程不访问文件系统，适合 WASM 或服务端等场景
+///
+/// # Errors
+///
+/// * 如果 `format` 不是无损格式，将返回错误
+/// * 如果 `input` 无法解码、是调色板 PNG，或是浮点颜色类型，将返回错误
+/// * 如果图像没有足够的空间来隐藏载荷，将返回错误
+/// * 如果结果图像无法以 `format` 编码，将返回错误
+pub fn hide_in_image_bytes(
+    input: &[u8],
+    payload: &[u8],
+    format: ImageFormat,
+) -> anyhow::Result<Vec<u8>> {
+    anyhow::ensure!(
+        is_lossless_format(format),
+        tr(
+            Message::NotLossless,
+            &[&format!("{:?}", format), &LOSSLESS_EXTENSIONS.join(", ")]
+        )
+    );
+    anyhow::ensure!(!is_indexed_png(input), tr(Message::IndexedPngBytes, &[]));
+    let img =
+        image::load_from_memory(input).with_context(|| tr(Message::DecodeCoverFailed, &[]))?;
+    let (width, height) = img.dimensions();
+    let high = high_bytes(&img);
+    let (mut pixel_bytes, stride) = into_pixel_bytes(img)?;
+
+    let options = HideOptions {
+        stride,
+        ..Default::default()
+    };
+    hide_bytes_with(&mut pixel_bytes, payload, &options)?;
+
+    let mut output = Cursor::new(Vec::new());
+    from_pixel_bytes(width, height, pixel_bytes, stride, high)?
+        .write_to(&mut output, format)
+        .with_context(|| tr(Message::EncodeOutputFailed, &[&format!("{:?}", format)]))?;
+    Ok(output.into_inner())
+}
+
+/// 从内存中的图像文件 (`input`) 里恢复载荷，与 [`hide_in_image_bytes`] 相对应
+///
+/// 与 `recover` 子命令一样会自动识别 `--skip-alpha` 方式隐藏的数据并按头部记录解压，但不支持口令
+///
+/// # Errors
+///
+/// * 如果 `input` 无法解码，或是浮点颜色类型，将返回错误
+/// * 如果图像中没有隐藏的载荷，或载荷已损坏，将返回错误
+/// * 如果载荷已加密或已打散 (需要口令)，或已白化 (需要密钥)，将返回错误
+pub fn recover_from_image_bytes(input: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let img =
+        image::load_from_memory(input).with_context(|| tr(Message::DecodeStegoFailed, &[]))?;
+    let (pixel_bytes, stride) = into_pixel_bytes(img)?;
+    let pixels = locate_stego_pixels(pixel_bytes, stride, 0, 0, None)?;
+    let picture_bytes = &pixels.bytes[pixels.start..];
+    if !has_magic(picture_bytes) {
+        return Err(StegError::MagicMismatch.into());
+    }
+
+    let options = RecoverOptions {
+        stride: pixels.stride,
+        ..Default::default()
+    };
+    let (header, payload) = recover_payload(picture_bytes, &options)?;
+    anyhow::ensure!(!header.is_encrypted(), tr(Message::EncryptedBytes, &[]));
+    anyhow::ensure!(!header.is_whitened(), tr(Message::WhitenedBytes, &[]));
+    header.compression.decompress(&payload)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::{MAX_BITS, NO_PAYLOAD_MESSAGE};
+
+    /// 一张 16x16 的纯色 RGBA PNG 图像
+    const COVER_PNG: &[u8] = &[
+        0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a, 0x00, 0x00, 0x00, 0x0d, 0x49, 0x48, 0x44,
+        0x52, 0x00, 0x00, 0x00, 0x10, 0x00, 0x00, 0x00, 0x10, 0x08, 0x06, 0x00, 0x00, 0x00, 0x1f,
+        0xf3, 0xff, 0x61, 0x00, 0x00, 0x00, 0x19, 0x49, 0x44, 0x41, 0x54, 0x78, 0xda, 0x63, 0x38,
+        0x51, 0xa1, 0xf1, 0x9f, 0x12, 0xcc, 0x30, 0x6a, 0xc0, 0xa8, 0x01, 0xa3, 0x06, 0x0c, 0x17,
+        0x03, 0x00, 0x5a, 0xbf, 0x67, 0x1f, 0x04, 0x7f, 0x5a, 0x05, 0x00, 0x00, 0x00, 0x00, 0x49,
+        0x45, 0x4e, 0x44, 0xae, 0x42, 0x60, 0x82,
+    ];
+
+    /// 测试只有文件大小变化超过阈值时才报告变化比例
+    #[test]
+    fn test_size_change() {
+        assert_eq!(size_change(1000, 1000), None);
+        assert_eq!(size_change(1000, 1250), None);
+        assert_eq!(size_change(1000, 1500), Some(0.5));
+        assert_eq!(size_change(1000, 100), Some(-0.9));
+        assert!(size_change(0, 10).is_some());
+    }
+
+    /// 测试在内存中隐藏并恢复载荷，输出仍是原始尺寸与颜色类型的 PNG
+    #[test]
+    fn test_hide_and_recover_in_image_bytes() {
+        let payload = b"never touches the disk";
+        let output = hide_in_image_bytes(COVER_PNG, payload, ImageFormat::Png)
+            .expect("Failed to hide payload in memory.");
+
+        assert_eq!(image::guess_format(&output).unwrap(), ImageFormat::Png);
+        let img = image::load_from_memory(&output).unwrap();
+        assert_eq!(img.dimensions(), (16, 16));
+        assert!(matches!(img, DynamicImage::ImageRgba8(_)));
+
+        let recovered =
+            recover_from_image_bytes(&output).expect("Failed to recover payload in memory.");
+        assert_eq!(recovered, payload);
+
+        // 原始图像中没有载荷
+        let err = recover_from_image_bytes(COVER_PNG).unwrap_err();
+        assert_eq!(err.to_string(), NO_PAYLOAD_MESSAGE);
+    }
+
+    /// 测试覆盖确认只接受 `y` 或 `yes`，并在询问中包含文件路径
+    #[test]
+    fn test_confirm_overwrite_reads_answer() {
+        let path = Path::new("out.png");
+        for (answer, expected) in [
+            ("y\n", true),
+            ("YES\n", true),
+            (" y \n", true),
+            ("n\n", false),
+            ("\n", false),
+            ("", false),
+            ("yep\n", false),
+        ] {
+            let mut prompt = Vec::new();
+            let confirmed = confirm_overwrite(path, &mut answer.as_bytes(), &mut prompt).unwrap();
+            assert_eq!(confirmed, expected, "{:?}", answer);
+            let prompt = String::from_utf8(prompt).unwrap();
+            assert!(prompt.contains("out.png"), "{}", prompt);
+            assert!(prompt.ends_with("Output file exists, overwrite? [y/N] "));
+        }
+    }
+
+    /// 测试非交互环境中已存在的文件直接报错，不会等待输入
+    #[test]
+    fn test_ensure_overwritable_without_terminal() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("out.png");
+        assert!(ensure_overwritable(&path, false, false).is_ok());
+
+        fs::write(&path, b"existing").unwrap();
+        let err = ensure_overwritable(&path, false, false).unwrap_err();
+        assert!(err.to_string().starts_with("Output file already exists"));
+        assert!(ensure_overwritable(&path, true, false).is_ok());
+    }
+
+    /// 测试清除只改变最低的若干位：清零模式下这些位全部为 0，随机模式下高位保持不变
+    #[test]
+    fn test_clean_low_bits() {
+        let original: Vec<u8> = (0..=255).collect();
+        for bits in 1..=MAX_BITS {
+            let high = !(((1u16 << bits) - 1) as u8);
+
+            let mut zeroed = original.clone();
+            clean_low_bits(&mut zeroed, bits, CleanMode::Zero);
+            assert!(
+                zeroed
+                    .iter()
+                    .zip(&original)
+                    .all(|(&after, &before)| after == before & high)
+            );
+
+            let mut randomized = original.clone();
+            clean_low_bits(&mut randomized, bits, CleanMode::Random);
+            assert!(
+                randomized
+                    .iter()
+                    .zip(&original)
+                    .all(|(&after, &before)| after & high == before & high)
+            );
+        }
+    }
+
+    /// 测试默认模板与原有的默认文件名一致，自定义模板按占位符展开
+    #[test]
+    fn test_name_template_expand() {
+        let default = NameTemplate::new(None).unwrap();
+        assert_eq!(default.expand("cat", Some("txt")), "recovered_cat.txt");
+        assert_eq!(default.expand("cat", None), "recovered_cat");
+
+        let custom = NameTemplate::new(Some("{stem}-secret.{ext}")).unwrap();
+        assert_eq!(custom.expand("cat", Some("bin")), "cat-secret.bin");
+        assert_eq!(custom.expand("cat", None), "cat-secret");
+        assert_eq!(custom.expand("{ext}", Some("bin")), "{ext}-secret.bin");
+
+        assert!(NameTemplate::new(Some("out.{ext}")).is_err());
+    }
+
+    /// 测试有损输出格式会被拒绝
+    #[test]
+    fn test_hide_in_image_bytes_rejects_lossy_format() {
+        let err = hide_in_image_bytes(COVER_PNG, b"x", ImageFormat::Jpeg).unwrap_err();
+        assert!(err.to_string().contains("not lossless"), "{}", err);
+    }
+
+    /// 测试差异摘要报告长度、第一个不同字节的偏移量与不同字节的总数
+    #[test]
+    fn test_describe_mismatch() {
+        assert_eq!(describe_mismatch(b"same", b"same"), None);
+        assert_eq!(
+            describe_mismatch(b"abcd", b"abXY").as_deref(),
+            Some(
+                "expected 4 bytes, recovered 4 bytes; first difference at byte 2, 2 bytes differ."
+            )
+        );
+        assert_eq!(
+            describe_mismatch(b"abc", b"abcde").as_deref(),
+            Some(
+                "expected 3 bytes, recovered 5 bytes; first difference at byte 3, 2 bytes differ."
+            )
+        );
+    }
+
+    /// 测试只有标记为文本的载荷会按 UTF-8 校验，二进制载荷与 `--binary` 都跳过校验
+    #[test]
+    fn test_should_validate_utf8() {
+        assert!(should_validate_utf8(Some(PayloadType::Text), false));
+        assert!(!should_validate_utf8(Some(PayloadType::Text), true));
+        for payload_type in [
+            None,
+            Some(PayloadType::Binary),
+            Some(PayloadType::Archive),
+            Some(PayloadType::Encrypted),
+            Some(PayloadType::Whitened),
+        ] {
+            assert!(!should_validate_utf8(payload_type, false));
+        }
+    }
+
+    /// 测试只有记录了原始编码，或指定了输出编码的文本载荷才会转换编码
+    #[test]
+    fn test_text_transcoding() {
+        use encoding_rs::{GBK, SHIFT_JIS, UTF_8};
+
+        let header_with = |text_encoding, payload_type| {
+            let mut pixels = vec![0u8; 1024];
+            let options = HideOptions {
+                text_encoding,
+                payload_type,
+                ..Default::default()
+            };
+            hide_bytes_with(&mut pixels, b"", &options).unwrap();
+            read_header(&pixels).unwrap()
+        };
+
+        let gbk = header_with(Some(GBK), Some(PayloadType::Text));
+        assert_eq!(text_transcoding(&gbk, None, false), Some((GBK, UTF_8)));
+        assert_eq!(
+            text_transcoding(&gbk, Some(SHIFT_JIS), false),
+            Some((GBK, SHIFT_JIS))
+        );
+        assert_eq!(text_transcoding(&gbk, None, true), None);
+
+        let utf8 = header_with(None, Some(PayloadType::Text));
+        assert_eq!(text_transcoding(&utf8, None, false), None);
+        assert_eq!(
+            text_transcoding(&utf8, Some(GBK), false),
+            Some((UTF_8, GBK))
+        );
+
+        let binary = header_with(None, Some(PayloadType::Binary));
+        assert_eq!(text_transcoding(&binary, Some(GBK), false), None);
+    }
+
+    /// 测试像素字节流或高字节的长度与图像尺寸不符时，报告预期与实际的字节数
+    #[test]
+    fn test_from_pixel_bytes_rejects_mismatched_length() {
+        let img = from_pixel_bytes(4, 3, vec![0; 4 * 3 * 3], 3, None).unwrap();
+        assert_eq!(img.dimensions(), (4, 3));
+
+        let err = from_pixel_bytes(4, 3, vec![0; 4 * 3 * 3 - 1], 3, None).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "The pixel buffer has 35 bytes, but a 4x3 RGB image needs 36 bytes."
+        );
+
+        let err = from_pixel_bytes(2, 2, vec![0; 8], 2, Some(vec![0; 7])).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "The pixel buffer has 7 bytes, but a 2x2 LA16 image needs 8 bytes."
+        );
+    }
+
+    /// 测试 PNG 与 BMP 可以安全写出，而 JPEG 不在安全输出格式中
+    #[test]
+    fn test_safe_output_formats() {
+        let write = safe_output_formats();
+        assert!(write.contains(&ImageFormat::Png));
+        assert!(write.contains(&ImageFormat::Bmp));
+        assert!(!write.contains(&ImageFormat::Jpeg));
+
+        // 能安全写出的格式也都能读取，隐写结果才能被恢复
+        let read = readable_formats();
+        assert!(write.iter().all(|format| read.contains(format)));
+    }
+
+    /// 测试每种已知的无损格式都被接受，且扩展名不区分大小写
+    #[test]
+    fn test_validate_lossless_format_accepts_lossless() {
+        for ext in LOSSLESS_EXTENSIONS {
+            let path = PathBuf::from(format!("out.{}", ext));
+            assert!(validate_lossless_format(&path).is_ok(), "{} rejected", ext);
+
+            let upper = PathBuf::from(format!("out.{}", ext.to_ascii_uppercase()));
+            assert!(validate_lossless_format(&upper).is_ok(), "{} rejected", ext);
+        }
+    }
+
+    /// 测试有损格式、未知格式以及没有扩展名的路径都会被拒绝
+    #[test]
+    fn test_validate_lossless_format_rejects_others() {
+        for ext in LOSSY_EXTENSIONS {
+            let err = validate_lossless_format(&PathBuf::from(format!("out.{}", ext)))
+                .expect_err("Lossy formats should be rejected.");
+            assert!(err.to_string().contains("lossy"), "{}", err);
+        }
+
+        for name in ["out.gif", "out.txt", "out"] {
+            let err = validate_lossless_format(&PathBuf::from(name))
+                .expect_err("Unknown formats should be rejected.");
+            assert!(
+                err.to_string().contains("Unsupported output format"),
+                "{}",
+                err
+            );
+        }
+    }
+}
@@ -0,0 +1,676 @@
+//! # 本地化模块
+//!
+//! 集中定义各子命令处理逻辑向用户输出的提示与错误信息，并按 `--lang` (或 `LANG` 环境变量) 选择英文或中文
+//! 每条信息都是一个 [`Message`]，其模板中的 `{}` 按顺序由参数替换；JSON 输出的字段名不受语言影响
+
+use crate::constants::EMPTY_PAYLOAD_MESSAGE;
+use clap::ValueEnum;
+use std::env;
+use std::fmt::{self, Write};
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// 提示与错误信息使用的语言
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum Lang {
+    /// 英文
+    #[default]
+    En,
+
+    /// 简体中文
+    Zh,
+}
+
+impl Lang {
+    /// 根据 `LANG` 环境变量选择语言：以 `zh` 开头 (如 `zh_CN.UTF-8`) 时为中文，其余情况为英文
+    pub fn from_env() -> Self {
+        match env::var("LANG") {
+            Ok(locale) if locale.starts_with("zh") => Lang::Zh,
+            _ => Lang::En,
+        }
+    }
+}
+
+/// 当前进程使用的语言，由 `main` 在分派子命令前设置一次
+static CURRENT: AtomicU8 = AtomicU8::new(Lang::En as u8);
+
+/// 设置当前进程使用的语言
+pub fn set_lang(lang: Lang) {
+    CURRENT.store(lang as u8, Ordering::Relaxed);
+}
+
+/// 当前进程使用的语言，未设置时为英文
+pub fn lang() -> Lang {
+    match CURRENT.load(Ordering::Relaxed) {
+        value if value == Lang::Zh as u8 => Lang::Zh,
+        _ => Lang::En,
+    }
+}
+
+/// 处理逻辑输出的每一条提示或错误信息
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Message {
+    UnreadableImage,
+    UnrecognizedFormat,
+    FrameRequiresApng,
+    UnreadableText,
+    UnreadableStdin,
+    StdinWithFiles,
+    ChunkSizeNeedsFile,
+    MissingCoverImage,
+    UnreadableImageDir,
+    NoCoverFits,
+    CoverSelected,
+    HideSegmentFailed,
+    SplitDone,
+    SegmentNeedsSplit,
+    NoSegmentsFound,
+    UnknownFileName,
+    ConfirmationFailed,
+    OutputExists,
+    OverwritePrompt,
+    InvalidNameTemplate,
+    CreateDirFailed,
+    WriteFileFailed,
+    MissingDirectory,
+    LossyOutput,
+    UnsupportedOutput,
+    OffsetOutOfRange,
+    UnsupportedColorType,
+    ImageBufferFailed,
+    PixelBufferMismatch,
+    AlphaThresholdNeedsAlpha,
+    RemoteInPlace,
+    InPlaceNeedsForce,
+    IndexedPng,
+    RawBmpLayout,
+    RawBmpOutput,
+    FrameOutputPng,
+    PreserveMetadataPng,
+    Warning,
+    EmptyPayload,
+    EmptyPayloadStrict,
+    EmptyHideWarning,
+    InputEncodingArchive,
+    InvalidInputEncoding,
+    SizeChangeWarning,
+    CapacityFractionExceeded,
+    WriteImageFailed,
+    HideDryRun,
+    HideDone,
+    SidecarWritten,
+    AutoBitsRaised,
+    PayloadSize,
+    TotalCapacity,
+    CapacityUsed,
+    ModifiedRange,
+    PasswordRequired,
+    XorKeyRequired,
+    RecoverFailed,
+    NoHeaderLength,
+    ArchiveEncoding,
+    ArchiveToStdout,
+    ArchiveToClipboard,
+    ArchiveRecovered,
+    RecoveredLength,
+    ExtractionEnd,
+    Resynchronized,
+    EmptyRecoverWarning,
+    InvalidUtf8Warning,
+    LengthCopiesDisagreeWarning,
+    PayloadTruncated,
+    PayloadCorrupt,
+    Clipboard,
+    CopiedToClipboard,
+    StandardOutput,
+    StdoutWriteFailed,
+    WrittenToStdout,
+    WriteTextFailed,
+    RecoverDone,
+    CapacityImage,
+    MaxPayloadWithAlpha,
+    MaxPayloadWithoutAlpha,
+    MaxPayload,
+    NoPayloadDetected,
+    CorruptedHeader,
+    PayloadDetected,
+    Yes,
+    No,
+    Unknown,
+    PayloadSizeUnknown,
+    LengthFieldDelimited,
+    LengthFieldFixed,
+    LengthFieldVariable,
+    FormatVersion,
+    BitDepth,
+    BitPlane,
+    ByteStride,
+    Encrypted,
+    Compression,
+    Channels,
+    Scattered,
+    Archive,
+    Redundancy,
+    PayloadType,
+    TextEncoding,
+    AlphaSkipped,
+    FileName,
+    EmbeddedBy,
+    UnreadableExpected,
+    UnreadableSeedFile,
+    UnreadableSidecar,
+    EmptySeedFile,
+    VerifyArchive,
+    VerifyMismatch,
+    MismatchSummary,
+    VerifyMatch,
+    ImageLabel,
+    ChiSquare,
+    HiddenProbability,
+    DiffShapeMismatch,
+    DiffCover,
+    DiffStego,
+    DiffChangedBytes,
+    DiffChangedLsbs,
+    DiffMaxDelta,
+    DiffPsnr,
+    DiffImageSaved,
+    PreviewSetBits,
+    PreviewSaved,
+    ReadableFormats,
+    SafeOutputFormats,
+    CleanDone,
+    NotLossless,
+    IndexedPngBytes,
+    DecodeCoverFailed,
+    EncodeOutputFailed,
+    DecodeStegoFailed,
+    EncryptedBytes,
+    WhitenedBytes,
+}
+
+impl Message {
+    /// 信息在 `lang` 下的模板，其中的 `{}` 由 [`tr`] 按顺序替换为参数
+    pub fn template(self, lang: Lang) -> &'static str {
+        let (en, zh) = match self {
+            Message::UnreadableImage => ("Unable to read image file: {}", "无法读取图像文件：{}"),
+            Message::UnrecognizedFormat => (
+                "Unrecognized image format: {}.\nSupported image formats: {}.",
+                "无法识别的图像格式：{}。\n支持的图像格式：{}。",
+            ),
+            Message::FrameRequiresApng => (
+                "--frame requires an animated PNG (APNG) image: {}",
+                "--frame 需要动画 PNG (APNG) 图像：{}",
+            ),
+            Message::UnreadableText => ("Unable to read text file: {}", "无法读取文本文件：{}"),
+            Message::UnreadableStdin => (
+                "Unable to read payload from standard input.",
+                "无法从标准输入读取载荷。",
+            ),
+            Message::StdinWithFiles => (
+                "Standard input cannot be combined with other files.",
+                "标准输入不能与其他文件同时使用。",
+            ),
+            Message::ChunkSizeNeedsFile => (
+                "--chunk-size streams exactly one payload file; it cannot read standard input or pack several files.",
+                "--chunk-size 只能分块读取单个载荷文件，不能读取标准输入或打包多个文件。",
+            ),
+            Message::MissingCoverImage => (
+                "No cover image given; use --image or --image-dir.",
+                "没有提供封面图像，请使用 --image 或 --image-dir。",
+            ),
+            Message::UnreadableImageDir => (
+                "Failed to read the image directory: {}",
+                "无法读取图像目录：{}",
+            ),
+            Message::NoCoverFits => (
+                "No image in {} has room for the {}-byte payload.",
+                "{} 中没有任何图像能容纳 {} 字节的载荷。",
+            ),
+            Message::HideSegmentFailed => ("Failed to hide segment {}: {}", "无法隐藏第 {} 段：{}"),
+            Message::SplitDone => (
+                "Payload split into {} images in: {}",
+                "载荷已拆分到 {} 张图像中，保存在：{}",
+            ),
+            Message::SegmentNeedsSplit => (
+                "The image holds segment {} of {} of a split payload; recover the whole set with --split.",
+                "该图像只包含拆分载荷的第 {} 段 (共 {} 段)，请使用 --split 恢复整组图像。",
+            ),
+            Message::NoSegmentsFound => (
+                "No payload segments found in {}; point --image at the directory written by hide --split.",
+                "{} 中没有找到任何载荷段；--image 应指向 hide --split 写出的目录。",
+            ),
+            Message::CoverSelected => ("Selected cover image: {}", "已选择封面图像：{}"),
+            Message::UnknownFileName => (
+                "Unable to determine the file name of: {}",
+                "无法确定文件名：{}",
+            ),
+            Message::ConfirmationFailed => (
+                "Failed to read the overwrite confirmation.",
+                "无法读取覆盖确认。",
+            ),
+            Message::OutputExists => (
+                "Output file already exists: {}.\nUse --force to overwrite.",
+                "输出文件已存在：{}。\n使用 --force 覆盖该文件。",
+            ),
+            Message::OverwritePrompt => (
+                "{}: Output file exists, overwrite? [y/N] ",
+                "{}：输出文件已存在，是否覆盖？[y/N] ",
+            ),
+            Message::InvalidNameTemplate => (
+                "Invalid --name-template: '{}' (it must contain {stem}).",
+                "无效的 --name-template：'{}' (必须包含 {stem})。",
+            ),
+            Message::CreateDirFailed => (
+                "Unable to create output directory: {}",
+                "无法创建输出目录：{}",
+            ),
+            Message::WriteFileFailed => {
+                ("Unable to write to target file: {}", "无法写入目标文件：{}")
+            }
+            Message::MissingDirectory => (
+                "Destination directory does not exist: {}.\nUse --mkdir to create it.",
+                "目标目录不存在：{}。\n使用 --mkdir 创建该目录。",
+            ),
+            Message::LossyOutput => (
+                "Output format is lossy: {}.\nLossy compression destroys the hidden LSB data, so it could not be recovered.\nUse a lossless format such as PNG, or pass --force-lossy to write it anyway.",
+                "输出格式是有损格式：{}。\n有损压缩会破坏隐藏在最低有效位中的数据，使其无法恢复。\n请使用 PNG 等无损格式，或指定 --force-lossy 强制写出。",
+            ),
+            Message::UnsupportedOutput => (
+                "Unsupported output format: {}.\nExpected a lossless format ({}), or pass --force-lossy to write it anyway.",
+                "不支持的输出格式：{}。\n应为无损格式 ({})，或指定 --force-lossy 强制写出。",
+            ),
+            Message::OffsetOutOfRange => (
+                "Offset of {} pixels exceeds the image size ({} pixels).",
+                "偏移量 {} 像素超出了图像大小 ({} 像素)。",
+            ),
+            Message::UnsupportedColorType => (
+                "Unsupported color type: {}.\nConvert the image to 8-bit or 16-bit grayscale, RGB or RGBA first.",
+                "不支持的颜色类型：{}。\n请先将图像转换为 8 位或 16 位灰度、RGB 或 RGBA。",
+            ),
+            Message::ImageBufferFailed => (
+                "Failed to create {} image buffer from modified bytes.",
+                "无法由修改后的字节创建 {} 图像缓冲区。",
+            ),
+            Message::PixelBufferMismatch => (
+                "The pixel buffer has {} bytes, but a {}x{} {} image needs {} bytes.",
+                "像素缓冲区有 {} 个字节，但 {}x{} 的 {} 图像需要 {} 个字节。",
+            ),
+            Message::AlphaThresholdNeedsAlpha => (
+                "--alpha-threshold requires an image with an alpha channel (RGBA or LA): {}",
+                "--alpha-threshold 需要带 alpha 通道的图像 (RGBA 或 LA)：{}",
+            ),
+            Message::RemoteInPlace => (
+                "A remote image cannot be modified in place: {}",
+                "无法原地修改远程图像：{}",
+            ),
+            Message::InPlaceNeedsForce => (
+                "--in-place overwrites the original image: {}.\nUse --force to confirm.",
+                "--in-place 会覆盖原始图像：{}。\n使用 --force 确认。",
+            ),
+            Message::IndexedPng => (
+                "Indexed-color (palette) PNG images are not supported: {}.\nHiding data would rewrite the image as truecolor RGB(A), changing its encoding and size. Convert the image to RGB or RGBA first.",
+                "不支持索引颜色 (调色板) PNG 图像：{}。\n隐藏数据会将图像重写为真彩色 RGB(A)，改变其编码与大小。请先将图像转换为 RGB 或 RGBA。",
+            ),
+            Message::RawBmpLayout => (
+                "{} is not an uncompressed BMP whose pixel data starts right after the {}-byte header, as --raw-bmp requires.",
+                "{} 不是像素数据紧跟在 {} 字节文件头之后的未压缩 BMP，无法使用 --raw-bmp。",
+            ),
+            Message::RawBmpOutput => (
+                "--raw-bmp keeps the original BMP file structure, so the output must be a BMP file: {}",
+                "--raw-bmp 保留原始的 BMP 文件结构，因此输出必须是 BMP 文件：{}",
+            ),
+            Message::FrameOutputPng => (
+                "--frame can only write animated PNG output: {}",
+                "--frame 只能写出动画 PNG：{}",
+            ),
+            Message::PreserveMetadataPng => (
+                "--preserve-metadata requires a PNG cover image and PNG output: {}",
+                "--preserve-metadata 要求原始图像与输出图像都是 PNG：{}",
+            ),
+            Message::Warning => ("Warning:", "警告:"),
+            Message::EmptyPayload => (EMPTY_PAYLOAD_MESSAGE, "载荷为空"),
+            Message::EmptyPayloadStrict => (
+                "{}. Refusing to hide it because --strict is set.",
+                "{}。已指定 --strict，拒绝隐藏。",
+            ),
+            Message::EmptyHideWarning => (
+                "{}; the image will carry a zero-length payload.",
+                "{}；图像将携带长度为零的载荷。",
+            ),
+            Message::InputEncodingArchive => (
+                "--input-encoding only applies to a single text payload, not a multi-file archive.",
+                "--input-encoding 只适用于单个文本载荷，不能用于多文件归档。",
+            ),
+            Message::InvalidInputEncoding => (
+                "The payload is not valid {} text; check --input-encoding.",
+                "载荷不是有效的 {} 文本；请检查 --input-encoding。",
+            ),
+            Message::SizeChangeWarning => (
+                "the output image is {} bytes while the cover image is {} bytes ({}% change); the stego image may be detectable by comparing file sizes or formats.",
+                "输出图像为 {} 字节，而原始图像为 {} 字节 (变化 {}%)；通过对比文件大小或格式可能发现隐写图像。",
+            ),
+            Message::CapacityFractionExceeded => (
+                "The payload would use {} of the image's capacity ({} of {} bytes), more than --max-capacity-fraction {}.",
+                "载荷将占用图像容量的 {} ({} / {} 字节)，超过了 --max-capacity-fraction {}。",
+            ),
+            Message::WriteImageFailed => (
+                "Unable to write to target image file: {}",
+                "无法写入目标图像文件：{}",
+            ),
+            Message::HideDryRun => (
+                "Dry run: the text would have been hidden and saved to: {}",
+                "试运行：文本将被隐藏并保存到：{}",
+            ),
+            Message::HideDone => (
+                "The text has been successfully hidden and saved: {}",
+                "文本已成功隐藏并保存：{}",
+            ),
+            Message::SidecarWritten => (
+                "Offset, length and checksum saved to sidecar file: {}",
+                "偏移量、长度与校验和已保存到边车文件：{}",
+            ),
+            Message::AutoBitsRaised => (
+                "The payload did not fit at {} bits per byte; the bit depth was raised to {}.",
+                "载荷在每字节 {} 位时放不下，位深度已提高到 {} 位。",
+            ),
+            Message::PayloadSize => ("Payload size: {} bytes", "载荷大小：{} 字节"),
+            Message::TotalCapacity => ("Total capacity: {} bytes", "总容量：{} 字节"),
+            Message::CapacityUsed => ("Capacity used: {}%", "已用容量：{}%"),
+            Message::ModifiedRange => ("Modified byte range: {}..{}", "修改的字节范围：{}..{}"),
+            Message::PasswordRequired => (
+                "The hidden payload is encrypted. Use --password to decrypt it.",
+                "隐藏的载荷已加密。请使用 --password 解密。",
+            ),
+            Message::XorKeyRequired => (
+                "The hidden payload is whitened. Use --xor-key with the key used when hiding it.",
+                "隐藏的载荷已白化。请使用 --xor-key 提供隐藏时使用的密钥。",
+            ),
+            Message::NoHeaderLength => (
+                "--no-header requires --length to know how many bytes to extract.",
+                "--no-header 需要通过 --length 指定要提取的字节数。",
+            ),
+            Message::RecoverFailed => (
+                "Failed to recover hidden data from image file: {}",
+                "无法从图像文件中恢复隐藏的数据：{}",
+            ),
+            Message::ArchiveEncoding => (
+                "The hidden payload is a multi-file archive; --encoding {} only applies to a single recovered file.",
+                "隐藏的载荷是多文件归档；--encoding {} 只适用于恢复单个文件。",
+            ),
+            Message::ArchiveToStdout => (
+                "The hidden payload is a multi-file archive and cannot be written to standard output.\nUse --text to choose an output directory.",
+                "隐藏的载荷是多文件归档，无法写入标准输出。\n请使用 --text 指定输出目录。",
+            ),
+            Message::ArchiveToClipboard => (
+                "The hidden payload is a multi-file archive and cannot be copied to the clipboard.\nUse --text to choose an output directory.",
+                "隐藏的载荷是多文件归档，无法复制到剪贴板。\n请使用 --text 指定输出目录。",
+            ),
+            Message::ArchiveRecovered => (
+                "The archive has been successfully recovered: {} files extracted to {}",
+                "归档已成功恢复：{} 个文件已解包到 {}",
+            ),
+            Message::RecoveredLength => ("Recovered length: {} bytes", "恢复的长度：{} 字节"),
+            Message::ExtractionEnd => (
+                "Extraction stopped at byte offset: {}",
+                "提取结束于字节偏移量：{}",
+            ),
+            Message::Resynchronized => (
+                "Header resynchronized: found {} bytes after the expected offset",
+                "头部已重新同步：在预期偏移量之后 {} 字节处找到",
+            ),
+            Message::EmptyRecoverWarning => (
+                "{}; the recovered output will be empty.",
+                "{}；恢复的输出将为空。",
+            ),
+            Message::InvalidUtf8Warning => (
+                "the payload is tagged as text but is not valid UTF-8; it may be corrupted.",
+                "载荷标记为文本，但不是有效的 UTF-8，可能已损坏。",
+            ),
+            Message::LengthCopiesDisagreeWarning => (
+                "the payload length in the header ({}) disagrees with the backup at the end of the image ({}); one copy is corrupted. The payload was recovered with the length that passes the integrity check.",
+                "头部记录的载荷长度 ({}) 与图像末尾的备份 ({}) 不一致，其中一份已损坏。已按通过完整性校验的长度恢复载荷。",
+            ),
+            Message::PayloadTruncated => (
+                "the payload is damaged. Only the first {} of {} bytes could be recovered; the rest was discarded.",
+                "载荷已损坏，只恢复了前 {} 个字节 (共 {} 个)，其余部分已丢弃。",
+            ),
+            Message::PayloadCorrupt => (
+                "the payload failed its integrity check. All {} bytes were recovered, but some of them may be corrupt.",
+                "载荷未通过完整性校验。已恢复全部 {} 个字节，但其中可能含有错误的字节。",
+            ),
+            Message::Clipboard => ("clipboard", "剪贴板"),
+            Message::CopiedToClipboard => (
+                "The text has been successfully recovered and copied to the {}",
+                "文本已成功恢复并复制到{}",
+            ),
+            Message::StandardOutput => ("standard output", "标准输出"),
+            Message::StdoutWriteFailed => (
+                "Unable to write recovered data to standard output.",
+                "无法将恢复的数据写入标准输出。",
+            ),
+            Message::WrittenToStdout => (
+                "The text has been successfully recovered and written to {}",
+                "文本已成功恢复并写入{}",
+            ),
+            Message::WriteTextFailed => (
+                "Unable to write to target text file: {}",
+                "无法写入目标文本文件：{}",
+            ),
+            Message::RecoverDone => (
+                "The text has been successfully recovered and saved: {}",
+                "文本已成功恢复并保存：{}",
+            ),
+            Message::CapacityImage => (
+                "Image: {} ({}x{}, {} channels)",
+                "图像：{} ({}x{}，{} 个通道)",
+            ),
+            Message::MaxPayloadWithAlpha => (
+                "Maximum payload size ({} channels): {} bytes",
+                "最大载荷大小 ({} 通道)：{} 字节",
+            ),
+            Message::MaxPayloadWithoutAlpha => (
+                "Maximum payload size ({} channels only): {} bytes",
+                "最大载荷大小 (仅 {} 通道)：{} 字节",
+            ),
+            Message::MaxPayload => ("Maximum payload size: {} bytes", "最大载荷大小：{} 字节"),
+            Message::NoPayloadDetected => (
+                "No lsb_hide payload detected in: {}",
+                "未在图像中检测到 lsb_hide 载荷：{}",
+            ),
+            Message::CorruptedHeader => (
+                "Found an lsb_hide payload but its header is corrupted: {}",
+                "找到了 lsb_hide 载荷，但其头部已损坏：{}",
+            ),
+            Message::PayloadDetected => (
+                "lsb_hide payload detected in: {}",
+                "在图像中检测到 lsb_hide 载荷：{}",
+            ),
+            Message::Yes => ("yes", "是"),
+            Message::No => ("no", "否"),
+            Message::Unknown => ("unknown", "未知"),
+            Message::PayloadSizeUnknown => ("Payload size: unknown", "载荷大小：未知"),
+            Message::LengthFieldDelimited => (
+                "Length field: none (delimited by markers)",
+                "长度字段：无 (以标记分隔)",
+            ),
+            Message::LengthFieldFixed => ("Length field: {}-bit fixed", "长度字段：固定 {} 位"),
+            Message::LengthFieldVariable => ("Length field: variable", "长度字段：可变"),
+            Message::FormatVersion => ("Format version: {}", "格式版本：{}"),
+            Message::BitDepth => ("Bit depth: {} bits per byte", "位深度：每字节 {} 位"),
+            Message::BitPlane => ("Bit plane: {}", "位平面：{}"),
+            Message::ByteStride => ("Byte stride: {}", "像素字节间隔：{}"),
+            Message::Encrypted => ("Encrypted: {}", "已加密：{}"),
+            Message::Compression => ("Compression: {}", "压缩算法：{}"),
+            Message::Channels => ("Channels: {}", "通道：{}"),
+            Message::Scattered => ("Scattered: {}", "已打散：{}"),
+            Message::Archive => ("Archive: {}", "归档：{}"),
+            Message::Redundancy => ("Redundancy: {}", "冗余份数：{}"),
+            Message::PayloadType => ("Payload type: {}", "载荷类型：{}"),
+            Message::TextEncoding => ("Text encoding: {}", "文本编码：{}"),
+            Message::AlphaSkipped => ("Alpha skipped: {}", "跳过 alpha：{}"),
+            Message::FileName => ("File name: {}", "文件名：{}"),
+            Message::EmbeddedBy => (
+                "Embedded by: lsb_hide {} at Unix time {}",
+                "嵌入来源：lsb_hide {}，Unix 时间 {}",
+            ),
+            Message::UnreadableExpected => (
+                "Unable to read expected payload file: {}",
+                "无法读取预期的载荷文件：{}",
+            ),
+            Message::UnreadableSeedFile => ("Unable to read seed file: {}", "无法读取种子文件：{}"),
+            Message::UnreadableSidecar => {
+                ("Unable to read sidecar file: {}", "无法读取边车文件：{}")
+            }
+            Message::EmptySeedFile => ("The seed file {} is empty.", "种子文件 {} 为空。"),
+            Message::VerifyArchive => (
+                "The hidden payload is a multi-file archive; verify only compares a single file.\nUse recover to extract the archive instead.",
+                "隐藏的载荷是多文件归档；verify 只比较单个文件。\n请改用 recover 解包该归档。",
+            ),
+            Message::VerifyMismatch => (
+                "The payload in {} does not match {}: {}",
+                "{} 中的载荷与 {} 不一致：{}",
+            ),
+            Message::MismatchSummary => (
+                "expected {} bytes, recovered {} bytes; first difference at byte {}, {} bytes differ.",
+                "预期 {} 字节，恢复出 {} 字节；第一处差异位于第 {} 字节，共有 {} 字节不同。",
+            ),
+            Message::VerifyMatch => (
+                "The payload in {} matches {} ({} bytes)",
+                "{} 中的载荷与 {} 一致 ({} 字节)",
+            ),
+            Message::ImageLabel => ("Image: {}", "图像：{}"),
+            Message::DiffShapeMismatch => (
+                "Cannot compare {} ({}x{}, {} channels) with {} ({}x{}, {} channels); both images must have the same size and color type.",
+                "无法比较 {} ({}x{}，{} 个通道) 与 {} ({}x{}，{} 个通道)；两张图像的尺寸与颜色类型必须相同。",
+            ),
+            Message::DiffCover => ("Cover: {}", "原始图像：{}"),
+            Message::DiffStego => ("Stego: {}", "隐写图像：{}"),
+            Message::DiffChangedBytes => {
+                ("Changed bytes: {} of {} ({})", "改动的字节：{} / {} ({})")
+            }
+            Message::DiffChangedLsbs => {
+                ("Bytes with a flipped LSB: {}", "最低有效位被翻转的字节：{}")
+            }
+            Message::DiffMaxDelta => ("Largest byte difference: {}", "最大字节差值：{}"),
+            Message::DiffPsnr => ("PSNR: {} dB", "PSNR：{} dB"),
+            Message::DiffImageSaved => ("Difference image saved to: {}", "差异图像已保存到：{}"),
+            Message::PreviewSetBits => (
+                "Bits set: {} of {} bytes ({})",
+                "该位为 1 的字节：{} / {} ({})",
+            ),
+            Message::PreviewSaved => (
+                "Bit plane {} preview saved to: {}",
+                "第 {} 位平面的预览已保存到：{}",
+            ),
+            Message::ChiSquare => (
+                "Chi-square statistic: {} ({} degrees of freedom)",
+                "卡方统计量：{} (自由度 {})",
+            ),
+            Message::HiddenProbability => {
+                ("Probability of hidden data: {}", "含有隐藏数据的概率：{}")
+            }
+            Message::ReadableFormats => ("Readable input formats:", "可读取的输入格式："),
+            Message::SafeOutputFormats => (
+                "Safe output formats (lossless):",
+                "可安全写出的输出格式 (无损)：",
+            ),
+            Message::CleanDone => (
+                "The lowest {} bits of every pixel byte have been cleaned ({}) and saved: {}",
+                "每个像素字节的最低 {} 位已清除 ({}) 并保存：{}",
+            ),
+            Message::NotLossless => (
+                "Output format is not lossless: {}.\nExpected a lossless format ({}).",
+                "输出格式不是无损格式：{}。\n应为无损格式 ({})。",
+            ),
+            Message::IndexedPngBytes => (
+                "Indexed-color (palette) PNG images are not supported.\nConvert the image to RGB or RGBA first.",
+                "不支持索引颜色 (调色板) PNG 图像。\n请先将图像转换为 RGB 或 RGBA。",
+            ),
+            Message::DecodeCoverFailed => {
+                ("Failed to decode the cover image.", "无法解码载体图像。")
+            }
+            Message::EncodeOutputFailed => (
+                "Failed to encode the output image as {}.",
+                "无法将输出图像编码为 {}。",
+            ),
+            Message::DecodeStegoFailed => {
+                ("Failed to decode the stego image.", "无法解码隐写图像。")
+            }
+            Message::EncryptedBytes => (
+                "The hidden payload is encrypted. Use the recover command with --password to decrypt it.",
+                "隐藏的载荷已加密。请使用 recover 命令并指定 --password 解密。",
+            ),
+            Message::WhitenedBytes => (
+                "The hidden payload is whitened. Use the recover command with --xor-key to restore it.",
+                "隐藏的载荷已白化。请使用 recover 命令并指定 --xor-key 还原。",
+            ),
+        };
+        match lang {
+            Lang::En => en,
+            Lang::Zh => zh,
+        }
+    }
+}
+
+/// 将 `template` 中的每个 `{}` 依次替换为 `args` 中的参数，多余的占位符保持原样
+pub fn render(template: &str, args: &[&dyn fmt::Display]) -> String {
+    let mut parts = template.split("{}");
+    let mut output = parts.next().unwrap_or_default().to_string();
+    let mut args = args.iter();
+    for part in parts {
+        match args.next() {
+            Some(arg) => write!(output, "{}", arg).expect("Writing to a String cannot fail."),
+            None => output.push_str("{}"),
+        }
+        output.push_str(part);
+    }
+    output
+}
+
+/// 以当前语言格式化 `message`，参数按顺序替换模板中的 `{}`
+pub fn tr(message: Message, args: &[&dyn fmt::Display]) -> String {
+    render(message.template(lang()), args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 测试参数按顺序替换占位符，`{stem}` 等具名花括号保持原样
+    #[test]
+    fn test_render_placeholders() {
+        assert_eq!(render("{} of {} bytes", &[&3, &"10"]), "3 of 10 bytes");
+        assert_eq!(
+            render(Message::InvalidNameTemplate.template(Lang::En), &[&"x"]),
+            "Invalid --name-template: 'x' (it must contain {stem})."
+        );
+        assert_eq!(render("{} and {}", &[&1]), "1 and {}");
+    }
+
+    /// 测试同一条信息在两种语言下使用各自的模板，且占位符数量一致
+    #[test]
+    fn test_templates_per_language() {
+        let message = Message::OutputExists;
+        assert!(
+            message
+                .template(Lang::En)
+                .starts_with("Output file already exists")
+        );
+        assert!(message.template(Lang::Zh).starts_with("输出文件已存在"));
+        assert_eq!(
+            Message::EmptyPayload.template(Lang::En),
+            EMPTY_PAYLOAD_MESSAGE
+        );
+        for message in [
+            Message::UnsupportedOutput,
+            Message::CapacityFractionExceeded,
+            Message::MismatchSummary,
+            Message::CleanDone,
+        ] {
+            assert_eq!(
+                message.template(Lang::En).matches("{}").count(),
+                message.template(Lang::Zh).matches("{}").count()
+            );
+        }
+    }
+}
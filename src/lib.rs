@@ -4,7 +4,28 @@
 
 // 声明库包含的所有模块
 
+pub mod analysis;
+pub mod animation;
+pub mod archive;
+pub mod channels;
 pub mod cli;
+pub mod clipboard;
+pub mod compression;
 pub mod constants;
+pub mod crypto;
+pub mod delimited;
+pub mod error;
 pub mod handler;
+pub mod i18n;
+pub mod metadata;
+pub mod progress;
+pub mod region;
+pub mod remote;
+pub mod scatter;
+pub mod segment;
+pub mod sidecar;
 pub mod steganography;
+pub mod style;
+pub mod text_encoding;
+pub mod varint;
+pub mod whiten;
@@ -1,21 +1,138 @@
+use anyhow::Context;
 use clap::Parser;
+use std::io::{self, IsTerminal};
 
 use lsb_hide::{
     cli::{Cli, Commands},
-    handler::{handle_hide, handle_recover},
+    constants::{EXIT_FAILURE, EXIT_SUCCESS},
+    error::{self, StegError},
+    handler::{
+        handle_analyze, handle_capacity, handle_clean, handle_diff, handle_hide, handle_info,
+        handle_list_formats, handle_preview, handle_recover, handle_verify,
+    },
+    i18n::{self, Lang},
+    style,
 };
 
 /// 程序的主入口点
 ///
-/// 负责解析命令行参数，并根据指定的子命令（`hide` 或 `recover`）
+/// 负责解析命令行参数，并根据指定的子命令（`hide`、`recover`、`capacity`、`info`、`verify`、`analyze`、`diff`、`preview`、`clean` 或 `list-formats`）
 /// 将执行分派到相应的处理函数
+///
+/// 失败时按错误的种类以不同的状态码退出，映射关系参见 [`error::exit_code`]
+///
+/// 指定 `--json` 时，错误同样以 JSON 对象的形式输出到标准输出；
+/// 空间不足的错误还会附带所需与可用的像素字节数，便于界面据此显示容量
 fn main() -> anyhow::Result<()> {
-    // 解析命令行参数
-    let cli = Cli::parse();
+    // 解析命令行参数；参数错误使用通用的失败状态码，避免与空间不足的状态码 2 混淆
+    let cli = Cli::try_parse().unwrap_or_else(|err| {
+        let code = if err.use_stderr() {
+            EXIT_FAILURE
+        } else {
+            EXIT_SUCCESS
+        };
+        let _ = err.print();
+        std::process::exit(code);
+    });
+    let json = cli.json;
+    let quiet = cli.quiet;
+
+    // 限制并行读写载荷的线程数，0 时保留 rayon 的默认线程池
+    if cli.threads > 0 {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(cli.threads)
+            .build_global()
+            .context("Failed to configure the worker thread pool.")?;
+    }
+
+    // 选择提示与错误信息的语言，未指定 `--lang` 时参考 `LANG` 环境变量
+    i18n::set_lang(cli.lang.unwrap_or_else(Lang::from_env));
+
+    // 只有标准输入与标准输出都连接到终端时才询问是否覆盖，脚本与 JSON 输出保持直接报错
+    let interactive = !json && io::stdin().is_terminal() && io::stdout().is_terminal();
+
+    // JSON 输出中不应包含终端颜色控制符
+    if json {
+        style::disable();
+    }
+
+    // 根据子命令调用相应的处理函数，并将全局的 `--json` 与 `--quiet` 标志传递下去
+    let result = match cli.command {
+        Commands::Hide(mut args) => {
+            args.json = json;
+            args.quiet = quiet;
+            args.interactive = interactive;
+            handle_hide(args)
+        }
+        Commands::Recover(mut args) => {
+            args.json = json;
+            args.quiet = quiet;
+            args.interactive = interactive;
+            handle_recover(args)
+        }
+        Commands::Capacity(mut args) => {
+            args.json = json;
+            handle_capacity(args)
+        }
+        Commands::Info(mut args) => {
+            args.json = json;
+            handle_info(args)
+        }
+        Commands::Verify(mut args) => {
+            args.json = json;
+            args.quiet = quiet;
+            handle_verify(args)
+        }
+        Commands::Analyze(mut args) => {
+            args.json = json;
+            handle_analyze(args)
+        }
+        Commands::Diff(mut args) => {
+            args.json = json;
+            args.quiet = quiet;
+            args.interactive = interactive;
+            handle_diff(args)
+        }
+        Commands::Preview(mut args) => {
+            args.json = json;
+            args.quiet = quiet;
+            args.interactive = interactive;
+            handle_preview(args)
+        }
+        Commands::Clean(mut args) => {
+            args.json = json;
+            args.quiet = quiet;
+            args.interactive = interactive;
+            handle_clean(args)
+        }
+        Commands::ListFormats(mut args) => {
+            args.json = json;
+            handle_list_formats(args)
+        }
+    };
 
-    // 根据子命令调用相应的处理函数
-    match cli.command {
-        Commands::Hide(args) => handle_hide(args),
-        Commands::Recover(args) => handle_recover(args),
+    let Err(err) = result else {
+        return Ok(());
+    };
+    let code = error::exit_code(&err);
+    if json {
+        let mut error = serde_json::json!({
+            "status": "error",
+            "message": format!("{:#}", err),
+            "exit_code": code,
+        });
+        if let Some(StegError::NotEnoughSpace {
+            required,
+            available,
+        }) = err.downcast_ref::<StegError>()
+        {
+            error["required"] = (*required).into();
+            error["available"] = (*available).into();
+        }
+        println!("{}", error);
+    } else {
+        // 与 `main` 直接返回错误时的输出格式相同
+        eprintln!("Error: {:?}", err);
     }
+    std::process::exit(code);
 }
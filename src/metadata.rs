@@ -0,0 +1,136 @@
+//! # 图像元数据模块
+//!
+//! `image` 库重新编码图像时会丢弃文本、EXIF 等辅助数据块，使输出文件与原始图像明显不同
+//! 本模块供 `--preserve-metadata` 使用，将原始 PNG 中的这些数据块 (`PRESERVED_PNG_CHUNKS`) 复制到重新编码的 PNG 中
+
+use crate::constants::{PNG_SIGNATURE, PRESERVED_PNG_CHUNKS};
+
+/// PNG 数据块中长度、类型与 CRC 字段各自占用的字节数
+const FIELD_LEN: usize = 4;
+
+/// PNG 文件中的一个数据块
+struct Chunk<'a> {
+    /// 数据块类型，例如 `IHDR`
+    kind: [u8; 4],
+
+    /// 包括长度、类型、数据与 CRC 在内的完整数据块字节
+    bytes: &'a [u8],
+}
+
+/// 将 PNG 文件拆分为数据块，不校验 CRC
+///
+/// # Errors
+///
+/// 如果 `png` 不以 PNG 签名开头，或某个数据块超出了文件末尾，将返回错误
+fn chunks(png: &[u8]) -> anyhow::Result<Vec<Chunk<'_>>> {
+    anyhow::ensure!(
+        png.starts_with(&PNG_SIGNATURE),
+        "The image is not a PNG file."
+    );
+    let mut chunks = Vec::new();
+    let mut offset = PNG_SIGNATURE.len();
+    while offset < png.len() {
+        let header = png
+            .get(offset..offset + 2 * FIELD_LEN)
+            .ok_or_else(|| anyhow::anyhow!("Truncated PNG chunk at byte {}.", offset))?;
+        let len = u32::from_be_bytes(header[..FIELD_LEN].try_into()?) as usize;
+        let end = offset
+            .checked_add(len + 3 * FIELD_LEN)
+            .filter(|&end| end <= png.len())
+            .ok_or_else(|| anyhow::anyhow!("Truncated PNG chunk at byte {}.", offset))?;
+        chunks.push(Chunk {
+            kind: header[FIELD_LEN..].try_into()?,
+            bytes: &png[offset..end],
+        });
+        offset = end;
+    }
+    Ok(chunks)
+}
+
+/// 将原始 PNG (`source`) 中的元数据块复制到重新编码的 PNG (`output`) 中，返回新的 PNG 文件字节
+///
+/// 复制的数据块按原始顺序插入到 IHDR 之后；`output` 中已存在的同类数据块保持不变，不会重复复制
+/// 数据块原样复制 (包括 CRC)，因此不会改变其中的任何内容
+///
+/// # Errors
+///
+/// 如果 `source` 或 `output` 不是结构完整的 PNG 文件，或 `output` 不以 IHDR 开头，将返回错误
+pub fn copy_png_metadata(source: &[u8], output: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let source_chunks = chunks(source)?;
+    let output_chunks = chunks(output)?;
+    let (ihdr, rest) = output_chunks
+        .split_first()
+        .filter(|(ihdr, _)| &ihdr.kind == b"IHDR")
+        .ok_or_else(|| anyhow::anyhow!("The encoded PNG does not start with an IHDR chunk."))?;
+
+    let preserved = source_chunks.iter().filter(|chunk| {
+        PRESERVED_PNG_CHUNKS.contains(&chunk.kind)
+            && !rest.iter().any(|existing| existing.kind == chunk.kind)
+    });
+
+    let mut merged = Vec::with_capacity(source.len() + output.len());
+    merged.extend_from_slice(&PNG_SIGNATURE);
+    merged.extend_from_slice(ihdr.bytes);
+    preserved.for_each(|chunk| merged.extend_from_slice(chunk.bytes));
+    rest.iter()
+        .for_each(|chunk| merged.extend_from_slice(chunk.bytes));
+    Ok(merged)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{ImageFormat, RgbaImage};
+    use std::io::Cursor;
+
+    /// 编码一张 2x2 的 PNG，并附加给定的文本数据块
+    fn png_with_text(text: &[(&str, &str)]) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        let mut encoder = png::Encoder::new(&mut bytes, 2, 2);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        for (keyword, value) in text {
+            encoder
+                .add_text_chunk(keyword.to_string(), value.to_string())
+                .unwrap();
+        }
+        let mut writer = encoder.write_header().unwrap();
+        writer.write_image_data(&[7u8; 16]).unwrap();
+        writer.finish().unwrap();
+        bytes
+    }
+
+    /// 测试文本数据块被复制到重新编码的 PNG 中，且图像数据保持不变
+    #[test]
+    fn test_copy_text_chunk() {
+        let source = png_with_text(&[("Author", "lsb_hide")]);
+        let mut output = Cursor::new(Vec::new());
+        RgbaImage::from_pixel(2, 2, image::Rgba([1, 2, 3, 4]))
+            .write_to(&mut output, ImageFormat::Png)
+            .unwrap();
+
+        let merged = copy_png_metadata(&source, output.get_ref()).unwrap();
+        let reader = png::Decoder::new(Cursor::new(&merged)).read_info().unwrap();
+        let text = &reader.info().uncompressed_latin1_text;
+        assert_eq!(text.len(), 1);
+        assert_eq!(
+            (&text[0].keyword[..], &text[0].text[..]),
+            ("Author", "lsb_hide")
+        );
+
+        let decoded = image::load_from_memory(&merged).unwrap().into_rgba8();
+        assert_eq!(decoded.get_pixel(1, 1), &image::Rgba([1, 2, 3, 4]));
+    }
+
+    /// 测试输出中已存在的同类数据块不会重复复制，非 PNG 或被截断的输入会被拒绝
+    #[test]
+    fn test_existing_chunks_and_invalid_input() {
+        let source = png_with_text(&[("Author", "source")]);
+        let output = png_with_text(&[("Author", "output")]);
+        let merged = copy_png_metadata(&source, &output).unwrap();
+        assert_eq!(merged, output);
+
+        assert!(copy_png_metadata(b"not a png", &output).is_err());
+        assert!(copy_png_metadata(&source, &output[..output.len() - 1]).is_err());
+    }
+}
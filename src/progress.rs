@@ -0,0 +1,71 @@
+//! # 进度显示模块
+//!
+//! 隐藏或恢复数 MB 的载荷需要数秒，本模块在终端中以进度条显示逐字节隐写的进度
+//! 载荷较小、使用 `--json` 或标准输出不是终端时不显示任何内容
+
+use crate::constants::{PROGRESS_ENV_VAR, PROGRESS_THRESHOLD_BYTES};
+use indicatif::{ProgressBar, ProgressStyle};
+use std::io::IsTerminal;
+
+/// 进度条的显示模板
+const PROGRESS_TEMPLATE: &str = "{msg} [{bar:40}] {bytes}/{total_bytes} ({eta})";
+
+/// 载荷隐写的进度条，未显示时所有操作均为空操作
+pub struct Progress(Option<ProgressBar>);
+
+impl Progress {
+    /// 为 `total` 个载荷字节创建进度条，`message` 显示在进度条之前
+    ///
+    /// 只有在 `enabled` 为真、载荷不小于 `PROGRESS_THRESHOLD_BYTES`，且标准输出是终端
+    /// (或环境变量 `PROGRESS_ENV_VAR` 为 `1`) 时才会显示
+    pub fn new(total: usize, message: &'static str, enabled: bool) -> Self {
+        let forced = std::env::var(PROGRESS_ENV_VAR).is_ok_and(|value| value == "1");
+        let visible = enabled
+            && total >= PROGRESS_THRESHOLD_BYTES
+            && (forced || std::io::stdout().is_terminal());
+
+        Self(visible.then(|| {
+            let style = ProgressStyle::with_template(PROGRESS_TEMPLATE)
+                .unwrap_or_else(|_| ProgressStyle::default_bar())
+                .progress_chars("=> ");
+            ProgressBar::new(total as u64)
+                .with_style(style)
+                .with_message(message)
+        }))
+    }
+
+    /// 进度条是否会被显示
+    pub fn is_visible(&self) -> bool {
+        self.0.is_some()
+    }
+
+    /// 将进度设置为已处理 `done` 个载荷字节
+    pub fn set(&self, done: usize) {
+        if let Some(bar) = &self.0 {
+            bar.set_position(done as u64);
+        }
+    }
+
+    /// 结束并清除进度条，避免与之后输出的结果信息混在一起
+    pub fn finish(&self) {
+        if let Some(bar) = &self.0 {
+            bar.finish_and_clear();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 测试小载荷或被禁用时不显示进度条，且空操作不会出错
+    #[test]
+    fn test_hidden_for_small_or_disabled_payloads() {
+        let small = Progress::new(PROGRESS_THRESHOLD_BYTES - 1, "Hiding", true);
+        assert!(!small.is_visible());
+        small.set(10);
+        small.finish();
+
+        assert!(!Progress::new(PROGRESS_THRESHOLD_BYTES * 4, "Hiding", false).is_visible());
+    }
+}
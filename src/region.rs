@@ -0,0 +1,166 @@
+//! # 矩形区域模块
+//!
+//! 供 `--region` 使用：只在图像的一个矩形区域 (例如纹理丰富的背景) 中隐藏数据，区域外的像素字节保持不变
+//!
+//! 区域内的像素按行优先顺序收集为连续的字节流，头部与载荷都照常写入这个字节流，之后再按相同的顺序写回图像；
+//! 区域不会记录在图像中，恢复时必须指定相同的区域
+
+use std::fmt;
+use std::ops::Range;
+use std::str::FromStr;
+
+/// 图像中的矩形区域，以像素为单位
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Region {
+    /// 左上角的列
+    pub x: u32,
+
+    /// 左上角的行
+    pub y: u32,
+
+    /// 宽度
+    pub width: u32,
+
+    /// 高度
+    pub height: u32,
+}
+
+impl Region {
+    /// 确认区域位于 `width` x `height` 的图像之内
+    ///
+    /// # Errors
+    ///
+    /// 如果区域超出图像的边界，将返回错误
+    pub fn ensure_within(&self, width: u32, height: u32) -> anyhow::Result<()> {
+        let fits = |start: u32, len: u32, limit: u32| {
+            start.checked_add(len).is_some_and(|end| end <= limit)
+        };
+        anyhow::ensure!(
+            fits(self.x, self.width, width) && fits(self.y, self.height, height),
+            "Region {} does not fit in the {}x{} image.",
+            self,
+            width,
+            height
+        );
+        Ok(())
+    }
+
+    /// 区域每一行在每像素 `stride` 个字节、宽 `image_width` 像素的字节流中所占的字节范围，按行排列
+    fn rows(&self, image_width: u32, stride: usize) -> impl Iterator<Item = Range<usize>> {
+        let row_len = self.width as usize * stride;
+        let (x, y) = (self.x as usize, self.y as usize);
+        (y..y + self.height as usize).map(move |row| {
+            let start = (row * image_width as usize + x) * stride;
+            start..start + row_len
+        })
+    }
+
+    /// 按行优先顺序收集区域内的像素字节，区域必须已通过 [`Region::ensure_within`] 检查
+    pub fn gather(&self, pixels: &[u8], image_width: u32, stride: usize) -> Vec<u8> {
+        self.rows(image_width, stride)
+            .flat_map(|row| &pixels[row])
+            .copied()
+            .collect()
+    }
+
+    /// 将由 [`Region::gather`] 收集、修改后的字节 (`bytes`) 写回区域内的像素
+    pub fn scatter(&self, pixels: &mut [u8], bytes: &[u8], image_width: u32, stride: usize) {
+        let row_len = self.width as usize * stride;
+        self.rows(image_width, stride)
+            .zip(bytes.chunks_exact(row_len))
+            .for_each(|(row, bytes)| pixels[row].copy_from_slice(bytes));
+    }
+
+    /// 将区域字节流中的第 `index` 个字节换算为完整字节流中的偏移量
+    pub fn full_offset(&self, index: usize, image_width: u32, stride: usize) -> usize {
+        let row_len = self.width as usize * stride;
+        let (row, column) = (index / row_len, index % row_len);
+        ((self.y as usize + row) * image_width as usize + self.x as usize) * stride + column
+    }
+}
+
+impl FromStr for Region {
+    type Err = anyhow::Error;
+
+    /// 解析 `x,y,w,h` 形式的区域，例如 `16,16,64,32`；宽度与高度必须大于 0
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let values = s
+            .split(',')
+            .map(|value| value.trim().parse::<u32>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| anyhow::anyhow!("Invalid region '{}' (expected x,y,w,h).", s))?;
+        let [x, y, width, height] = values[..] else {
+            anyhow::bail!("Invalid region '{}' (expected x,y,w,h).", s);
+        };
+        anyhow::ensure!(
+            width > 0 && height > 0,
+            "The region must be at least 1x1 pixels."
+        );
+        Ok(Self {
+            x,
+            y,
+            width,
+            height,
+        })
+    }
+}
+
+impl fmt::Display for Region {
+    /// 显示与命令行参数 `--region` 相同的 `x,y,w,h` 形式
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{},{},{},{}", self.x, self.y, self.width, self.height)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 测试区域的解析、显示与边界检查
+    #[test]
+    fn test_parse_display_and_bounds() {
+        let region: Region = "1, 2,3,4".parse().expect("Failed to parse region.");
+        assert_eq!(
+            region,
+            Region {
+                x: 1,
+                y: 2,
+                width: 3,
+                height: 4
+            }
+        );
+        assert_eq!(region.to_string(), "1,2,3,4");
+        assert!(region.ensure_within(4, 6).is_ok());
+        assert!(region.ensure_within(3, 6).is_err());
+        assert!(region.ensure_within(4, 5).is_err());
+
+        assert!("1,2,3".parse::<Region>().is_err());
+        assert!("1,2,3,4,5".parse::<Region>().is_err());
+        assert!("a,2,3,4".parse::<Region>().is_err());
+        assert!("0,0,0,4".parse::<Region>().is_err());
+        let huge: Region = "4294967295,0,1,1".parse().unwrap();
+        assert!(huge.ensure_within(u32::MAX, 1).is_err());
+    }
+
+    /// 测试按行收集区域内的字节后写回，区域外的字节保持不变，偏移量换算与收集顺序一致
+    #[test]
+    fn test_gather_scatter_round_trip() {
+        // 5x4 的双通道图像，每个字节的值等于其在字节流中的偏移量
+        let (width, stride) = (5, 2);
+        let mut pixels: Vec<u8> = (0..5 * 4 * 2).collect();
+        let region: Region = "1,1,2,2".parse().unwrap();
+
+        let gathered = region.gather(&pixels, width, stride);
+        assert_eq!(gathered, [12, 13, 14, 15, 22, 23, 24, 25]);
+        for (index, &byte) in gathered.iter().enumerate() {
+            assert_eq!(region.full_offset(index, width, stride), byte as usize);
+        }
+
+        let original = pixels.clone();
+        region.scatter(&mut pixels, &[u8::MAX; 8], width, stride);
+        for (index, (&before, &after)) in original.iter().zip(&pixels).enumerate() {
+            let inside = gathered.contains(&(index as u8));
+            assert_eq!(after, if inside { u8::MAX } else { before });
+        }
+    }
+}
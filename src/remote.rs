@@ -0,0 +1,92 @@
+//! # 远程图像模块
+//!
+//! 解析 `--image` 参数中的 URL：`http(s)://` 开头的图像在启用 `remote` 特性时直接下载到内存中，
+//! `file://` 开头的 URL 则去掉前缀后按本地路径处理
+
+use std::path::{Path, PathBuf};
+
+/// 下载远程图像时的超时时间 (秒)
+#[cfg(feature = "remote")]
+const FETCH_TIMEOUT_SECS: u64 = 30;
+
+/// 如果 `path` 是 `http://` 或 `https://` URL，返回该 URL
+pub fn remote_url(path: &Path) -> Option<&str> {
+    path.to_str().filter(|path| {
+        ["http://", "https://"].iter().any(|scheme| {
+            path.get(..scheme.len())
+                .is_some_and(|prefix| prefix.eq_ignore_ascii_case(scheme))
+        })
+    })
+}
+
+/// 将 `file://` URL 转换为本地路径，其余路径原样返回
+pub fn local_path(path: &Path) -> PathBuf {
+    path.to_str()
+        .and_then(|path| path.strip_prefix("file://"))
+        .map_or_else(|| path.to_path_buf(), PathBuf::from)
+}
+
+/// 下载 `url` 指向的图像，返回其原始字节
+///
+/// # Errors
+///
+/// * 如果网络请求失败、超时，或服务器返回了错误状态码，将返回错误
+/// * 如果程序编译时没有启用 `remote` 特性，将返回错误
+#[cfg(feature = "remote")]
+pub fn fetch(url: &str) -> anyhow::Result<Vec<u8>> {
+    use anyhow::Context;
+    use std::time::Duration;
+
+    let client = reqwest::blocking::Client::builder()
+        .timeout(Duration::from_secs(FETCH_TIMEOUT_SECS))
+        .build()
+        .context("Failed to initialize the HTTP client.")?;
+    let response = client
+        .get(url)
+        .send()
+        .and_then(|response| response.error_for_status())
+        .with_context(|| format!("Failed to download image: {}", url))?;
+    let bytes = response
+        .bytes()
+        .with_context(|| format!("Failed to read the downloaded image: {}", url))?;
+    Ok(bytes.to_vec())
+}
+
+/// 下载 `url` 指向的图像，返回其原始字节
+///
+/// # Errors
+///
+/// 程序编译时没有启用 `remote` 特性，总是返回错误
+#[cfg(not(feature = "remote"))]
+pub fn fetch(url: &str) -> anyhow::Result<Vec<u8>> {
+    anyhow::bail!(
+        "Remote images are not supported by this build: {}\nRebuild lsb_hide with `--features remote`, or download the image first.",
+        url
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 测试只有 http(s) URL 会被识别为远程图像，file:// URL 会被转换为本地路径
+    #[test]
+    fn test_remote_url_and_local_path() {
+        assert_eq!(
+            remote_url(Path::new("https://example.com/cover.png")),
+            Some("https://example.com/cover.png")
+        );
+        assert!(remote_url(Path::new("HTTP://example.com/cover.png")).is_some());
+        assert!(remote_url(Path::new("cover.png")).is_none());
+        assert!(remote_url(Path::new("file:///tmp/cover.png")).is_none());
+
+        assert_eq!(
+            local_path(Path::new("file:///tmp/cover.png")),
+            PathBuf::from("/tmp/cover.png")
+        );
+        assert_eq!(
+            local_path(Path::new("cover.png")),
+            PathBuf::from("cover.png")
+        );
+    }
+}
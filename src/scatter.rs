@@ -0,0 +1,71 @@
+//! # 像素位置打散模块
+//!
+//! 顺序嵌入会把所有改动集中在图像开头，很容易被检测到
+//! 本模块根据种子生成可复现的伪随机排列，将载荷分散到整个可用像素区域中
+//!
+//! 为保证不同版本间结果一致，排列使用 ChaCha20 生成随机数并自行实现 Fisher-Yates 洗牌，
+//! 不依赖 `rand` 中可能随版本变化的采样算法
+
+use rand::{RngCore, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use sha2::{Digest, Sha256};
+
+/// 打散排列所用种子的字节数
+pub const SEED_LEN: usize = 32;
+
+/// 派生种子时使用的域分隔前缀，避免与其他用途的口令哈希相同
+const SEED_DOMAIN: &[u8] = b"lsb_hide scatter seed v1";
+
+/// 从任意密钥材料 (例如口令的 UTF-8 字节) 派生打散种子
+///
+/// 相同的输入总是得到相同的种子
+pub fn seed_from_bytes(material: &[u8]) -> [u8; SEED_LEN] {
+    let mut hasher = Sha256::new();
+    hasher.update(SEED_DOMAIN);
+    hasher.update(material);
+    hasher.finalize().into()
+}
+
+/// 生成 `0..len` 的伪随机排列
+///
+/// 相同的 `len` 与 `seed` 总是得到完全相同的排列
+pub fn permutation(len: usize, seed: &[u8; SEED_LEN]) -> Vec<usize> {
+    let mut rng = ChaCha20Rng::from_seed(*seed);
+    let mut indices: Vec<usize> = (0..len).collect();
+
+    // Fisher-Yates 洗牌：从后向前，将每个位置与其之前 (含自身) 的随机位置交换
+    for i in (1..len).rev() {
+        let j = (rng.next_u64() % (i as u64 + 1)) as usize;
+        indices.swap(i, j);
+    }
+
+    indices
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 测试排列包含每个索引恰好一次，且对相同种子可复现
+    #[test]
+    fn test_permutation_is_reproducible_and_complete() {
+        let seed = seed_from_bytes(b"password");
+        let first = permutation(1000, &seed);
+        let second = permutation(1000, &seed);
+        assert_eq!(
+            first, second,
+            "Same seed should yield the same permutation."
+        );
+
+        let mut sorted = first.clone();
+        sorted.sort_unstable();
+        assert_eq!(sorted, (0..1000).collect::<Vec<_>>());
+        assert_ne!(first, sorted, "Permutation should not be the identity.");
+
+        let other = permutation(1000, &seed_from_bytes(b"other"));
+        assert_ne!(
+            first, other,
+            "Different seeds should yield different permutations."
+        );
+    }
+}
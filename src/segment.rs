@@ -0,0 +1,243 @@
+//! # 分段模块
+//!
+//! 将一个载荷拆分到多张图像中 (`hide --split`)，并在恢复时按序号重新拼接
+//!
+//! 每张图像隐藏一段，头部中的类型标签为 `PayloadType::Segment`，载荷由段头与本段数据组成
+//! 段头布局 (所有整数均为小端序)：
+//! 1. 完整载荷的类型标签编号：`u8`，0 表示没有类型标签
+//! 2. 本段序号 (从 0 开始)：`u16`
+//! 3. 总段数：`u16`
+//! 4. 完整载荷的 CRC32：`u32`，用于确认各段属于同一个载荷且拼接无误
+
+use crate::steganography::PayloadType;
+use anyhow::Context;
+
+/// 段头占用的字节数
+pub const SEGMENT_HEADER_LEN: usize = 1 + 2 + 2 + 4;
+
+/// 从一张图像中恢复出的一段载荷
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Segment {
+    /// 完整载荷的类型标签
+    pub payload_type: Option<PayloadType>,
+
+    /// 本段序号，从 0 开始
+    pub index: u16,
+
+    /// 总段数
+    pub count: u16,
+
+    /// 完整载荷的 CRC32
+    pub checksum: u32,
+
+    /// 本段数据
+    pub data: Vec<u8>,
+}
+
+impl Segment {
+    /// 将段头与本段数据编码为要隐藏的字节
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(SEGMENT_HEADER_LEN + self.data.len());
+        bytes.push(self.payload_type.map_or(0, PayloadType::id));
+        bytes.extend_from_slice(&self.index.to_le_bytes());
+        bytes.extend_from_slice(&self.count.to_le_bytes());
+        bytes.extend_from_slice(&self.checksum.to_le_bytes());
+        bytes.extend_from_slice(&self.data);
+        bytes
+    }
+
+    /// 解析由 [`Segment::encode`] 编码的字节
+    ///
+    /// # Errors
+    ///
+    /// * 如果字节数不足一个段头，将返回错误
+    /// * 如果类型标签编号未知，或序号不小于总段数，将返回错误
+    pub fn decode(bytes: &[u8]) -> anyhow::Result<Self> {
+        anyhow::ensure!(
+            bytes.len() >= SEGMENT_HEADER_LEN,
+            "Truncated segment header: {} bytes (expected at least {}).",
+            bytes.len(),
+            SEGMENT_HEADER_LEN
+        );
+        let payload_type = match bytes[0] {
+            0 => None,
+            id => Some(PayloadType::from_id(id)?),
+        };
+        let index = u16::from_le_bytes([bytes[1], bytes[2]]);
+        let count = u16::from_le_bytes([bytes[3], bytes[4]]);
+        let checksum = u32::from_le_bytes([bytes[5], bytes[6], bytes[7], bytes[8]]);
+        anyhow::ensure!(
+            index < count,
+            "Invalid segment index: {} of {}.",
+            u32::from(index) + 1,
+            count
+        );
+        Ok(Self {
+            payload_type,
+            index,
+            count,
+            checksum,
+            data: bytes[SEGMENT_HEADER_LEN..].to_vec(),
+        })
+    }
+}
+
+/// 按 `capacities` 依次填满每张图像，将长度为 `payload_len` 的载荷拆分为若干段
+///
+/// 返回每一段所在图像在 `capacities` 中的下标与该段的数据长度。每张图像的容量都要先扣除段头，
+/// 放不下段头的图像会被跳过；空载荷仍占用一段，以便恢复时得到空的结果
+///
+/// # Errors
+///
+/// * 如果所有图像的容量之和仍放不下载荷，将返回错误并报告可以容纳的字节数
+/// * 如果需要的段数超过 `u16::MAX`，将返回错误
+pub fn plan(payload_len: usize, capacities: &[usize]) -> anyhow::Result<Vec<(usize, usize)>> {
+    let mut remaining = payload_len;
+    let mut parts = Vec::new();
+    for (image, capacity) in capacities.iter().enumerate() {
+        if *capacity < SEGMENT_HEADER_LEN || (remaining == 0 && !parts.is_empty()) {
+            continue;
+        }
+        let size = (capacity - SEGMENT_HEADER_LEN).min(remaining);
+        parts.push((image, size));
+        remaining -= size;
+    }
+    anyhow::ensure!(
+        remaining == 0 && !parts.is_empty(),
+        "The images can hold only {} of the {} payload bytes.",
+        payload_len - remaining,
+        payload_len
+    );
+    anyhow::ensure!(
+        parts.len() <= u16::MAX as usize,
+        "Too many segments: {} (max {}).",
+        parts.len(),
+        u16::MAX
+    );
+    Ok(parts)
+}
+
+/// 按序号拼接各段，返回完整载荷及其类型标签
+///
+/// 同一序号出现多次时只保留第一段
+///
+/// # Errors
+///
+/// * 如果没有任何一段，或各段记录的总段数、校验和、类型标签不一致，将返回错误
+/// * 如果缺少某些段，将返回错误并列出缺少的序号 (从 1 开始)
+/// * 如果拼接结果的 CRC32 与段头记录的不同，将返回错误
+pub fn join(mut segments: Vec<Segment>) -> anyhow::Result<(Vec<u8>, Option<PayloadType>)> {
+    let first = segments.first().context("No payload segments found.")?;
+    let (count, checksum, payload_type) = (first.count, first.checksum, first.payload_type);
+    anyhow::ensure!(
+        segments.iter().all(|segment| segment.count == count
+            && segment.checksum == checksum
+            && segment.payload_type == payload_type),
+        "The segments belong to different payloads."
+    );
+
+    segments.sort_by_key(|segment| segment.index);
+    segments.dedup_by_key(|segment| segment.index);
+    let missing: Vec<String> = (0..count)
+        .filter(|index| {
+            segments
+                .binary_search_by_key(index, |segment| segment.index)
+                .is_err()
+        })
+        .map(|index| (index + 1).to_string())
+        .collect();
+    anyhow::ensure!(
+        missing.is_empty(),
+        "Missing segments {} of {}.",
+        missing.join(", "),
+        count
+    );
+
+    let payload: Vec<u8> = segments
+        .into_iter()
+        .flat_map(|segment| segment.data)
+        .collect();
+    anyhow::ensure!(
+        crc32fast::hash(&payload) == checksum,
+        "The reassembled payload does not match its checksum."
+    );
+    Ok((payload, payload_type))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 按容量拆分并编码各段
+    fn split(payload: &[u8], capacities: &[usize]) -> Vec<Segment> {
+        let parts = plan(payload.len(), capacities).unwrap();
+        let count = parts.len() as u16;
+        let mut offset = 0;
+        parts
+            .into_iter()
+            .enumerate()
+            .map(|(index, (_, size))| {
+                let data = payload[offset..offset + size].to_vec();
+                offset += size;
+                Segment {
+                    payload_type: Some(PayloadType::Binary),
+                    index: index as u16,
+                    count,
+                    checksum: crc32fast::hash(payload),
+                    data,
+                }
+            })
+            .collect()
+    }
+
+    /// 测试拆分后的各段以任意顺序编码、解码后都能拼接回原始载荷
+    #[test]
+    fn test_split_and_join_round_trip() {
+        let payload: Vec<u8> = (0..=255).cycle().take(1000).collect();
+        assert_eq!(
+            plan(payload.len(), &[5, 409, 300, 600]).unwrap(),
+            [(1, 400), (2, 291), (3, 309)]
+        );
+        assert_eq!(plan(0, &[5, 9]).unwrap(), [(1, 0)]);
+        let mut segments = split(&payload, &[5, 409, 300, 600]);
+        assert_eq!(segments.len(), 3);
+
+        segments.reverse();
+        let decoded = segments
+            .iter()
+            .map(|segment| Segment::decode(&segment.encode()).unwrap())
+            .collect();
+        let (joined, payload_type) = join(decoded).unwrap();
+        assert_eq!(joined, payload);
+        assert_eq!(payload_type, Some(PayloadType::Binary));
+    }
+
+    /// 测试容量不足、缺少某段与混入其他载荷的段时报错
+    #[test]
+    fn test_plan_and_join_errors() {
+        let err = plan(1000, &[100, 200]).unwrap_err();
+        assert!(err.to_string().contains("only 282 of the 1000"));
+
+        let payload = vec![7u8; 900];
+        let mut segments = split(&payload, &[309, 309, 309]);
+        let removed = segments.remove(1);
+        let err = join(segments.clone()).unwrap_err();
+        assert_eq!(err.to_string(), "Missing segments 2 of 3.");
+
+        segments.push(Segment {
+            checksum: removed.checksum ^ 1,
+            ..removed
+        });
+        let err = join(segments).unwrap_err();
+        assert!(err.to_string().contains("different payloads"));
+
+        assert!(Segment::decode(&[0; SEGMENT_HEADER_LEN - 1]).is_err());
+
+        // 序号为 `u16::MAX` 时报告的序号不能溢出
+        let mut bytes = [0; SEGMENT_HEADER_LEN];
+        bytes[1..3].copy_from_slice(&u16::MAX.to_le_bytes());
+        bytes[3..5].copy_from_slice(&3u16.to_le_bytes());
+        let err = Segment::decode(&bytes).unwrap_err();
+        assert_eq!(err.to_string(), "Invalid segment index: 65536 of 3.");
+    }
+}
@@ -0,0 +1,112 @@
+//! # 边车文件模块
+//!
+//! `hide --no-length-header` 不在图像中写入任何头部，只隐藏载荷的原始字节，使图像中不存在可供识别的魔数与长度字段
+//! 提取载荷所需的偏移量、长度与校验和改为写入图像旁的边车文件 (JSON)，由 `recover --meta` 读取后精确提取
+//!
+//! 边车文件布局：
+//! ```json
+//! { "offset": 0, "length": 12, "checksum": "1c291ca3" }
+//! ```
+//! `offset` 为载荷起始处跳过的像素数 (即 `--offset`)，`checksum` 为载荷的 CRC32 (8 位十六进制)
+
+use crate::constants::SIDECAR_EXTENSION;
+use anyhow::Context;
+use serde_json::{Value, json};
+use std::path::{Path, PathBuf};
+
+/// 无头部载荷的定位与校验信息
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sidecar {
+    /// 载荷起始处跳过的像素数
+    pub offset: usize,
+
+    /// 载荷的字节长度
+    pub length: u64,
+
+    /// 载荷的 CRC32 校验和
+    pub checksum: u32,
+}
+
+impl Sidecar {
+    /// 图像 `image` 默认的边车文件路径：在完整文件名之后追加 `.meta` (例如 "out.png.meta")
+    pub fn path_for(image: &Path) -> PathBuf {
+        let mut path = image.as_os_str().to_owned();
+        path.push(".");
+        path.push(SIDECAR_EXTENSION);
+        PathBuf::from(path)
+    }
+
+    /// 将边车信息编码为带缩进的 JSON 文本，以换行结尾
+    pub fn encode(&self) -> String {
+        let value = json!({
+            "offset": self.offset,
+            "length": self.length,
+            "checksum": format!("{:08x}", self.checksum),
+        });
+        format!("{:#}\n", value)
+    }
+
+    /// 解析由 [`Sidecar::encode`] 编码的 JSON 文本
+    ///
+    /// # Errors
+    ///
+    /// 如果文本不是 JSON 对象，或缺少某个字段、字段的类型或取值无效，将返回错误
+    pub fn decode(text: &str) -> anyhow::Result<Self> {
+        let value: Value =
+            serde_json::from_str(text).context("The sidecar file is not valid JSON.")?;
+        let field = |name: &str| {
+            value
+                .get(name)
+                .with_context(|| format!("The sidecar file has no \"{}\" field.", name))
+        };
+        let offset = field("offset")?
+            .as_u64()
+            .and_then(|offset| usize::try_from(offset).ok())
+            .context("Invalid sidecar offset.")?;
+        let length = field("length")?
+            .as_u64()
+            .context("Invalid sidecar length.")?;
+        let checksum = field("checksum")?
+            .as_str()
+            .filter(|checksum| checksum.len() == 8)
+            .and_then(|checksum| u32::from_str_radix(checksum, 16).ok())
+            .context("Invalid sidecar checksum.")?;
+        Ok(Self {
+            offset,
+            length,
+            checksum,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 测试编码后再解码得到相同的边车信息，默认路径追加在完整文件名之后
+    #[test]
+    fn test_encode_decode_round_trip() {
+        let sidecar = Sidecar {
+            offset: 7,
+            length: 1234,
+            checksum: 0x0012_abcd,
+        };
+        let text = sidecar.encode();
+        assert!(text.contains("\"checksum\": \"0012abcd\""));
+        assert_eq!(Sidecar::decode(&text).unwrap(), sidecar);
+        assert_eq!(
+            Sidecar::path_for(Path::new("dir/out.png")),
+            Path::new("dir/out.png.meta")
+        );
+    }
+
+    /// 测试缺少字段或字段无效时报错
+    #[test]
+    fn test_decode_errors() {
+        assert!(Sidecar::decode("not json").is_err());
+        let err = Sidecar::decode(r#"{"offset": 0, "checksum": "00000000"}"#).unwrap_err();
+        assert!(err.to_string().contains("\"length\""));
+        assert!(Sidecar::decode(r#"{"offset": -1, "length": 1, "checksum": "00000000"}"#).is_err());
+        assert!(Sidecar::decode(r#"{"offset": 0, "length": 1, "checksum": "xyz"}"#).is_err());
+    }
+}
@@ -2,14 +2,281 @@
 //!
 //! 提供了 `modify` 和 `recover` 两个核心函数，用于在字节切片中
 //! 实现基于 LSB (最低有效位) 的数据隐藏和恢复
+//! 每个像素字节中承载数据的位窗口 (位深度与位平面) 由 `Codec` 描述，所有读写都经由它完成
+//!
+//! 在此之上，`hide_bytes` 和 `recover_bytes` 封装了“长度前缀 + 逐字节隐写”的完整流程，
+//! 可直接作用于内存中的像素缓冲区，无需任何文件 I/O
+//!
+//! 像素缓冲区是 `image` 库解码后的原始像素字节，不含任何文件格式的头部 (例如 BMP 的 54 字节文件头)，
+//! 因此头部总是从缓冲区的第 0 个字节开始写入；需要保留的区域由调用方先行切掉 (即 `--offset`)
+//! `--raw-bmp` 同样如此：调用方切掉 `BMP_HEADER_SIZE` 字节的文件头后，再把其余的原始文件字节作为缓冲区传入
 
-use crate::constants::{DATA_MASK, LSB_MASK};
+use crate::channels::Channels;
+use crate::compression::Compression;
+use crate::constants::{
+    BITS_HIDING_BYTES, BITS_OFFSET, BYTES_PER_CHAR, CHANNELS_HIDING_BYTES, CHANNELS_OFFSET,
+    CHECKSUM_HIDING_BYTES, CHECKSUM_OFFSET, COMPRESSION_HIDING_BYTES, COMPRESSION_OFFSET,
+    DATA_MASK, DEFAULT_BITS, DELIMITER_END, DELIMITER_START, EDGE_GRADIENT_THRESHOLD, FLAG_ARCHIVE,
+    FLAG_DELIMITED, FLAG_ENCRYPTED, FLAG_FIXED_LENGTH, FLAG_PROVENANCE, FLAG_REDUNDANT,
+    FLAG_SCATTERED, FLAG_TYPED, FLAGS_HIDING_BYTES, FLAGS_OFFSET, FORMAT_VERSION, HEADER_BYTES,
+    LENGTH_BACKUP_BYTES, LENGTH_BACKUP_MAGIC, LENGTH_BITS_CHOICES, LENGTH_OFFSET,
+    LENGTH_PREFIX_HIDING_BYTES, LSB_MASK, MAGIC, MAX_BITS, MAX_NAME_LEN, MAX_REDUNDANCY,
+    MAX_SPACING, NAME_LENGTH_HIDING_BYTES, PLANE_HIDING_BYTES, PLANE_OFFSET, PROGRESS_STEP_BYTES,
+    SPACING_SHIFT, TEXT_ENCODING_SHIFT, TIMESTAMP_LEN, VERSION_HIDING_BYTES, VERSION_OFFSET,
+};
+use crate::delimited;
+use crate::error::{ErrorCode, StegError};
+use crate::scatter::{self, SEED_LEN};
+use crate::text_encoding;
+use crate::varint::{self, MAX_VARINT_LEN};
 use anyhow::Context;
+use encoding_rs::Encoding;
+use rayon::prelude::*;
+use std::borrow::Cow;
+use std::fmt;
+use std::io::{self, Read};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// 根据每个像素字节使用的位数 (`bits`)，计算用于提取数据位的掩码
+///
+/// 例如 `bits = 2` 时返回 `0b0000_0011`，与 `LSB_MASK` 一致
+pub fn lsb_mask(bits: u8) -> u8 {
+    ((1u16 << bits) - 1) as u8
+}
+
+/// 计算在给定位深度 (`bits`) 下，隐藏一个载荷字节所需的像素字节数
+///
+/// 当 8 不能被 `bits` 整除时向上取整，例如 `bits = 3` 时需要 3 个像素字节
+pub fn bytes_per_char(bits: u8) -> usize {
+    8usize.div_ceil(bits as usize)
+}
+
+/// 校验位深度 (`bits`) 是否在支持的范围 `1..=MAX_BITS` 内
+fn ensure_bits(bits: u8) -> Result<(), StegError> {
+    if (1..=MAX_BITS).contains(&bits) {
+        Ok(())
+    } else {
+        Err(StegError::UnsupportedBits(bits))
+    }
+}
+
+/// 校验从第 `plane` 位开始的 `bits` 位数据窗口完全落在一个像素字节之内
+fn ensure_plane(plane: u8, bits: u8) -> Result<(), StegError> {
+    if plane as u16 + bits as u16 <= u8::BITS as u16 {
+        Ok(())
+    } else {
+        Err(StegError::UnsupportedPlane { plane, bits })
+    }
+}
+
+/// 校验 `count` 个像素字节、每个 `bits` 位的总位数不超过一个 `u64` 的 64 bits
+fn ensure_size_limit(count: usize, bits: u8) -> Result<(), StegError> {
+    // 一个 u64 只能存储 64 bits，例如 2 bits/字节 时最多需要 32 个像素字节
+    let requested = count.saturating_mul(bits as usize);
+    if requested <= 64 {
+        Ok(())
+    } else {
+        Err(StegError::SizeLimitExceeded { requested })
+    }
+}
+
+/// 计算 `dix..dix + size` 区域的结束索引，并校验其不超出长度为 `len` 的缓冲区
+fn region_end(dix: usize, size: usize, len: usize) -> Result<usize, StegError> {
+    let end = dix
+        .checked_add(size)
+        .ok_or(StegError::Overflow { dix, size })?;
+    if end <= len {
+        Ok(end)
+    } else {
+        Err(StegError::OutOfBounds {
+            start: dix,
+            end,
+            len,
+        })
+    }
+}
+
+/// 一个值拆分为多组 `bits` 位后，各组写入像素字节的顺序
+///
+/// 只影响载荷内容，头部字段始终按小端序写入；该选项不会记录在头部中，恢复时必须与隐藏时相同
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum Endian {
+    /// 小端序：最低的一组位写入第一个像素字节
+    #[default]
+    #[value(name = "le")]
+    Little,
+
+    /// 大端序：最高的一组位写入第一个像素字节
+    #[value(name = "be")]
+    Big,
+}
+
+impl fmt::Display for Endian {
+    /// 显示与命令行参数 `--endian` 相同的名称
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Endian::Little => "le",
+            Endian::Big => "be",
+        };
+        f.write_str(name)
+    }
+}
+
+/// 单个像素字节中承载数据的位窗口：从第 `plane` 位开始的 `bits` 位
+///
+/// 构造时校验位深度与位平面并推导出读写所需的掩码，`modify`、`recover` 等函数都通过它读写每个像素字节
+/// 默认值为 `DEFAULT_BITS` 位、位平面 0、小端序，即头部使用的编码方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Codec {
+    bits: u8,
+    plane: u8,
+    value_mask: u8,
+    data_mask: u8,
+    endian: Endian,
+}
+
+impl Codec {
+    /// 构造使用每个像素字节第 `plane` 位到第 `plane + bits - 1` 位的编码器
+    ///
+    /// # Errors
+    ///
+    /// * 如果 `bits` 不在 `1..=MAX_BITS` 范围内，将返回 `StegError::UnsupportedBits`
+    /// * 如果 `plane + bits` 超过 8，将返回 `StegError::UnsupportedPlane`
+    pub fn new(bits: u8, plane: u8) -> Result<Self, StegError> {
+        ensure_bits(bits)?;
+        ensure_plane(plane, bits)?;
+        let value_mask = lsb_mask(bits);
+        Ok(Self {
+            bits,
+            plane,
+            value_mask,
+            data_mask: !(value_mask << plane),
+            endian: Endian::Little,
+        })
+    }
+
+    /// 返回按 `endian` 顺序组合各组位的编码器，位深度与位平面不变
+    pub fn with_endian(self, endian: Endian) -> Self {
+        Self { endian, ..self }
+    }
+
+    /// 每个像素字节承载的数据位数
+    pub fn bits(&self) -> u8 {
+        self.bits
+    }
+
+    /// 数据窗口的最低位在像素字节中的位置
+    pub fn plane(&self) -> u8 {
+        self.plane
+    }
+
+    /// 各组位写入像素字节的顺序
+    pub fn endian(&self) -> Endian {
+        self.endian
+    }
+
+    /// 隐藏一个载荷字节所需的像素字节数
+    pub fn bytes_per_char(&self) -> usize {
+        bytes_per_char(self.bits)
+    }
+
+    /// 一个值占用 `count` 个像素字节时，第 `index` 个像素字节承载的那组位在值中的起始位置
+    fn shift(&self, index: usize, count: usize) -> usize {
+        let group = match self.endian {
+            Endian::Little => index,
+            Endian::Big => count - 1 - index,
+        };
+        group * self.bits as usize
+    }
+
+    /// 将 `value` 的最低 `bits` 位写入像素字节 `byte` 的数据窗口，返回新的像素字节，窗口之外的位保持不变
+    pub fn embed(&self, byte: u8, value: u8) -> u8 {
+        ((value & self.value_mask) << self.plane) | (byte & self.data_mask)
+    }
+
+    /// 从像素字节 `byte` 的数据窗口中取出 `bits` 位数据，结果位于返回值的最低位
+    pub fn extract(&self, byte: u8) -> u8 {
+        byte >> self.plane & self.value_mask
+    }
+}
+
+impl Default for Codec {
+    fn default() -> Self {
+        let value_mask = lsb_mask(DEFAULT_BITS);
+        Self {
+            bits: DEFAULT_BITS,
+            plane: 0,
+            value_mask,
+            data_mask: !value_mask,
+            endian: Endian::Little,
+        }
+    }
+}
+
+/// 以一对位掩码描述的位窗口，供实验性的编码方式直接指定每个像素字节中承载数据的位
+///
+/// `lsb_mask` 中的位承载数据，`data_mask` 中的位保持不变；两者必须互补 (`lsb_mask | data_mask == 0xFF` 且互不重叠)，
+/// `lsb_mask` 必须是连续的 1 到 `MAX_BITS` 位。默认值为 `LSB_MASK` 与 `DATA_MASK`，即默认位深度下的最低有效位
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MaskConfig {
+    /// 承载数据的位
+    pub lsb_mask: u8,
+
+    /// 保持不变的位
+    pub data_mask: u8,
+}
+
+impl MaskConfig {
+    /// 由两个掩码构造位窗口，并立即校验两者是否一致
+    ///
+    /// # Errors
+    ///
+    /// 参见 [`MaskConfig::codec`]
+    pub fn new(lsb_mask: u8, data_mask: u8) -> Result<Self, StegError> {
+        let config = Self {
+            lsb_mask,
+            data_mask,
+        };
+        config.codec()?;
+        Ok(config)
+    }
+
+    /// 转换为读写像素字节所用的编码器
+    ///
+    /// # Errors
+    ///
+    /// * 如果两个掩码不互补，或 `lsb_mask` 为 0 或不是连续的若干位，将返回 `StegError::InvalidMask`
+    /// * 如果 `lsb_mask` 超过 `MAX_BITS` 位，将返回 `StegError::UnsupportedBits`
+    pub fn codec(&self) -> Result<Codec, StegError> {
+        let plane = self.lsb_mask.trailing_zeros() as u8;
+        let bits = self.lsb_mask.count_ones() as u8;
+        let consistent = self.lsb_mask & self.data_mask == 0
+            && self.lsb_mask | self.data_mask == u8::MAX
+            && self.lsb_mask != 0
+            && self.lsb_mask >> plane == lsb_mask(bits);
+        if !consistent {
+            return Err(StegError::InvalidMask {
+                lsb_mask: self.lsb_mask,
+                data_mask: self.data_mask,
+            });
+        }
+        Codec::new(bits, plane)
+    }
+}
+
+impl Default for MaskConfig {
+    fn default() -> Self {
+        Self {
+            lsb_mask: LSB_MASK,
+            data_mask: DATA_MASK,
+        }
+    }
+}
 
 /// 隐藏一个 64 位值 (`value`) 到像素数组 (`pix`) 的指定区域
 ///
-/// 隐写采用 LSB (最低有效位) 机制，使用像素字节的最低两位 (`& 0x3`) 来存储数据
-/// 每个像素字节可以存储 2 bits 的数据，因此 `size` 字节可存储 `size * 2` bits
+/// 隐写采用 LSB (最低有效位) 机制，使用像素字节的最低 `bits` 位来存储数据
+/// 每个像素字节可以存储 `bits` bits 的数据，因此 `size` 字节可存储 `size * bits` bits
 /// 数据是按小端序 (Little-Endian) 方式写入的：`value` 的最低位写入 `sub_pix` 的第一个字节
 ///
 /// # Arguments
@@ -18,49 +285,67 @@ use anyhow::Context;
 /// * `pix` - 包含图像像素数据的可变字节切片
 /// * `dix` - 数据开始隐写的索引偏移量 (Data Index)
 /// * `size` - 用于隐写的字节数 (像素字节数)
+/// * `bits` - 每个像素字节使用的最低有效位数 (1 到 `MAX_BITS`)
 ///
 /// # Errors
 ///
-/// * 如果 `dix + size` 的计算导致整数溢出，将返回 `ErrorKind::InvalidInput` 错误
-/// * 如果计算出的隐写区域 `dix..end` 超出了 `pix` 的边界，将返回 `ErrorKind::InvalidInput` 错误
-pub fn modify(mut value: u64, pix: &mut [u8], dix: usize, size: usize) -> anyhow::Result<()> {
-    // 一个 u64 只能存储 64 bits，需要 32 个像素字节 (32 * 2 bits)
-    anyhow::ensure!(
-        size <= 32,
-        "Steganography size limit exceeded (max 32 bytes for a u64 value)."
-    );
-
-    // 计算恢复区域的结束索引
-    let end = dix.checked_add(size).with_context(|| {
-        format!(
-            "Integer overflow when calculating end index.\ndix: {}, size: {}",
-            dix, size
-        )
-    })?;
+/// * 如果 `bits` 不在 `1..=MAX_BITS` 范围内，将返回 `StegError::UnsupportedBits`
+/// * 如果 `size * bits` 超过 64，将返回 `StegError::SizeLimitExceeded`
+/// * 如果 `dix + size` 的计算导致整数溢出，将返回 `StegError::Overflow`
+/// * 如果计算出的隐写区域 `dix..end` 超出了 `pix` 的边界，将返回 `StegError::OutOfBounds`
+pub fn modify(
+    mut value: u64,
+    pix: &mut [u8],
+    dix: usize,
+    size: usize,
+    bits: u8,
+) -> Result<(), StegError> {
+    let codec = Codec::new(bits, 0)?;
+    ensure_size_limit(size, bits)?;
 
-    // 获取用于隐写的像素子切片
-    let sub_pix = pix.get_mut(dix..end).with_context(|| {
-        format!(
-            "Steganography region out of bounds.\n dix: {}, end: {}",
-            dix, end
-        )
-    })?;
+    // 计算隐写区域的结束索引，并获取用于隐写的像素子切片
+    let end = region_end(dix, size, pix.len())?;
+    let sub_pix = &mut pix[dix..end];
 
-    // 遍历每个像素字节，将 value 的 2 bits 写入其 LSB
+    // 遍历每个像素字节，将 value 的 `bits` 位写入其 LSB
     for byte in sub_pix.iter_mut() {
-        // 清除像素字节的最低两位，然后或上 value 的最低两位
-        *byte = ((value & (LSB_MASK as u64)) as u8) | (*byte & DATA_MASK);
+        // 清除像素字节的最低 `bits` 位，然后或上 value 的最低 `bits` 位
+        *byte = codec.embed(*byte, value as u8);
 
-        // value 右移两位，为下一次迭代做准备
-        value >>= 2;
+        // value 右移 `bits` 位，为下一次迭代做准备
+        value >>= bits;
     }
 
     Ok(())
 }
 
+/// 与 `modify` 相同，但不修改 `pix`，而是返回隐藏了 `value` 的新缓冲区
+///
+/// 便于不持有可变缓冲区的调用方使用，或比较隐藏前后的差异；需要复制整个缓冲区，性能敏感的场景应使用 `modify`
+///
+/// # Errors
+///
+/// 参见 [`modify`]，出错时不会分配新的缓冲区
+pub fn modify_copy(
+    value: u64,
+    pix: &[u8],
+    dix: usize,
+    size: usize,
+    bits: u8,
+) -> Result<Vec<u8>, StegError> {
+    // 先校验参数，避免为注定失败的调用复制缓冲区
+    Codec::new(bits, 0)?;
+    ensure_size_limit(size, bits)?;
+    region_end(dix, size, pix.len())?;
+
+    let mut copy = pix.to_vec();
+    modify(value, &mut copy, dix, size, bits)?;
+    Ok(copy)
+}
+
 /// 从像素数组 (`pix`) 的指定区域恢复一个 64 位值
 ///
-/// 从每个像素字节的最低两位 (`& 0x3`) 中提取数据，并按照小端序 (Little-Endian)
+/// 从每个像素字节的最低 `bits` 位中提取数据，并按照小端序 (Little-Endian)
 /// 方式组合成一个 64 位整数
 ///
 /// # Arguments
@@ -68,6 +353,7 @@ pub fn modify(mut value: u64, pix: &mut [u8], dix: usize, size: usize) -> anyhow
 /// * `pix` - 包含图像像素数据的字节切片
 /// * `dix` - 数据开始恢复的索引偏移量 (Data Index)
 /// * `size` - 用于恢复的字节数 (像素字节数)
+/// * `bits` - 每个像素字节使用的最低有效位数 (1 到 `MAX_BITS`)
 ///
 /// # Returns
 ///
@@ -75,207 +361,4216 @@ pub fn modify(mut value: u64, pix: &mut [u8], dix: usize, size: usize) -> anyhow
 ///
 /// # Errors
 ///
-/// * 如果 `dix + size` 的计算导致整数溢出，将返回 `ErrorKind::InvalidInput` 错误
-/// * 如果计算出的恢复区域 `dix..end` 超出了 `pix` 的边界，将返回 `ErrorKind::InvalidInput` 错误
-/// * 如果 `size` 大于 32，由于 u64 只有 64 bits (32 bytes * 2 bits/byte)，将返回 `ErrorKind::InvalidInput` 错误
-pub fn recover(pix: &[u8], dix: usize, size: usize) -> anyhow::Result<u64> {
-    // 一个 u64 只能存储 64 bits，需要 32 个像素字节 (32 * 2 bits)
-    anyhow::ensure!(
-        size <= 32,
-        "Extraction size limit exceeded (max 32 bytes for a u64 value)."
-    );
-
-    // 计算恢复区域的结束索引
-    let end = dix.checked_add(size).with_context(|| {
-        format!(
-            "Integer overflow when calculating end index.\ndix: {}, size: {}",
-            dix, size
-        )
-    })?;
+/// * 如果 `bits` 不在 `1..=MAX_BITS` 范围内，将返回 `StegError::UnsupportedBits`
+/// * 如果 `dix + size` 的计算导致整数溢出，将返回 `StegError::Overflow`
+/// * 如果计算出的恢复区域 `dix..end` 超出了 `pix` 的边界，将返回 `StegError::OutOfBounds`
+/// * 如果 `size * bits` 大于 64，由于 u64 只有 64 bits，将返回 `StegError::SizeLimitExceeded`
+pub fn recover(pix: &[u8], dix: usize, size: usize, bits: u8) -> Result<u64, StegError> {
+    let codec = Codec::new(bits, 0)?;
+    ensure_size_limit(size, bits)?;
 
-    // 获取用于恢复的像素子切片
-    let sub_pix = pix
-        .get(dix..end)
-        .with_context(|| format!("Extraction area out of bounds.\ndix: {}, end: {}", dix, end))?;
+    // 计算恢复区域的结束索引，并获取用于恢复的像素子切片
+    let end = region_end(dix, size, pix.len())?;
+    let sub_pix = &pix[dix..end];
 
-    // 从每个像素字节的 LSB 中提取 2 bits，并将其组合成一个 u64 值
+    // 从每个像素字节的 LSB 中提取 `bits` 位，并将其组合成一个 u64 值
     let result = sub_pix.iter().enumerate().fold(0u64, |acc, (i, &byte)| {
-        // 提取最低两位，并左移到正确的位置，然后累加到结果中
-        acc | ((byte & LSB_MASK) as u64) << (i * 2)
+        // 提取最低 `bits` 位，并左移到正确的位置，然后累加到结果中
+        acc | (codec.extract(byte) as u64) << (i * bits as usize)
     });
 
     Ok(result)
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::constants::*;
-    use rand::RngCore;
+/// 与 [`modify`] 相同，但失败时只返回一个字节的 [`ErrorCode`]
+///
+/// 两者都不会在错误路径上分配内存；需要区间、位数等详细信息时使用 `modify`
+///
+/// # Errors
+///
+/// 参见 [`modify`]，返回对应 `StegError` 的 [`StegError::code`]
+#[inline]
+pub fn try_modify(
+    value: u64,
+    pix: &mut [u8],
+    dix: usize,
+    size: usize,
+    bits: u8,
+) -> Result<(), ErrorCode> {
+    modify(value, pix, dix, size, bits).map_err(|err| err.code())
+}
 
-    /// 一个完整的端到端测试，模拟隐藏和恢复过程
-    #[test]
-    fn test_hide_and_recover_e2e() {
-        // 1. 准备测试数据
-        // 模拟一个 BMP 文件的头部和足够大的数据区
-        let mut picture = vec![0u8; 1024];
+/// 与 [`recover`] 相同，但失败时只返回一个字节的 [`ErrorCode`]
+///
+/// # Errors
+///
+/// 参见 [`recover`]，返回对应 `StegError` 的 [`StegError::code`]
+#[inline]
+pub fn try_recover(pix: &[u8], dix: usize, size: usize, bits: u8) -> Result<u64, ErrorCode> {
+    recover(pix, dix, size, bits).map_err(|err| err.code())
+}
 
-        // 填充一些伪随机数据，模拟真实的图像像素
-        rand::rng().fill_bytes(&mut picture);
+/// 将一个 64 位值 (`value`) 隐藏到像素数组 (`pix`) 中由 `positions` 指定的任意位置
+///
+/// 与 `modify` 相同，但不要求像素字节连续：第 `i` 个 `bits` 位分组写入 `pix[positions[i]]`
+/// 这使得载荷可以按任意索引映射 (例如伪随机排列) 分散在图像中
+///
+/// 数据写入每个像素字节的第 `plane` 位到第 `plane + bits - 1` 位，即掩码整体左移 `plane` 位，
+/// 该窗口之外的位保持不变；`plane = 0` 时与 `modify` 一样使用最低有效位
+///
+/// # Errors
+///
+/// * 如果 `bits` 不在 `1..=MAX_BITS` 范围内，将返回 `StegError::UnsupportedBits`
+/// * 如果 `plane + bits` 超过 8，将返回 `StegError::UnsupportedPlane`
+/// * 如果 `positions.len() * bits` 超过 64，将返回 `StegError::SizeLimitExceeded`
+/// * 如果任一位置超出了 `pix` 的边界，将返回 `StegError::OutOfBounds`，且不会修改任何像素字节
+pub fn modify_at(
+    value: u64,
+    pix: &mut [u8],
+    positions: &[usize],
+    bits: u8,
+    plane: u8,
+) -> Result<(), StegError> {
+    modify_with(&Codec::new(bits, plane)?, value, pix, positions)
+}
 
-        // 模拟要隐藏的文本
-        let original_text = "Hello, Steganography! 你好，隐写术！";
-        let text_bytes = original_text.as_bytes();
-        let text_len = text_bytes.len() as u64;
+/// 与 `modify_at` 相同，但位深度、位平面与各组位的顺序 (参见 [`Endian`]) 由已校验的 `codec` 给出
+///
+/// # Errors
+///
+/// * 如果 `positions.len() * bits` 超过 64，将返回 `StegError::SizeLimitExceeded`
+/// * 如果任一位置超出了 `pix` 的边界，将返回 `StegError::OutOfBounds`，且不会修改任何像素字节
+pub fn modify_with(
+    codec: &Codec,
+    value: u64,
+    pix: &mut [u8],
+    positions: &[usize],
+) -> Result<(), StegError> {
+    ensure_size_limit(positions.len(), codec.bits)?;
+    ensure_positions(pix.len(), positions)?;
 
-        // 2. 隐藏数据
-        // 隐藏文本长度
-        modify(text_len, &mut picture, 0, LENGTH_HIDING_BYTES)
-            .expect("Failed to hide text length.");
+    for (i, &index) in positions.iter().enumerate() {
+        let group = value >> codec.shift(i, positions.len());
+        pix[index] = codec.embed(pix[index], group as u8);
+    }
 
-        // 逐字节隐藏文本内容
-        for (i, &char_byte) in text_bytes.iter().enumerate() {
-            let offset = LENGTH_HIDING_BYTES + BYTES_PER_CHAR * i;
-            modify(char_byte as u64, &mut picture, offset, BYTES_PER_CHAR)
-                .expect("Failed to hide a character.");
-        }
+    Ok(())
+}
 
-        // 3. 恢复数据
-        // 恢复文本长度
-        let recovered_len =
-            recover(&picture, 0, LENGTH_HIDING_BYTES).expect("Failed to recover text length.");
+/// 与 `modify_with` 相同，但位窗口由自定义的掩码 `mask` 给出
+///
+/// # Errors
+///
+/// * 如果掩码不一致，将返回 `StegError::InvalidMask` 或 `StegError::UnsupportedBits` (参见 [`MaskConfig::codec`])
+/// * 其余错误参见 [`modify_with`]
+pub fn modify_with_mask(
+    mask: &MaskConfig,
+    value: u64,
+    pix: &mut [u8],
+    positions: &[usize],
+) -> Result<(), StegError> {
+    modify_with(&mask.codec()?, value, pix, positions)
+}
 
-        // 断言长度一致
-        assert_eq!(
-            text_len, recovered_len,
-            "Recovered length should match original length."
-        );
+/// 从像素数组 (`pix`) 中由 `positions` 指定的任意位置恢复一个 64 位值
+///
+/// 是 `modify_at` 的逆操作，位的组合顺序与 `recover` 相同 (小端序)
+/// 数据从每个像素字节的第 `plane` 位开始读取，必须与隐藏时的位平面相同
+///
+/// # Errors
+///
+/// * 如果 `bits` 不在 `1..=MAX_BITS` 范围内，将返回 `StegError::UnsupportedBits`
+/// * 如果 `plane + bits` 超过 8，将返回 `StegError::UnsupportedPlane`
+/// * 如果 `positions.len() * bits` 超过 64，将返回 `StegError::SizeLimitExceeded`
+/// * 如果任一位置超出了 `pix` 的边界，将返回 `StegError::OutOfBounds`
+pub fn recover_at(pix: &[u8], positions: &[usize], bits: u8, plane: u8) -> Result<u64, StegError> {
+    recover_with(&Codec::new(bits, plane)?, pix, positions)
+}
 
-        // 逐字节恢复文本内容
-        let recovered_bytes: Vec<u8> = (0..recovered_len as usize)
-            .map(|i| {
-                let offset = LENGTH_HIDING_BYTES + BYTES_PER_CHAR * i;
-                recover(&picture, offset, BYTES_PER_CHAR)
-                    .map(|val| val as u8)
-                    .expect("Failed to recover a character.")
-            })
-            .collect();
+/// 与 `recover_at` 相同，但位深度、位平面与各组位的顺序由已校验的 `codec` 给出
+///
+/// # Errors
+///
+/// * 如果 `positions.len() * bits` 超过 64，将返回 `StegError::SizeLimitExceeded`
+/// * 如果任一位置超出了 `pix` 的边界，将返回 `StegError::OutOfBounds`
+pub fn recover_with(codec: &Codec, pix: &[u8], positions: &[usize]) -> Result<u64, StegError> {
+    ensure_size_limit(positions.len(), codec.bits)?;
+    ensure_positions(pix.len(), positions)?;
 
-        // 4. 断言结果
-        // 断言恢复的文本内容与原始文本完全一致
-        assert_eq!(
-            text_bytes,
-            recovered_bytes.as_slice(),
-            "Recovered text should match original text."
-        );
-    }
+    let result = positions.iter().enumerate().fold(0u64, |acc, (i, &index)| {
+        acc | (codec.extract(pix[index]) as u64) << codec.shift(i, positions.len())
+    });
 
-    /// 测试 modify 函数在数据不足时能否正确返回错误
-    #[test]
-    fn test_modify_not_enough_space() {
-        let mut picture = vec![0u8; 7];
-        // 尝试在 7 字节的缓冲区中写入 8 字节的数据
-        let result = modify(123, &mut picture, 0, 8);
-        assert!(
-            result.is_err(),
-            "Modify should fail when there is not enough space."
-        );
+    Ok(result)
+}
+
+/// 与 `recover_with` 相同，但位窗口由自定义的掩码 `mask` 给出
+///
+/// # Errors
+///
+/// * 如果掩码不一致，将返回 `StegError::InvalidMask` 或 `StegError::UnsupportedBits` (参见 [`MaskConfig::codec`])
+/// * 其余错误参见 [`recover_with`]
+pub fn recover_with_mask(
+    mask: &MaskConfig,
+    pix: &[u8],
+    positions: &[usize],
+) -> Result<u64, StegError> {
+    recover_with(&mask.codec()?, pix, positions)
+}
+
+/// 校验所有位置都落在长度为 `len` 的缓冲区内
+fn ensure_positions(len: usize, positions: &[usize]) -> Result<(), StegError> {
+    match positions.iter().find(|&&index| index >= len) {
+        Some(&index) => Err(StegError::OutOfBounds {
+            start: index,
+            end: index.saturating_add(1),
+            len,
+        }),
+        None => Ok(()),
     }
+}
 
-    /// 测试 recover 函数在数据不足时能否正确返回错误
-    #[test]
-    fn test_recover_not_enough_data() {
-        // 只有 7 个字节，但我们需要 8 个字节来恢复一个 u64
-        let picture = vec![0u8; 7];
-        let result = recover(&picture, 0, 8);
+/// 可选的来源信息：载荷于何时、由哪个版本的工具隐藏
+///
+/// 写在头部的文件名之后，每个字节以默认位深度占用 `BYTES_PER_CHAR` 个像素字节：
+/// `TIMESTAMP_LEN` 个字节的小端序 Unix 时间戳、1 个字节的版本字符串长度以及 UTF-8 版本字符串
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Provenance {
+    /// 隐藏时的 Unix 时间戳 (秒)
+    pub timestamp: u64,
 
-        // 断言结果是 Err
-        assert!(
-            result.is_err(),
-            "Recover should fail when there is not enough data."
-        );
+    /// 隐藏载荷的工具版本，最长 255 个字节
+    pub tool_version: String,
+}
+
+impl Provenance {
+    /// 以当前时间与本程序的版本号构造来源信息
+    pub fn now() -> Self {
+        Self {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_secs()),
+            tool_version: env!("CARGO_PKG_VERSION").to_string(),
+        }
     }
 
-    /// 测试整数溢出时，modify 函数是否会失败
-    #[test]
-    fn test_modify_integer_overflow() {
-        let mut picture = vec![0u8; 10];
-        // dix + size 会导致 usize 溢出
-        let result = modify(123, &mut picture, usize::MAX, 10);
-        assert!(
-            result.is_err(),
-            "Recover function should fail on integer overflow."
-        );
+    /// 来源信息在头部中占用的字节数 (每个字节再占用 `BYTES_PER_CHAR` 个像素字节)
+    pub fn encoded_len(&self) -> usize {
+        TIMESTAMP_LEN + 1 + self.tool_version.len()
     }
+}
 
-    /// 测试整数溢出时，recover 函数是否会失败
-    #[test]
-    fn test_recover_integer_overflow() {
-        let picture = vec![0u8; 10];
-        // dix + size 会导致 usize 溢出
-        let result = recover(&picture, usize::MAX, 10);
-        assert!(
-            result.is_err(),
-            "Recover function should fail on integer overflow."
-        );
+/// 载荷的类型标签，记录在头部中供恢复时选择合适的默认处理方式
+///
+/// 加密或白化的载荷只记录 `Encrypted` 或 `Whitened`，不透露明文是文本还是二进制数据
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PayloadType {
+    /// 有效的 UTF-8 文本
+    Text,
+    /// 任意二进制数据
+    Binary,
+    /// 多文件归档
+    Archive,
+    /// 已加密的载荷，明文类型未知
+    Encrypted,
+    /// 已与 `--xor-key` 派生的密钥流异或的载荷，明文类型未知
+    Whitened,
+    /// `--split` 拆分到多张图像中的一段，完整载荷的类型记录在段头中
+    Segment,
+}
+
+impl PayloadType {
+    /// 写入隐写头部的类型编号
+    pub fn id(self) -> u8 {
+        match self {
+            PayloadType::Text => 1,
+            PayloadType::Binary => 2,
+            PayloadType::Archive => 3,
+            PayloadType::Encrypted => 4,
+            PayloadType::Whitened => 5,
+            PayloadType::Segment => 6,
+        }
     }
 
-    /// 测试当请求恢复的 size 大于 32 时，modify 函数是否会失败
-    #[test]
-    fn test_modify_size_too_large() {
-        let mut picture = vec![0u8; 64];
-        // size=33 超过了 u64 能容纳的 32 字节
-        let result = modify(123, &mut picture, 0, 33);
-        assert!(
-            result.is_err(),
-            "Modify should fail when size is greater than 32."
-        );
+    /// 根据隐写头部中的类型编号还原类型标签
+    ///
+    /// # Errors
+    ///
+    /// 如果编号未知，将返回错误
+    pub fn from_id(id: u8) -> anyhow::Result<Self> {
+        match id {
+            1 => Ok(PayloadType::Text),
+            2 => Ok(PayloadType::Binary),
+            3 => Ok(PayloadType::Archive),
+            4 => Ok(PayloadType::Encrypted),
+            5 => Ok(PayloadType::Whitened),
+            6 => Ok(PayloadType::Segment),
+            _ => anyhow::bail!("Unknown payload type id: {}", id),
+        }
     }
+}
 
-    /// 测试当请求恢复的 size 大于 32 时，recover 函数是否会失败
-    #[test]
-    fn test_recover_size_too_large() {
-        let picture = vec![0u8; 64];
-        // size=33 超过了 u64 能容纳的 32 字节
-        let result = recover(&picture, 0, 33);
-        assert!(
-            result.is_err(),
-            "Recover should fail when size is greater than 32."
-        );
+impl fmt::Display for PayloadType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            PayloadType::Text => "text",
+            PayloadType::Binary => "binary",
+            PayloadType::Archive => "archive",
+            PayloadType::Encrypted => "encrypted",
+            PayloadType::Whitened => "whitened",
+            PayloadType::Segment => "segment",
+        };
+        f.write_str(name)
     }
+}
 
-    /// 测试隐藏和恢复一个空文本（长度为 0）
-    #[test]
-    fn test_hide_and_recover_empty_text() {
-        let mut picture = vec![0u8; 128];
-        let text_len = 0u64;
+/// 隐藏载荷时使用的参数
+///
+/// 除打散种子外，这些参数都会被记录在隐写头部中，恢复时无需再次指定
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HideOptions {
+    /// 载荷每个像素字节使用的最低有效位数 (1 到 `MAX_BITS`)
+    pub bits: u8,
 
-        // 隐藏长度 0
-        modify(text_len, &mut picture, 0, LENGTH_HIDING_BYTES)
-            .expect("Failed to hide zero length.");
+    /// 载荷标志位，例如 `FLAG_ENCRYPTED`
+    ///
+    /// `FLAG_SCATTERED` 由 `scatter_seed` 自动决定，无需手动设置
+    pub flags: u8,
 
-        // 恢复长度
-        let recovered_len =
-            recover(&picture, 0, LENGTH_HIDING_BYTES).expect("Failed to recover zero length.");
+    /// 载荷数据窗口的最低位在每个像素字节中的位置 (0 表示最低有效位)
+    ///
+    /// `plane + bits` 不能超过 8，头部字段始终写在最低有效位中
+    pub plane: u8,
 
-        assert_eq!(text_len, recovered_len, "Recovered length should be 0.");
-    }
+    /// 载荷在隐藏前所使用的压缩算法
+    ///
+    /// 仅作记录，压缩本身由调用方在隐藏之前完成
+    pub compression: Compression,
 
-    /// 测试隐藏和恢复 `u64` 的最大值，以确保所有 64 位都被正确处理
-    #[test]
-    fn test_hide_and_recover_u64_max() {
-        // 准备一个足够大的字节缓冲区
-        let mut picture = vec![0u8; 64];
+    /// 文本载荷的原始编码，记录在头部中供恢复时转换编码
+    ///
+    /// 仅作记录，载荷按原始编码的字节隐藏
+    pub text_encoding: Option<&'static Encoding>,
 
-        // 定义要隐藏的值为 u64 的最大可能值
-        let value_to_hide = u64::MAX;
+    /// 承载载荷的颜色通道，未选中通道的像素字节保持不变
+    pub channels: Channels,
 
-        // 隐藏 u64::MAX
-        modify(value_to_hide, &mut picture, 0, 32).expect("Failed to hide u64::MAX");
+    /// 每个像素包含的通道字节数 (RGB 图像为 3，RGBA 图像为 4)
+    ///
+    /// 仅在 `channels` 未覆盖全部通道时用于定位各通道的字节
+    pub stride: usize,
 
-        // 恢复 u64::MAX
-        let recovered_value = recover(&picture, 0, 32).expect("Failed to recover u64::MAX");
+    /// 打散种子
+    ///
+    /// 提供后，载荷将按该种子生成的伪随机排列分散到头部之后的像素字节中，
+    /// 恢复时必须提供相同的种子
+    pub scatter_seed: Option<[u8; SEED_LEN]>,
 
-        assert_eq!(value_to_hide, recovered_value);
+    /// 载荷的原始文件名，供恢复时选择默认的输出路径
+    ///
+    /// 为空表示未知，最长 `MAX_NAME_LEN` 个字节
+    pub name: String,
+
+    /// 可选的来源信息
+    ///
+    /// 提供后会记录在头部中并自动设置 `FLAG_PROVENANCE`；为 `None` 时不占用任何容量
+    pub provenance: Option<Provenance>,
+
+    /// 以定长方式记录载荷长度时使用的位数 (`LENGTH_BITS_CHOICES` 之一)
+    ///
+    /// 提供后自动设置 `FLAG_FIXED_LENGTH`，载荷长度不能超出该位数所能表示的范围；
+    /// 为 `None` 时使用变长编码，总是得到最短的头部
+    pub length_bits: Option<u8>,
+
+    /// 载荷重复写入的份数 (不超过 `MAX_REDUNDANCY` 的奇数)
+    ///
+    /// 大于 1 时自动设置 `FLAG_REDUNDANT`，恢复时逐位多数表决，可以容忍少数副本损坏；
+    /// 容量按份数等比例减少
+    pub redundancy: u8,
+
+    /// 载荷的类型标签
+    ///
+    /// 提供后会记录在头部中并自动设置 `FLAG_TYPED`；为 `None` 时不占用任何容量
+    pub payload_type: Option<PayloadType>,
+
+    /// 不记录载荷长度，而是以起始与结束标记包围转义后的载荷 (参见 [`delimited`])
+    ///
+    /// 自动设置 `FLAG_DELIMITED`；不能与打散、定长长度字段或冗余副本同时使用
+    pub delimited: bool,
+
+    /// 载荷在 `bits` 位深度下放不下时，逐位增加位深度 (至多 `MAX_BITS`) 直到放得下
+    ///
+    /// 实际使用的位深度记录在头部中，并通过 [`EmbedStats::bits`] 返回
+    pub auto_bits: bool,
+
+    /// 在像素缓冲区末尾额外写入一份载荷长度的备份 (占用 `LENGTH_BACKUP_BYTES` 个像素字节)
+    ///
+    /// 头部中的长度字段损坏时，恢复仍可按备份的长度提取载荷；不能与分隔模式同时使用
+    pub length_backup: bool,
+
+    /// 载荷像素字节的间隔 (1 到 `MAX_SPACING`)：只使用所选通道中每 `spacing` 个像素字节中的第一个
+    ///
+    /// 间隔记录在头部中，恢复时无需再次指定；容量按间隔等比例减少
+    pub spacing: usize,
+
+    /// 只在纹理丰富 (梯度不低于 `EDGE_GRADIENT_THRESHOLD`) 的像素字节中隐藏载荷，平滑区域保持不变
+    ///
+    /// 该选项不会记录在头部中，恢复时必须同样指定；不能与分隔模式同时使用
+    pub edge_adaptive: bool,
+
+    /// 载荷每个字节拆分为多组位后写入像素字节的顺序
+    ///
+    /// 该选项不会记录在头部中，恢复时必须指定相同的顺序，否则完整性校验失败
+    pub endian: Endian,
+}
+
+impl Default for HideOptions {
+    fn default() -> Self {
+        Self {
+            bits: DEFAULT_BITS,
+            flags: 0,
+            plane: 0,
+            compression: Compression::None,
+            text_encoding: None,
+            channels: Channels::ALL,
+            stride: 4,
+            scatter_seed: None,
+            name: String::new(),
+            provenance: None,
+            length_bits: None,
+            redundancy: 1,
+            payload_type: None,
+            delimited: false,
+            auto_bits: false,
+            length_backup: false,
+            spacing: 1,
+            edge_adaptive: false,
+            endian: Endian::Little,
+        }
+    }
+}
+
+/// 恢复载荷时使用的参数
+///
+/// 仅包含无法记录在头部中的参数：秘密的打散种子，以及由图像本身决定的像素布局
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecoverOptions {
+    /// 每个像素包含的通道字节数，必须与隐藏时相同
+    pub stride: usize,
+
+    /// 打散种子，必须与隐藏时使用的种子相同
+    pub scatter_seed: Option<[u8; SEED_LEN]>,
+
+    /// 最多提取的载荷字节数
+    ///
+    /// 头部声称的长度超过该上限时，在提取任何载荷字节之前返回 `StegError::PayloadLimitExceeded`；
+    /// 为 `None` 时上限为图像的实际容量
+    pub payload_limit: Option<u64>,
+
+    /// 载荷是否只隐藏在纹理丰富的像素字节中，必须与隐藏时相同 (参见 [`HideOptions::edge_adaptive`])
+    pub edge_adaptive: bool,
+
+    /// 载荷各组位的顺序，必须与隐藏时相同 (参见 [`HideOptions::endian`])
+    pub endian: Endian,
+
+    /// 载荷损坏时是否返回已恢复的部分，而不是报错
+    ///
+    /// 遇到第一个无效字节时停止提取，返回之前成功恢复的字节；完整性校验失败时照常返回全部字节。
+    /// 两种情况都会在 [`EmbedStats::damaged`] 中记录；头部本身无效时仍然返回错误
+    pub best_effort: bool,
+}
+
+impl Default for RecoverOptions {
+    fn default() -> Self {
+        Self {
+            stride: 4,
+            scatter_seed: None,
+            payload_limit: None,
+            edge_adaptive: false,
+            endian: Endian::Little,
+            best_effort: false,
+        }
+    }
+}
+
+/// 从隐写头部中解析出的载荷参数
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Header {
+    /// 写入载荷时使用的格式版本
+    pub version: u8,
+
+    /// 载荷每个像素字节使用的最低有效位数
+    pub bits: u8,
+
+    /// 载荷标志位，例如 `FLAG_ENCRYPTED`
+    pub flags: u8,
+
+    /// 载荷数据窗口的最低位在每个像素字节中的位置
+    pub plane: u8,
+
+    /// 载荷在隐藏前所使用的压缩算法
+    pub compression: Compression,
+
+    /// 文本载荷的原始编码，为 `None` 表示未记录
+    pub text_encoding: Option<&'static Encoding>,
+
+    /// 承载载荷的颜色通道
+    pub channels: Channels,
+
+    /// 载荷像素字节的间隔，1 表示使用所选通道中的每一个像素字节
+    pub spacing: usize,
+
+    /// 载荷的字节长度
+    pub length: u64,
+
+    /// 载荷的 CRC32 校验和，用于在恢复时检测数据损坏
+    pub checksum: u32,
+
+    /// 隐藏时记录的原始文件名，为空表示未知
+    ///
+    /// 来自图像中的数据，用作路径之前必须校验
+    pub name: String,
+
+    /// 隐藏时记录的来源信息，仅当设置了 `FLAG_PROVENANCE` 时存在
+    pub provenance: Option<Provenance>,
+
+    /// 定长长度字段的位数，仅当设置了 `FLAG_FIXED_LENGTH` 时存在；为 `None` 表示使用变长编码
+    pub length_bits: Option<u8>,
+
+    /// 载荷重复写入的份数，未设置 `FLAG_REDUNDANT` 时为 1
+    pub redundancy: u8,
+
+    /// 载荷的类型标签，仅当设置了 `FLAG_TYPED` 时存在
+    pub payload_type: Option<PayloadType>,
+}
+
+impl Header {
+    /// 判断载荷是否经过加密
+    pub fn is_encrypted(&self) -> bool {
+        self.flags & FLAG_ENCRYPTED != 0
+    }
+
+    /// 判断载荷是否按伪随机排列打散
+    pub fn is_scattered(&self) -> bool {
+        self.flags & FLAG_SCATTERED != 0
+    }
+
+    /// 判断载荷是否为多文件归档
+    pub fn is_archive(&self) -> bool {
+        self.flags & FLAG_ARCHIVE != 0
+    }
+
+    /// 判断载荷是否以起始与结束标记分隔，而不是记录长度
+    ///
+    /// 此时 `read_header` 返回的 `length` 为 0，恢复载荷后才能得知实际长度
+    pub fn is_delimited(&self) -> bool {
+        self.flags & FLAG_DELIMITED != 0
+    }
+
+    /// 判断载荷是否经过 `--xor-key` 白化
+    ///
+    /// 标志位已全部占用，白化由类型标签 `PayloadType::Whitened` 记录
+    pub fn is_whitened(&self) -> bool {
+        self.payload_type == Some(PayloadType::Whitened)
+    }
+
+    /// 载荷内容使用的编码器 (位深度与位平面)
+    ///
+    /// # Errors
+    ///
+    /// 由 `read_header` 返回的头部已校验过两者；手动构造的头部位深度或位平面无效时返回相应的 `StegError`
+    pub fn codec(&self) -> Result<Codec, StegError> {
+        Codec::new(self.bits, self.plane)
+    }
+
+    /// 载荷内容在像素缓冲区中的起始偏移量，即完整头部占用的像素字节数
+    pub fn payload_offset(&self) -> usize {
+        let length_len = if self.is_delimited() {
+            0
+        } else {
+            length_field_len(self.length, self.length_bits)
+        };
+        name_field_end(length_len, self.metadata_len())
+    }
+
+    /// 头部中变长元数据 (文件名、可选的来源信息、冗余份数与类型标签) 占用的字节数
+    fn metadata_len(&self) -> usize {
+        metadata_len(
+            &self.name,
+            self.provenance.as_ref(),
+            self.redundancy,
+            self.payload_type,
+        )
+    }
+}
+
+/// 计算隐藏长度为 `payload_len`、文件名为 `name_len` 个字节的载荷时，完整头部占用的像素字节数
+///
+/// 长度以变长编码记录，因此较短的载荷头部更小；不记录文件名时最小为 `HEADER_BYTES`
+/// 记录了来源信息时，将其 `Provenance::encoded_len` 计入 `name_len` 即可得到完整的头部大小
+pub fn header_len(payload_len: u64, name_len: usize) -> usize {
+    name_field_end(varint::encoded_len(payload_len), name_len)
+}
+
+/// 计算头部中文件名、来源信息、冗余份数 (仅当大于 1 时记录，占 1 个字节)
+/// 与类型标签 (仅当存在时记录，占 1 个字节) 合计占用的字节数
+fn metadata_len(
+    name: &str,
+    provenance: Option<&Provenance>,
+    redundancy: u8,
+    payload_type: Option<PayloadType>,
+) -> usize {
+    name.len()
+        + provenance.map_or(0, Provenance::encoded_len)
+        + usize::from(redundancy > 1)
+        + usize::from(payload_type.is_some())
+}
+
+/// 计算载荷长度 `payload_len` 的编码占用的字节数：定长时为 `length_bits / 8`，否则为变长编码的长度
+fn length_field_len(payload_len: u64, length_bits: Option<u8>) -> usize {
+    match length_bits {
+        Some(length_bits) => length_bits as usize / 8,
+        None => varint::encoded_len(payload_len),
+    }
+}
+
+/// 按 `length_bits` 编码载荷长度：定长时为小端序的低 `length_bits / 8` 个字节，否则为 LEB128
+fn encode_length(payload_len: u64, length_bits: Option<u8>) -> Vec<u8> {
+    match length_bits {
+        Some(length_bits) => payload_len.to_le_bytes()[..length_bits as usize / 8].to_vec(),
+        None => varint::encode(payload_len),
+    }
+}
+
+/// 计算长度编码占用 `varint_len` 个字节时，长度字段的结束偏移量 (即文件名字段的起始偏移量)
+fn length_field_end(varint_len: usize) -> usize {
+    LENGTH_OFFSET + LENGTH_PREFIX_HIDING_BYTES + varint_len * bytes_per_char(DEFAULT_BITS)
+}
+
+/// 计算长度编码占用 `varint_len` 个字节、文件名占用 `name_len` 个字节时，整个头部的结束偏移量
+fn name_field_end(varint_len: usize, name_len: usize) -> usize {
+    length_field_end(varint_len)
+        + NAME_LENGTH_HIDING_BYTES
+        + name_len * bytes_per_char(DEFAULT_BITS)
+}
+
+/// 计算长度为 `len` 的像素缓冲区在给定通道布局与位深度下最多可隐藏的载荷字节数 (不记录文件名)
+///
+/// 长度编码越长头部越大，因此依次尝试每一种长度编码字节数并取其中的最大值
+pub fn payload_capacity(len: usize, channels: Channels, stride: usize, bits: u8) -> usize {
+    capacity_with_metadata(len, 0, None, 1, channels, stride, 1, bits)
+}
+
+/// 计算长度为 `len` 的像素缓冲区按 `options` 隐藏时最多可容纳的载荷字节数，头部中的文件名、来源信息等元数据均已扣除
+///
+/// 边缘自适应模式的容量取决于像素内容，这里按普通模式估计
+pub fn hide_capacity(len: usize, options: &HideOptions) -> usize {
+    let metadata_len = metadata_len(
+        &options.name,
+        options.provenance.as_ref(),
+        options.redundancy,
+        options.payload_type,
+    );
+    if options.delimited {
+        delimited_capacity(
+            len,
+            metadata_len,
+            options.channels,
+            options.stride,
+            options.spacing,
+            options.bits,
+        )
+    } else {
+        capacity_with_metadata(
+            len,
+            metadata_len,
+            options.length_bits,
+            options.redundancy,
+            options.channels,
+            options.stride,
+            options.spacing,
+            options.bits,
+        )
+    }
+}
+
+/// 与 [`payload_capacity`] 相同，但头部中还记录了 `metadata_len` 个字节的变长元数据 (文件名及可选的来源信息)，
+/// 载荷长度按 `length_bits` 定长记录 (为 `None` 时使用变长编码)，载荷重复写入 `redundancy` 份且按 `spacing` 间隔选取像素字节
+#[allow(clippy::too_many_arguments)]
+fn capacity_with_metadata(
+    len: usize,
+    metadata_len: usize,
+    length_bits: Option<u8>,
+    redundancy: u8,
+    channels: Channels,
+    stride: usize,
+    spacing: usize,
+    bits: u8,
+) -> usize {
+    let copy_size = bytes_per_char(bits) * redundancy as usize;
+    let fit = |length_len: usize, representable: u64| {
+        let start = name_field_end(length_len, metadata_len);
+        let fit = count_positions(start, len, channels, stride, spacing) / copy_size;
+        fit.min(usize::try_from(representable).unwrap_or(usize::MAX))
+    };
+    match length_bits {
+        Some(length_bits) => fit(
+            length_bits as usize / 8,
+            u64::MAX >> (u64::BITS - length_bits as u32),
+        ),
+        None => (1..=MAX_VARINT_LEN)
+            .map(|varint_len| fit(varint_len, varint::max_value(varint_len)))
+            .max()
+            .unwrap_or(0),
+    }
+}
+
+/// 与 [`capacity_with_metadata`] 相同，但载荷以分隔模式隐藏：头部不记录长度，载荷前后各有一个标记
+///
+/// 假设载荷中没有需要转义的字节，每个 `DELIMITER_ESCAPE` 字节会再占用一个字节的容量
+fn delimited_capacity(
+    len: usize,
+    metadata_len: usize,
+    channels: Channels,
+    stride: usize,
+    spacing: usize,
+    bits: u8,
+) -> usize {
+    let start = name_field_end(0, metadata_len);
+    (count_positions(start, len, channels, stride, spacing) / bytes_per_char(bits))
+        .saturating_sub(DELIMITER_START.len() + DELIMITER_END.len())
+}
+
+/// 计算 `payload_len` 个载荷字节以 `bits` 位深度写入 `redundancy` 份时占用的像素字节数
+///
+/// # Errors
+///
+/// 如果结果超出 `usize` 的范围，将返回 `StegError::PayloadTooLarge`
+fn required_space(payload_len: usize, bits: u8, redundancy: u8) -> Result<usize, StegError> {
+    payload_len
+        .checked_mul(bytes_per_char(bits))
+        .and_then(|copy_space| copy_space.checked_mul(redundancy as usize))
+        .ok_or(StegError::PayloadTooLarge { len: payload_len })
+}
+
+/// 计算 `pixel_count` 个像素字节 (即像素数 × 每像素通道字节数) 使用全部通道、
+/// 每个像素字节 `bits_per_byte` 位时最多可隐藏的载荷字节数，已扣除头部占用的空间
+///
+/// 位深度不在 `1..=MAX_BITS` 范围内时无法隐藏任何数据，返回 0
+pub fn max_payload_bytes(pixel_count: usize, bits_per_byte: usize) -> usize {
+    match u8::try_from(bits_per_byte) {
+        Ok(bits) if ensure_bits(bits).is_ok() => {
+            // 使用全部通道时每个像素字节都可用，与每像素的通道字节数无关
+            payload_capacity(pixel_count, Channels::ALL, 1, bits)
+        }
+        _ => 0,
+    }
+}
+
+/// 一次隐藏或恢复操作的统计信息
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EmbedStats {
+    /// 隐藏在图像中的载荷字节数
+    pub payload_len: usize,
+
+    /// 在相同参数下最多可隐藏的载荷字节数
+    pub capacity: usize,
+
+    /// 被读写的像素字节区域的结束偏移量 (不含)
+    ///
+    /// 区域总是从头部所在的偏移量 0 开始
+    pub end_offset: usize,
+
+    /// 载荷每个像素字节使用的最低有效位数
+    pub bits: u8,
+
+    /// 恢复时头部记录的长度与图像末尾的长度备份不一致，依次为头部与备份记录的长度
+    ///
+    /// 此时载荷按通过完整性校验的那一份长度恢复，调用方应报告其中一份已损坏
+    pub length_mismatch: Option<(u64, u64)>,
+
+    /// 尽力恢复时载荷已损坏，记录头部声称的载荷长度
+    ///
+    /// 此时返回的载荷是遇到第一个无效字节之前成功恢复的部分，
+    /// 或者全部字节都能解码但未通过完整性校验；调用方应报告载荷可能不完整或含有错误的字节
+    pub damaged: Option<u64>,
+}
+
+impl EmbedStats {
+    /// 载荷占用容量的比例 (0.0 到 1.0)
+    pub fn usage_fraction(&self) -> f64 {
+        if self.capacity == 0 {
+            0.0
+        } else {
+            self.payload_len as f64 / self.capacity as f64
+        }
+    }
+
+    /// 载荷占用容量的百分比
+    pub fn usage_percent(&self) -> f64 {
+        self.usage_fraction() * 100.0
+    }
+
+    /// 根据头部结束位置 (`payload_start`)、载荷使用的像素字节位置与位深度 (`bits`) 构造统计信息
+    fn new(
+        payload_len: usize,
+        capacity: usize,
+        payload_start: usize,
+        positions: &[usize],
+        bits: u8,
+    ) -> Self {
+        Self {
+            payload_len,
+            capacity,
+            end_offset: positions.iter().max().map_or(payload_start, |&max| max + 1),
+            bits,
+            length_mismatch: None,
+            damaged: None,
+        }
+    }
+}
+
+/// 将完整的载荷 (`payload`) 以默认参数隐藏到像素缓冲区 (`pixels`) 中
+///
+/// 等价于 `hide_bytes_with(pixels, payload, &HideOptions::default())`
+///
+/// # Errors
+///
+/// 参见 [`hide_bytes_with`]
+pub fn hide_bytes(pixels: &mut [u8], payload: &[u8]) -> anyhow::Result<()> {
+    hide_bytes_with(pixels, payload, &HideOptions::default()).map(|_| ())
+}
+
+/// 将完整的载荷 (`payload`) 按指定参数 (`options`) 隐藏到像素缓冲区 (`pixels`) 中
+///
+/// 缓冲区布局依次为：
+/// 1. `MAGIC_HIDING_BYTES` 个字节：以默认位深度记录的魔数 `MAGIC`
+/// 2. `VERSION_HIDING_BYTES` 个字节：以默认位深度记录的格式版本 `FORMAT_VERSION`
+/// 3. `BITS_HIDING_BYTES` 个字节：以默认位深度记录的载荷位深度
+/// 4. `FLAGS_HIDING_BYTES` 个字节：以默认位深度记录的载荷标志位
+/// 5. `COMPRESSION_HIDING_BYTES` 个字节：以默认位深度记录的压缩算法编号
+/// 6. `CHANNELS_HIDING_BYTES` 个字节：以默认位深度记录的通道掩码
+/// 7. `PLANE_HIDING_BYTES` 个字节：以默认位深度记录的载荷位平面
+/// 8. `CHECKSUM_HIDING_BYTES` 个字节：以默认位深度记录的载荷 CRC32 校验和
+/// 9. `LENGTH_PREFIX_HIDING_BYTES` 个字节：以默认位深度记录长度编码的字节数
+/// 10. 长度编码的每个字节占用 `BYTES_PER_CHAR` 个字节：以默认位深度记录的 LEB128 载荷长度，
+///     设置了 `FLAG_FIXED_LENGTH` 时改为 `length_bits / 8` 个字节的小端序定长编码
+/// 11. `NAME_LENGTH_HIDING_BYTES` 个字节：以默认位深度记录文件名的字节数 (0 表示未知)
+/// 12. 文件名的每个字节占用 `BYTES_PER_CHAR` 个字节：以默认位深度记录的 UTF-8 文件名
+/// 13. 仅当设置了 `FLAG_PROVENANCE` 时：以默认位深度记录的来源信息 (参见 `Provenance`)
+/// 14. 仅当设置了 `FLAG_REDUNDANT` 时：`BYTES_PER_CHAR` 个字节，以默认位深度记录的冗余份数
+/// 15. 仅当设置了 `FLAG_TYPED` 时：`BYTES_PER_CHAR` 个字节，以默认位深度记录的类型标签编号
+/// 16. 载荷内容：每个载荷字节占用 `bytes_per_char(bits)` 个所选通道的像素字节，写入位平面 `plane`
+///
+/// 设置了 `FLAG_DELIMITED` 时，第 10 项不存在 (长度编码的字节数记录为 0)，
+/// 第 16 项改为起始标记、转义后的载荷与结束标记 (参见 [`delimited`])
+///
+/// 头部字段始终使用默认位深度并顺序写入最低有效位，以便恢复时无需预先知道载荷的参数
+///
+/// 隐藏过程是确定性的：相同的像素、载荷与参数 (包括打散种子) 总是得到逐字节相同的结果
+/// 载荷内容只写入头部之后属于所选通道的像素字节，如果提供了打散种子，
+/// 则按伪随机排列分散到这些像素字节中
+///
+/// # Arguments
+///
+/// * `pixels` - 包含图像像素数据的可变字节切片
+/// * `payload` - 要隐藏的任意字节数据
+/// * `options` - 位深度、标志位、打散种子等隐藏参数
+///
+/// # Returns
+///
+/// 成功时返回载荷大小、容量及被修改区域等统计信息
+///
+/// # Errors
+///
+/// * 如果 `options.bits` 不在 `1..=MAX_BITS` 范围内，将返回错误
+/// * 如果 `options.plane + options.bits` 超过 8，将返回 `StegError::UnsupportedPlane`
+/// * 如果所选通道在图像中都不存在，将返回错误
+/// * 如果像素缓冲区比最小的头部 (`HEADER_BYTES`) 还小，将返回 `StegError::ImageTooSmall`
+/// * 如果载荷所需的像素字节数超出 `usize` 的范围，将返回 `StegError::PayloadTooLarge`
+/// * 如果像素缓冲区没有足够的空间容纳头部和全部载荷，将返回 `StegError::NotEnoughSpace`；
+///   指定了 `options.auto_bits` 时，只有在 `MAX_BITS` 位深度下仍然放不下才会返回，并报告该位深度下所需与可用的字节数
+/// * 如果核心隐写函数 (`modify`) 在执行过程中失败，将返回错误
+pub fn hide_bytes_with(
+    pixels: &mut [u8],
+    payload: &[u8],
+    options: &HideOptions,
+) -> anyhow::Result<EmbedStats> {
+    hide_bytes_with_progress(pixels, payload, options, &|_| {})
+}
+
+/// 与 [`hide_bytes_with`] 相同，但在隐藏载荷内容的过程中通过 `progress` 报告已写入的载荷字节数
+///
+/// 回调每隔 `PROGRESS_STEP_BYTES` 个字节以及全部写入完成时各调用一次
+///
+/// # Errors
+///
+/// 参见 [`hide_bytes_with`]
+pub fn hide_bytes_with_progress(
+    pixels: &mut [u8],
+    payload: &[u8],
+    options: &HideOptions,
+    progress: &dyn Fn(usize),
+) -> anyhow::Result<EmbedStats> {
+    if options.auto_bits {
+        return hide_with_auto_bits(pixels, payload, options, progress);
+    }
+    if options.length_backup {
+        return hide_with_length_backup(pixels, payload, options, progress);
+    }
+
+    // 分隔模式下实际写入的是带标记的转义载荷
+    let body = if options.delimited {
+        Cow::Owned(delimited::encode(payload))
+    } else {
+        Cow::Borrowed(payload)
+    };
+    let layout = write_header(
+        pixels,
+        body.len(),
+        payload.len(),
+        crc32fast::hash(payload),
+        options,
+    )?;
+
+    // 每份副本依次使用相邻的一段位置，进度按全部副本折算
+    let copies = options.redundancy as usize;
+    for copy in 0..copies {
+        let copy_positions = &layout.positions[copy * layout.copy_space..][..layout.copy_space];
+        write_bytes_at(pixels, copy_positions, &body, &layout.codec, &|done| {
+            progress(((copy * body.len() + done) / copies).min(payload.len()))
+        })?;
+    }
+    Ok(layout.stats)
+}
+
+/// 分块扫描得到的流式载荷概要，隐藏前由 [`scan_stream`] 计算
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StreamSummary {
+    /// 载荷的字节数
+    pub len: u64,
+
+    /// 载荷的 CRC32 (IEEE) 校验和
+    pub checksum: u32,
+
+    /// 载荷是否为有效的 UTF-8 文本，供调用方选择类型标签
+    pub utf8: bool,
+}
+
+/// 每次最多读取 `chunk_size` 个字节，读到 `reader` 结束为止，计算载荷的概要
+///
+/// 读到的数据不会被保留，内存占用与载荷大小无关
+///
+/// # Errors
+///
+/// 如果读取失败，将返回错误
+pub fn scan_stream(reader: &mut impl Read, chunk_size: usize) -> io::Result<StreamSummary> {
+    let mut chunk = vec![0u8; chunk_size.max(1)];
+    let mut hasher = crc32fast::Hasher::new();
+    let mut len = 0u64;
+    let mut utf8 = true;
+
+    // 多字节字符可能跨越两块，未完整的尾部字节留到下一块开头一起检查
+    let mut pending = Vec::new();
+    loop {
+        let read = match reader.read(&mut chunk) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err),
+        };
+        hasher.update(&chunk[..read]);
+        len += read as u64;
+        if utf8 {
+            pending.extend_from_slice(&chunk[..read]);
+            match std::str::from_utf8(&pending) {
+                Ok(_) => pending.clear(),
+                Err(err) if err.error_len().is_none() => {
+                    pending.drain(..err.valid_up_to());
+                }
+                Err(_) => utf8 = false,
+            }
+        }
+    }
+    Ok(StreamSummary {
+        len,
+        checksum: hasher.finalize(),
+        utf8: utf8 && pending.is_empty(),
+    })
+}
+
+/// 与 [`hide_bytes_with_progress`] 相同，但载荷从 `reader` 中每次最多读取 `chunk_size` 个字节，边读边隐藏
+///
+/// 头部需要预先记录载荷的长度与校验和，因此调用方必须先由 [`scan_stream`] 得到 `summary`，再从头读取同一载荷；
+/// 整个过程中只保留一块载荷，内存占用与载荷大小无关
+///
+/// # Errors
+///
+/// * 参见 [`hide_bytes_with`]
+/// * 如果 `options` 要求分隔模式、自动选择位深度或长度备份，将返回错误：它们都需要在写入前得到完整的载荷
+/// * 如果读取失败，或读到的数据与 `summary` 不一致 (载荷在两次读取之间被修改)，将返回错误
+pub fn hide_stream_with_progress(
+    pixels: &mut [u8],
+    reader: &mut impl Read,
+    summary: &StreamSummary,
+    options: &HideOptions,
+    chunk_size: usize,
+    progress: &dyn Fn(usize),
+) -> anyhow::Result<EmbedStats> {
+    anyhow::ensure!(
+        !options.delimited && !options.auto_bits && !options.length_backup,
+        "Streamed payloads cannot be delimited, choose their bit depth automatically or back up their length."
+    );
+    let len = usize::try_from(summary.len)
+        .context("The payload is too large to hide on this platform.")?;
+    let layout = write_header(pixels, len, len, summary.checksum, options)?;
+
+    let char_size = layout.codec.bytes_per_char();
+    let copies = options.redundancy as usize;
+    let mut chunk = vec![0u8; chunk_size.max(1)];
+    let mut hasher = crc32fast::Hasher::new();
+    let mut done = 0;
+    loop {
+        let read = match reader.read(&mut chunk) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err).context("Failed to read the payload."),
+        };
+        anyhow::ensure!(done + read <= len, STREAM_CHANGED_MESSAGE);
+        let data = &chunk[..read];
+        hasher.update(data);
+
+        // 每份副本中，这一块都写在与其在载荷中的偏移量对应的位置上
+        for copy in 0..copies {
+            let start = copy * layout.copy_space + done * char_size;
+            let copy_positions = &layout.positions[start..][..read * char_size];
+            write_bytes_at(pixels, copy_positions, data, &layout.codec, &|_| {})?;
+        }
+        done += read;
+        progress(done);
+    }
+    anyhow::ensure!(
+        done == len && hasher.finalize() == summary.checksum,
+        STREAM_CHANGED_MESSAGE
+    );
+    Ok(layout.stats)
+}
+
+/// 流式隐藏时读到的载荷与预先扫描的概要不一致时报告的错误信息
+const STREAM_CHANGED_MESSAGE: &str = "The payload changed while it was being hidden.";
+
+/// 写入头部后确定的载荷布局
+struct Layout {
+    /// 载荷全部副本依次使用的像素字节位置
+    positions: Vec<usize>,
+
+    /// 每份副本占用的位置数
+    copy_space: usize,
+
+    /// 载荷内容使用的位窗口
+    codec: Codec,
+
+    /// 写入全部载荷后的统计信息
+    stats: EmbedStats,
+}
+
+/// 校验参数与空间，写入头部并确定载荷内容的位置，但不写入载荷内容本身
+///
+/// `body_len` 为实际写入的字节数 (分隔模式下为转义后的长度)，`payload_len` 与 `checksum` 描述原始载荷
+///
+/// # Errors
+///
+/// 参见 [`hide_bytes_with`]
+fn write_header(
+    pixels: &mut [u8],
+    body_len: usize,
+    payload_len: usize,
+    checksum: u32,
+    options: &HideOptions,
+) -> anyhow::Result<Layout> {
+    let bits = options.bits;
+    let codec = Codec::new(bits, options.plane)?.with_endian(options.endian);
+    ensure_channels(options.channels, options.stride)?;
+    if pixels.len() < HEADER_BYTES {
+        return Err(StegError::ImageTooSmall {
+            len: pixels.len(),
+            required: HEADER_BYTES,
+        }
+        .into());
+    }
+    anyhow::ensure!(
+        options.name.len() <= MAX_NAME_LEN,
+        "File name is too long to embed: {} bytes (max {}).",
+        options.name.len(),
+        MAX_NAME_LEN
+    );
+    if let Some(length_bits) = options.length_bits {
+        anyhow::ensure!(
+            LENGTH_BITS_CHOICES.contains(&length_bits),
+            "Unsupported length field size: {} bits (expected one of {:?}).",
+            length_bits,
+            LENGTH_BITS_CHOICES
+        );
+        anyhow::ensure!(
+            length_bits == u64::BITS as u8 || (payload_len as u64) >> length_bits == 0,
+            "The payload is too large for a {}-bit length field: {} bytes.",
+            length_bits,
+            payload_len
+        );
+    }
+    anyhow::ensure!(
+        (1..=MAX_SPACING).contains(&options.spacing),
+        "Unsupported stride: {} (expected 1 to {}).",
+        options.spacing,
+        MAX_SPACING
+    );
+    anyhow::ensure!(
+        options.redundancy % 2 == 1 && options.redundancy <= MAX_REDUNDANCY,
+        "Unsupported redundancy: {} (expected an odd number from 1 to {}, so that majority votes cannot tie).",
+        options.redundancy,
+        MAX_REDUNDANCY
+    );
+    anyhow::ensure!(
+        !options.delimited
+            || (options.scatter_seed.is_none()
+                && options.length_bits.is_none()
+                && options.redundancy == 1
+                && !options.edge_adaptive),
+        "Delimited payloads cannot be scattered, use a fixed-length field, be written redundantly or be edge-adaptive."
+    );
+    if let Some(provenance) = &options.provenance {
+        anyhow::ensure!(
+            provenance.tool_version.len() <= u8::MAX as usize,
+            "Tool version is too long to embed: {} bytes (max {}).",
+            provenance.tool_version.len(),
+            u8::MAX
+        );
+    }
+
+    // 检查像素缓冲区是否有足够的空间来隐藏载荷
+    let required_space = required_space(body_len, bits, options.redundancy)?;
+    let copy_space = required_space / options.redundancy as usize;
+    let metadata_len = metadata_len(
+        &options.name,
+        options.provenance.as_ref(),
+        options.redundancy,
+        options.payload_type,
+    );
+    let length_len = if options.delimited {
+        0
+    } else {
+        length_field_len(payload_len as u64, options.length_bits)
+    };
+    let payload_start = name_field_end(length_len, metadata_len);
+
+    // 自适应模式下只有纹理丰富的位置可用；这些位置只取决于不会被修改的高位，写入头部前后都相同
+    let eligible = options.edge_adaptive.then(|| {
+        edge_positions(
+            pixels,
+            payload_start,
+            options.channels,
+            options.stride,
+            options.spacing,
+            edge_low_bits(bits, options.plane),
+        )
+    });
+    let available_space = match &eligible {
+        Some(eligible) => eligible.len(),
+        None => count_positions(
+            payload_start,
+            pixels.len(),
+            options.channels,
+            options.stride,
+            options.spacing,
+        ),
+    };
+
+    if available_space < required_space || pixels.len() < payload_start {
+        return Err(StegError::NotEnoughSpace {
+            required: required_space,
+            available: available_space,
+        }
+        .into());
+    }
+
+    // 打散标志完全由是否提供种子决定，来源信息标志同理
+    let flags = match options.scatter_seed {
+        Some(_) => options.flags | FLAG_SCATTERED,
+        None => options.flags & !FLAG_SCATTERED,
+    };
+    let flags = match options.provenance {
+        Some(_) => flags | FLAG_PROVENANCE,
+        None => flags & !FLAG_PROVENANCE,
+    };
+    let flags = match options.length_bits {
+        Some(_) => flags | FLAG_FIXED_LENGTH,
+        None => flags & !FLAG_FIXED_LENGTH,
+    };
+    let flags = match options.redundancy {
+        1 => flags & !FLAG_REDUNDANT,
+        _ => flags | FLAG_REDUNDANT,
+    };
+    let flags = match options.payload_type {
+        Some(_) => flags | FLAG_TYPED,
+        None => flags & !FLAG_TYPED,
+    };
+    let flags = match options.delimited {
+        true => flags | FLAG_DELIMITED,
+        false => flags & !FLAG_DELIMITED,
+    };
+
+    // 隐藏魔数，供恢复时识别
+    write_bytes(pixels, 0, &MAGIC, DEFAULT_BITS).context("Failed to hide the magic marker.")?;
+
+    // 隐藏格式版本，供恢复时确认能否理解之后的字段
+    write_bytes(pixels, VERSION_OFFSET, &[FORMAT_VERSION], DEFAULT_BITS)
+        .context("Failed to hide the format version.")?;
+
+    // 隐藏载荷位深度、标志位与压缩算法
+    write_bytes(pixels, BITS_OFFSET, &[bits], DEFAULT_BITS)
+        .with_context(|| format!("Failed to hide the bit depth: {}", bits))?;
+    write_bytes(pixels, FLAGS_OFFSET, &[flags], DEFAULT_BITS)
+        .with_context(|| format!("Failed to hide the payload flags: {:#010b}", flags))?;
+    let encoding_id = options.text_encoding.map_or(0, text_encoding::id);
+    let compression = options.compression.id() | encoding_id << TEXT_ENCODING_SHIFT;
+    write_bytes(pixels, COMPRESSION_OFFSET, &[compression], DEFAULT_BITS)
+        .context("Failed to hide the compression algorithm.")?;
+    write_bytes(
+        pixels,
+        CHANNELS_OFFSET,
+        &[options.channels.id()],
+        DEFAULT_BITS,
+    )
+    .with_context(|| format!("Failed to hide the channel selection: {}", options.channels))?;
+    let plane = options.plane | ((options.spacing - 1) as u8) << SPACING_SHIFT;
+    write_bytes(pixels, PLANE_OFFSET, &[plane], DEFAULT_BITS)
+        .with_context(|| format!("Failed to hide the bit plane: {}", options.plane))?;
+
+    // 隐藏载荷校验和，供恢复时检测数据是否损坏
+    modify(
+        checksum as u64,
+        pixels,
+        CHECKSUM_OFFSET,
+        CHECKSUM_HIDING_BYTES,
+        DEFAULT_BITS,
+    )
+    .with_context(|| format!("Failed to hide the payload checksum: {:#010x}", checksum))?;
+
+    // 以变长编码 (或指定的定长编码) 隐藏载荷长度：先记录编码的字节数，再逐字节记录编码本身
+    // 分隔模式不记录长度，编码的字节数为 0
+    let length_bytes = if options.delimited {
+        Vec::new()
+    } else {
+        encode_length(payload_len as u64, options.length_bits)
+    };
+    modify(
+        length_bytes.len() as u64,
+        pixels,
+        LENGTH_OFFSET,
+        LENGTH_PREFIX_HIDING_BYTES,
+        DEFAULT_BITS,
+    )
+    .map_err(anyhow::Error::from)
+    .and_then(|_| {
+        write_bytes(
+            pixels,
+            LENGTH_OFFSET + LENGTH_PREFIX_HIDING_BYTES,
+            &length_bytes,
+            DEFAULT_BITS,
+        )
+    })
+    .with_context(|| format!("Failed to hide the payload length: {}", payload_len))?;
+
+    // 隐藏原始文件名：先记录其字节数，再逐字节记录文件名本身
+    let name_offset = length_field_end(length_bytes.len());
+    write_bytes(
+        pixels,
+        name_offset,
+        &[options.name.len() as u8],
+        DEFAULT_BITS,
+    )
+    .and_then(|_| {
+        write_bytes(
+            pixels,
+            name_offset + NAME_LENGTH_HIDING_BYTES,
+            options.name.as_bytes(),
+            DEFAULT_BITS,
+        )
+    })
+    .with_context(|| format!("Failed to hide the file name: {}", options.name))?;
+
+    // 隐藏可选的来源信息：时间戳、版本字符串的字节数以及版本字符串本身
+    if let Some(provenance) = &options.provenance {
+        let mut bytes = provenance.timestamp.to_le_bytes().to_vec();
+        bytes.push(provenance.tool_version.len() as u8);
+        bytes.extend_from_slice(provenance.tool_version.as_bytes());
+        write_bytes(
+            pixels,
+            name_field_end(length_bytes.len(), options.name.len()),
+            &bytes,
+            DEFAULT_BITS,
+        )
+        .context("Failed to hide the provenance metadata.")?;
+    }
+
+    // 隐藏冗余份数，紧跟在来源信息之后
+    let provenance_end = name_field_end(
+        length_bytes.len(),
+        options.name.len()
+            + options
+                .provenance
+                .as_ref()
+                .map_or(0, Provenance::encoded_len),
+    );
+    if options.redundancy > 1 {
+        write_bytes(pixels, provenance_end, &[options.redundancy], DEFAULT_BITS)
+            .with_context(|| format!("Failed to hide the redundancy: {}", options.redundancy))?;
+    }
+
+    // 隐藏类型标签，紧跟在冗余份数之后
+    if let Some(payload_type) = options.payload_type {
+        let offset = name_field_end(length_bytes.len(), metadata_len - 1);
+        write_bytes(pixels, offset, &[payload_type.id()], DEFAULT_BITS)
+            .with_context(|| format!("Failed to hide the payload type: {}", payload_type))?;
+    }
+
+    // 载荷内容只使用所选通道 (自适应模式下为纹理丰富) 的位置，并按需打散
+    let seed = options.scatter_seed.as_ref();
+    let positions = match eligible {
+        Some(eligible) => pick_positions(eligible, required_space, seed),
+        None => payload_positions(
+            pixels.len(),
+            payload_start,
+            required_space,
+            options.channels,
+            options.stride,
+            options.spacing,
+            seed,
+        ),
+    };
+
+    let capacity = if options.edge_adaptive {
+        available_space / (bytes_per_char(bits) * options.redundancy as usize)
+    } else if options.delimited {
+        delimited_capacity(
+            pixels.len(),
+            metadata_len,
+            options.channels,
+            options.stride,
+            options.spacing,
+            bits,
+        )
+    } else {
+        capacity_with_metadata(
+            pixels.len(),
+            metadata_len,
+            options.length_bits,
+            options.redundancy,
+            options.channels,
+            options.stride,
+            options.spacing,
+            bits,
+        )
+    };
+    let stats = EmbedStats::new(payload_len, capacity, payload_start, &positions, bits);
+    Ok(Layout {
+        positions,
+        copy_space,
+        codec,
+        stats,
+    })
+}
+
+/// 从 `options.bits` 开始逐位尝试隐藏载荷，返回第一个放得下载荷的位深度下的结果
+///
+/// 空间检查在写入任何像素字节之前完成，因此放不下的尝试不会修改 `pixels`
+fn hide_with_auto_bits(
+    pixels: &mut [u8],
+    payload: &[u8],
+    options: &HideOptions,
+    progress: &dyn Fn(usize),
+) -> anyhow::Result<EmbedStats> {
+    let mut options = HideOptions {
+        auto_bits: false,
+        ..options.clone()
+    };
+    while options.bits < MAX_BITS {
+        match hide_bytes_with_progress(pixels, payload, &options, progress) {
+            Err(err) if matches!(err.downcast_ref(), Some(StegError::NotEnoughSpace { .. })) => {
+                options.bits += 1;
+            }
+            result => return result,
+        }
+    }
+    hide_bytes_with_progress(pixels, payload, &options, progress)
+}
+
+/// 将头部与载荷隐藏到除最后 `LENGTH_BACKUP_BYTES` 个像素字节之外的区域，再在末尾写入载荷长度的备份
+///
+/// 恢复时由末尾的 `LENGTH_BACKUP_MAGIC` 识别备份，并同样只在其余区域中读取头部与载荷
+fn hide_with_length_backup(
+    pixels: &mut [u8],
+    payload: &[u8],
+    options: &HideOptions,
+    progress: &dyn Fn(usize),
+) -> anyhow::Result<EmbedStats> {
+    anyhow::ensure!(
+        !options.delimited,
+        "Delimited payloads do not record a length to back up."
+    );
+    let split = pixels
+        .len()
+        .checked_sub(LENGTH_BACKUP_BYTES)
+        .ok_or(StegError::ImageTooSmall {
+            len: pixels.len(),
+            required: HEADER_BYTES + LENGTH_BACKUP_BYTES,
+        })?;
+    let (front, backup) = pixels.split_at_mut(split);
+    let options = HideOptions {
+        length_backup: false,
+        ..options.clone()
+    };
+    let stats = hide_bytes_with_progress(front, payload, &options, progress)?;
+
+    let mut bytes = LENGTH_BACKUP_MAGIC.to_vec();
+    bytes.extend_from_slice(&(payload.len() as u64).to_le_bytes());
+    write_bytes(backup, 0, &bytes, DEFAULT_BITS).context("Failed to hide the length backup.")?;
+    Ok(stats)
+}
+
+/// 读取像素缓冲区 (`pixels`) 末尾的载荷长度备份，末尾没有 `LENGTH_BACKUP_MAGIC` 时返回 `None`
+pub fn read_length_backup(pixels: &[u8]) -> Option<u64> {
+    let start = pixels.len().checked_sub(LENGTH_BACKUP_BYTES)?;
+    let bytes = read_bytes(
+        pixels,
+        start,
+        LENGTH_BACKUP_BYTES / BYTES_PER_CHAR,
+        DEFAULT_BITS,
+    )
+    .ok()?;
+    let (magic, length) = bytes.split_at(LENGTH_BACKUP_MAGIC.len());
+    (magic == LENGTH_BACKUP_MAGIC).then(|| u64::from_le_bytes(length.try_into().unwrap()))
+}
+
+/// 在像素缓冲区 (`pixels`) 中已隐藏的载荷之后追加 `data`
+///
+/// 只改写头部中的长度与校验和，并把新增的字节写在原载荷结束处之后，原载荷的像素字节保持不变；
+/// 位深度、通道、间隔等参数沿用头部的记录，`options` 给出无法记录在头部中的参数，必须与隐藏时相同。
+/// 长度字段的编码因此变长时载荷的起始位置随之后移，此时先恢复原载荷，再将拼接后的载荷整体重新隐藏。
+/// 末尾带有长度备份时同样更新备份
+///
+/// # Errors
+///
+/// * 如果缓冲区中没有有效的头部，将返回错误
+/// * 如果原载荷经过加密、压缩或白化，是归档或分段，或以分隔模式、冗余副本隐藏，无法直接拼接，将返回错误
+/// * 如果载荷是打散嵌入的而 `options` 没有提供种子，将返回错误
+/// * 如果剩余容量放不下追加的字节，将返回 `StegError::NotEnoughSpace`，且不会修改任何像素字节
+pub fn append_bytes(
+    pixels: &mut [u8],
+    data: &[u8],
+    options: &RecoverOptions,
+) -> anyhow::Result<EmbedStats> {
+    // 末尾带有长度备份时，头部与载荷只位于备份之前的区域
+    let (pixels, backup) = match read_length_backup(pixels) {
+        Some(_) => {
+            let split = pixels.len() - LENGTH_BACKUP_BYTES;
+            let (front, backup) = pixels.split_at_mut(split);
+            (front, Some(backup))
+        }
+        None => (pixels, None),
+    };
+    let header = read_header(pixels)?;
+    anyhow::ensure!(
+        !header.is_encrypted()
+            && header.compression == Compression::None
+            && !header.is_delimited()
+            && header.redundancy == 1
+            && !header.is_archive()
+            && !matches!(
+                header.payload_type,
+                Some(PayloadType::Whitened | PayloadType::Segment)
+            ),
+        "Only plain payloads can be appended to: the hidden payload is encrypted, compressed, whitened, delimited, redundant, an archive or a segment."
+    );
+    let scatter_seed = if header.is_scattered() {
+        Some(options.scatter_seed.context(
+            "The hidden payload is scattered. A password or seed file is required to locate it.",
+        )?)
+    } else {
+        None
+    };
+    let existing = ensure_length_fits(&header, pixels.len(), options.stride)?;
+    let length = existing + data.len();
+
+    // CRC32 可以从原载荷的校验和继续计算，无需读出原载荷
+    let mut hasher = crc32fast::Hasher::new_with_initial(header.checksum);
+    hasher.update(data);
+    let checksum = hasher.finalize();
+
+    let hide_options = HideOptions {
+        bits: header.bits,
+        flags: header.flags,
+        plane: header.plane,
+        compression: header.compression,
+        text_encoding: header.text_encoding,
+        channels: header.channels,
+        stride: options.stride,
+        scatter_seed,
+        name: header.name,
+        provenance: header.provenance,
+        length_bits: header.length_bits,
+        payload_type: header.payload_type,
+        spacing: header.spacing,
+        edge_adaptive: options.edge_adaptive,
+        endian: options.endian,
+        ..Default::default()
+    };
+    let stats = if length_field_len(length as u64, header.length_bits)
+        == length_field_len(existing as u64, header.length_bits)
+    {
+        // 头部大小不变，打散的排列也只取决于可用位置，原载荷的位置仍是新载荷位置的前缀
+        let layout = write_header(pixels, length, length, checksum, &hide_options)?;
+        let start = existing * layout.codec.bytes_per_char();
+        write_bytes_at(
+            pixels,
+            &layout.positions[start..],
+            data,
+            &layout.codec,
+            &|_| {},
+        )?;
+        layout.stats
+    } else {
+        let strict = RecoverOptions {
+            best_effort: false,
+            ..*options
+        };
+        let (_, mut payload) = recover_payload(pixels, &strict)?;
+        payload.extend_from_slice(data);
+        hide_bytes_with(pixels, &payload, &hide_options)?
+    };
+
+    if let Some(backup) = backup {
+        let mut bytes = LENGTH_BACKUP_MAGIC.to_vec();
+        bytes.extend_from_slice(&(length as u64).to_le_bytes());
+        write_bytes(backup, 0, &bytes, DEFAULT_BITS)
+            .context("Failed to hide the length backup.")?;
+    }
+    Ok(stats)
+}
+
+/// 校验所选通道中至少有一个存在于每像素 `stride` 个通道字节的图像中
+fn ensure_channels(channels: Channels, stride: usize) -> anyhow::Result<()> {
+    anyhow::ensure!(
+        (0..stride).any(|channel| channels.contains(channel)),
+        "None of the selected channels ({}) exist in this image ({} channels).",
+        channels,
+        stride
+    );
+    Ok(())
+}
+
+/// 检查像素缓冲区 (`pixels`) 开头是否带有 lsb_hide 的魔数
+///
+/// 可用于在恢复之前快速判断一张图像是否包含由本工具隐藏的数据
+pub fn has_magic(pixels: &[u8]) -> bool {
+    read_bytes(pixels, 0, MAGIC.len(), DEFAULT_BITS).is_ok_and(|magic| magic == MAGIC)
+}
+
+/// 在像素缓冲区 (`pixels`) 的前 `max_shift + 1` 个位置中查找魔数，返回魔数所在的偏移量
+///
+/// 用于恢复被其他工具重新保存后整体错位了几个字节的载荷；`max_shift` 为 0 时等同于 `has_magic`
+pub fn find_magic(pixels: &[u8], max_shift: usize) -> Option<usize> {
+    (0..=max_shift.min(pixels.len())).find(|&shift| has_magic(&pixels[shift..]))
+}
+
+/// 从像素缓冲区 (`pixels`) 中读取并校验隐写头部
+///
+/// # Errors
+///
+/// * 如果缓冲区开头没有魔数，即图像不包含 lsb_hide 载荷，将返回 `StegError::MagicMismatch`
+/// * 如果格式版本不是当前支持的 `FORMAT_VERSION`，将返回 `StegError::UnsupportedVersion`
+/// * 如果无法从缓冲区中恢复头部字段，或位深度无效，将返回错误
+pub fn read_header(pixels: &[u8]) -> anyhow::Result<Header> {
+    if !has_magic(pixels) {
+        return Err(StegError::MagicMismatch.into());
+    }
+
+    // 之后的字段布局由格式版本决定，无法识别的版本不能继续解析
+    let version = recover(pixels, VERSION_OFFSET, VERSION_HIDING_BYTES, DEFAULT_BITS)
+        .context("Failed to recover the format version.")? as u8;
+    if version != FORMAT_VERSION {
+        return Err(StegError::UnsupportedVersion(version).into());
+    }
+
+    // 恢复载荷的位深度、标志位与压缩算法
+    let bits = recover(pixels, BITS_OFFSET, BITS_HIDING_BYTES, DEFAULT_BITS)
+        .context("Failed to recover the bit depth.")? as u8;
+    ensure_bits(bits)?;
+    let flags = recover(pixels, FLAGS_OFFSET, FLAGS_HIDING_BYTES, DEFAULT_BITS)
+        .context("Failed to recover the payload flags.")? as u8;
+    let compression_id = recover(
+        pixels,
+        COMPRESSION_OFFSET,
+        COMPRESSION_HIDING_BYTES,
+        DEFAULT_BITS,
+    )
+    .context("Failed to recover the compression algorithm.")? as u8;
+    let compression = Compression::from_id(compression_id & lsb_mask(TEXT_ENCODING_SHIFT))?;
+    let text_encoding = text_encoding::from_id(compression_id >> TEXT_ENCODING_SHIFT)?;
+    let channels_id = recover(pixels, CHANNELS_OFFSET, CHANNELS_HIDING_BYTES, DEFAULT_BITS)
+        .context("Failed to recover the channel selection.")?;
+    let channels = Channels::from_id(channels_id as u8)?;
+    let plane = recover(pixels, PLANE_OFFSET, PLANE_HIDING_BYTES, DEFAULT_BITS)
+        .context("Failed to recover the bit plane.")? as u8;
+    let spacing = (plane >> SPACING_SHIFT) as usize + 1;
+    let plane = plane & lsb_mask(SPACING_SHIFT);
+    ensure_plane(plane, bits)?;
+
+    // 恢复载荷校验和
+    let checksum = recover(pixels, CHECKSUM_OFFSET, CHECKSUM_HIDING_BYTES, DEFAULT_BITS)
+        .context("Failed to recover the payload checksum.")? as u32;
+
+    // 恢复载荷长度：先读取编码的字节数，再读取编码本身
+    // 设置了 `FLAG_FIXED_LENGTH` 时为小端序定长编码，否则为变长编码
+    let length_len = recover(
+        pixels,
+        LENGTH_OFFSET,
+        LENGTH_PREFIX_HIDING_BYTES,
+        DEFAULT_BITS,
+    )
+    .context("Failed to recover the payload length.")? as usize;
+    let length_bits = (flags & FLAG_FIXED_LENGTH != 0).then_some((length_len * 8) as u8);
+    let delimited = flags & FLAG_DELIMITED != 0;
+    let valid_len = match length_bits {
+        // 分隔模式与打散、定长长度字段和冗余副本互斥
+        _ if delimited => {
+            length_len == 0 && flags & (FLAG_SCATTERED | FLAG_FIXED_LENGTH | FLAG_REDUNDANT) == 0
+        }
+        Some(length_bits) => LENGTH_BITS_CHOICES.contains(&length_bits),
+        None => (1..=MAX_VARINT_LEN).contains(&length_len),
+    };
+    anyhow::ensure!(
+        valid_len,
+        "Invalid payload length encoding: {} bytes.",
+        length_len
+    );
+    let length_bytes = read_bytes(
+        pixels,
+        LENGTH_OFFSET + LENGTH_PREFIX_HIDING_BYTES,
+        length_len,
+        DEFAULT_BITS,
+    )
+    .context("Failed to recover the payload length.")?;
+    let length = match length_bits {
+        _ if delimited => 0,
+        Some(_) => {
+            let mut bytes = [0u8; 8];
+            bytes[..length_len].copy_from_slice(&length_bytes);
+            u64::from_le_bytes(bytes)
+        }
+        None => {
+            let length =
+                varint::decode(&length_bytes).context("Failed to recover the payload length.")?;
+            // 非最短编码会让头部长度与载荷起始位置不一致
+            anyhow::ensure!(
+                varint::encoded_len(length) == length_len,
+                "Invalid payload length encoding: {} bytes.",
+                length_len
+            );
+            length
+        }
+    };
+
+    // 恢复原始文件名
+    let name_offset = length_field_end(length_len);
+    let name = read_bytes(pixels, name_offset, 1, DEFAULT_BITS)
+        .and_then(|name_len| {
+            read_bytes(
+                pixels,
+                name_offset + NAME_LENGTH_HIDING_BYTES,
+                name_len[0] as usize,
+                DEFAULT_BITS,
+            )
+        })
+        .context("Failed to recover the file name.")?;
+    let name = String::from_utf8(name).context("The embedded file name is not valid UTF-8.")?;
+
+    // 恢复可选的来源信息
+    let provenance = if flags & FLAG_PROVENANCE != 0 {
+        Some(read_provenance(
+            pixels,
+            name_field_end(length_len, name.len()),
+        )?)
+    } else {
+        None
+    };
+
+    // 恢复冗余份数，紧跟在来源信息之后
+    let redundancy = if flags & FLAG_REDUNDANT != 0 {
+        let offset = name_field_end(
+            length_len,
+            metadata_len(&name, provenance.as_ref(), 1, None),
+        );
+        let redundancy = read_bytes(pixels, offset, 1, DEFAULT_BITS)
+            .context("Failed to recover the redundancy.")?[0];
+        anyhow::ensure!(
+            redundancy > 1 && redundancy % 2 == 1 && redundancy <= MAX_REDUNDANCY,
+            "Invalid redundancy in header: {}.",
+            redundancy
+        );
+        redundancy
+    } else {
+        1
+    };
+
+    // 恢复类型标签，紧跟在冗余份数之后
+    let payload_type = if flags & FLAG_TYPED != 0 {
+        let offset = name_field_end(
+            length_len,
+            metadata_len(&name, provenance.as_ref(), redundancy, None),
+        );
+        let id = read_bytes(pixels, offset, 1, DEFAULT_BITS)
+            .context("Failed to recover the payload type.")?[0];
+        Some(PayloadType::from_id(id)?)
+    } else {
+        None
+    };
+
+    Ok(Header {
+        version,
+        bits,
+        flags,
+        plane,
+        compression,
+        text_encoding,
+        channels,
+        spacing,
+        length,
+        checksum,
+        name,
+        provenance,
+        length_bits,
+        redundancy,
+        payload_type,
+    })
+}
+
+/// 从 `offset` 开始读取由 `hide_bytes_with` 写入的来源信息
+fn read_provenance(pixels: &[u8], offset: usize) -> anyhow::Result<Provenance> {
+    let char_size = bytes_per_char(DEFAULT_BITS);
+    let timestamp = read_bytes(pixels, offset, TIMESTAMP_LEN, DEFAULT_BITS)
+        .context("Failed to recover the embedding timestamp.")?;
+    let version_offset = offset + TIMESTAMP_LEN * char_size;
+    let tool_version = read_bytes(pixels, version_offset, 1, DEFAULT_BITS)
+        .and_then(|len| {
+            read_bytes(
+                pixels,
+                version_offset + char_size,
+                len[0] as usize,
+                DEFAULT_BITS,
+            )
+        })
+        .context("Failed to recover the tool version.")?;
+
+    let mut timestamp_bytes = [0u8; TIMESTAMP_LEN];
+    timestamp_bytes.copy_from_slice(&timestamp);
+    Ok(Provenance {
+        timestamp: u64::from_le_bytes(timestamp_bytes),
+        tool_version: String::from_utf8(tool_version)
+            .context("The embedded tool version is not valid UTF-8.")?,
+    })
+}
+
+/// 从像素缓冲区 (`pixels`) 中恢复由 `hide_bytes` 隐藏的完整载荷
+///
+/// 等价于 `recover_payload(pixels, &RecoverOptions::default())` 并丢弃头部信息
+///
+/// # Errors
+///
+/// 参见 [`recover_payload`]
+pub fn recover_bytes(pixels: &[u8]) -> anyhow::Result<Vec<u8>> {
+    recover_payload(pixels, &RecoverOptions::default()).map(|(_, payload)| payload)
+}
+
+/// 从像素缓冲区 (`pixels`) 中恢复隐写头部及其描述的完整载荷
+///
+/// 先读取并校验头部，再按头部记录的位深度和长度逐字节恢复载荷内容
+/// 返回的载荷是隐藏时的原始字节，如果头部标记为已加密，需要调用方自行解密
+///
+/// # Errors
+///
+/// * 如果头部无效或缺失，将返回错误 (参见 [`read_header`])
+/// * 如果载荷已打散但 `options` 中没有提供种子，将返回错误
+/// * 如果头部记录的载荷长度超过图像实际容量，将在提取之前返回 `StegError::LengthExceedsCapacity`
+/// * 如果某个载荷字节所在的区域超出缓冲区边界或无效，将返回错误
+/// * 如果恢复出的载荷与头部记录的 CRC32 校验和不一致，将返回错误
+pub fn recover_payload(
+    pixels: &[u8],
+    options: &RecoverOptions,
+) -> anyhow::Result<(Header, Vec<u8>)> {
+    recover_payload_with_stats(pixels, options).map(|(header, payload, _)| (header, payload))
+}
+
+/// 与 [`recover_payload`] 相同，但额外返回载荷长度、容量及读取区域等统计信息
+///
+/// # Errors
+///
+/// 参见 [`recover_payload`]
+pub fn recover_payload_with_stats(
+    pixels: &[u8],
+    options: &RecoverOptions,
+) -> anyhow::Result<(Header, Vec<u8>, EmbedStats)> {
+    recover_payload_with_progress(pixels, options, &|_| {})
+}
+
+/// 与 [`recover_payload_with_stats`] 相同，但在恢复载荷内容的过程中通过 `progress` 报告已读取的载荷字节数
+///
+/// 回调每隔 `PROGRESS_STEP_BYTES` 个字节以及全部读取完成时各调用一次
+///
+/// # Errors
+///
+/// 参见 [`recover_payload`]
+pub fn recover_payload_with_progress(
+    pixels: &[u8],
+    options: &RecoverOptions,
+    progress: &dyn Fn(usize),
+) -> anyhow::Result<(Header, Vec<u8>, EmbedStats)> {
+    // 末尾带有长度备份时，头部与载荷只位于备份之前的区域
+    let backup = read_length_backup(pixels);
+    let pixels = match backup {
+        Some(_) => &pixels[..pixels.len() - LENGTH_BACKUP_BYTES],
+        None => pixels,
+    };
+    let header = read_header(pixels)?;
+    ensure_channels(header.channels, options.stride)?;
+
+    let seed = if header.is_scattered() {
+        let seed = options.scatter_seed.as_ref().context(
+            "The hidden payload is scattered. A password or seed file is required to locate it.",
+        )?;
+        Some(seed)
+    } else {
+        None
+    };
+
+    if header.is_delimited() {
+        return recover_delimited(pixels, header, options, progress);
+    }
+
+    // 两份长度不一致说明其中一份已损坏：先按头部记录的长度恢复，完整性校验失败时改用备份的长度
+    match backup {
+        Some(length) if length != header.length => {
+            let header_length = header.length;
+            // 尽力恢复只作为最后的手段，否则按错误的长度恢复出的部分数据会掩盖另一份有效的长度
+            let strict = RecoverOptions {
+                best_effort: false,
+                ..*options
+            };
+            let backup_header = Header {
+                length,
+                ..header.clone()
+            };
+            recover_counted(pixels, header.clone(), &strict, seed, progress)
+                .or_else(|_| recover_counted(pixels, backup_header, &strict, seed, progress))
+                .or_else(|err| {
+                    if options.best_effort {
+                        recover_counted(pixels, header, options, seed, progress)
+                    } else {
+                        Err(err)
+                    }
+                })
+                .map(|(header, payload, stats)| {
+                    let stats = EmbedStats {
+                        length_mismatch: Some((header_length, length)),
+                        ..stats
+                    };
+                    (header, payload, stats)
+                })
+                .map_err(|_| {
+                    StegError::LengthCopiesDisagree {
+                        header: header_length,
+                        backup: length,
+                    }
+                    .into()
+                })
+        }
+        _ => recover_counted(pixels, header, options, seed, progress),
+    }
+}
+
+/// 按头部记录的长度恢复载荷，`seed` 为打散载荷使用的种子
+fn recover_counted(
+    pixels: &[u8],
+    header: Header,
+    options: &RecoverOptions,
+    seed: Option<&[u8; SEED_LEN]>,
+    progress: &dyn Fn(usize),
+) -> anyhow::Result<(Header, Vec<u8>, EmbedStats)> {
+    let payload_start = header.payload_offset();
+
+    // 损坏的长度字段可能声称一个巨大的载荷，在分配和提取之前先与上限及实际容量比较
+    ensure_payload_limit(header.length, options.payload_limit)?;
+    let count = ensure_length_fits(&header, pixels.len(), options.stride)?;
+    let char_size = bytes_per_char(header.bits);
+    let copies = header.redundancy as usize;
+    let copy_space = count * char_size;
+    let positions = if options.edge_adaptive {
+        let eligible = edge_positions(
+            pixels,
+            payload_start,
+            header.channels,
+            options.stride,
+            header.spacing,
+            edge_low_bits(header.bits, header.plane),
+        );
+        if eligible.len() < copy_space * copies {
+            return Err(StegError::LengthExceedsCapacity {
+                length: header.length,
+                capacity: eligible.len() / (char_size * copies),
+            }
+            .into());
+        }
+        pick_positions(eligible, copy_space * copies, seed)
+    } else {
+        payload_positions(
+            pixels.len(),
+            payload_start,
+            copy_space * copies,
+            header.channels,
+            options.stride,
+            header.spacing,
+            seed,
+        )
+    };
+    let codec = header.codec()?.with_endian(options.endian);
+    let payload = if copies > 1 {
+        // 逐位多数表决出每个位置的数据位，再按单份副本解码
+        let voted = majority_vote(pixels, &positions, copies, &codec);
+        let voted_positions: Vec<usize> = (0..copy_space).collect();
+        read_bytes_at(
+            &voted,
+            &voted_positions,
+            count,
+            &codec,
+            options.best_effort,
+            progress,
+        )?
+    } else {
+        read_bytes_at(
+            pixels,
+            &positions,
+            count,
+            &codec,
+            options.best_effort,
+            progress,
+        )?
+    };
+
+    // 图像被其他工具重新保存或部分损坏时，拒绝返回错误的数据；尽力恢复时改为标记损坏
+    let intact = payload.len() == count && crc32fast::hash(&payload) == header.checksum;
+    anyhow::ensure!(intact || options.best_effort, StegError::ChecksumMismatch);
+
+    let capacity = if options.edge_adaptive {
+        positions.len() / (char_size * copies)
+    } else {
+        capacity_with_metadata(
+            pixels.len(),
+            header.metadata_len(),
+            header.length_bits,
+            header.redundancy,
+            header.channels,
+            options.stride,
+            header.spacing,
+            header.bits,
+        )
+    };
+    let stats = EmbedStats {
+        damaged: (!intact).then_some(header.length),
+        ..EmbedStats::new(
+            payload.len(),
+            capacity,
+            payload_start,
+            &positions,
+            header.bits,
+        )
+    };
+    Ok((header, payload, stats))
+}
+
+/// 不写入隐写头部，只在像素缓冲区 (`pixels`) 开头按默认位深度隐藏载荷的原始字节，与 [`recover_headerless`] 对应
+///
+/// 图像中没有魔数、长度与校验和，恢复时由调用方另行提供长度 (例如 `hide --no-length-header` 写出的边车文件)
+///
+/// # Errors
+///
+/// * 如果缓冲区放不下全部载荷，将在修改任何像素字节之前返回 `StegError::NotEnoughSpace`
+/// * 如果载荷所需的像素字节数超出 `usize` 的范围，将返回 `StegError::PayloadTooLarge`
+pub fn hide_headerless(pixels: &mut [u8], payload: &[u8]) -> anyhow::Result<EmbedStats> {
+    let required = payload
+        .len()
+        .checked_mul(BYTES_PER_CHAR)
+        .ok_or(StegError::PayloadTooLarge { len: payload.len() })?;
+    if pixels.len() < required {
+        return Err(StegError::NotEnoughSpace {
+            required,
+            available: pixels.len(),
+        }
+        .into());
+    }
+    write_bytes(pixels, 0, payload, DEFAULT_BITS)?;
+    Ok(EmbedStats {
+        payload_len: payload.len(),
+        capacity: pixels.len() / BYTES_PER_CHAR,
+        end_offset: required,
+        bits: DEFAULT_BITS,
+        length_mismatch: None,
+        damaged: None,
+    })
+}
+
+/// 从不含隐写头部的像素缓冲区 (`pixels`) 开头按默认位深度提取恰好 `length` 个字节
+///
+/// 用于恢复其他工具或旧版本写入的、没有记录长度的载荷：长度由调用方提供，不读取也不校验魔数
+/// 返回的头部描述以默认参数写入的未加密、未压缩载荷，校验和按恢复出的字节计算
+///
+/// 没有魔数可以确认载荷存在，长度为 0 时无法与不含载荷的图像区分，因此视为没有载荷，而不是返回空的结果
+///
+/// # Errors
+///
+/// * 如果 `length` 为 0，将返回 `StegError::MagicMismatch`
+/// * 如果 `length` 超过 `payload_limit`，将返回 `StegError::PayloadLimitExceeded`
+/// * 如果 `length` 超过缓冲区的容量，将在提取之前返回 `StegError::LengthExceedsCapacity`
+/// * 如果某个位置恢复出的值超出 `u8` 范围，将返回 `StegError::ByteOverflow`
+pub fn recover_headerless(
+    pixels: &[u8],
+    length: u64,
+    payload_limit: Option<u64>,
+) -> Result<(Header, Vec<u8>, EmbedStats), StegError> {
+    if length == 0 {
+        return Err(StegError::MagicMismatch);
+    }
+    ensure_payload_limit(length, payload_limit)?;
+    let capacity = pixels.len() / BYTES_PER_CHAR;
+    let count = match usize::try_from(length) {
+        Ok(count) if count <= capacity => count,
+        _ => return Err(StegError::LengthExceedsCapacity { length, capacity }),
+    };
+    let payload = embedded_bytes(pixels, 0)
+        .take(count)
+        .collect::<Result<Vec<u8>, StegError>>()?;
+
+    let header = Header {
+        version: FORMAT_VERSION,
+        bits: DEFAULT_BITS,
+        flags: 0,
+        plane: 0,
+        compression: Compression::None,
+        text_encoding: None,
+        channels: Channels::ALL,
+        spacing: 1,
+        length,
+        checksum: crc32fast::hash(&payload),
+        name: String::new(),
+        provenance: None,
+        length_bits: None,
+        redundancy: 1,
+        payload_type: None,
+    };
+    let stats = EmbedStats {
+        payload_len: count,
+        capacity,
+        end_offset: count * BYTES_PER_CHAR,
+        bits: DEFAULT_BITS,
+        length_mismatch: None,
+        damaged: None,
+    };
+    Ok((header, payload, stats))
+}
+
+/// 校验头部记录的载荷长度不超过 `pixels_len` 个像素字节 (每像素 `stride` 个通道字节) 在该头部之后的实际容量，
+/// 返回载荷的字节数
+///
+/// 只读取头部即可完成，调用方可以在分配缓冲区或逐字节提取之前据此尽早失败；
+/// 分隔模式的头部不记录长度，总是通过
+///
+/// # Errors
+///
+/// 如果头部记录的长度 (及冗余副本) 所需的像素字节超过头部之后可用的像素字节，将返回 `StegError::LengthExceedsCapacity`
+pub fn ensure_length_fits(
+    header: &Header,
+    pixels_len: usize,
+    stride: usize,
+) -> Result<usize, StegError> {
+    let available_space = count_positions(
+        header.payload_offset(),
+        pixels_len,
+        header.channels,
+        stride,
+        header.spacing,
+    );
+    let capacity = available_space / (bytes_per_char(header.bits) * header.redundancy as usize);
+    match usize::try_from(header.length) {
+        Ok(length) if length <= capacity => Ok(length),
+        _ => Err(StegError::LengthExceedsCapacity {
+            length: header.length,
+            capacity,
+        }),
+    }
+}
+
+/// 校验载荷长度 (`length`) 不超过调用方指定的上限 (`limit`)，未指定上限时总是通过
+///
+/// # Errors
+///
+/// 如果 `length` 超过 `limit`，将返回 `StegError::PayloadLimitExceeded`
+pub fn ensure_payload_limit(length: u64, limit: Option<u64>) -> Result<(), StegError> {
+    match limit {
+        Some(limit) if length > limit => Err(StegError::PayloadLimitExceeded { limit }),
+        _ => Ok(()),
+    }
+}
+
+/// 恢复以分隔模式隐藏的载荷：从头部之后按顺序逐字节读取，直到遇到结束标记
+///
+/// 头部不记录长度，因此按 `options.payload_limit` 限制读取的字节数：
+/// 不超过上限的载荷转义后至多占用 `2 * limit + 4` 个字节，读到更多字节时立即返回错误
+///
+/// 返回的头部中 `length` 被更新为实际恢复出的载荷长度
+fn recover_delimited(
+    pixels: &[u8],
+    mut header: Header,
+    options: &RecoverOptions,
+    progress: &dyn Fn(usize),
+) -> anyhow::Result<(Header, Vec<u8>, EmbedStats)> {
+    let stride = options.stride;
+    let payload_start = header.payload_offset();
+    let codec = header.codec()?.with_endian(options.endian);
+    let char_size = codec.bytes_per_char();
+    let positions: Vec<usize> = channel_positions(
+        payload_start,
+        pixels.len(),
+        header.channels,
+        stride,
+        header.spacing,
+    )
+    .collect();
+
+    // 按需读取，读到结束标记即停止，不会解码之后的像素字节
+    let bytes = positions
+        .chunks_exact(char_size)
+        .enumerate()
+        .map(|(i, chunk)| {
+            if let Some(limit) = options.payload_limit
+                && i as u64 >= delimited::max_encoded_len(limit)
+            {
+                return Err(StegError::PayloadLimitExceeded { limit }.into());
+            }
+            let byte = recover_with(&codec, pixels, chunk)
+                .and_then(|value| checked_byte(value, i))
+                .with_context(|| format!("Failed to recover byte at index {}.", i))?;
+            report_progress(progress, i + 1, usize::MAX);
+            Ok(byte)
+        });
+    let (payload, consumed) = delimited::decode(bytes)?;
+    ensure_payload_limit(payload.len() as u64, options.payload_limit)?;
+
+    // 分隔模式只能读到结束标记为止，尽力恢复时仅容忍完整性校验失败
+    let intact = crc32fast::hash(&payload) == header.checksum;
+    anyhow::ensure!(intact || options.best_effort, StegError::ChecksumMismatch);
+
+    header.length = payload.len() as u64;
+    let capacity = delimited_capacity(
+        pixels.len(),
+        header.metadata_len(),
+        header.channels,
+        stride,
+        header.spacing,
+        header.bits,
+    );
+    let stats = EmbedStats {
+        damaged: (!intact).then_some(payload.len() as u64),
+        ..EmbedStats::new(
+            payload.len(),
+            capacity,
+            payload_start,
+            &positions[..consumed * char_size],
+            header.bits,
+        )
+    };
+    Ok((header, payload, stats))
+}
+
+/// 对 `copies` 份相邻排列在 `positions` 中的副本逐位多数表决
+///
+/// 返回的缓冲区中第 `j` 个字节的数据窗口保存了各副本第 `j` 个位置上的表决结果，可直接按单份副本解码
+fn majority_vote(pixels: &[u8], positions: &[usize], copies: usize, codec: &Codec) -> Vec<u8> {
+    let copy_space = positions.len() / copies;
+    (0..copy_space)
+        .into_par_iter()
+        .map(|j| {
+            let value = (0..codec.bits()).fold(0u8, |value, bit| {
+                let ones = (0..copies)
+                    .filter(|copy| {
+                        codec.extract(pixels[positions[copy * copy_space + j]]) >> bit & 1 == 1
+                    })
+                    .count();
+                value | u8::from(ones * 2 > copies) << bit
+            });
+            codec.embed(0, value)
+        })
+        .collect()
+}
+
+/// 按顺序列出 `start` 之后属于所选通道的像素字节位置，每 `spacing` 个位置只取第一个
+fn channel_positions(
+    start: usize,
+    len: usize,
+    channels: Channels,
+    stride: usize,
+    spacing: usize,
+) -> impl Iterator<Item = usize> {
+    (start..len)
+        .filter(move |&index| channels.selects(index, stride))
+        .step_by(spacing)
+}
+
+/// 统计 `start..len` 中属于所选通道、按 `spacing` 间隔选取的像素字节数，结果与 `channel_positions(..).count()` 相同
+fn count_positions(
+    start: usize,
+    len: usize,
+    channels: Channels,
+    stride: usize,
+    spacing: usize,
+) -> usize {
+    // 计算 `0..end` 中属于所选通道的像素字节数
+    let selected_before = |end: usize| {
+        if channels.covers(stride) {
+            return end;
+        }
+        let per_pixel = (0..stride).filter(|&c| channels.contains(c)).count();
+        let partial = (0..end % stride).filter(|&c| channels.contains(c)).count();
+        end / stride * per_pixel + partial
+    };
+    selected_before(len)
+        .saturating_sub(selected_before(start.min(len)))
+        .div_ceil(spacing)
+}
+
+/// 计算载荷使用的前 `count` 个像素字节位置，载荷从头部结束处 (`start`) 开始
+///
+/// 未打散时按顺序取所选通道的位置；打散时排列覆盖所选通道的全部位置，
+/// 因此结果只取决于缓冲区长度、载荷起始位置、通道布局和种子
+fn payload_positions(
+    len: usize,
+    start: usize,
+    count: usize,
+    channels: Channels,
+    stride: usize,
+    spacing: usize,
+    seed: Option<&[u8; SEED_LEN]>,
+) -> Vec<usize> {
+    match seed {
+        Some(_) => pick_positions(
+            channel_positions(start, len, channels, stride, spacing).collect(),
+            count,
+            seed,
+        ),
+        None => channel_positions(start, len, channels, stride, spacing)
+            .take(count)
+            .collect(),
+    }
+}
+
+/// 从可用位置 (`available`) 中选取载荷使用的前 `count` 个位置，提供种子时按其生成的排列打散
+fn pick_positions(
+    mut available: Vec<usize>,
+    count: usize,
+    seed: Option<&[u8; SEED_LEN]>,
+) -> Vec<usize> {
+    match seed {
+        Some(seed) => scatter::permutation(available.len(), seed)
+            .into_iter()
+            .take(count)
+            .map(|k| available[k])
+            .collect(),
+        None => {
+            available.truncate(count);
+            available
+        }
+    }
+}
+
+/// 按顺序列出 `start` 之后属于所选通道、且处于纹理丰富区域的像素字节位置
+///
+/// 纹理由该字节与同一通道左右相邻像素字节之差的绝对值之和衡量，不低于 `EDGE_GRADIENT_THRESHOLD` 时可用；
+/// 只比较最低 `low_bits` 位之上的高位，这些位在隐藏时不会被修改，因此恢复时得到的位置与隐藏时完全相同
+fn edge_positions(
+    pixels: &[u8],
+    start: usize,
+    channels: Channels,
+    stride: usize,
+    spacing: usize,
+    low_bits: u8,
+) -> Vec<usize> {
+    let high_mask = !lsb_mask(low_bits);
+    let gradient = |index: usize, neighbor: Option<usize>| {
+        neighbor
+            .and_then(|neighbor| pixels.get(neighbor))
+            .map_or(0, |&value| {
+                (pixels[index] & high_mask).abs_diff(value & high_mask) as u16
+            })
+    };
+    channel_positions(start, pixels.len(), channels, stride, spacing)
+        .filter(|&index| {
+            gradient(index, index.checked_sub(stride)) + gradient(index, Some(index + stride))
+                >= EDGE_GRADIENT_THRESHOLD
+        })
+        .collect()
+}
+
+/// 自适应模式下计算纹理时忽略的低位数：载荷数据窗口与头部字段可能修改的全部低位
+fn edge_low_bits(bits: u8, plane: u8) -> u8 {
+    (plane + bits).max(DEFAULT_BITS)
+}
+
+/// 计算从 `offset` 开始、每个字节占用 `char_size` 个像素字节时第 `index` 个字节的起始偏移量
+///
+/// 索引极大时乘法或加法可能溢出 `usize`，此时返回 `StegError::Overflow` 而不是回绕到错误的位置
+fn byte_offset(offset: usize, char_size: usize, index: usize) -> Result<usize, StegError> {
+    char_size
+        .checked_mul(index)
+        .and_then(|size| offset.checked_add(size))
+        .ok_or(StegError::Overflow {
+            dix: offset,
+            size: char_size.saturating_mul(index),
+        })
+}
+
+/// 从 `offset` 开始，将字节序列 (`bytes`) 逐字节隐藏到像素缓冲区中
+///
+/// 每个字节占用 `bytes_per_char(bits)` 个像素字节
+fn write_bytes(pixels: &mut [u8], offset: usize, bytes: &[u8], bits: u8) -> anyhow::Result<()> {
+    let char_size = bytes_per_char(bits);
+    bytes.iter().enumerate().try_for_each(|(i, &byte)| {
+        let dix = byte_offset(offset, char_size, i)?;
+        modify(byte as u64, pixels, dix, char_size, bits)
+            .with_context(|| format!("Failed to hide byte {:#04x} (at index {}).", byte, i))
+    })
+}
+
+/// 将恢复出的第 `index` 个值转换为字节，超出 `u8` 范围时返回 `StegError::ByteOverflow` 而不是截断
+fn checked_byte(value: u64, index: usize) -> Result<u8, StegError> {
+    u8::try_from(value).map_err(|_| StegError::ByteOverflow { value, index })
+}
+
+/// 从 `offset` 开始，逐字节恢复 `count` 个由 `write_bytes` 隐藏的字节
+fn read_bytes(pixels: &[u8], offset: usize, count: usize, bits: u8) -> anyhow::Result<Vec<u8>> {
+    let char_size = bytes_per_char(bits);
+    (0..count)
+        .map(|i| {
+            let dix = byte_offset(offset, char_size, i)?;
+            recover(pixels, dix, char_size, bits)
+                .and_then(|value| checked_byte(value, i))
+                .with_context(|| {
+                    format!(
+                        "Failed to recover byte at index {}. \nThe data at offset {} appears to be corrupted or invalid.",
+                        i, dix
+                    )
+                })
+        })
+        .collect()
+}
+
+/// 从 `start` 开始，按需逐字节恢复以默认位深度 (`DEFAULT_BITS`，即每 `BYTES_PER_CHAR` 个像素字节一个载荷字节) 隐藏的字节
+///
+/// 只有在迭代时才会读取像素字节，适合流式恢复或在遇到结束标记时停止；
+/// 剩余的像素字节不足以组成一个完整的字节时迭代结束
+///
+/// # Errors
+///
+/// 如果某个位置恢复出的值超出 `u8` 范围，该项为 `StegError::ByteOverflow`
+pub fn embedded_bytes(
+    pix: &[u8],
+    start: usize,
+) -> impl Iterator<Item = Result<u8, StegError>> + '_ {
+    let available = pix.len().saturating_sub(start) / BYTES_PER_CHAR;
+    (0..available).map(move |i| {
+        recover(
+            pix,
+            start + i * BYTES_PER_CHAR,
+            BYTES_PER_CHAR,
+            DEFAULT_BITS,
+        )
+        .and_then(|value| checked_byte(value, i))
+    })
+}
+
+/// 报告已处理 `done` 个字节 (共 `total` 个)，只在每隔 `PROGRESS_STEP_BYTES` 个字节及完成时调用回调
+fn report_progress(progress: &dyn Fn(usize), done: usize, total: usize) {
+    if done.is_multiple_of(PROGRESS_STEP_BYTES) || done == total {
+        progress(done);
+    }
+}
+
+/// 按 `positions` 给出的像素字节位置，将字节序列 (`bytes`) 逐字节隐藏到像素缓冲区中
+///
+/// 第 `i` 个字节使用 `positions` 中的第 `i` 组 `codec.bytes_per_char()` 个位置
+///
+/// 每 `PROGRESS_STEP_BYTES` 个字节为一块：块内各字节的新像素值在 rayon 线程池中并行计算，再按顺序写回，
+/// 因此结果与线程数无关；进度回调始终在调用线程中执行
+fn write_bytes_at(
+    pixels: &mut [u8],
+    positions: &[usize],
+    bytes: &[u8],
+    codec: &Codec,
+    progress: &dyn Fn(usize),
+) -> anyhow::Result<()> {
+    let char_size = codec.bytes_per_char();
+    let count = bytes.len().min(positions.len() / char_size);
+    let mut done = 0;
+    for (block, block_positions) in bytes[..count]
+        .chunks(PROGRESS_STEP_BYTES)
+        .zip(positions.chunks(PROGRESS_STEP_BYTES * char_size))
+    {
+        let block_positions = &block_positions[..block.len() * char_size];
+        ensure_positions(pixels.len(), block_positions)
+            .with_context(|| format!("Failed to hide bytes {}..{}.", done, done + block.len()))?;
+
+        // 载荷的各个位置互不相同，因此可以先并行地由原像素值计算新值，再统一写回
+        let source = &*pixels;
+        let embedded: Vec<u8> = block
+            .par_iter()
+            .zip(block_positions.par_chunks_exact(char_size))
+            .flat_map_iter(|(&byte, chunk)| {
+                chunk.iter().enumerate().map(move |(i, &index)| {
+                    let group = (byte as u64) >> codec.shift(i, chunk.len());
+                    codec.embed(source[index], group as u8)
+                })
+            })
+            .collect();
+        for (&index, value) in block_positions.iter().zip(embedded) {
+            pixels[index] = value;
+        }
+
+        done += block.len();
+        report_progress(progress, done, bytes.len());
+    }
+    Ok(())
+}
+
+/// 按 `positions` 给出的像素字节位置，逐字节恢复 `count` 个由 `write_bytes_at` 隐藏的字节
+///
+/// `best_effort` 为 `true` 时，遇到第一个无效字节即停止并返回之前恢复的字节，而不是返回错误
+fn read_bytes_at(
+    pixels: &[u8],
+    positions: &[usize],
+    count: usize,
+    codec: &Codec,
+    best_effort: bool,
+    progress: &dyn Fn(usize),
+) -> anyhow::Result<Vec<u8>> {
+    let char_size = codec.bytes_per_char();
+    anyhow::ensure!(
+        positions.len() >= count * char_size,
+        "Not enough positions to recover {} bytes.",
+        count
+    );
+
+    // 与 `write_bytes_at` 相同，按块并行解码，每块完成后在调用线程中报告进度
+    let mut bytes = Vec::with_capacity(count);
+    for block in positions[..count * char_size].chunks(PROGRESS_STEP_BYTES * char_size) {
+        let offset = bytes.len();
+        let decoded = block
+            .par_chunks_exact(char_size)
+            .enumerate()
+            .map(|(i, chunk)| {
+                let index = offset + i;
+                recover_with(codec, pixels, chunk)
+                    .and_then(|value| checked_byte(value, index))
+                    .with_context(|| format!("Failed to recover byte at index {}.", index))
+            })
+            .collect::<Vec<anyhow::Result<u8>>>();
+
+        // 按顺序累积到第一个无效字节为止
+        for byte in decoded {
+            match byte {
+                Ok(byte) => bytes.push(byte),
+                Err(_) if best_effort => return Ok(bytes),
+                Err(err) => return Err(err),
+            }
+        }
+        report_progress(progress, bytes.len(), count);
+    }
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::*;
+    use rand::RngCore;
+
+    /// 一个完整的端到端测试，模拟隐藏和恢复过程
+    #[test]
+    fn test_hide_and_recover_e2e() {
+        // 1. 准备测试数据
+        // 模拟解码后的像素缓冲区，数据从第 0 个字节开始写入
+        let mut picture = vec![0u8; 1024];
+
+        // 填充一些伪随机数据，模拟真实的图像像素
+        rand::rng().fill_bytes(&mut picture);
+
+        // 模拟要隐藏的文本
+        let original_text = "Hello, Steganography! 你好，隐写术！";
+        let text_bytes = original_text.as_bytes();
+        let text_len = text_bytes.len() as u64;
+
+        // 2. 隐藏数据
+        // 隐藏文本长度
+        modify(text_len, &mut picture, 0, LENGTH_HIDING_BYTES, DEFAULT_BITS)
+            .expect("Failed to hide text length.");
+
+        // 逐字节隐藏文本内容
+        for (i, &char_byte) in text_bytes.iter().enumerate() {
+            let offset = LENGTH_HIDING_BYTES + BYTES_PER_CHAR * i;
+            modify(
+                char_byte as u64,
+                &mut picture,
+                offset,
+                BYTES_PER_CHAR,
+                DEFAULT_BITS,
+            )
+            .expect("Failed to hide a character.");
+        }
+
+        // 3. 恢复数据
+        // 恢复文本长度
+        let recovered_len = recover(&picture, 0, LENGTH_HIDING_BYTES, DEFAULT_BITS)
+            .expect("Failed to recover text length.");
+
+        // 断言长度一致
+        assert_eq!(
+            text_len, recovered_len,
+            "Recovered length should match original length."
+        );
+
+        // 逐字节恢复文本内容
+        let recovered_bytes: Vec<u8> = (0..recovered_len as usize)
+            .map(|i| {
+                let offset = LENGTH_HIDING_BYTES + BYTES_PER_CHAR * i;
+                recover(&picture, offset, BYTES_PER_CHAR, DEFAULT_BITS)
+                    .map(|val| val as u8)
+                    .expect("Failed to recover a character.")
+            })
+            .collect();
+
+        // 4. 断言结果
+        // 断言恢复的文本内容与原始文本完全一致
+        assert_eq!(
+            text_bytes,
+            recovered_bytes.as_slice(),
+            "Recovered text should match original text."
+        );
+    }
+
+    /// 测试 modify 函数在数据不足时能否正确返回错误
+    #[test]
+    fn test_modify_not_enough_space() {
+        let mut picture = vec![0u8; 7];
+        // 尝试在 7 字节的缓冲区中写入 8 字节的数据
+        let result = modify(123, &mut picture, 0, 8, DEFAULT_BITS);
+        assert!(
+            matches!(result, Err(StegError::OutOfBounds { end: 8, len: 7, .. })),
+            "Modify should fail when there is not enough space."
+        );
+    }
+
+    /// 测试 recover 函数在数据不足时能否正确返回错误
+    #[test]
+    fn test_recover_not_enough_data() {
+        // 只有 7 个字节，但我们需要 8 个字节来恢复一个 u64
+        let picture = vec![0u8; 7];
+        let result = recover(&picture, 0, 8, DEFAULT_BITS);
+
+        // 断言结果是越界错误
+        assert!(
+            matches!(result, Err(StegError::OutOfBounds { end: 8, len: 7, .. })),
+            "Recover should fail when there is not enough data."
+        );
+    }
+
+    /// 测试整数溢出时，modify 函数是否会失败
+    #[test]
+    fn test_modify_integer_overflow() {
+        let mut picture = vec![0u8; 10];
+        // dix + size 会导致 usize 溢出
+        let result = modify(123, &mut picture, usize::MAX, 10, DEFAULT_BITS);
+        assert!(
+            matches!(result, Err(StegError::Overflow { .. })),
+            "Recover function should fail on integer overflow."
+        );
+    }
+
+    /// 测试整数溢出时，recover 函数是否会失败
+    #[test]
+    fn test_recover_integer_overflow() {
+        let picture = vec![0u8; 10];
+        // dix + size 会导致 usize 溢出
+        let result = recover(&picture, usize::MAX, 10, DEFAULT_BITS);
+        assert!(
+            matches!(result, Err(StegError::Overflow { .. })),
+            "Recover function should fail on integer overflow."
+        );
+    }
+
+    /// 测试 try_modify/try_recover 成功时与 modify/recover 完全一致，失败时返回对应的错误种类
+    #[test]
+    fn test_try_variants_match_rich_variants() {
+        assert_eq!(std::mem::size_of::<Result<(), ErrorCode>>(), 1);
+
+        let mut rich = vec![0x5Au8; 16];
+        let mut lean = rich.clone();
+        modify(0xBEEF, &mut rich, 2, 8, 2).unwrap();
+        try_modify(0xBEEF, &mut lean, 2, 8, 2).unwrap();
+        assert_eq!(rich, lean);
+        assert_eq!(recover(&rich, 2, 8, 2).unwrap(), 0xBEEF);
+        assert_eq!(try_recover(&lean, 2, 8, 2), Ok(0xBEEF));
+
+        let cases: [(usize, usize, u8); 4] =
+            [(0, 8, 9), (0, 64, 8), (10, 8, 1), (usize::MAX, 10, 1)];
+        for (dix, size, bits) in cases {
+            let expected = recover(&lean, dix, size, bits).unwrap_err().code();
+            assert_eq!(try_recover(&lean, dix, size, bits), Err(expected));
+            assert_eq!(
+                try_modify(1, &mut lean, dix, size, bits),
+                Err(modify(1, &mut rich, dix, size, bits).unwrap_err().code())
+            );
+        }
+        assert_eq!(try_recover(&lean, 10, 8, 1), Err(ErrorCode::OutOfBounds));
+        assert_eq!(try_recover(&lean, 0, 8, 9), Err(ErrorCode::UnsupportedBits));
+    }
+
+    /// 测试当请求恢复的 size 大于 32 时，modify 函数是否会失败
+    #[test]
+    fn test_modify_size_too_large() {
+        let mut picture = vec![0u8; 64];
+        // size=33 超过了 u64 能容纳的 32 字节
+        let result = modify(123, &mut picture, 0, 33, DEFAULT_BITS);
+        assert!(
+            matches!(result, Err(StegError::SizeLimitExceeded { requested: 66 })),
+            "Modify should fail when size is greater than 32."
+        );
+    }
+
+    /// 测试当请求恢复的 size 大于 32 时，recover 函数是否会失败
+    #[test]
+    fn test_recover_size_too_large() {
+        let picture = vec![0u8; 64];
+        // size=33 超过了 u64 能容纳的 32 字节
+        let result = recover(&picture, 0, 33, DEFAULT_BITS);
+        assert!(
+            matches!(result, Err(StegError::SizeLimitExceeded { requested: 66 })),
+            "Recover should fail when size is greater than 32."
+        );
+    }
+
+    /// 测试隐藏和恢复一个空文本（长度为 0）
+    #[test]
+    fn test_hide_and_recover_empty_text() {
+        let mut picture = vec![0u8; 128];
+        let text_len = 0u64;
+
+        // 隐藏长度 0
+        modify(text_len, &mut picture, 0, LENGTH_HIDING_BYTES, DEFAULT_BITS)
+            .expect("Failed to hide zero length.");
+
+        // 恢复长度
+        let recovered_len = recover(&picture, 0, LENGTH_HIDING_BYTES, DEFAULT_BITS)
+            .expect("Failed to recover zero length.");
+
+        assert_eq!(text_len, recovered_len, "Recovered length should be 0.");
+    }
+
+    /// 测试隐藏和恢复 `u64` 的最大值，以确保所有 64 位都被正确处理
+    #[test]
+    fn test_hide_and_recover_u64_max() {
+        // 准备一个足够大的字节缓冲区
+        let mut picture = vec![0u8; 64];
+
+        // 定义要隐藏的值为 u64 的最大可能值
+        let value_to_hide = u64::MAX;
+
+        // 隐藏 u64::MAX
+        modify(value_to_hide, &mut picture, 0, 32, DEFAULT_BITS).expect("Failed to hide u64::MAX");
+
+        // 恢复 u64::MAX
+        let recovered_value =
+            recover(&picture, 0, 32, DEFAULT_BITS).expect("Failed to recover u64::MAX");
+
+        assert_eq!(value_to_hide, recovered_value);
+    }
+
+    /// 测试 hide_bytes/recover_bytes 能够完全在内存中完成载荷的往返
+    #[test]
+    fn test_hide_bytes_and_recover_bytes_in_memory() {
+        let mut pixels = vec![0u8; 4096];
+        rand::rng().fill_bytes(&mut pixels);
+
+        let mut payload = vec![0u8; 256];
+        rand::rng().fill_bytes(&mut payload);
+
+        hide_bytes(&mut pixels, &payload).expect("Failed to hide payload in memory.");
+        let recovered = recover_bytes(&pixels).expect("Failed to recover payload from memory.");
+
+        assert_eq!(
+            payload, recovered,
+            "Recovered payload should match original."
+        );
+    }
+
+    /// 测试按需迭代恢复的字节与逐字节恢复的结果一致，且不足一个字节的剩余像素字节被忽略
+    #[test]
+    fn test_embedded_bytes_matches_read_bytes() {
+        let mut pixels = vec![0u8; 64 * BYTES_PER_CHAR + 3];
+        rand::rng().fill_bytes(&mut pixels);
+        let payload = b"streamed one byte at a time";
+        write_bytes(&mut pixels, 8, payload, DEFAULT_BITS).unwrap();
+
+        let expected = read_bytes(&pixels, 8, payload.len(), DEFAULT_BITS).unwrap();
+        let streamed: Vec<u8> = embedded_bytes(&pixels, 8)
+            .take(payload.len())
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(streamed, expected);
+        assert_eq!(streamed, payload);
+
+        assert_eq!(
+            embedded_bytes(&pixels, 8).count(),
+            (pixels.len() - 8) / BYTES_PER_CHAR
+        );
+        assert_eq!(embedded_bytes(&pixels, pixels.len() + 1).count(), 0);
+    }
+
+    /// 测试 hide_bytes 在缓冲区空间不足时返回错误且不修改缓冲区
+    #[test]
+    fn test_hide_bytes_not_enough_space() {
+        let mut pixels = vec![0u8; HEADER_BYTES + BYTES_PER_CHAR * 3];
+        let err = hide_bytes(&mut pixels, b"four").expect_err("hide_bytes should fail.");
+
+        assert_eq!(
+            err.downcast_ref::<StegError>(),
+            Some(&StegError::NotEnoughSpace {
+                required: BYTES_PER_CHAR * 4,
+                available: BYTES_PER_CHAR * 3,
+            }),
+            "hide_bytes should fail without enough space."
+        );
+        assert!(
+            pixels.iter().all(|&b| b == 0),
+            "Buffer should be untouched when the capacity check fails."
+        );
+    }
+
+    /// 测试 `auto_bits` 在默认位深度放不下时选择第一个放得下的位深度，并将其记录在头部中
+    #[test]
+    fn test_auto_bits_selects_smallest_fitting_depth() {
+        // 6 个字节在 2 位时需要 24 个像素字节，3 位时只需 18 个
+        let payload = b"secret";
+        let mut pixels = vec![0u8; HEADER_BYTES + bytes_per_char(3) * payload.len()];
+        let options = HideOptions {
+            auto_bits: true,
+            ..Default::default()
+        };
+
+        let stats = hide_bytes_with(&mut pixels, payload, &options).unwrap();
+        assert_eq!(stats.bits, 3);
+        assert_eq!(read_header(&pixels).unwrap().bits, 3);
+        assert_eq!(recover_bytes(&pixels).unwrap(), payload);
+
+        // 4 位仍然放不下时报告 4 位下所需与可用的字节数
+        let mut pixels = vec![0u8; HEADER_BYTES + payload.len()];
+        let err = hide_bytes_with(&mut pixels, payload, &options).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<StegError>(),
+            Some(&StegError::NotEnoughSpace {
+                required: bytes_per_char(MAX_BITS) * payload.len(),
+                available: payload.len(),
+            })
+        );
+    }
+
+    /// 测试载荷所需空间与逐字节偏移量在溢出 `usize` 时返回错误而不是回绕
+    ///
+    /// 用接近 `usize::MAX` 的合成长度触发，与平台位宽无关，也不需要真的分配这么大的载荷
+    #[test]
+    fn test_capacity_math_overflow() {
+        assert_eq!(required_space(100, 2, 3), Ok(100 * BYTES_PER_CHAR * 3));
+        let huge = usize::MAX / BYTES_PER_CHAR + 1;
+        assert_eq!(
+            required_space(huge, 2, 1),
+            Err(StegError::PayloadTooLarge { len: huge })
+        );
+        // 单份不溢出，但乘以冗余份数后溢出
+        let len = usize::MAX / BYTES_PER_CHAR;
+        assert_eq!(
+            required_space(len, 2, 3),
+            Err(StegError::PayloadTooLarge { len })
+        );
+
+        assert_eq!(byte_offset(32, BYTES_PER_CHAR, 5), Ok(52));
+        assert!(matches!(
+            byte_offset(32, BYTES_PER_CHAR, huge),
+            Err(StegError::Overflow { dix: 32, .. })
+        ));
+        assert!(matches!(
+            byte_offset(usize::MAX, BYTES_PER_CHAR, 1),
+            Err(StegError::Overflow { .. })
+        ));
+    }
+
+    /// 测试所有支持的位深度都能正确往返同一段载荷
+    #[test]
+    fn test_hide_and_recover_all_bit_depths() {
+        let mut payload = vec![0u8; 200];
+        rand::rng().fill_bytes(&mut payload);
+
+        for bits in 1..=MAX_BITS {
+            let mut pixels = vec![0u8; header_len(200, 0) + payload.len() * bytes_per_char(bits)];
+            rand::rng().fill_bytes(&mut pixels);
+
+            hide_bytes_with(
+                &mut pixels,
+                &payload,
+                &HideOptions {
+                    bits,
+                    ..Default::default()
+                },
+            )
+            .unwrap_or_else(|e| panic!("Failed to hide payload at {} bits: {}", bits, e));
+            let recovered = recover_bytes(&pixels)
+                .unwrap_or_else(|e| panic!("Failed to recover payload at {} bits: {}", bits, e));
+
+            assert_eq!(payload, recovered, "Round trip failed at {} bits.", bits);
+        }
+    }
+
+    /// 测试长度编码跨越字节边界时，头部随之增长，且恰好容纳的载荷仍能完整往返
+    #[test]
+    fn test_varint_length_boundaries() {
+        assert_eq!(header_len(0, 0), HEADER_BYTES);
+        assert_eq!(header_len(127, 0), HEADER_BYTES);
+        assert_eq!(header_len(128, 0), HEADER_BYTES + BYTES_PER_CHAR);
+        assert_eq!(header_len(16384, 0), HEADER_BYTES + 2 * BYTES_PER_CHAR);
+
+        for len in [127usize, 128, 16384] {
+            let mut payload = vec![0u8; len];
+            rand::rng().fill_bytes(&mut payload);
+            let mut pixels = vec![0u8; header_len(len as u64, 0) + len * BYTES_PER_CHAR];
+
+            let stats = hide_bytes_with(&mut pixels, &payload, &HideOptions::default())
+                .unwrap_or_else(|e| panic!("Failed to hide {} bytes: {}", len, e));
+            assert_eq!(stats.capacity, len);
+            assert_eq!(stats.end_offset, pixels.len());
+
+            let (header, recovered) = recover_payload(&pixels, &RecoverOptions::default())
+                .unwrap_or_else(|e| panic!("Failed to recover {} bytes: {}", len, e));
+            assert_eq!(header.payload_offset(), header_len(len as u64, 0));
+            assert_eq!(recovered, payload);
+
+            // 少一个像素字节就放不下
+            let mut short = vec![0u8; pixels.len() - 1];
+            assert!(hide_bytes_with(&mut short, &payload, &HideOptions::default()).is_err());
+        }
+    }
+
+    /// 测试按公式统计的通道位置数量与逐个枚举的结果一致
+    #[test]
+    fn test_count_positions_matches_enumeration() {
+        for channels in ["rgba", "rgb", "b", "ra"] {
+            let channels: Channels = channels.parse().unwrap();
+            for (stride, spacing) in [(3, 1), (4, 1), (4, 3)] {
+                for (start, len) in [(0, 0), (HEADER_BYTES, 10), (5, 103), (HEADER_BYTES, 1001)] {
+                    assert_eq!(
+                        count_positions(start, len, channels, stride, spacing),
+                        channel_positions(start, len, channels, stride, spacing).count(),
+                        "channels {}, stride {}, spacing {}, {}..{}",
+                        channels,
+                        stride,
+                        spacing,
+                        start,
+                        len
+                    );
+                }
+            }
+        }
+    }
+
+    /// 测试不同像素字节数与位深度下的最大载荷，且结果恰好能被隐藏
+    #[test]
+    fn test_max_payload_bytes() {
+        // 头部之外的空间不足一个载荷字节
+        assert_eq!(max_payload_bytes(0, 2), 0);
+        assert_eq!(max_payload_bytes(HEADER_BYTES, 2), 0);
+        assert_eq!(max_payload_bytes(HEADER_BYTES + BYTES_PER_CHAR, 2), 1);
+
+        // 载荷不超过 127 字节时头部大小为 HEADER_BYTES
+        assert_eq!(max_payload_bytes(400, 2), (400 - HEADER_BYTES) / 4);
+        assert_eq!(max_payload_bytes(400, 1), (400 - HEADER_BYTES) / 8);
+        // 超过 127 字节时长度编码多占 1 个字节
+        assert_eq!(max_payload_bytes(400, 4), (400 - header_len(128, 0)) / 2);
+
+        // 超出范围的位深度无法隐藏任何数据
+        assert_eq!(max_payload_bytes(400, 0), 0);
+        assert_eq!(max_payload_bytes(400, MAX_BITS as usize + 1), 0);
+        assert_eq!(max_payload_bytes(400, 256), 0);
+
+        for pixel_count in [500, 10 * 10 * 3, 64 * 64 * 4, 70_001] {
+            for bits in 1..=MAX_BITS {
+                let capacity = max_payload_bytes(pixel_count, bits as usize);
+                let options = HideOptions {
+                    bits,
+                    ..Default::default()
+                };
+
+                let mut pixels = vec![0u8; pixel_count];
+                hide_bytes_with(&mut pixels, &vec![0xA5; capacity], &options).unwrap_or_else(|e| {
+                    panic!(
+                        "{} bytes should fit in {} at {} bits: {}",
+                        capacity, pixel_count, bits, e
+                    )
+                });
+                assert!(
+                    hide_bytes_with(&mut pixels, &vec![0xA5; capacity + 1], &options).is_err(),
+                    "{} bytes should not fit in {} at {} bits",
+                    capacity + 1,
+                    pixel_count,
+                    bits
+                );
+            }
+        }
+    }
+
+    /// 测试位深度越大，所需的像素字节数越少，且超出范围的位深度会被拒绝
+    #[test]
+    fn test_bit_depth_capacity_and_validation() {
+        assert_eq!(bytes_per_char(1), 8);
+        assert_eq!(bytes_per_char(2), BYTES_PER_CHAR);
+        assert_eq!(bytes_per_char(3), 3);
+        assert_eq!(bytes_per_char(4), 2);
+        assert_eq!(lsb_mask(DEFAULT_BITS), LSB_MASK);
+        assert_eq!(!lsb_mask(DEFAULT_BITS), DATA_MASK);
+
+        let mut pixels = vec![0u8; 256];
+        for bits in [0, MAX_BITS + 1] {
+            let options = HideOptions {
+                bits,
+                ..Default::default()
+            };
+            let err = hide_bytes_with(&mut pixels, b"x", &options).unwrap_err();
+            assert_eq!(
+                err.downcast_ref::<StegError>(),
+                Some(&StegError::UnsupportedBits(bits))
+            );
+        }
+        assert!(matches!(
+            modify(1, &mut pixels, 0, 17, 4),
+            Err(StegError::SizeLimitExceeded { .. })
+        ));
+        assert!(matches!(
+            recover(&pixels, 0, 17, 4),
+            Err(StegError::SizeLimitExceeded { .. })
+        ));
+    }
+
+    /// 测试隐藏后能检测到魔数，而普通缓冲区会被拒绝并给出明确错误
+    #[test]
+    fn test_magic_marker_detection() {
+        let mut pixels = vec![0u8; 512];
+        assert!(
+            !has_magic(&pixels),
+            "A zeroed buffer should not carry the magic."
+        );
+
+        let err =
+            recover_bytes(&pixels).expect_err("Recover should reject a buffer without magic.");
+        assert_eq!(
+            err.downcast_ref::<StegError>(),
+            Some(&StegError::MagicMismatch)
+        );
+        assert_eq!(err.to_string(), NO_PAYLOAD_MESSAGE);
+
+        hide_bytes(&mut pixels, b"magic").expect("Failed to hide payload.");
+        assert!(has_magic(&pixels), "A stego buffer should carry the magic.");
+
+        // 魔数区域太小时应安全地返回 false 而不是 panic
+        assert!(!has_magic(&pixels[..MAGIC_HIDING_BYTES - 1]));
+    }
+
+    /// 测试分隔模式在载荷中恰好包含结束标记时仍能完整往返，且头部不记录长度
+    #[test]
+    fn test_delimited_round_trip_with_marker_in_payload() {
+        let payload = [
+            b"before".as_slice(),
+            &DELIMITER_END,
+            &DELIMITER_START,
+            b"after",
+        ]
+        .concat();
+        for (bits, channels) in [
+            (DEFAULT_BITS, Channels::ALL),
+            (3, "rg".parse::<Channels>().unwrap()),
+        ] {
+            let mut pixels = vec![0u8; 1024];
+            let options = HideOptions {
+                bits,
+                channels,
+                delimited: true,
+                name: "x.bin".to_string(),
+                ..Default::default()
+            };
+            let stats = hide_bytes_with(&mut pixels, &payload, &options).unwrap();
+
+            let header = read_header(&pixels).unwrap();
+            assert!(header.is_delimited());
+            assert_eq!(header.length, 0);
+            assert_eq!(
+                header.payload_offset(),
+                header_len(0, "x.bin".len()) - BYTES_PER_CHAR
+            );
+
+            let (header, recovered, recover_stats) =
+                recover_payload_with_stats(&pixels, &RecoverOptions::default()).unwrap();
+            assert_eq!(recovered, payload);
+            assert_eq!(header.length, payload.len() as u64);
+            assert_eq!(recover_stats, stats);
+        }
+    }
+
+    /// 测试分隔模式在结束标记被破坏时报告截断，并拒绝与打散、定长长度字段或冗余副本组合
+    #[test]
+    fn test_delimited_truncation_and_conflicts() {
+        let mut pixels = vec![0u8; 512];
+        let options = HideOptions {
+            delimited: true,
+            ..Default::default()
+        };
+        let stats = hide_bytes_with(&mut pixels, b"cut short", &options).unwrap();
+
+        // 抹掉结束标记与之后的全部像素字节，模拟截断
+        let end_marker = stats.end_offset - DELIMITER_END.len() * BYTES_PER_CHAR;
+        let err = recover_bytes(&pixels[..end_marker]).unwrap_err();
+        assert!(format!("{:#}", err).contains("truncated"), "{:#}", err);
+
+        for conflicting in [
+            HideOptions {
+                scatter_seed: Some([7; SEED_LEN]),
+                ..options.clone()
+            },
+            HideOptions {
+                length_bits: Some(32),
+                ..options.clone()
+            },
+            HideOptions {
+                redundancy: 3,
+                ..options.clone()
+            },
+        ] {
+            let err = hide_bytes_with(&mut vec![0u8; 512], b"x", &conflicting).unwrap_err();
+            assert!(err.to_string().starts_with("Delimited payloads cannot"));
+        }
+    }
+
+    /// 测试自适应模式只修改纹理丰富区域的像素字节，并且只有同样指定该模式才能恢复
+    #[test]
+    fn test_edge_adaptive_round_trip_skips_smooth_region() {
+        // 前后两段为噪点，中间一段为纯色的平滑区域
+        let mut cover = vec![0x80u8; 8192];
+        rand::rng().fill_bytes(&mut cover[..2048]);
+        rand::rng().fill_bytes(&mut cover[4096..]);
+        // 头部之后紧跟一小段平滑区域，载荷必然绕开它，不指定自适应模式时不会碰巧按顺序读出相同的载荷
+        cover[104..136].fill(0x80);
+        let payload = b"Only the noisy parts carry this.";
+
+        for seed in [None, Some([7u8; SEED_LEN])] {
+            let mut pixels = cover.clone();
+            let options = HideOptions {
+                edge_adaptive: true,
+                scatter_seed: seed,
+                ..Default::default()
+            };
+            hide_bytes_with(&mut pixels, payload, &options).unwrap();
+
+            // 与噪点相邻的边界字节本身也有梯度，只检查平滑区域内部
+            assert_eq!(&pixels[2048 + 4..4096 - 4], &cover[2048 + 4..4096 - 4]);
+
+            let options = RecoverOptions {
+                scatter_seed: seed,
+                edge_adaptive: true,
+                ..Default::default()
+            };
+            let (_, recovered) = recover_payload(&pixels, &options).unwrap();
+            assert_eq!(recovered, payload);
+
+            let options = RecoverOptions {
+                edge_adaptive: false,
+                ..options
+            };
+            assert!(recover_payload(&pixels, &options).is_err());
+        }
+
+        // 完全平滑的图像没有可用的位置
+        let mut pixels = vec![0x80u8; 8192];
+        let options = HideOptions {
+            edge_adaptive: true,
+            ..Default::default()
+        };
+        let err = hide_bytes_with(&mut pixels, payload, &options).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<StegError>(),
+            Some(StegError::NotEnoughSpace { available: 0, .. })
+        ));
+    }
+
+    /// 测试追加的字节接在原载荷之后，原载荷的像素字节保持不变，且恢复出拼接后的载荷
+    #[test]
+    fn test_append_bytes() {
+        let mut cover = vec![0u8; 4096];
+        rand::rng().fill_bytes(&mut cover);
+
+        for (seed, length_backup) in [(None, false), (Some([5u8; SEED_LEN]), true)] {
+            let mut pixels = cover.clone();
+            let options = HideOptions {
+                scatter_seed: seed,
+                length_backup,
+                payload_type: Some(PayloadType::Text),
+                ..Default::default()
+            };
+            hide_bytes_with(&mut pixels, b"foo", &options).unwrap();
+            let before = pixels.clone();
+
+            let options = RecoverOptions {
+                scatter_seed: seed,
+                ..Default::default()
+            };
+            let stats = append_bytes(&mut pixels, b"bar", &options).unwrap();
+            assert_eq!(stats.payload_len, 6);
+            let (header, recovered, stats) = recover_payload_with_stats(&pixels, &options).unwrap();
+            assert_eq!(recovered, b"foobar");
+            assert_eq!(header.checksum, crc32fast::hash(b"foobar"));
+            assert_eq!(header.payload_type, Some(PayloadType::Text));
+            assert_eq!(stats.length_mismatch, None);
+
+            // 只有长度、校验和与新增字节所在的像素字节发生变化
+            let changed = before.iter().zip(&pixels).filter(|(a, b)| a != b).count();
+            assert!(changed <= 3 * BYTES_PER_CHAR + CHECKSUM_HIDING_BYTES + LENGTH_BACKUP_BYTES);
+        }
+
+        // 长度字段变长时整体重新隐藏
+        let mut pixels = cover.clone();
+        let long = vec![b'x'; 127];
+        hide_bytes_with(&mut pixels, &long, &HideOptions::default()).unwrap();
+        append_bytes(&mut pixels, b"tail", &RecoverOptions::default()).unwrap();
+        let (_, recovered) = recover_payload(&pixels, &RecoverOptions::default()).unwrap();
+        assert_eq!(recovered, [long.as_slice(), b"tail"].concat());
+
+        // 容量不足或载荷无法拼接时不修改任何像素字节
+        let before = pixels.clone();
+        let err = append_bytes(&mut pixels, &[0u8; 2048], &RecoverOptions::default()).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<StegError>(),
+            Some(StegError::NotEnoughSpace { .. })
+        ));
+        let mut compressed = cover.clone();
+        let options = HideOptions {
+            compression: Compression::Gzip,
+            ..Default::default()
+        };
+        hide_bytes_with(&mut compressed, b"zip", &options).unwrap();
+        assert!(append_bytes(&mut compressed, b"more", &RecoverOptions::default()).is_err());
+        assert_eq!(pixels, before);
+    }
+
+    /// 测试文本编码与压缩算法共用同一个头部字段，两者都能原样恢复
+    #[test]
+    fn test_text_encoding_shares_compression_field() {
+        for (compression, text_encoding) in [
+            (Compression::None, None),
+            (Compression::Gzip, Some(encoding_rs::GB18030)),
+            (Compression::Zstd, Some(encoding_rs::X_USER_DEFINED)),
+        ] {
+            let mut pixels = vec![0u8; 1024];
+            let options = HideOptions {
+                compression,
+                text_encoding,
+                ..Default::default()
+            };
+            hide_bytes_with(&mut pixels, b"text", &options).unwrap();
+
+            let header = read_header(&pixels).unwrap();
+            assert_eq!(header.compression, compression);
+            assert_eq!(header.text_encoding, text_encoding);
+        }
+    }
+
+    /// 测试间隔为 3 时往返恢复，头部记录间隔，且载荷区域中被跳过的像素字节保持不变
+    #[test]
+    fn test_spacing_round_trip_leaves_skipped_bytes_untouched() {
+        let mut cover = vec![0u8; 4096];
+        rand::rng().fill_bytes(&mut cover);
+        let payload = b"Every third pixel byte carries this.";
+
+        for seed in [None, Some([3u8; SEED_LEN])] {
+            let mut pixels = cover.clone();
+            let options = HideOptions {
+                spacing: 3,
+                plane: 1,
+                scatter_seed: seed,
+                ..Default::default()
+            };
+            let stats = hide_bytes_with(&mut pixels, payload, &options).unwrap();
+
+            let header = read_header(&pixels).unwrap();
+            assert_eq!((header.spacing, header.plane), (3, 1));
+            let start = header.payload_offset();
+            assert!(
+                (start..pixels.len())
+                    .filter(|index| !(index - start).is_multiple_of(3))
+                    .all(|index| pixels[index] == cover[index])
+            );
+            assert_eq!(
+                stats.capacity,
+                hide_capacity(
+                    cover.len(),
+                    &HideOptions {
+                        spacing: 3,
+                        ..Default::default()
+                    }
+                )
+            );
+
+            let options = RecoverOptions {
+                scatter_seed: seed,
+                ..Default::default()
+            };
+            let (_, recovered) = recover_payload(&pixels, &options).unwrap();
+            assert_eq!(recovered, payload);
+        }
+
+        for spacing in [0, MAX_SPACING + 1] {
+            let options = HideOptions {
+                spacing,
+                ..Default::default()
+            };
+            assert!(hide_bytes_with(&mut cover.clone(), payload, &options).is_err());
+        }
+    }
+
+    /// 测试两种字节序都能往返，而以大端序隐藏的载荷按小端序恢复时校验失败
+    #[test]
+    fn test_endian_round_trip_and_mismatch() {
+        let payload = b"Byte order matters for every payload byte.";
+        for bits in [1, 2, 3, 4] {
+            let mut hidden = Vec::new();
+            for endian in [Endian::Little, Endian::Big] {
+                let mut pixels = vec![0u8; 4096];
+                let options = HideOptions {
+                    bits,
+                    endian,
+                    ..Default::default()
+                };
+                hide_bytes_with(&mut pixels, payload, &options).unwrap();
+                let options = RecoverOptions {
+                    endian,
+                    ..Default::default()
+                };
+                let (header, recovered) = recover_payload(&pixels, &options).unwrap();
+                assert_eq!(recovered, payload);
+                assert_eq!(header.bits, bits);
+                hidden.push(pixels);
+            }
+
+            // 头部按小端序写入，两者只有载荷部分不同
+            let start = read_header(&hidden[0]).unwrap().payload_offset();
+            assert_eq!(&hidden[0][..start], &hidden[1][..start]);
+            assert_ne!(&hidden[0][start..], &hidden[1][start..]);
+
+            // 位深度为 3 时，逆序组合出的值可能超出一个字节，在完整性校验之前就会报错
+            assert!(recover_payload(&hidden[1], &RecoverOptions::default()).is_err());
+        }
+    }
+
+    /// 测试头部中的长度损坏时按末尾的备份恢复，两份都无效时报告损坏
+    #[test]
+    fn test_length_backup() {
+        let payload = b"backed up length";
+        let mut pixels = vec![0u8; 1024];
+        let options = HideOptions {
+            length_backup: true,
+            ..Default::default()
+        };
+        hide_bytes_with(&mut pixels, payload, &options).unwrap();
+        assert_eq!(read_length_backup(&pixels), Some(payload.len() as u64));
+        let (_, recovered, stats) =
+            recover_payload_with_stats(&pixels, &RecoverOptions::default()).unwrap();
+        assert_eq!(recovered, payload);
+        assert_eq!(stats.length_mismatch, None);
+
+        // 改写头部中的长度字节，编码的字节数不变
+        let length_offset = LENGTH_OFFSET + LENGTH_PREFIX_HIDING_BYTES;
+        write_bytes(&mut pixels, length_offset, &[40], DEFAULT_BITS).unwrap();
+        let (header, recovered, stats) =
+            recover_payload_with_stats(&pixels, &RecoverOptions::default()).unwrap();
+        assert_eq!(recovered, payload);
+        assert_eq!(header.length, payload.len() as u64);
+        assert_eq!(stats.length_mismatch, Some((40, payload.len() as u64)));
+
+        // 备份同样损坏时两份长度都无法通过校验
+        let backup_offset = pixels.len() - LENGTH_BACKUP_BYTES;
+        write_bytes(
+            &mut pixels,
+            backup_offset + LENGTH_BACKUP_MAGIC.len() * BYTES_PER_CHAR,
+            &[41],
+            DEFAULT_BITS,
+        )
+        .unwrap();
+        let err = recover_payload(&pixels, &RecoverOptions::default()).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<StegError>(),
+            Some(&StegError::LengthCopiesDisagree {
+                header: 40,
+                backup: 41
+            })
+        );
+
+        let options = HideOptions {
+            delimited: true,
+            ..options
+        };
+        assert!(hide_bytes_with(&mut pixels, payload, &options).is_err());
+    }
+
+    /// 测试不含头部地隐藏后按给定长度提取，并拒绝为 0、超过上限或超过容量的长度
+    #[test]
+    fn test_recover_headerless() {
+        let mut pixels = vec![0u8; 64];
+        let stats = hide_headerless(&mut pixels, b"raw").unwrap();
+        assert_eq!((stats.payload_len, stats.capacity), (3, 16));
+        assert!(pixels[3 * BYTES_PER_CHAR..].iter().all(|&byte| byte == 0));
+        let err = hide_headerless(&mut pixels.clone(), &[0u8; 17]).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<StegError>(),
+            Some(&StegError::NotEnoughSpace {
+                required: 17 * BYTES_PER_CHAR,
+                available: 64
+            })
+        );
+
+        let (header, payload, stats) = recover_headerless(&pixels, 3, None).unwrap();
+        assert_eq!(payload, b"raw");
+        assert_eq!(header.checksum, crc32fast::hash(b"raw"));
+        assert_eq!(stats.end_offset, 3 * BYTES_PER_CHAR);
+
+        assert_eq!(
+            recover_headerless(&pixels, 0, None).unwrap_err(),
+            StegError::MagicMismatch
+        );
+        assert_eq!(
+            recover_headerless(&pixels, 3, Some(2)).unwrap_err(),
+            StegError::PayloadLimitExceeded { limit: 2 }
+        );
+        assert_eq!(
+            recover_headerless(&pixels, 17, None).unwrap_err(),
+            StegError::LengthExceedsCapacity {
+                length: 17,
+                capacity: 16
+            }
+        );
+    }
+
+    /// 测试 `payload_limit` 在提取之前拒绝声称超过上限的长度，分隔模式下读到超过上限的载荷时同样拒绝
+    #[test]
+    fn test_payload_limit() {
+        let payload = [DELIMITER_ESCAPE; 40];
+        for delimited in [false, true] {
+            let mut pixels = vec![0u8; 1024];
+            let options = HideOptions {
+                delimited,
+                ..Default::default()
+            };
+            hide_bytes_with(&mut pixels, &payload, &options).unwrap();
+
+            let recover_with_limit = |limit: u64| {
+                let options = RecoverOptions {
+                    payload_limit: Some(limit),
+                    ..Default::default()
+                };
+                recover_payload(&pixels, &options).map(|(_, payload)| payload)
+            };
+            let err = recover_with_limit(39).unwrap_err();
+            assert_eq!(
+                err.downcast_ref::<StegError>(),
+                Some(&StegError::PayloadLimitExceeded { limit: 39 }),
+                "{:#}",
+                err
+            );
+            assert_eq!(recover_with_limit(40).unwrap(), payload);
+        }
+        assert_eq!(
+            delimited::max_encoded_len(40) as usize,
+            delimited::encoded_len(&payload)
+        );
+    }
+
+    /// 测试类型标签与来源信息、冗余份数一起往返，且只在提供时占用头部空间
+    #[test]
+    fn test_payload_type_round_trip() {
+        for payload_type in [
+            PayloadType::Text,
+            PayloadType::Binary,
+            PayloadType::Archive,
+            PayloadType::Encrypted,
+        ] {
+            let mut pixels = vec![0u8; 1024];
+            let options = HideOptions {
+                name: "a.bin".to_string(),
+                provenance: Some(Provenance::now()),
+                redundancy: 3,
+                payload_type: Some(payload_type),
+                ..Default::default()
+            };
+            hide_bytes_with(&mut pixels, b"typed", &options).expect("Failed to hide payload.");
+
+            let (header, payload) = recover_payload(&pixels, &RecoverOptions::default())
+                .expect("Failed to recover payload.");
+            assert_eq!(header.payload_type, Some(payload_type));
+            assert_ne!(header.flags & FLAG_TYPED, 0);
+            assert_eq!(header.redundancy, 3);
+            assert_eq!(header.provenance, options.provenance);
+            assert_eq!(payload, b"typed");
+            assert_eq!(
+                PayloadType::from_id(payload_type.id()).unwrap(),
+                payload_type
+            );
+        }
+
+        let mut pixels = vec![0u8; 1024];
+        let stats = hide_bytes_with(&mut pixels, b"untyped", &HideOptions::default()).unwrap();
+        let header = read_header(&pixels).unwrap();
+        assert_eq!(header.payload_type, None);
+        assert_eq!(header.flags & FLAG_TYPED, 0);
+        assert_eq!(header.payload_offset(), header_len(7, 0));
+        assert_eq!(
+            stats.capacity,
+            payload_capacity(1024, Channels::ALL, 4, DEFAULT_BITS)
+        );
+        assert!(PayloadType::from_id(0).is_err());
+    }
+
+    /// 测试头部中的标志位、压缩算法与文件名能够原样往返
+    #[test]
+    fn test_header_flags_round_trip() {
+        let mut pixels = vec![0u8; 256];
+        let options = HideOptions {
+            bits: 3,
+            flags: FLAG_ENCRYPTED,
+            compression: Compression::Zstd,
+            name: "报告.pdf".to_string(),
+            ..Default::default()
+        };
+        hide_bytes_with(&mut pixels, b"flags", &options).expect("Failed to hide payload.");
+
+        let (header, payload) = recover_payload(&pixels, &RecoverOptions::default())
+            .expect("Failed to recover payload.");
+        assert_eq!(
+            header,
+            Header {
+                version: FORMAT_VERSION,
+                bits: 3,
+                flags: FLAG_ENCRYPTED,
+                plane: 0,
+                compression: Compression::Zstd,
+                text_encoding: None,
+                channels: Channels::ALL,
+                spacing: 1,
+                length: 5,
+                checksum: crc32fast::hash(b"flags"),
+                name: "报告.pdf".to_string(),
+                provenance: None,
+                length_bits: None,
+                redundancy: 1,
+                payload_type: None,
+            }
+        );
+        assert!(header.is_encrypted());
+        assert_eq!(header.payload_offset(), header_len(5, "报告.pdf".len()));
+        assert_eq!(payload, b"flags");
+
+        // 过长的文件名无法记录在头部中
+        let too_long = HideOptions {
+            name: "x".repeat(MAX_NAME_LEN + 1),
+            ..Default::default()
+        };
+        assert!(hide_bytes_with(&mut vec![0u8; 4096], b"flags", &too_long).is_err());
+    }
+
+    /// 测试比最小头部还小的像素缓冲区会被明确拒绝，而恰好容纳头部时可以隐藏空载荷
+    #[test]
+    fn test_hide_rejects_image_smaller_than_header() {
+        let err = hide_bytes(&mut [0u8; 4], b"").unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<StegError>(),
+            Some(&StegError::ImageTooSmall {
+                len: 4,
+                required: HEADER_BYTES
+            })
+        );
+        assert!(
+            err.to_string()
+                .contains("Image too small to hold even the length header")
+        );
+
+        let mut pixels = vec![0u8; HEADER_BYTES];
+        hide_bytes(&mut pixels, b"").expect("An empty payload fits in the bare header.");
+        assert!(recover_bytes(&pixels).unwrap().is_empty());
+    }
+
+    /// 测试头部记录当前的格式版本，无法识别的版本会被明确拒绝
+    #[test]
+    fn test_read_header_rejects_unknown_version() {
+        let mut pixels = vec![0u8; 512];
+        hide_bytes(&mut pixels, b"versioned").expect("Failed to hide payload.");
+        let header = read_header(&pixels).expect("Failed to read header.");
+        assert_eq!(header.version, FORMAT_VERSION);
+
+        let future = FORMAT_VERSION + 1;
+        write_bytes(&mut pixels, VERSION_OFFSET, &[future], DEFAULT_BITS).unwrap();
+        let err = read_header(&pixels).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<StegError>(),
+            Some(&StegError::UnsupportedVersion(future))
+        );
+        assert_eq!(
+            err.to_string(),
+            format!("Unsupported lsb_hide format version {}.", future)
+        );
+    }
+
+    /// 测试 3 bits/字节 时，9 位窗口解码出超过 255 的值会被报告为数据损坏，而不是被截断
+    #[test]
+    fn test_recovered_byte_overflow_is_rejected() {
+        let pixels = [0b111u8; 6];
+        let positions = [0, 1, 2, 3, 4, 5];
+        let codec = Codec::new(3, 0).unwrap();
+        let err = read_bytes_at(&pixels, &positions, 2, &codec, false, &|_| {}).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<StegError>(),
+            Some(&StegError::ByteOverflow {
+                value: 511,
+                index: 0
+            })
+        );
+
+        // 高位为 0 时正常恢复
+        let pixels = [0b111, 0b111, 0b011];
+        assert_eq!(read_bytes(&pixels, 0, 1, 3).unwrap(), [0xFF]);
+        assert!(read_bytes(&[0b111; 3], 0, 1, 3).is_err());
+    }
+
+    /// 测试每种定长长度字段都能往返，且头部大小与所选位数一致
+    #[test]
+    fn test_fixed_length_field_round_trip() {
+        for length_bits in LENGTH_BITS_CHOICES {
+            let options = HideOptions {
+                length_bits: Some(length_bits),
+                ..Default::default()
+            };
+            let mut pixels = vec![0u8; 1024];
+            hide_bytes_with(&mut pixels, b"fixed width", &options)
+                .expect("Failed to hide payload.");
+
+            let (header, payload) = recover_payload(&pixels, &RecoverOptions::default())
+                .expect("Failed to recover payload.");
+            assert_ne!(header.flags & FLAG_FIXED_LENGTH, 0);
+            assert_eq!(header.length_bits, Some(length_bits));
+            assert_eq!(
+                header.payload_offset(),
+                HEADER_BYTES + (length_bits as usize / 8 - 1) * BYTES_PER_CHAR
+            );
+            assert_eq!(payload, b"fixed width");
+        }
+
+        // 默认的变长编码不设置标志
+        let mut pixels = vec![0u8; 1024];
+        hide_bytes(&mut pixels, b"varint").expect("Failed to hide payload.");
+        let header = read_header(&pixels).expect("Failed to read header.");
+        assert_eq!(header.flags & FLAG_FIXED_LENGTH, 0);
+        assert_eq!(header.length_bits, None);
+    }
+
+    /// 测试三份冗余写入时，一份副本所在区域被完全破坏仍能通过多数表决恢复
+    #[test]
+    fn test_redundancy_survives_one_corrupted_copy() {
+        let payload = b"majority rules";
+        let copy_space = payload.len() * BYTES_PER_CHAR;
+        let corrupt_second_copy = |pixels: &mut [u8], header: &Header| {
+            let start = header.payload_offset() + copy_space;
+            for byte in &mut pixels[start..start + copy_space] {
+                *byte ^= 0b11;
+            }
+        };
+
+        let options = HideOptions {
+            redundancy: 3,
+            ..Default::default()
+        };
+        let mut pixels = vec![0u8; 1024];
+        let stats =
+            hide_bytes_with(&mut pixels, payload, &options).expect("Failed to hide payload.");
+        let header = read_header(&pixels).expect("Failed to read header.");
+        assert_ne!(header.flags & FLAG_REDUNDANT, 0);
+        assert_eq!(header.redundancy, 3);
+        assert_eq!(stats.end_offset, header.payload_offset() + 3 * copy_space);
+
+        corrupt_second_copy(&mut pixels, &header);
+        let (_, recovered) = recover_payload(&pixels, &RecoverOptions::default())
+            .expect("Failed to recover payload.");
+        assert_eq!(recovered, payload);
+
+        // 没有冗余时同样的破坏会被校验和发现
+        let mut pixels = vec![0u8; 1024];
+        hide_bytes(&mut pixels, payload).expect("Failed to hide payload.");
+        let header = read_header(&pixels).expect("Failed to read header.");
+        assert_eq!(header.flags & FLAG_REDUNDANT, 0);
+        assert_eq!(header.redundancy, 1);
+        let start = header.payload_offset();
+        for byte in &mut pixels[start..start + copy_space] {
+            *byte ^= 0b11;
+        }
+        let err = recover_payload(&pixels, &RecoverOptions::default()).unwrap_err();
+        assert_eq!(err.to_string(), CHECKSUM_MISMATCH_MESSAGE);
+    }
+
+    /// 测试尽力恢复时，完整性校验失败的载荷照常返回全部字节并被标记为损坏
+    #[test]
+    fn test_best_effort_returns_payload_failing_checksum() {
+        let payload = b"Every byte decodes, but one of them is wrong.";
+        let mut pixels = vec![0u8; 1024];
+        hide_bytes(&mut pixels, payload).expect("Failed to hide payload.");
+        let header = read_header(&pixels).expect("Failed to read header.");
+        pixels[header.payload_offset()] ^= 0b1;
+
+        let options = RecoverOptions {
+            best_effort: true,
+            ..Default::default()
+        };
+        let (_, recovered, stats) =
+            recover_payload_with_stats(&pixels, &options).expect("Failed to recover payload.");
+        assert_eq!(recovered.len(), payload.len());
+        assert_eq!(recovered[1..], payload[1..]);
+        assert_ne!(recovered[0], payload[0]);
+        assert_eq!(stats.damaged, Some(payload.len() as u64));
+
+        // 未损坏的载荷不会被标记
+        hide_bytes(&mut pixels, payload).expect("Failed to hide payload.");
+        let (_, _, stats) =
+            recover_payload_with_stats(&pixels, &options).expect("Failed to recover payload.");
+        assert_eq!(stats.damaged, None);
+    }
+
+    /// 测试分块流式隐藏与一次性隐藏得到逐字节相同的像素，包括打散与冗余写入
+    #[test]
+    fn test_hide_stream_matches_hide_bytes() {
+        let mut payload = vec![0u8; 1000];
+        rand::rng().fill_bytes(&mut payload);
+        let options = HideOptions {
+            scatter_seed: Some(scatter::seed_from_bytes(b"stream")),
+            redundancy: 3,
+            ..Default::default()
+        };
+        let mut cover = vec![0u8; 16 * 1024];
+        rand::rng().fill_bytes(&mut cover);
+
+        let mut expected = cover.clone();
+        let expected_stats =
+            hide_bytes_with(&mut expected, &payload, &options).expect("Failed to hide payload.");
+
+        // 块大小不整除载荷长度，最后一块只有部分数据
+        let summary = scan_stream(&mut payload.as_slice(), 64).expect("Failed to scan payload.");
+        assert_eq!(summary.len, payload.len() as u64);
+        assert_eq!(summary.checksum, crc32fast::hash(&payload));
+        let mut streamed = cover.clone();
+        let stats = hide_stream_with_progress(
+            &mut streamed,
+            &mut payload.as_slice(),
+            &summary,
+            &options,
+            64,
+            &|_| {},
+        )
+        .expect("Failed to stream payload.");
+        assert_eq!(stats, expected_stats);
+        assert_eq!(streamed, expected);
+
+        // 两次读取之间载荷被修改时拒绝写出错误的校验和
+        let mut changed = payload.clone();
+        changed[500] ^= 1;
+        let err = hide_stream_with_progress(
+            &mut cover,
+            &mut changed.as_slice(),
+            &summary,
+            &options,
+            64,
+            &|_| {},
+        )
+        .unwrap_err();
+        assert_eq!(err.to_string(), STREAM_CHANGED_MESSAGE);
+    }
+
+    /// 测试扫描时跨越块边界的多字节字符仍被识别为有效的 UTF-8
+    #[test]
+    fn test_scan_stream_utf8_across_chunks() {
+        let text = "分块读取的文本".as_bytes();
+        for chunk_size in 1..8 {
+            let summary = scan_stream(&mut &text[..], chunk_size).expect("Failed to scan text.");
+            assert!(summary.utf8, "chunk size {}", chunk_size);
+        }
+        assert!(!scan_stream(&mut &text[..text.len() - 1], 4).unwrap().utf8);
+        assert!(!scan_stream(&mut &[b'a', 0xFF, b'b'][..], 2).unwrap().utf8);
+    }
+
+    /// 测试偶数或过大的冗余份数会被拒绝，且容量按份数等比例减少
+    #[test]
+    fn test_redundancy_limits() {
+        for redundancy in [0, 2, MAX_REDUNDANCY + 2] {
+            let err = hide_bytes_with(
+                &mut [0u8; 1024],
+                b"x",
+                &HideOptions {
+                    redundancy,
+                    ..Default::default()
+                },
+            )
+            .unwrap_err();
+            assert!(err.to_string().contains("Unsupported redundancy"));
+        }
+
+        let single = capacity_with_metadata(1024, 0, None, 1, Channels::ALL, 4, 1, DEFAULT_BITS);
+        let triple = capacity_with_metadata(1024, 1, None, 3, Channels::ALL, 4, 1, DEFAULT_BITS);
+        assert!(triple <= single / 3);
+        let mut pixels = vec![0u8; 1024];
+        let options = HideOptions {
+            redundancy: 3,
+            ..Default::default()
+        };
+        hide_bytes_with(&mut pixels, &vec![0u8; triple], &options)
+            .expect("Failed to hide payload.");
+        assert!(hide_bytes_with(&mut pixels, &vec![0u8; triple + 1], &options).is_err());
+    }
+
+    /// 测试超出定长长度字段范围的载荷与不支持的位数都会被拒绝
+    #[test]
+    fn test_fixed_length_field_limits() {
+        let mut pixels = vec![0u8; 1024];
+        let too_large = vec![0u8; 1 << 16];
+        let err = hide_bytes_with(
+            &mut pixels,
+            &too_large,
+            &HideOptions {
+                length_bits: Some(16),
+                ..Default::default()
+            },
+        )
+        .unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("too large for a 16-bit length field")
+        );
+
+        let err = hide_bytes_with(
+            &mut pixels,
+            b"x",
+            &HideOptions {
+                length_bits: Some(24),
+                ..Default::default()
+            },
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("Unsupported length field size"));
+    }
+
+    /// 测试来源信息只在提供时写入头部，且时间戳与版本可以完整往返
+    #[test]
+    fn test_provenance_round_trip() {
+        let provenance = Provenance::now();
+        let options = HideOptions {
+            name: "a.txt".to_string(),
+            provenance: Some(provenance.clone()),
+            ..Default::default()
+        };
+        let mut pixels = vec![0u8; 1024];
+        hide_bytes_with(&mut pixels, b"provenance", &options).expect("Failed to hide payload.");
+
+        let (header, payload) = recover_payload(&pixels, &RecoverOptions::default())
+            .expect("Failed to recover payload.");
+        assert_ne!(header.flags & FLAG_PROVENANCE, 0);
+        assert_eq!(header.provenance, Some(provenance.clone()));
+        assert_eq!(
+            header.payload_offset(),
+            header_len(10, "a.txt".len() + provenance.encoded_len())
+        );
+        assert_eq!(payload, b"provenance");
+
+        // 未提供来源信息时不设置标志，也不占用容量
+        hide_bytes(&mut pixels, b"provenance").expect("Failed to hide payload.");
+        let header = read_header(&pixels).expect("Failed to read header.");
+        assert_eq!(header.flags & FLAG_PROVENANCE, 0);
+        assert_eq!(header.provenance, None);
+    }
+
+    /// 测试写入位平面 2 时，头部之后每个像素字节的最低两位保持不变，且恢复时自动识别位平面
+    #[test]
+    fn test_hide_into_higher_plane() {
+        let mut pixels = vec![0u8; 1024];
+        rand::rng().fill_bytes(&mut pixels);
+        let original = pixels.clone();
+        let payload = b"Hidden above the lowest bits.";
+
+        let options = HideOptions {
+            plane: 2,
+            ..Default::default()
+        };
+        hide_bytes_with(&mut pixels, payload, &options).expect("Failed to hide payload.");
+
+        let payload_start = header_len(payload.len() as u64, 0);
+        assert!(
+            (payload_start..pixels.len()).all(|i| pixels[i] & LSB_MASK == original[i] & LSB_MASK),
+            "The lowest bits must be untouched outside the header."
+        );
+        assert!(
+            (payload_start..pixels.len()).all(|i| pixels[i] & 0xF0 == original[i] & 0xF0),
+            "Bits above the selected window must be untouched."
+        );
+
+        let (header, recovered) = recover_payload(&pixels, &RecoverOptions::default())
+            .expect("Failed to recover payload.");
+        assert_eq!(header.plane, 2);
+        assert_eq!(recovered, payload);
+
+        // 数据窗口超出一个字节时拒绝隐藏
+        let overflowing = HideOptions {
+            bits: 4,
+            plane: 5,
+            ..Default::default()
+        };
+        let err = hide_bytes_with(&mut pixels, payload, &overflowing).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<StegError>(),
+            Some(&StegError::UnsupportedPlane { plane: 5, bits: 4 })
+        );
+    }
+
+    /// 测试相同的输入两次隐藏得到完全相同的像素缓冲区，打散模式也不例外
+    #[test]
+    fn test_hide_is_deterministic() {
+        let mut cover = vec![0u8; 2048];
+        rand::rng().fill_bytes(&mut cover);
+        let options = HideOptions {
+            bits: 3,
+            scatter_seed: Some(scatter::seed_from_bytes(b"password")),
+            name: "notes.txt".to_string(),
+            ..Default::default()
+        };
+
+        let mut first = cover.clone();
+        let mut second = cover;
+        hide_bytes_with(&mut first, b"deterministic", &options).expect("Failed to hide payload.");
+        hide_bytes_with(&mut second, b"deterministic", &options).expect("Failed to hide payload.");
+        assert_eq!(first, second);
+    }
+
+    /// 测试打散模式：正确种子可以恢复，错误种子或缺少种子则无法恢复
+    #[test]
+    fn test_scattered_hide_and_recover() {
+        let mut pixels = vec![0u8; 4096];
+        rand::rng().fill_bytes(&mut pixels);
+        let original = pixels.clone();
+        let payload = b"Scattered across the whole image.";
+
+        let seed = scatter::seed_from_bytes(b"right password");
+        let options = HideOptions {
+            scatter_seed: Some(seed),
+            ..Default::default()
+        };
+        hide_bytes_with(&mut pixels, payload, &options).expect("Failed to hide scattered payload.");
+
+        // 载荷不应集中在头部之后的连续区域内
+        let sequential_end = HEADER_BYTES + payload.len() * BYTES_PER_CHAR;
+        let changed_outside = (sequential_end..pixels.len())
+            .filter(|&i| pixels[i] != original[i])
+            .count();
+        assert!(
+            changed_outside > 0,
+            "Scattered payload should reach beyond the sequential region."
+        );
+
+        let header = read_header(&pixels).expect("Failed to read header.");
+        assert!(header.is_scattered());
+
+        // 正确的种子
+        let right = RecoverOptions {
+            scatter_seed: Some(seed),
+            ..Default::default()
+        };
+        let (_, recovered) = recover_payload(&pixels, &right).expect("Failed to recover payload.");
+        assert_eq!(recovered, payload);
+
+        // 错误的种子读到的是错误的数据，会被校验和拒绝
+        let wrong = RecoverOptions {
+            scatter_seed: Some(scatter::seed_from_bytes(b"wrong password")),
+            ..Default::default()
+        };
+        let err = recover_payload(&pixels, &wrong).expect_err("Wrong seed should be detected.");
+        assert_eq!(err.to_string(), CHECKSUM_MISMATCH_MESSAGE);
+
+        // 缺少种子时应明确报错
+        assert!(recover_bytes(&pixels).is_err());
+    }
+
+    /// 测试翻转载荷中的一个最低有效位后，恢复会报告数据损坏而不是返回错误的数据
+    #[test]
+    fn test_checksum_detects_flipped_bit() {
+        let mut pixels = vec![0u8; 1024];
+        rand::rng().fill_bytes(&mut pixels);
+        hide_bytes(&mut pixels, b"integrity matters").expect("Failed to hide payload.");
+
+        pixels[HEADER_BYTES + 5] ^= 0b1;
+
+        let err = recover_bytes(&pixels).expect_err("Corruption should be detected.");
+        assert_eq!(err.to_string(), CHECKSUM_MISMATCH_MESSAGE);
+    }
+
+    /// 测试缓冲区恰好只容纳头部时，头部记录的载荷长度在提取之前就被拒绝
+    #[test]
+    fn test_length_beyond_tiny_buffer_is_rejected() {
+        let mut pixels = vec![0u8; 1024];
+        hide_bytes(&mut pixels, b"short").expect("Failed to hide payload.");
+        let header_end = header_len(5, 0);
+        let tiny = &pixels[..header_end];
+
+        let header = read_header(tiny).expect("The header should still be readable.");
+        let expected = StegError::LengthExceedsCapacity {
+            length: 5,
+            capacity: 0,
+        };
+        assert_eq!(
+            ensure_length_fits(&header, tiny.len(), 4),
+            Err(expected.clone())
+        );
+        let err = recover_bytes(tiny).expect_err("Missing payload bytes should be rejected.");
+        assert_eq!(err.downcast_ref::<StegError>(), Some(&expected));
+
+        // 只差一个字符的空间时同样拒绝，容量足够时通过
+        let almost = &pixels[..header_end + BYTES_PER_CHAR * 5 - 1];
+        assert!(ensure_length_fits(&header, almost.len(), 4).is_err());
+        let exact = &pixels[..header_end + BYTES_PER_CHAR * 5];
+        assert_eq!(ensure_length_fits(&header, exact.len(), 4), Ok(5));
+        assert_eq!(recover_bytes(exact).unwrap(), b"short");
+    }
+
+    /// 测试头部记录了远超图像容量的载荷长度时，恢复在提取之前返回有界的错误
+    #[test]
+    fn test_absurd_length_is_rejected() {
+        let mut pixels = vec![0u8; 1024];
+        rand::rng().fill_bytes(&mut pixels);
+        hide_bytes(&mut pixels, b"short").expect("Failed to hide payload.");
+
+        // 将长度字段改写为 u64::MAX，其编码占用 10 个字节
+        let absurd = varint::encode(u64::MAX);
+        modify(
+            absurd.len() as u64,
+            &mut pixels,
+            LENGTH_OFFSET,
+            LENGTH_PREFIX_HIDING_BYTES,
+            DEFAULT_BITS,
+        )
+        .unwrap();
+        write_bytes(
+            &mut pixels,
+            LENGTH_OFFSET + LENGTH_PREFIX_HIDING_BYTES,
+            &absurd,
+            DEFAULT_BITS,
+        )
+        .unwrap();
+        write_bytes(
+            &mut pixels,
+            length_field_end(absurd.len()),
+            &[0],
+            DEFAULT_BITS,
+        )
+        .unwrap();
+
+        let err = recover_bytes(&pixels).expect_err("Absurd length should be rejected.");
+        let capacity = (pixels.len() - header_len(u64::MAX, 0)) / BYTES_PER_CHAR;
+        assert_eq!(
+            err.downcast_ref::<StegError>(),
+            Some(&StegError::LengthExceedsCapacity {
+                length: u64::MAX,
+                capacity,
+            })
+        );
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "Recovered length {} exceeds image capacity {}.",
+                u64::MAX,
+                capacity
+            )
+        );
+    }
+
+    /// 测试只使用部分通道时，其余通道的像素字节保持不变且载荷可以恢复
+    #[test]
+    fn test_hide_and_recover_selected_channels() {
+        let mut pixels = vec![0u8; 3 * 200];
+        rand::rng().fill_bytes(&mut pixels);
+        let original = pixels.clone();
+
+        let options = HideOptions {
+            channels: "b".parse().unwrap(),
+            stride: 3,
+            ..Default::default()
+        };
+        hide_bytes_with(&mut pixels, b"blue only", &options).expect("Failed to hide payload.");
+
+        assert!(
+            (HEADER_BYTES..pixels.len())
+                .filter(|i| i % 3 != 2)
+                .all(|i| pixels[i] == original[i]),
+            "Unselected channels should be untouched."
+        );
+
+        let recover_options = RecoverOptions {
+            stride: 3,
+            ..Default::default()
+        };
+        let (header, payload) =
+            recover_payload(&pixels, &recover_options).expect("Failed to recover payload.");
+        assert_eq!(header.channels.to_string(), "b");
+        assert_eq!(payload, b"blue only");
+
+        // RGB 图像中不存在 alpha 通道
+        let alpha_only = HideOptions {
+            channels: "a".parse().unwrap(),
+            stride: 3,
+            ..Default::default()
+        };
+        assert!(hide_bytes_with(&mut pixels, b"x", &alpha_only).is_err());
+    }
+
+    /// 测试 modify_copy 不改动输入，且结果与原地修改的 modify 完全相同
+    #[test]
+    fn test_modify_copy_matches_modify() {
+        // 低位全为 0，隐藏非零值后结果必然不同
+        let original: Vec<u8> = (0..64u8).map(|i| i << 4).collect();
+
+        for bits in 1..=MAX_BITS {
+            let before = original.clone();
+            let copy = modify_copy(0xDEAD_BEEF, &original, 3, 32 / bits as usize, bits)
+                .expect("Failed to modify a copy.");
+            assert_eq!(original, before);
+
+            let mut in_place = original.clone();
+            modify(0xDEAD_BEEF, &mut in_place, 3, 32 / bits as usize, bits)
+                .expect("Failed to modify in place.");
+            assert_eq!(copy, in_place);
+            assert_ne!(copy, original);
+        }
+
+        assert!(matches!(
+            modify_copy(1, &original, 60, 8, DEFAULT_BITS),
+            Err(StegError::OutOfBounds { .. })
+        ));
+    }
+
+    /// 测试不同位深度与位平面的 `Codec` 只读写各自的数据窗口，并拒绝无效的组合
+    #[test]
+    fn test_codec_configurations() {
+        assert_eq!(Codec::default(), Codec::new(DEFAULT_BITS, 0).unwrap());
+
+        for (bits, plane) in [(1, 0), (2, 0), (3, 2), (4, 4), (1, 7), (2, 6)] {
+            let codec = Codec::new(bits, plane).unwrap();
+            let window = lsb_mask(bits) << plane;
+            for byte in [0x00u8, 0x5A, 0xA5, 0xFF] {
+                for value in 0..=lsb_mask(bits) {
+                    let embedded = codec.embed(byte, value);
+                    assert_eq!(codec.extract(embedded), value);
+                    assert_eq!(embedded & !window, byte & !window);
+                }
+            }
+            assert_eq!(codec.bytes_per_char(), bytes_per_char(bits));
+        }
+
+        // 超出窗口的高位会被忽略
+        assert_eq!(Codec::new(2, 3).unwrap().embed(0, 0xFF), 0b0001_1000);
+
+        assert_eq!(Codec::new(0, 0), Err(StegError::UnsupportedBits(0)));
+        assert_eq!(
+            Codec::new(4, 5),
+            Err(StegError::UnsupportedPlane { plane: 5, bits: 4 })
+        );
+    }
+
+    /// 测试 modify_with/recover_with 在非默认编码器下往返，且与 modify_at/recover_at 的结果一致
+    #[test]
+    fn test_modify_with_and_recover_with() {
+        let codec = Codec::new(3, 1).unwrap();
+        let positions = [4, 0, 2];
+        let mut with_codec = vec![0x81u8; 6];
+        let mut with_args = with_codec.clone();
+
+        modify_with(&codec, 0b101_110_011, &mut with_codec, &positions).unwrap();
+        modify_at(0b101_110_011, &mut with_args, &positions, 3, 1).unwrap();
+        assert_eq!(with_codec, with_args);
+        assert_eq!(
+            recover_with(&codec, &with_codec, &positions).unwrap(),
+            0b101_110_011
+        );
+        assert_eq!(
+            recover_with(&codec, &with_codec, &positions).unwrap(),
+            recover_at(&with_codec, &positions, 3, 1).unwrap()
+        );
+    }
+
+    /// 测试不一致的掩码被拒绝，非默认的有效掩码能够往返，且与等价的位深度与位平面结果一致
+    #[test]
+    fn test_mask_config() {
+        assert_eq!(MaskConfig::default().codec().unwrap(), Codec::default());
+
+        for (lsb_mask, data_mask) in [
+            (0b0000_0011, 0b1111_1000),
+            (0b0000_0011, 0b1111_1110),
+            (0b0000_0101, 0b1111_1010),
+            (0, u8::MAX),
+        ] {
+            assert_eq!(
+                MaskConfig::new(lsb_mask, data_mask),
+                Err(StegError::InvalidMask {
+                    lsb_mask,
+                    data_mask
+                })
+            );
+        }
+        assert_eq!(
+            MaskConfig::new(0b0011_1110, 0b1100_0001),
+            Err(StegError::UnsupportedBits(5))
+        );
+
+        let mask = MaskConfig::new(0b0001_1100, 0b1110_0011).unwrap();
+        let positions = [3, 1, 0];
+        let mut pixels = vec![0xA5u8; 4];
+        modify_with_mask(&mask, 0b101_010_111, &mut pixels, &positions).unwrap();
+        assert!(
+            pixels
+                .iter()
+                .all(|&byte| byte & 0b1110_0011 == 0xA5 & 0b1110_0011)
+        );
+        assert_eq!(pixels[2], 0xA5);
+        assert_eq!(
+            recover_with_mask(&mask, &pixels, &positions).unwrap(),
+            0b101_010_111
+        );
+        assert_eq!(
+            recover_at(&pixels, &positions, 3, 2).unwrap(),
+            0b101_010_111
+        );
+
+        let invalid = MaskConfig {
+            lsb_mask: 0b0000_0001,
+            data_mask: 0b1111_1111,
+        };
+        assert!(modify_with_mask(&invalid, 1, &mut pixels, &positions).is_err());
+        assert!(recover_with_mask(&invalid, &pixels, &positions).is_err());
+    }
+
+    /// 测试大端序编码器先写入最高的一组位，且与小端序互为逆序
+    #[test]
+    fn test_big_endian_codec_order() {
+        let codec = Codec::new(3, 0).unwrap().with_endian(Endian::Big);
+        assert_eq!(codec.endian(), Endian::Big);
+        let positions = [0, 1, 2];
+        let mut big = vec![0u8; 3];
+        modify_with(&codec, 0b101_110_011, &mut big, &positions).unwrap();
+        assert_eq!(big, [0b101, 0b110, 0b011]);
+        assert_eq!(
+            recover_with(&codec, &big, &positions).unwrap(),
+            0b101_110_011
+        );
+
+        let little = Codec::new(3, 0).unwrap();
+        assert_eq!(
+            recover_with(&little, &big, &positions).unwrap(),
+            0b011_110_101
+        );
+    }
+
+    /// 测试 find_magic 在扫描范围内找到错位的魔数，超出范围则放弃
+    #[test]
+    fn test_find_magic_within_bound() {
+        let mut pixels = vec![0u8; 512];
+        hide_bytes(&mut pixels[5..], b"shifted").expect("Failed to hide payload.");
+
+        assert!(!has_magic(&pixels));
+        assert_eq!(find_magic(&pixels, 0), None);
+        assert_eq!(find_magic(&pixels, 4), None);
+        assert_eq!(find_magic(&pixels, 5), Some(5));
+        assert_eq!(find_magic(&pixels, usize::MAX), Some(5));
+        assert_eq!(find_magic(&pixels[5..], 100), Some(0));
+    }
+
+    /// 测试 modify_at/recover_at 在任意位置上往返，并拒绝越界位置
+    #[test]
+    fn test_modify_at_and_recover_at() {
+        let mut pixels = vec![0u8; 16];
+        let positions = [15, 3, 9, 0];
+        modify_at(0xA7, &mut pixels, &positions, DEFAULT_BITS, 0).expect("Failed to modify.");
+        assert_eq!(
+            recover_at(&pixels, &positions, DEFAULT_BITS, 0).unwrap(),
+            0xA7
+        );
+
+        let untouched: Vec<usize> = (0..16).filter(|i| !positions.contains(i)).collect();
+        assert!(untouched.iter().all(|&i| pixels[i] == 0));
+
+        let before = pixels.clone();
+        assert_eq!(
+            modify_at(1, &mut pixels, &[1, 16], DEFAULT_BITS, 0),
+            Err(StegError::OutOfBounds {
+                start: 16,
+                end: 17,
+                len: 16
+            })
+        );
+        assert_eq!(
+            pixels, before,
+            "Out-of-bounds positions must not modify anything."
+        );
+        assert!(matches!(
+            recover_at(&pixels, &[16], DEFAULT_BITS, 0),
+            Err(StegError::OutOfBounds { start: 16, .. })
+        ));
+    }
+
+    /// 测试位置为 `usize::MAX` 时返回越界错误，而不是在计算区域结尾时溢出
+    #[test]
+    fn test_recover_at_max_position() {
+        assert_eq!(
+            recover_at(&[0; 4], &[usize::MAX], 1, 0),
+            Err(StegError::OutOfBounds {
+                start: usize::MAX,
+                end: usize::MAX,
+                len: 4
+            })
+        );
     }
 }
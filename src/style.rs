@@ -0,0 +1,82 @@
+//! # 终端样式模块
+//!
+//! 为提示信息中的路径、标签与数值着色，供各处理函数统一使用
+//! 着色只在启用 `color` 特性 (默认启用) 时编译；关闭该特性后各函数原样返回文本，输出中不含任何 ANSI 控制符
+
+/// 着色的语义，决定文本使用的颜色
+#[derive(Debug, Clone, Copy)]
+enum Tone {
+    /// 错误信息中需要突出的内容，红色
+    Error,
+
+    /// 警告及需要用户留意的内容，黄色
+    Warning,
+
+    /// 成功完成时的输出位置等内容，绿色
+    Success,
+}
+
+/// 以红色粗体突出错误信息中的内容 (例如无法处理的文件路径)
+pub fn error(text: impl AsRef<str>) -> String {
+    paint(text.as_ref(), Tone::Error)
+}
+
+/// 以黄色粗体突出警告信息中的内容
+pub fn warning(text: impl AsRef<str>) -> String {
+    paint(text.as_ref(), Tone::Warning)
+}
+
+/// 以绿色粗体突出成功信息中的内容
+pub fn success(text: impl AsRef<str>) -> String {
+    paint(text.as_ref(), Tone::Success)
+}
+
+/// 关闭之后的全部着色，例如 JSON 输出中不应包含终端控制符
+pub fn disable() {
+    #[cfg(feature = "color")]
+    colored::control::set_override(false);
+}
+
+/// 按 `tone` 为文本着色；是否真正输出控制符仍由 `colored` 根据终端与 `NO_COLOR` 等环境变量决定
+#[cfg(feature = "color")]
+fn paint(text: &str, tone: Tone) -> String {
+    use colored::{Color, Colorize};
+
+    let color = match tone {
+        Tone::Error => Color::Red,
+        Tone::Warning => Color::Yellow,
+        Tone::Success => Color::Green,
+    };
+    text.color(color).bold().to_string()
+}
+
+/// 未启用 `color` 特性，原样返回文本
+#[cfg(not(feature = "color"))]
+fn paint(text: &str, _tone: Tone) -> String {
+    text.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 测试启用 `color` 特性时，只有 `colored` 判断应当着色时才输出 ANSI 控制符
+    #[cfg(feature = "color")]
+    #[test]
+    fn test_paint_follows_colored_control() {
+        let colorize = colored::control::SHOULD_COLORIZE.should_colorize();
+        for text in [error("path"), warning("path"), success("path")] {
+            assert!(text.contains("path"));
+            assert_eq!(text.contains('\x1b'), colorize, "{:?}", text);
+        }
+    }
+
+    /// 测试未启用 `color` 特性时，各函数原样返回文本，不含任何 ANSI 控制符
+    #[cfg(not(feature = "color"))]
+    #[test]
+    fn test_plain_without_color_feature() {
+        for text in [error("path"), warning("path"), success("path")] {
+            assert_eq!(text, "path");
+        }
+    }
+}
@@ -0,0 +1,157 @@
+//! # 文本编码模块
+//!
+//! 记录非 UTF-8 文本载荷的原始编码 (`hide --input-encoding`)，恢复时按 `recover --output-encoding` 转换
+//! 编码以编号记录在头部中，编号即其在 [`ENCODINGS`] 中的下标加 1，0 表示未记录；已分配的编号不能改变
+
+use anyhow::Context;
+use encoding_rs::{
+    BIG5, EUC_JP, EUC_KR, Encoding, GB18030, GBK, IBM866, ISO_2022_JP, ISO_8859_2, ISO_8859_3,
+    ISO_8859_4, ISO_8859_5, ISO_8859_6, ISO_8859_7, ISO_8859_8, ISO_8859_8_I, ISO_8859_10,
+    ISO_8859_13, ISO_8859_14, ISO_8859_15, ISO_8859_16, KOI8_R, KOI8_U, MACINTOSH, SHIFT_JIS,
+    UTF_8, UTF_16BE, UTF_16LE, WINDOWS_874, WINDOWS_1250, WINDOWS_1251, WINDOWS_1252, WINDOWS_1253,
+    WINDOWS_1254, WINDOWS_1255, WINDOWS_1256, WINDOWS_1257, WINDOWS_1258, X_MAC_CYRILLIC,
+    X_USER_DEFINED,
+};
+
+/// 可以记录在头部中的文本编码，按编号排列
+///
+/// 包含 Encoding Standard 中除 `replacement` 之外的全部编码；Latin-1 (`latin1`、`iso-8859-1`) 按标准映射为 windows-1252
+pub const ENCODINGS: [&Encoding; 39] = [
+    UTF_8,
+    GBK,
+    GB18030,
+    BIG5,
+    SHIFT_JIS,
+    EUC_JP,
+    ISO_2022_JP,
+    EUC_KR,
+    WINDOWS_1252,
+    WINDOWS_1250,
+    WINDOWS_1251,
+    WINDOWS_1253,
+    WINDOWS_1254,
+    WINDOWS_1255,
+    WINDOWS_1256,
+    WINDOWS_1257,
+    WINDOWS_1258,
+    WINDOWS_874,
+    ISO_8859_2,
+    ISO_8859_3,
+    ISO_8859_4,
+    ISO_8859_5,
+    ISO_8859_6,
+    ISO_8859_7,
+    ISO_8859_8,
+    ISO_8859_8_I,
+    ISO_8859_10,
+    ISO_8859_13,
+    ISO_8859_14,
+    ISO_8859_15,
+    ISO_8859_16,
+    KOI8_R,
+    KOI8_U,
+    IBM866,
+    MACINTOSH,
+    X_MAC_CYRILLIC,
+    UTF_16LE,
+    UTF_16BE,
+    X_USER_DEFINED,
+];
+
+/// 返回记录在头部中的编码编号
+pub fn id(encoding: &'static Encoding) -> u8 {
+    ENCODINGS
+        .iter()
+        .position(|&known| known == encoding)
+        .map_or(0, |index| index as u8 + 1)
+}
+
+/// 根据头部中的编号还原文本编码，0 表示未记录
+///
+/// # Errors
+///
+/// 如果编号未知，将返回错误
+pub fn from_id(id: u8) -> anyhow::Result<Option<&'static Encoding>> {
+    match id {
+        0 => Ok(None),
+        id => ENCODINGS
+            .get(id as usize - 1)
+            .copied()
+            .map(Some)
+            .with_context(|| format!("Unknown text encoding id: {}.", id)),
+    }
+}
+
+/// 解析命令行中的编码名称 (例如 `gbk`、`latin1`、`shift_jis`)，供 clap 使用
+pub fn parse_label(label: &str) -> Result<&'static Encoding, String> {
+    Encoding::for_label(label.as_bytes())
+        .filter(|&encoding| id(encoding) != 0)
+        .ok_or_else(|| format!("unknown text encoding: {}", label))
+}
+
+/// 判断 `bytes` 是否为 `encoding` 编码的有效文本
+pub fn is_valid(bytes: &[u8], encoding: &'static Encoding) -> bool {
+    encoding
+        .decode_without_bom_handling_and_without_replacement(bytes)
+        .is_some()
+}
+
+/// 将 `from` 编码的文本 `bytes` 转换为 `to` 编码
+///
+/// 即使两者相同也会校验输入；UTF-16 无法作为输出编码，`to` 为 UTF-16 时与 encoding_rs 一样输出 UTF-8
+///
+/// # Errors
+///
+/// * 如果 `bytes` 不是有效的 `from` 编码文本，将返回错误
+/// * 如果文本中有 `to` 编码无法表示的字符，将返回错误
+pub fn transcode(
+    bytes: &[u8],
+    from: &'static Encoding,
+    to: &'static Encoding,
+) -> anyhow::Result<Vec<u8>> {
+    let text = from
+        .decode_without_bom_handling_and_without_replacement(bytes)
+        .with_context(|| format!("The recovered text is not valid {}.", from.name()))?;
+    let (encoded, _, unmappable) = to.encode(&text);
+    anyhow::ensure!(
+        !unmappable,
+        "The recovered text contains characters that {} cannot represent.",
+        to.output_encoding().name()
+    );
+    Ok(encoded.into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 测试编号与编码一一对应，未知的编号与名称被拒绝
+    #[test]
+    fn test_ids_and_labels() {
+        for (index, &encoding) in ENCODINGS.iter().enumerate() {
+            assert_eq!(from_id(id(encoding)).unwrap(), Some(encoding));
+            assert_eq!(id(encoding) as usize, index + 1);
+        }
+        assert_eq!(from_id(0).unwrap(), None);
+        assert!(from_id(ENCODINGS.len() as u8 + 1).is_err());
+        assert_eq!(parse_label("latin1"), Ok(WINDOWS_1252));
+        assert_eq!(parse_label("GB2312"), Ok(GBK));
+        assert!(parse_label("replacement").is_err());
+        assert!(parse_label("klingon").is_err());
+    }
+
+    /// 测试 GBK 与 UTF-8 之间往返转换，无效的输入与无法表示的字符被拒绝
+    #[test]
+    fn test_transcode() {
+        let gbk = [0xC4, 0xE3, 0xBA, 0xC3];
+        assert!(is_valid(&gbk, GBK));
+        assert!(!is_valid(&gbk, UTF_8));
+
+        let utf8 = transcode(&gbk, GBK, UTF_8).unwrap();
+        assert_eq!(utf8, "你好".as_bytes());
+        assert_eq!(transcode(&utf8, UTF_8, GBK).unwrap(), gbk);
+        assert!(transcode(&[0x81], GBK, UTF_8).is_err());
+        assert!(transcode(&[0xFF], UTF_8, UTF_8).is_err());
+        assert!(transcode(&utf8, UTF_8, WINDOWS_1252).is_err());
+    }
+}
@@ -0,0 +1,107 @@
+//! # 变长整数编码模块
+//!
+//! 使用 LEB128 (无符号) 编码载荷长度：每个字节的低 7 位存放数据，最高位表示后面是否还有字节
+//! 较短的载荷只需要 1 到 2 个字节即可记录长度，从而减少头部占用的像素字节
+
+/// 一个 `u64` 在 LEB128 编码下最多占用的字节数
+pub const MAX_VARINT_LEN: usize = 10;
+
+/// 计算 `value` 编码后占用的字节数
+pub fn encoded_len(value: u64) -> usize {
+    let significant_bits = (u64::BITS - value.leading_zeros()).max(1) as usize;
+    significant_bits.div_ceil(7)
+}
+
+/// 计算恰好用 `len` 个字节编码时可以表示的最大值
+pub fn max_value(len: usize) -> u64 {
+    match len {
+        0 => 0,
+        len if len * 7 >= u64::BITS as usize => u64::MAX,
+        len => (1u64 << (len * 7)) - 1,
+    }
+}
+
+/// 将 `value` 编码为 LEB128 字节序列
+pub fn encode(mut value: u64) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(encoded_len(value));
+    loop {
+        let byte = (value & 0x7F) as u8;
+        value >>= 7;
+        if value == 0 {
+            bytes.push(byte);
+            return bytes;
+        }
+        bytes.push(byte | 0x80);
+    }
+}
+
+/// 解码恰好由 `bytes` 组成的 LEB128 字节序列
+///
+/// # Errors
+///
+/// * 如果字节序列为空、在最后一个字节之前结束或之后仍有后续标志，将返回错误
+/// * 如果解码结果超出 `u64` 的范围，将返回错误
+pub fn decode(bytes: &[u8]) -> anyhow::Result<u64> {
+    anyhow::ensure!(
+        !bytes.is_empty() && bytes.len() <= MAX_VARINT_LEN,
+        "Invalid varint length: {} bytes.",
+        bytes.len()
+    );
+
+    let last = bytes.len() - 1;
+    bytes.iter().enumerate().try_fold(0u64, |acc, (i, &byte)| {
+        anyhow::ensure!(
+            (byte & 0x80 != 0) == (i != last),
+            "Malformed varint: unexpected continuation flag at byte {}.",
+            i
+        );
+        let part = (byte & 0x7F) as u64;
+        let shift = i * 7;
+        anyhow::ensure!(
+            shift < 63 || part >> (64 - shift) == 0,
+            "Varint overflows a u64 value."
+        );
+        Ok(acc | part << shift)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 测试跨越字节边界的值能够正确编码、解码，且占用的字节数符合预期
+    #[test]
+    fn test_encode_decode_boundaries() {
+        let cases = [
+            (0, 1),
+            (127, 1),
+            (128, 2),
+            (16383, 2),
+            (16384, 3),
+            (u32::MAX as u64, 5),
+            (u64::MAX, MAX_VARINT_LEN),
+        ];
+        for (value, len) in cases {
+            let bytes = encode(value);
+            assert_eq!(bytes.len(), len, "Unexpected length for {}", value);
+            assert_eq!(encoded_len(value), len);
+            assert!(value <= max_value(len));
+            assert_eq!(decode(&bytes).unwrap(), value);
+        }
+        assert_eq!(max_value(1), 127);
+        assert_eq!(max_value(2), 16383);
+    }
+
+    /// 测试格式错误的字节序列会被拒绝
+    #[test]
+    fn test_decode_rejects_malformed_input() {
+        assert!(decode(&[]).is_err());
+        assert!(decode(&[0x80]).is_err(), "Missing final byte");
+        assert!(decode(&[0x01, 0x01]).is_err(), "Continuation flag missing");
+        assert!(decode(&[0xFF; 11]).is_err(), "Too long");
+
+        let mut overflow = vec![0xFF; 9];
+        overflow.push(0x02);
+        assert!(decode(&overflow).is_err(), "Overflows u64");
+    }
+}
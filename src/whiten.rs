@@ -0,0 +1,43 @@
+//! # 载荷白化模块
+//!
+//! 供 `--xor-key` 使用：将载荷与由密钥派生的 ChaCha20 密钥流逐字节异或，使最低有效位中不再直接出现明文
+//! 白化只是轻量的混淆，不提供认证，也不能替代 `--password` 的加密；密钥错误时只会得到不同的字节，而不会报错
+
+use rand::{RngCore, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use sha2::{Digest, Sha256};
+
+/// 派生密钥流种子时使用的域分隔前缀，避免与打散种子等其他用途的密钥哈希相同
+const WHITEN_DOMAIN: &[u8] = b"lsb_hide xor whitening v1";
+
+/// 将 `data` 与由 `key` 派生的密钥流逐字节异或
+///
+/// 异或是对称的，对白化后的数据以相同的密钥再调用一次即可还原；相同的密钥总是得到相同的密钥流
+pub fn apply(data: &[u8], key: &str) -> Vec<u8> {
+    let mut hasher = Sha256::new();
+    hasher.update(WHITEN_DOMAIN);
+    hasher.update(key.as_bytes());
+    let mut keystream = vec![0u8; data.len()];
+    ChaCha20Rng::from_seed(hasher.finalize().into()).fill_bytes(&mut keystream);
+
+    data.iter()
+        .zip(keystream)
+        .map(|(byte, key_byte)| byte ^ key_byte)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 测试相同密钥可以还原数据，不同密钥得到不同的字节
+    #[test]
+    fn test_apply_round_trip() {
+        let data = b"whitened, not encrypted";
+        let whitened = apply(data, "key");
+        assert_ne!(&whitened[..], &data[..]);
+        assert_eq!(apply(&whitened, "key"), data);
+        assert_ne!(apply(&whitened, "other"), data);
+        assert!(apply(&[], "key").is_empty());
+    }
+}
@@ -1,19 +1,50 @@
 use anyhow::Ok;
-use image::{ImageBuffer, Rgba};
+use base64::prelude::*;
+use image::{Delay, Frame, GenericImageView, ImageBuffer, Rgba};
 use lsb_hide::{
-    cli::{HideArgs, RecoverArgs},
-    handler::{handle_hide, handle_recover},
+    animation::Animation,
+    cli::{
+        CapacityArgs, CleanArgs, CleanMode, DiffArgs, Encoding, HideArgs, OutputFormat,
+        RecoverArgs, VerifyArgs,
+    },
+    compression::Compression,
+    constants::{
+        BMP_HEADER_SIZE, BYTES_PER_CHAR, CHECKSUM_MISMATCH_MESSAGE, DEFAULT_BITS,
+        DEFAULT_DIFF_AMPLIFY, DELIMITER_END, DELIMITER_ESCAPE, EMPTY_PAYLOAD_MESSAGE,
+        EXIT_CORRUPTED, EXIT_FAILURE, EXIT_IO_ERROR, EXIT_NO_PAYLOAD, EXIT_NOT_ENOUGH_SPACE,
+        LENGTH_OFFSET, LENGTH_PREFIX_HIDING_BYTES, NO_PAYLOAD_MESSAGE, PROGRESS_ENV_VAR,
+        PROGRESS_THRESHOLD_BYTES, SPLIT_MANIFEST_NAME,
+    },
+    crypto::DECRYPTION_FAILED_MESSAGE,
+    error::{StegError, exit_code},
+    handler::{
+        handle_capacity, handle_clean, handle_diff, handle_hide, handle_recover, handle_verify,
+    },
+    sidecar::Sidecar,
+    steganography::{
+        HideOptions, PayloadType, RecoverOptions, has_magic, header_len, hide_bytes_with,
+        max_payload_bytes, modify, read_header, recover_bytes, recover_payload,
+    },
 };
-use rand::RngCore;
+use rand::{RngCore, SeedableRng};
+use rand_chacha::ChaCha20Rng;
 use std::fs;
+use std::io::Write;
 use std::path::Path;
+use std::process::{Command, Stdio};
+use std::time::{SystemTime, UNIX_EPOCH};
 use tempfile::tempdir;
 
-/// 一个辅助函数，用于创建一个带有随机像素的测试图像
+/// `hide` 子命令总是在头部中记录 1 个字节的类型标签，计算头部大小与容量时需要计入
+const TYPE_TAG_LEN: usize = 1;
+
+/// 一个辅助函数，用于创建一个带有伪随机像素的测试图像
+///
+/// 像素由固定种子生成，相同尺寸的测试图像总是完全相同，便于复现失败的测试
 fn create_test_image(path: &Path, width: u32, height: u32) {
     let mut img_buf = ImageBuffer::new(width, height);
     let mut raw_pixels = vec![0u8; (width * height * 4) as usize];
-    rand::rng().fill_bytes(&mut raw_pixels);
+    ChaCha20Rng::seed_from_u64(0x4C53_4248).fill_bytes(&mut raw_pixels);
 
     img_buf
         .pixels_mut()
@@ -41,10 +72,11 @@ fn test_handle_hide_and_recover_integration() -> anyhow::Result<()> {
 
     // 2. 测试 handle_hide
     let hide_args = HideArgs {
-        image: original_image_path.clone(),
-        text: source_text_path.clone(),
+        image: Some(original_image_path.clone()),
+        text: vec![source_text_path.clone()],
         dest: Some(hidden_image_path.clone()),
         force: false,
+        ..Default::default()
     };
     handle_hide(hide_args)?;
     assert!(
@@ -57,6 +89,7 @@ fn test_handle_hide_and_recover_integration() -> anyhow::Result<()> {
         image: hidden_image_path.clone(),
         text: Some(recovered_text_path.clone()),
         force: false,
+        ..Default::default()
     };
     handle_recover(recover_args)?;
     assert!(
@@ -88,10 +121,11 @@ fn test_handle_hide_and_recover_with_defaults() -> anyhow::Result<()> {
 
     // 2. 测试 handle_hide，不提供 dest 路径
     let hide_args = HideArgs {
-        image: original_image_path.clone(),
-        text: source_text_path.clone(),
+        image: Some(original_image_path.clone()),
+        text: vec![source_text_path.clone()],
         dest: None, // 关键：测试 None 的情况
         force: false,
+        ..Default::default()
     };
     handle_hide(hide_args)?;
 
@@ -108,6 +142,7 @@ fn test_handle_hide_and_recover_with_defaults() -> anyhow::Result<()> {
         image: expected_hidden_path, // 使用上一步生成的默认文件
         text: None,                  // 关键：测试 None 的情况
         force: false,
+        ..Default::default()
     };
     handle_recover(recover_args)?;
 
@@ -148,10 +183,11 @@ fn test_overwrite_protection_and_force_flag() -> anyhow::Result<()> {
 
     // 构建参数，不使用 --force
     let hide_args_no_force = HideArgs {
-        image: image_path.clone(),
-        text: text_path.clone(),
+        image: Some(image_path.clone()),
+        text: vec![text_path.clone()],
         dest: Some(dest_path.clone()),
         force: false,
+        ..Default::default()
     };
 
     // 执行并断言操作会失败
@@ -167,10 +203,11 @@ fn test_overwrite_protection_and_force_flag() -> anyhow::Result<()> {
     // 3. 场景二：测试强制覆盖
     // 构建参数，这次使用 --force
     let hide_args_with_force = HideArgs {
-        image: image_path.clone(),
-        text: text_path.clone(),
+        image: Some(image_path.clone()),
+        text: vec![text_path.clone()],
         dest: Some(dest_path.clone()),
         force: true,
+        ..Default::default()
     };
 
     // 执行并断言操作会成功
@@ -187,6 +224,43 @@ fn test_overwrite_protection_and_force_flag() -> anyhow::Result<()> {
     Ok(())
 }
 
+/// 验证标准输入不是终端时，即使通过管道提供 "y" 也不会询问，仍然拒绝覆盖已存在的文件
+#[test]
+fn test_overwrite_without_terminal_still_errors() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let image_path = dir.path().join("image.png");
+    let dest_path = dir.path().join("dest.png");
+
+    create_test_image(&image_path, 50, 50);
+    fs::write(&dest_path, "existing")?;
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_lsb_hide"))
+        .args([
+            "hide",
+            "-i",
+            image_path.to_str().unwrap(),
+            "-m",
+            "secret",
+            "-d",
+            dest_path.to_str().unwrap(),
+        ])
+        .env("NO_COLOR", "1")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    child.stdin.take().unwrap().write_all(b"y\n")?;
+    let output = child.wait_with_output()?;
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Output file already exists"), "{}", stderr);
+    assert!(!stderr.contains("overwrite? [y/N]"), "{}", stderr);
+    assert_eq!(fs::read(&dest_path)?, b"existing");
+
+    Ok(())
+}
+
 /// 验证空间不足时的错误处理
 #[test]
 fn test_handle_hide_not_enough_space() -> anyhow::Result<()> {
@@ -204,17 +278,4223 @@ fn test_handle_hide_not_enough_space() -> anyhow::Result<()> {
 
     // 2. 执行并断言错误
     let hide_args = HideArgs {
-        image: image_path,
-        text: text_path,
+        image: Some(image_path.clone()),
+        text: vec![text_path.clone()],
         dest: Some(dest_path),
         force: false,
+        ..Default::default()
     };
     let result = handle_hide(hide_args);
 
-    assert!(result.is_err());
-    if let Err(e) = result {
-        assert!(e.to_string().contains("Not enough space"));
+    // 所需与可用的像素字节数可以直接从错误中读取，而不必解析错误信息
+    let available = 10 * 10 * 4 - header_len(5000, "large.txt".len() + TYPE_TAG_LEN);
+    let e = result.expect_err("Hiding should fail without enough space.");
+    match e.downcast_ref::<StegError>() {
+        Some(&StegError::NotEnoughSpace {
+            required,
+            available: actual,
+        }) => {
+            assert_eq!(required, 5000 * BYTES_PER_CHAR);
+            assert_eq!(actual, available);
+            assert!(
+                e.to_string()
+                    .contains(&format!("Required: {}, Available: {}", required, actual))
+            );
+        }
+        other => panic!("Expected NotEnoughSpace, got {:?}", other),
+    }
+
+    // JSON 错误输出同样带有这两个字段
+    let output = Command::new(env!("CARGO_BIN_EXE_lsb_hide"))
+        .args([
+            "--json",
+            "hide",
+            "-i",
+            image_path.to_str().unwrap(),
+            "-t",
+            text_path.to_str().unwrap(),
+            "-d",
+            dir.path().join("dest.png").to_str().unwrap(),
+        ])
+        .output()?;
+    assert!(!output.status.success());
+    let error_json: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(error_json["status"], "error");
+    assert_eq!(error_json["required"], 5000 * BYTES_PER_CHAR);
+    assert_eq!(error_json["available"], available);
+
+    Ok(())
+}
+
+/// 验证空载荷默认只打印警告并可以恢复，`--strict` 下则拒绝隐藏
+#[test]
+fn test_handle_hide_empty_payload() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let image_path = dir.path().join("original.png");
+    let empty_path = dir.path().join("empty.txt");
+    let hidden_image_path = dir.path().join("hidden.png");
+    let recovered_text_path = dir.path().join("recovered.txt");
+
+    create_test_image(&image_path, 10, 10);
+    fs::write(&empty_path, "")?;
+
+    let err = handle_hide(HideArgs {
+        image: Some(image_path.clone()),
+        text: vec![empty_path.clone()],
+        dest: Some(hidden_image_path.clone()),
+        strict: true,
+        ..Default::default()
+    })
+    .expect_err("Hiding an empty payload under --strict should fail.");
+    assert!(err.to_string().contains(EMPTY_PAYLOAD_MESSAGE));
+    assert!(!hidden_image_path.exists());
+
+    let output = Command::new(env!("CARGO_BIN_EXE_lsb_hide"))
+        .args([
+            "hide",
+            "-i",
+            image_path.to_str().unwrap(),
+            "-t",
+            empty_path.to_str().unwrap(),
+            "-d",
+            hidden_image_path.to_str().unwrap(),
+        ])
+        .env("NO_COLOR", "1")
+        .output()?;
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains(EMPTY_PAYLOAD_MESSAGE));
+
+    handle_recover(RecoverArgs {
+        image: hidden_image_path,
+        text: Some(recovered_text_path.clone()),
+        ..Default::default()
+    })?;
+    assert!(fs::read(&recovered_text_path)?.is_empty());
+
+    Ok(())
+}
+
+/// 验证 1x1 的图像连头部都无法容纳时会被明确拒绝
+#[test]
+fn test_handle_hide_rejects_tiny_image() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let image_path = dir.path().join("tiny.png");
+    let text_path = dir.path().join("source.txt");
+
+    create_test_image(&image_path, 1, 1);
+    fs::write(&text_path, "x")?;
+
+    let err = handle_hide(HideArgs {
+        image: Some(image_path),
+        text: vec![text_path],
+        dest: Some(dir.path().join("dest.png")),
+        ..Default::default()
+    })
+    .expect_err("A 1x1 image cannot hold the header.");
+    assert!(matches!(
+        err.downcast_ref::<StegError>(),
+        Some(StegError::ImageTooSmall { len: 4, .. })
+    ));
+
+    Ok(())
+}
+
+/// 验证二进制模式下任意字节载荷能够逐字节无损地往返
+#[test]
+fn test_handle_hide_and_recover_binary_payload() -> anyhow::Result<()> {
+    // 1. 准备环境
+    let dir = tempdir()?;
+    let original_image_path = dir.path().join("original.png");
+    let hidden_image_path = dir.path().join("hidden.png");
+    let payload_path = dir.path().join("payload.bin");
+
+    create_test_image(&original_image_path, 100, 100);
+
+    // 生成一段随机二进制数据，其中几乎必然包含非 UTF-8 字节序列
+    let mut payload = vec![0u8; 512];
+    rand::rng().fill_bytes(&mut payload);
+    fs::write(&payload_path, &payload)?;
+
+    // 2. 以二进制模式隐藏
+    let hide_args = HideArgs {
+        image: Some(original_image_path),
+        text: vec![payload_path],
+        dest: Some(hidden_image_path.clone()),
+        binary: true,
+        ..Default::default()
+    };
+    handle_hide(hide_args)?;
+
+    // 3. 以二进制模式恢复，不提供输出路径
+    let recover_args = RecoverArgs {
+        image: hidden_image_path,
+        text: None,
+        binary: true,
+        ..Default::default()
+    };
+    handle_recover(recover_args)?;
+
+    // 默认输出文件应使用 .bin 扩展名
+    let expected_recovered_path = dir.path().join("recovered_hidden.bin");
+    assert!(
+        expected_recovered_path.exists(),
+        "Default binary output should be created at: {:?}",
+        expected_recovered_path
+    );
+
+    // 4. 验证结果
+    let recovered = fs::read(&expected_recovered_path)?;
+    assert_eq!(
+        payload, recovered,
+        "Recovered bytes must match the original."
+    );
+
+    Ok(())
+}
+
+/// 验证隐藏时记录的原始文件名会决定恢复时默认输出文件的扩展名
+#[test]
+fn test_recover_defaults_to_embedded_extension() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let original_image_path = dir.path().join("original.png");
+    let hidden_image_path = dir.path().join("hidden.png");
+    let payload_path = dir.path().join("report.pdf");
+
+    create_test_image(&original_image_path, 50, 50);
+    let payload = b"%PDF-1.7\n\x00\xFF binary body".to_vec();
+    fs::write(&payload_path, &payload)?;
+
+    handle_hide(HideArgs {
+        image: Some(original_image_path),
+        text: vec![payload_path],
+        dest: Some(hidden_image_path.clone()),
+        binary: true,
+        ..Default::default()
+    })?;
+
+    handle_recover(RecoverArgs {
+        image: hidden_image_path,
+        binary: true,
+        ..Default::default()
+    })?;
+
+    let expected_recovered_path = dir.path().join("recovered_hidden.pdf");
+    assert!(
+        expected_recovered_path.exists(),
+        "Default output should keep the embedded .pdf extension: {:?}",
+        expected_recovered_path
+    );
+    assert_eq!(fs::read(&expected_recovered_path)?, payload);
+
+    Ok(())
+}
+
+/// 验证 `--name-template` 决定省略 `--text` 时的默认输出文件名
+#[test]
+fn test_recover_with_name_template() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let original_image_path = dir.path().join("original.png");
+    let hidden_image_path = dir.path().join("holiday.png");
+
+    create_test_image(&original_image_path, 50, 50);
+    handle_hide(HideArgs {
+        image: Some(original_image_path),
+        message: Some("templated".to_string()),
+        dest: Some(hidden_image_path.clone()),
+        ..Default::default()
+    })?;
+
+    handle_recover(RecoverArgs {
+        image: hidden_image_path.clone(),
+        binary: true,
+        name_template: Some("secret-{stem}.{ext}".to_string()),
+        ..Default::default()
+    })?;
+    assert_eq!(
+        fs::read_to_string(dir.path().join("secret-holiday.bin"))?,
+        "templated"
+    );
+
+    // 不指定模板时保持原有的默认文件名
+    handle_recover(RecoverArgs {
+        image: hidden_image_path,
+        ..Default::default()
+    })?;
+    assert_eq!(
+        fs::read_to_string(dir.path().join("recovered_holiday.txt"))?,
+        "templated"
+    );
+
+    Ok(())
+}
+
+/// 验证没有启用 `clipboard` 特性时 `--clipboard` 会给出重新编译的提示，且不会写出默认的输出文件
+#[cfg(not(feature = "clipboard"))]
+#[test]
+fn test_recover_clipboard_requires_feature() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let original_image_path = dir.path().join("original.png");
+    let hidden_image_path = dir.path().join("hidden.png");
+
+    create_test_image(&original_image_path, 50, 50);
+    handle_hide(HideArgs {
+        image: Some(original_image_path),
+        message: Some("hunter2".to_string()),
+        dest: Some(hidden_image_path.clone()),
+        ..Default::default()
+    })?;
+
+    let err = handle_recover(RecoverArgs {
+        image: hidden_image_path,
+        clipboard: true,
+        ..Default::default()
+    })
+    .unwrap_err();
+    assert!(err.to_string().contains("--features clipboard"), "{}", err);
+    assert!(!dir.path().join("recovered_hidden.txt").exists());
+
+    Ok(())
+}
+
+/// 验证类型标签随头部往返：文本载荷标记为 text，非 UTF-8 载荷标记为 binary 并默认恢复为 `.bin`
+#[test]
+fn test_payload_type_tag_selects_defaults() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let original_image_path = dir.path().join("original.png");
+    let text_image_path = dir.path().join("text.png");
+    let binary_image_path = dir.path().join("blob.png");
+    let blob_path = dir.path().join("blob");
+    let blob = [0xff, 0x00, 0xfe, 0x80];
+
+    create_test_image(&original_image_path, 50, 50);
+    fs::write(&blob_path, blob)?;
+    handle_hide(HideArgs {
+        image: Some(original_image_path.clone()),
+        message: Some("plain words".to_string()),
+        dest: Some(text_image_path.clone()),
+        ..Default::default()
+    })?;
+    handle_hide(HideArgs {
+        image: Some(original_image_path),
+        text: vec![blob_path],
+        dest: Some(binary_image_path.clone()),
+        ..Default::default()
+    })?;
+
+    let info = |path: &Path| -> anyhow::Result<serde_json::Value> {
+        Ok(serde_json::from_str(&run_cli(&[
+            "--json",
+            "info",
+            "-i",
+            path.to_str().unwrap(),
+        ]))?)
+    };
+    assert_eq!(info(&text_image_path)?["payload_type"], "text");
+    assert_eq!(info(&binary_image_path)?["payload_type"], "binary");
+
+    // 不指定 `--binary`，二进制载荷也原样写出到 `.bin` 文件
+    handle_recover(RecoverArgs {
+        image: binary_image_path,
+        ..Default::default()
+    })?;
+    assert_eq!(fs::read(dir.path().join("recovered_blob.bin"))?, blob);
+
+    handle_recover(RecoverArgs {
+        image: text_image_path,
+        ..Default::default()
+    })?;
+    assert_eq!(
+        fs::read_to_string(dir.path().join("recovered_text.txt"))?,
+        "plain words"
+    );
+
+    Ok(())
+}
+
+/// 验证 verify 在载荷一致时成功，不一致时以非零状态码退出并给出差异摘要
+#[test]
+fn test_verify_matching_and_mismatching_payload() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let original_image_path = dir.path().join("original.png");
+    let hidden_image_path = dir.path().join("hidden.png");
+    let expected_path = dir.path().join("asset.txt");
+    let other_path = dir.path().join("other.txt");
+
+    create_test_image(&original_image_path, 50, 50);
+    fs::write(&expected_path, "embedded asset v1")?;
+    fs::write(&other_path, "embedded asset v2!")?;
+    handle_hide(HideArgs {
+        image: Some(original_image_path.clone()),
+        text: vec![expected_path.clone()],
+        dest: Some(hidden_image_path.clone()),
+        password: Some("ci".to_string()),
+        compress: Compression::Gzip,
+        ..Default::default()
+    })?;
+
+    handle_verify(VerifyArgs {
+        image: hidden_image_path.clone(),
+        text: expected_path.clone(),
+        password: Some("ci".to_string()),
+        ..Default::default()
+    })?;
+
+    let err = handle_verify(VerifyArgs {
+        image: hidden_image_path.clone(),
+        text: other_path.clone(),
+        password: Some("ci".to_string()),
+        ..Default::default()
+    })
+    .unwrap_err();
+    assert!(
+        err.to_string().ends_with(
+            "expected 18 bytes, recovered 17 bytes; first difference at byte 16, 2 bytes differ."
+        ),
+        "{}",
+        err
+    );
+
+    // 命令行以非零状态码报告不一致，且不会写出任何文件
+    let output = Command::new(env!("CARGO_BIN_EXE_lsb_hide"))
+        .args([
+            "verify",
+            "-i",
+            hidden_image_path.to_str().unwrap(),
+            "-t",
+            other_path.to_str().unwrap(),
+            "-p",
+            "ci",
+        ])
+        .env("NO_COLOR", "1")
+        .output()?;
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("does not match"));
+    assert_eq!(fs::read_dir(dir.path())?.count(), 4);
+
+    // 没有载荷的图像同样校验失败
+    let err = handle_verify(VerifyArgs {
+        image: original_image_path,
+        text: expected_path,
+        ..Default::default()
+    })
+    .unwrap_err();
+    assert_eq!(err.to_string(), NO_PAYLOAD_MESSAGE);
+
+    Ok(())
+}
+
+/// 验证 `--delimited` 以标记代替长度字段隐藏载荷，载荷中出现结束标记的字节时也能完整恢复
+#[test]
+fn test_hide_and_recover_delimited() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let original_image_path = dir.path().join("original.png");
+    let hidden_image_path = dir.path().join("hidden.png");
+    let source_path = dir.path().join("source.bin");
+    let recovered_path = dir.path().join("recovered.bin");
+
+    create_test_image(&original_image_path, 50, 50);
+    let payload = [b"data".as_slice(), &DELIMITER_END, &[DELIMITER_ESCAPE; 3]].concat();
+    fs::write(&source_path, &payload)?;
+
+    handle_hide(HideArgs {
+        image: Some(original_image_path.clone()),
+        text: vec![source_path],
+        dest: Some(hidden_image_path.clone()),
+        delimited: true,
+        ..Default::default()
+    })?;
+    handle_recover(RecoverArgs {
+        image: hidden_image_path.clone(),
+        text: Some(recovered_path.clone()),
+        ..Default::default()
+    })?;
+    assert_eq!(fs::read(&recovered_path)?, payload);
+
+    let info: serde_json::Value = serde_json::from_str(&run_cli(&[
+        "--json",
+        "info",
+        "-i",
+        hidden_image_path.to_str().unwrap(),
+    ]))?;
+    assert_eq!(info["delimited"], true);
+    assert!(info["payload_bytes"].is_null());
+
+    // 分隔模式与定长长度字段互斥
+    let output = Command::new(env!("CARGO_BIN_EXE_lsb_hide"))
+        .args([
+            "hide",
+            "-i",
+            original_image_path.to_str().unwrap(),
+            "-m",
+            "x",
+            "--delimited",
+            "--length-bits",
+            "32",
+        ])
+        .output()?;
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("cannot be used with"));
+
+    Ok(())
+}
+
+/// 验证 `--threads 1` 与 `--threads 4` 得到逐字节相同的输出，且跨越多个并行块的载荷能完整恢复
+#[test]
+fn test_threads_produce_identical_output() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let original_image_path = dir.path().join("original.png");
+    let source_path = dir.path().join("source.bin");
+
+    // 载荷超过一个进度块 (`PROGRESS_STEP_BYTES`)；不加密，因为加密使用随机的盐与 nonce
+    create_test_image(&original_image_path, 300, 300);
+    let mut payload = vec![0u8; 70_000];
+    ChaCha20Rng::seed_from_u64(7).fill_bytes(&mut payload);
+    fs::write(&source_path, &payload)?;
+
+    let mut outputs = Vec::new();
+    for threads in ["1", "4"] {
+        let hidden_image_path = dir.path().join(format!("hidden_{}.png", threads));
+        let recovered_path = dir.path().join(format!("recovered_{}.bin", threads));
+        run_cli(&[
+            "--threads",
+            threads,
+            "hide",
+            "-i",
+            original_image_path.to_str().unwrap(),
+            "-t",
+            source_path.to_str().unwrap(),
+            "-d",
+            hidden_image_path.to_str().unwrap(),
+        ]);
+        run_cli(&[
+            "--threads",
+            threads,
+            "recover",
+            "-i",
+            hidden_image_path.to_str().unwrap(),
+            "-t",
+            recovered_path.to_str().unwrap(),
+        ]);
+        assert_eq!(fs::read(&recovered_path)?, payload);
+        outputs.push(image::open(&hidden_image_path)?.into_rgba8().into_raw());
+    }
+    assert_eq!(outputs[0], outputs[1]);
+
+    Ok(())
+}
+
+/// 验证 `--preserve-metadata` 将原始 PNG 的 tEXt 数据块保留到输出图像中，且不影响恢复；未指定时数据块被丢弃
+#[test]
+fn test_hide_preserves_png_text_chunk() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let image_path = dir.path().join("tagged.png");
+    let hidden_image_path = dir.path().join("hidden.png");
+    let plain_image_path = dir.path().join("plain.png");
+
+    // 以 png 编码器写出带 tEXt 数据块的 RGBA 图像
+    let mut encoder = png::Encoder::new(fs::File::create(&image_path)?, 20, 20);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.add_text_chunk("Comment".to_string(), "Holiday photo".to_string())?;
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&[0x80u8; 20 * 20 * 4])?;
+    writer.finish()?;
+
+    let hide_args = |dest: &Path, preserve_metadata: bool| HideArgs {
+        image: Some(image_path.clone()),
+        message: Some("keep the comment".to_string()),
+        dest: Some(dest.to_path_buf()),
+        preserve_metadata,
+        ..Default::default()
+    };
+    handle_hide(hide_args(&hidden_image_path, true))?;
+    handle_hide(hide_args(&plain_image_path, false))?;
+
+    let text_chunks = |path: &Path| -> anyhow::Result<Vec<(String, String)>> {
+        let reader =
+            png::Decoder::new(std::io::BufReader::new(fs::File::open(path)?)).read_info()?;
+        Ok(reader
+            .info()
+            .uncompressed_latin1_text
+            .iter()
+            .map(|chunk| (chunk.keyword.clone(), chunk.text.clone()))
+            .collect())
+    };
+    assert_eq!(
+        text_chunks(&hidden_image_path)?,
+        [("Comment".to_string(), "Holiday photo".to_string())]
+    );
+    assert!(text_chunks(&plain_image_path)?.is_empty());
+
+    let recovered_path = dir.path().join("recovered.txt");
+    handle_recover(RecoverArgs {
+        image: hidden_image_path,
+        text: Some(recovered_path.clone()),
+        ..Default::default()
+    })?;
+    assert_eq!(fs::read_to_string(recovered_path)?, "keep the comment");
+
+    // 其他输出格式无法保留 PNG 数据块，直接报错
+    let err = handle_hide(hide_args(&dir.path().join("hidden.bmp"), true))
+        .expect_err("--preserve-metadata should require PNG output.");
+    assert!(err.to_string().contains("--preserve-metadata"));
+
+    Ok(())
+}
+
+/// 验证图像只容纳得下头部时，恢复在提取之前就以长度超出容量的错误失败，且不会写出任何文件
+#[test]
+fn test_recover_tiny_image_with_length_fails_fast() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let original_image_path = dir.path().join("original.png");
+    let hidden_image_path = dir.path().join("hidden.png");
+    let tiny_image_path = dir.path().join("tiny.png");
+    let source_text_path = dir.path().join("source.txt");
+    let recovered_text_path = dir.path().join("recovered.txt");
+
+    create_test_image(&original_image_path, 50, 50);
+    let original_text = "Only the header survives.";
+    fs::write(&source_text_path, original_text)?;
+    handle_hide(HideArgs {
+        image: Some(original_image_path),
+        text: vec![source_text_path],
+        dest: Some(hidden_image_path.clone()),
+        ..Default::default()
+    })?;
+
+    // 只保留头部所在的像素，载荷本身已不在图像中
+    let header_pixels = header_len(
+        original_text.len() as u64,
+        "source.txt".len() + TYPE_TAG_LEN,
+    )
+    .div_ceil(4);
+    let hidden = image::open(&hidden_image_path)?.into_rgba8();
+    ImageBuffer::<Rgba<u8>, _>::from_raw(
+        header_pixels as u32,
+        1,
+        hidden.as_raw()[..header_pixels * 4].to_vec(),
+    )
+    .unwrap()
+    .save(&tiny_image_path)?;
+
+    let err = handle_recover(RecoverArgs {
+        image: tiny_image_path,
+        text: Some(recovered_text_path.clone()),
+        ..Default::default()
+    })
+    .expect_err("Recovering from a header-only image should fail.");
+    assert!(matches!(
+        err.downcast_ref::<StegError>(),
+        Some(&StegError::LengthExceedsCapacity { length, .. }) if length == original_text.len() as u64
+    ));
+    assert!(!recovered_text_path.exists());
+
+    Ok(())
+}
+
+/// 测试 `--xor-key` 白化的载荷只能用相同的密钥还原，错误的密钥得到不同的字节
+#[test]
+fn test_xor_key_whitening_round_trip() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let original_image_path = dir.path().join("original.png");
+    let hidden_image_path = dir.path().join("hidden.png");
+    let source_text_path = dir.path().join("source.txt");
+    let recovered_text_path = dir.path().join("recovered.txt");
+
+    create_test_image(&original_image_path, 50, 50);
+    let original_text = "Whitened bytes do not show the plaintext.";
+    fs::write(&source_text_path, original_text)?;
+    handle_hide(HideArgs {
+        image: Some(original_image_path),
+        text: vec![source_text_path],
+        dest: Some(hidden_image_path.clone()),
+        xor_key: Some("key".to_string()),
+        ..Default::default()
+    })?;
+
+    // 图像中隐藏的是白化后的字节，类型标签不透露明文是文本
+    let hidden = image::open(&hidden_image_path)?.into_rgba8();
+    assert_eq!(
+        read_header(&hidden)?.payload_type,
+        Some(PayloadType::Whitened)
+    );
+    assert_ne!(recover_bytes(&hidden)?, original_text.as_bytes());
+
+    let recover = |xor_key: Option<&str>| {
+        handle_recover(RecoverArgs {
+            image: hidden_image_path.clone(),
+            text: Some(recovered_text_path.clone()),
+            force: true,
+            xor_key: xor_key.map(str::to_string),
+            ..Default::default()
+        })
+    };
+
+    recover(Some("key"))?;
+    assert_eq!(fs::read_to_string(&recovered_text_path)?, original_text);
+
+    recover(Some("wrong key"))?;
+    let recovered = fs::read(&recovered_text_path)?;
+    assert_eq!(recovered.len(), original_text.len());
+    assert_ne!(recovered, original_text.as_bytes());
+
+    let err = recover(None).expect_err("Recovering a whitened payload needs the key.");
+    assert!(err.to_string().contains("--xor-key"), "{}", err);
+
+    Ok(())
+}
+
+/// 测试 `--edge-adaptive` 往返恢复，并且图像中的纯色区域保持不变
+#[test]
+fn test_edge_adaptive_leaves_smooth_region_untouched() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let original_image_path = dir.path().join("original.png");
+    let hidden_image_path = dir.path().join("hidden.png");
+    let source_text_path = dir.path().join("source.txt");
+    let recovered_text_path = dir.path().join("recovered.txt");
+
+    // 上半部分为噪点，下半部分为纯色
+    create_test_image(&original_image_path, 64, 64);
+    let mut original = image::open(&original_image_path)?.into_rgba8();
+    for y in 32..64 {
+        for x in 0..64 {
+            original.put_pixel(x, y, Rgba([90, 140, 200, 255]));
+        }
     }
+    original.save(&original_image_path)?;
+
+    let original_text = "Hidden where the texture hides it.";
+    fs::write(&source_text_path, original_text)?;
+    handle_hide(HideArgs {
+        image: Some(original_image_path),
+        text: vec![source_text_path],
+        dest: Some(hidden_image_path.clone()),
+        edge_adaptive: true,
+        ..Default::default()
+    })?;
+
+    // 第一行纯色像素与噪点相邻，只比较其后的各行
+    let hidden = image::open(&hidden_image_path)?.into_rgba8();
+    let smooth_start = 33 * 64 * 4;
+    assert_eq!(
+        &hidden.as_raw()[smooth_start..],
+        &original.as_raw()[smooth_start..]
+    );
+
+    handle_recover(RecoverArgs {
+        image: hidden_image_path,
+        text: Some(recovered_text_path.clone()),
+        edge_adaptive: true,
+        ..Default::default()
+    })?;
+    assert_eq!(fs::read_to_string(&recovered_text_path)?, original_text);
+
+    Ok(())
+}
+
+/// 测试 `--append` 把新载荷接在已有载荷之后：先以 `--bits 1` 隐藏 "foo"，再追加 "bar"，恢复出 "foobar"
+#[test]
+fn test_hide_append_extends_payload() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let original_image_path = dir.path().join("original.png");
+    let hidden_image_path = dir.path().join("hidden.png");
+    let recovered_text_path = dir.path().join("recovered.txt");
+    create_test_image(&original_image_path, 20, 20);
+
+    run_cli(&[
+        "hide",
+        "-i",
+        original_image_path.to_str().unwrap(),
+        "-m",
+        "foo",
+        "-d",
+        hidden_image_path.to_str().unwrap(),
+        "--bits",
+        "1",
+    ]);
+    // 追加时沿用已有头部中的 1 位深度，不是自动提高了位深度
+    let append_output = run_cli(&[
+        "hide",
+        "-i",
+        hidden_image_path.to_str().unwrap(),
+        "-m",
+        "bar",
+        "--append",
+        "--in-place",
+        "--force",
+    ]);
+    assert!(!append_output.contains("the bit depth was raised"));
+
+    handle_recover(RecoverArgs {
+        image: hidden_image_path.clone(),
+        text: Some(recovered_text_path.clone()),
+        ..Default::default()
+    })?;
+    assert_eq!(fs::read_to_string(&recovered_text_path)?, "foobar");
+
+    // 没有载荷的图像无法追加
+    let err = handle_hide(HideArgs {
+        image: Some(original_image_path),
+        message: Some("bar".to_string()),
+        dest: Some(dir.path().join("appended.png")),
+        append: true,
+        ..Default::default()
+    })
+    .expect_err("Appending needs an existing payload.");
+    assert_eq!(
+        err.downcast_ref::<StegError>(),
+        Some(&StegError::MagicMismatch)
+    );
+
+    Ok(())
+}
+
+/// 测试 `--stride 3` 往返恢复，间隔记录在头部中，载荷区域中被跳过的像素字节保持不变
+#[test]
+fn test_hide_with_byte_stride() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let original_image_path = dir.path().join("original.png");
+    let hidden_image_path = dir.path().join("hidden.png");
+    let recovered_text_path = dir.path().join("recovered.txt");
+    create_test_image(&original_image_path, 32, 32);
+
+    run_cli(&[
+        "hide",
+        "-i",
+        original_image_path.to_str().unwrap(),
+        "-m",
+        "Spread thin.",
+        "-d",
+        hidden_image_path.to_str().unwrap(),
+        "--stride",
+        "3",
+    ]);
+    let info: serde_json::Value = serde_json::from_str(&run_cli(&[
+        "--json",
+        "info",
+        "-i",
+        hidden_image_path.to_str().unwrap(),
+    ]))?;
+    assert_eq!(info["stride"], 3);
+
+    let original = image::open(&original_image_path)?.into_rgba8();
+    let hidden = image::open(&hidden_image_path)?.into_rgba8();
+    let start = read_header(&hidden)?.payload_offset();
+    assert!(
+        (start..hidden.len())
+            .filter(|index| !(index - start).is_multiple_of(3))
+            .all(|index| hidden.as_raw()[index] == original.as_raw()[index])
+    );
+
+    handle_recover(RecoverArgs {
+        image: hidden_image_path,
+        text: Some(recovered_text_path.clone()),
+        ..Default::default()
+    })?;
+    assert_eq!(fs::read_to_string(&recovered_text_path)?, "Spread thin.");
+
+    Ok(())
+}
+
+/// 测试隐藏 GBK 编码的文本文件，默认恢复为 UTF-8，也可以转换回 GBK
+#[test]
+fn test_hide_gbk_text_and_recover_as_utf8() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let original_image_path = dir.path().join("original.png");
+    let hidden_image_path = dir.path().join("hidden.png");
+    let text_path = dir.path().join("gbk.txt");
+    let utf8_path = dir.path().join("recovered.txt");
+    let gbk_path = dir.path().join("recovered_gbk.txt");
+    create_test_image(&original_image_path, 32, 32);
+
+    let text = "你好，世界！";
+    let (gbk, _, _) = encoding_rs::GBK.encode(text);
+    fs::write(&text_path, &gbk)?;
+
+    // 不是所声明编码的文本时拒绝隐藏
+    assert!(
+        handle_hide(HideArgs {
+            image: Some(original_image_path.clone()),
+            text: vec![text_path.clone()],
+            dest: Some(hidden_image_path.clone()),
+            input_encoding: Some(encoding_rs::UTF_8),
+            ..Default::default()
+        })
+        .is_err()
+    );
+
+    run_cli(&[
+        "hide",
+        "-i",
+        original_image_path.to_str().unwrap(),
+        "-t",
+        text_path.to_str().unwrap(),
+        "-d",
+        hidden_image_path.to_str().unwrap(),
+        "--input-encoding",
+        "gbk",
+    ]);
+    let info: serde_json::Value = serde_json::from_str(&run_cli(&[
+        "--json",
+        "info",
+        "-i",
+        hidden_image_path.to_str().unwrap(),
+    ]))?;
+    assert_eq!(info["text_encoding"], "GBK");
+    assert_eq!(info["payload_type"], "text");
+    assert_eq!(info["payload_bytes"], gbk.len());
+
+    handle_recover(RecoverArgs {
+        image: hidden_image_path.clone(),
+        text: Some(utf8_path.clone()),
+        ..Default::default()
+    })?;
+    assert_eq!(fs::read_to_string(&utf8_path)?, text);
+
+    run_cli(&[
+        "recover",
+        "-i",
+        hidden_image_path.to_str().unwrap(),
+        "-t",
+        gbk_path.to_str().unwrap(),
+        "--output-encoding",
+        "gbk",
+    ]);
+    assert_eq!(fs::read(&gbk_path)?, gbk.as_ref());
+
+    Ok(())
+}
+
+/// 测试 `--endian be` 往返恢复，且不指定相同字节序时无法恢复
+#[test]
+fn test_big_endian_payload_needs_matching_endian() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let original_image_path = dir.path().join("original.png");
+    let hidden_image_path = dir.path().join("hidden.png");
+    let recovered_text_path = dir.path().join("recovered.txt");
+    create_test_image(&original_image_path, 32, 32);
+
+    run_cli(&[
+        "hide",
+        "-i",
+        original_image_path.to_str().unwrap(),
+        "-m",
+        "Most significant bits first.",
+        "-d",
+        hidden_image_path.to_str().unwrap(),
+        "--bits",
+        "4",
+        "--endian",
+        "be",
+    ]);
+    run_cli(&[
+        "recover",
+        "-i",
+        hidden_image_path.to_str().unwrap(),
+        "-t",
+        recovered_text_path.to_str().unwrap(),
+        "--endian",
+        "be",
+    ]);
+    assert_eq!(
+        fs::read_to_string(&recovered_text_path)?,
+        "Most significant bits first."
+    );
+
+    let result = handle_recover(RecoverArgs {
+        image: hidden_image_path,
+        text: Some(dir.path().join("little.txt")),
+        ..Default::default()
+    });
+    assert!(result.is_err());
+
+    Ok(())
+}
+
+/// 测试 `--length-backup` 在头部中的长度损坏后仍能按图像末尾的备份恢复
+#[test]
+fn test_length_backup_survives_corrupted_header_length() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let original_image_path = dir.path().join("original.png");
+    let hidden_image_path = dir.path().join("hidden.png");
+    let source_text_path = dir.path().join("source.txt");
+    let recovered_text_path = dir.path().join("recovered.txt");
+
+    create_test_image(&original_image_path, 50, 50);
+    let original_text = "The length also lives at the end of the image.";
+    fs::write(&source_text_path, original_text)?;
+    handle_hide(HideArgs {
+        image: Some(original_image_path),
+        text: vec![source_text_path],
+        dest: Some(hidden_image_path.clone()),
+        length_backup: true,
+        ..Default::default()
+    })?;
+
+    // 改写头部中记录长度的字节，模拟图像开头的损坏
+    let mut hidden = image::open(&hidden_image_path)?.into_rgba8();
+    modify(
+        original_text.len() as u64 + 20,
+        &mut hidden,
+        LENGTH_OFFSET + LENGTH_PREFIX_HIDING_BYTES,
+        BYTES_PER_CHAR,
+        DEFAULT_BITS,
+    )?;
+    hidden.save(&hidden_image_path)?;
+    assert_eq!(
+        read_header(&hidden)?.length,
+        original_text.len() as u64 + 20
+    );
+
+    handle_recover(RecoverArgs {
+        image: hidden_image_path,
+        text: Some(recovered_text_path.clone()),
+        ..Default::default()
+    })?;
+    assert_eq!(fs::read_to_string(&recovered_text_path)?, original_text);
+
+    Ok(())
+}
+
+/// 测试全零图像 (长度字段解码为 0) 报告没有载荷，而不是写出空文件
+#[test]
+fn test_recover_zeroed_image_reports_no_payload() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let image_path = dir.path().join("zeroed.png");
+    let recovered_path = dir.path().join("recovered.bin");
+    ImageBuffer::<Rgba<u8>, _>::new(16, 16).save(&image_path)?;
+
+    for no_header in [false, true] {
+        let err = handle_recover(RecoverArgs {
+            image: image_path.clone(),
+            text: Some(recovered_path.clone()),
+            no_header,
+            length: no_header.then_some(0),
+            ..Default::default()
+        })
+        .expect_err("A zeroed image should not yield an empty payload.");
+        assert_eq!(
+            err.downcast_ref::<StegError>(),
+            Some(&StegError::MagicMismatch),
+            "{:#}",
+            err
+        );
+        assert!(format!("{:#}", err).contains(NO_PAYLOAD_MESSAGE));
+        assert_eq!(exit_code(&err), EXIT_NO_PAYLOAD);
+        assert!(!recovered_path.exists());
+    }
+
+    Ok(())
+}
+
+/// 测试 `--no-header` 与 `--length` 从不含头部的图像中按给定偏移量提取原始字节
+#[test]
+fn test_recover_headerless_with_explicit_length() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let image_path = dir.path().join("legacy.png");
+    let recovered_path = dir.path().join("recovered.bin");
+
+    // 不写入头部，从第 10 个像素开始逐字节隐藏原始载荷
+    let payload = b"Legacy payload without a header.";
+    let offset = 10;
+    let mut pixels = vec![0x5Au8; 20 * 20 * 4];
+    for (i, &byte) in payload.iter().enumerate() {
+        modify(
+            byte as u64,
+            &mut pixels,
+            offset * 4 + i * BYTES_PER_CHAR,
+            BYTES_PER_CHAR,
+            DEFAULT_BITS,
+        )?;
+    }
+    ImageBuffer::<Rgba<u8>, _>::from_raw(20, 20, pixels)
+        .unwrap()
+        .save(&image_path)?;
+    assert!(!has_magic(&image::open(&image_path)?.into_rgba8()));
+
+    handle_recover(RecoverArgs {
+        image: image_path.clone(),
+        text: Some(recovered_path.clone()),
+        offset,
+        no_header: true,
+        length: Some(payload.len() as u64),
+        ..Default::default()
+    })?;
+    assert_eq!(fs::read(&recovered_path)?, payload);
+
+    // 超出图像容量的长度在提取之前即被拒绝
+    let err = handle_recover(RecoverArgs {
+        image: image_path,
+        text: Some(dir.path().join("too_long.bin")),
+        no_header: true,
+        length: Some(20 * 20 * 4),
+        ..Default::default()
+    })
+    .expect_err("A length beyond the image capacity should be rejected.");
+    assert!(matches!(
+        err.downcast_ref::<StegError>(),
+        Some(&StegError::LengthExceedsCapacity { .. })
+    ));
+
+    Ok(())
+}
+
+/// 测试 `--no-length-header` 只隐藏原始字节并写出边车文件，`recover --meta` 按边车文件精确恢复
+#[test]
+fn test_hide_without_header_and_recover_with_sidecar() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let original_image_path = dir.path().join("original.png");
+    let hidden_image_path = dir.path().join("hidden.png");
+    let meta_path = dir.path().join("hidden.png.meta");
+    let recovered_path = dir.path().join("recovered.txt");
+    create_test_image(&original_image_path, 20, 20);
+    let message = "No header, no magic.";
+
+    run_cli(&[
+        "hide",
+        "-i",
+        original_image_path.to_str().unwrap(),
+        "-m",
+        message,
+        "-d",
+        hidden_image_path.to_str().unwrap(),
+        "--offset",
+        "5",
+        "--no-length-header",
+    ]);
+    let sidecar = Sidecar::decode(&fs::read_to_string(&meta_path)?)?;
+    assert_eq!(
+        sidecar,
+        Sidecar {
+            offset: 5,
+            length: message.len() as u64,
+            checksum: crc32fast::hash(message.as_bytes()),
+        }
+    );
+
+    // 图像中没有头部，只有载荷所在的像素字节发生变化
+    let original = image::open(&original_image_path)?.into_rgba8();
+    let hidden = image::open(&hidden_image_path)?.into_rgba8();
+    assert!(!has_magic(&hidden.as_raw()[5 * 4..]));
+    let payload_range = 5 * 4..5 * 4 + message.len() * BYTES_PER_CHAR;
+    assert!(
+        (0..hidden.len())
+            .filter(|index| !payload_range.contains(index))
+            .all(|index| hidden.as_raw()[index] == original.as_raw()[index])
+    );
+    let err = handle_recover(RecoverArgs {
+        image: hidden_image_path.clone(),
+        text: Some(recovered_path.clone()),
+        ..Default::default()
+    })
+    .unwrap_err();
+    assert_eq!(exit_code(&err), EXIT_NO_PAYLOAD);
+
+    run_cli(&[
+        "recover",
+        "-i",
+        hidden_image_path.to_str().unwrap(),
+        "-t",
+        recovered_path.to_str().unwrap(),
+        "--meta",
+        meta_path.to_str().unwrap(),
+    ]);
+    assert_eq!(fs::read_to_string(&recovered_path)?, message);
+
+    // 边车文件与图像不匹配时报告校验和错误
+    let wrong_meta_path = dir.path().join("wrong.meta");
+    let wrong = Sidecar {
+        checksum: sidecar.checksum ^ 1,
+        ..sidecar
+    };
+    fs::write(&wrong_meta_path, wrong.encode())?;
+    let err = handle_recover(RecoverArgs {
+        image: hidden_image_path,
+        text: Some(dir.path().join("wrong.txt")),
+        meta: Some(wrong_meta_path),
+        ..Default::default()
+    })
+    .unwrap_err();
+    assert_eq!(
+        err.downcast_ref::<StegError>(),
+        Some(&StegError::ChecksumMismatch)
+    );
+
+    Ok(())
+}
+
+/// 测试 `--payload-limit` 在提取之前拒绝声称长度超过上限的图像
+#[test]
+fn test_recover_payload_limit_rejects_large_length() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let original_image_path = dir.path().join("original.png");
+    let hidden_image_path = dir.path().join("hidden.png");
+    let source_text_path = dir.path().join("source.txt");
+    let recovered_text_path = dir.path().join("recovered.txt");
+
+    create_test_image(&original_image_path, 50, 50);
+    let original_text = "x".repeat(1000);
+    fs::write(&source_text_path, &original_text)?;
+    handle_hide(HideArgs {
+        image: Some(original_image_path),
+        text: vec![source_text_path],
+        dest: Some(hidden_image_path.clone()),
+        ..Default::default()
+    })?;
+
+    let err = handle_recover(RecoverArgs {
+        image: hidden_image_path.clone(),
+        text: Some(recovered_text_path.clone()),
+        payload_limit: Some(100),
+        ..Default::default()
+    })
+    .expect_err("Recovering beyond the payload limit should fail.");
+    assert!(
+        format!("{:#}", err).contains("Declared payload length exceeds --payload-limit"),
+        "{:#}",
+        err
+    );
+    assert!(!recovered_text_path.exists());
+
+    handle_recover(RecoverArgs {
+        image: hidden_image_path,
+        text: Some(recovered_text_path.clone()),
+        payload_limit: Some(original_text.len() as u64),
+        ..Default::default()
+    })?;
+    assert_eq!(fs::read_to_string(&recovered_text_path)?, original_text);
+
+    Ok(())
+}
+
+/// 验证 `--auto-bits` 在默认的 2 位放不下载荷时自动选择 3 位，并可以正常恢复
+#[test]
+fn test_hide_with_auto_bits() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let image_path = dir.path().join("image.png");
+    let text_path = dir.path().join("secret.txt");
+    let hidden_image_path = dir.path().join("hidden.png");
+    let recovered_path = dir.path().join("recovered.txt");
+
+    // 90 个字节在 2 位时需要 360 个像素字节，加上头部超出 10x10 RGBA 图像的 400 个字节，3 位时只需 270 个
+    create_test_image(&image_path, 10, 10);
+    let text = "a".repeat(90);
+    fs::write(&text_path, &text)?;
+    let header = header_len(90, "secret.txt".len() + TYPE_TAG_LEN);
+    assert!(header + 90 * 4 > 400 && header + 90 * 3 <= 400);
+
+    let hide_args = || HideArgs {
+        image: Some(image_path.clone()),
+        text: vec![text_path.clone()],
+        dest: Some(hidden_image_path.clone()),
+        force: true,
+        ..Default::default()
+    };
+    let err = handle_hide(hide_args()).expect_err("The payload should not fit at 2 bits.");
+    assert!(matches!(
+        err.downcast_ref::<StegError>(),
+        Some(StegError::NotEnoughSpace { .. })
+    ));
+
+    handle_hide(HideArgs {
+        auto_bits: true,
+        ..hide_args()
+    })?;
+    let info: serde_json::Value = serde_json::from_str(&run_cli(&[
+        "--json",
+        "info",
+        "-i",
+        hidden_image_path.to_str().unwrap(),
+    ]))?;
+    assert_eq!(info["bits"], 3);
+
+    handle_recover(RecoverArgs {
+        image: hidden_image_path,
+        text: Some(recovered_path.clone()),
+        ..Default::default()
+    })?;
+    assert_eq!(fs::read_to_string(recovered_path)?, text);
+
+    Ok(())
+}
+
+/// 验证 `--lang zh` 将错误信息切换为中文，`LANG` 环境变量在未指定 `--lang` 时同样生效，且 `--lang en` 优先于环境变量
+#[test]
+fn test_lang_switches_error_messages() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let image_path = dir.path().join("image.png");
+    let dest_path = dir.path().join("dest.png");
+
+    create_test_image(&image_path, 50, 50);
+    fs::write(&dest_path, "existing")?;
+
+    let hide = |lang: Option<&str>, locale: &str| {
+        let mut args = vec![
+            "hide",
+            "-i",
+            image_path.to_str().unwrap(),
+            "-m",
+            "secret",
+            "-d",
+            dest_path.to_str().unwrap(),
+        ];
+        if let Some(lang) = lang {
+            args.extend(["--lang", lang]);
+        }
+        let output = Command::new(env!("CARGO_BIN_EXE_lsb_hide"))
+            .args(args)
+            .env("NO_COLOR", "1")
+            .env("LANG", locale)
+            .output()
+            .expect("Failed to run lsb_hide binary.");
+        assert!(!output.status.success());
+        String::from_utf8_lossy(&output.stderr).into_owned()
+    };
+
+    assert!(hide(Some("zh"), "C").contains("输出文件已存在"));
+    assert!(hide(None, "zh_CN.UTF-8").contains("输出文件已存在"));
+    assert!(hide(Some("en"), "zh_CN.UTF-8").contains("Output file already exists"));
+    assert!(hide(None, "C").contains("Output file already exists"));
+
+    Ok(())
+}
+
+/// 测试将 BMP 原图写为 PNG 导致文件大小明显变化时给出警告，指定 `--quiet` 时不输出
+#[test]
+fn test_hide_warns_when_output_size_changes() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let image_path = dir.path().join("cover.bmp");
+    let dest_path = dir.path().join("stego.png");
+
+    // 纯色图像在 PNG 中压缩得很小，而 BMP 不压缩
+    ImageBuffer::from_pixel(64, 64, Rgba([40u8, 120, 200, 255])).save(&image_path)?;
+
+    let hide = |quiet: bool| {
+        let mut args = vec![
+            "hide",
+            "-i",
+            image_path.to_str().unwrap(),
+            "-m",
+            "secret",
+            "-d",
+            dest_path.to_str().unwrap(),
+            "--force",
+        ];
+        if quiet {
+            args.insert(0, "--quiet");
+        }
+        let output = Command::new(env!("CARGO_BIN_EXE_lsb_hide"))
+            .args(args)
+            .env("NO_COLOR", "1")
+            .env("LANG", "C")
+            .output()
+            .expect("Failed to run lsb_hide binary.");
+        assert!(output.status.success());
+        String::from_utf8_lossy(&output.stderr).into_owned()
+    };
+
+    let stderr = hide(false);
+    assert!(stderr.contains("may be detectable"), "{}", stderr);
+    assert!(hide(true).is_empty());
+
+    Ok(())
+}
+
+/// 测试错误信息中的路径只在启用 `color` 特性时着色，关闭该特性后即使强制着色也不含 ANSI 控制符
+#[test]
+fn test_color_feature_controls_ansi_codes() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let image_path = dir.path().join("image.png");
+    let dest_path = dir.path().join("dest.png");
+
+    create_test_image(&image_path, 50, 50);
+    fs::write(&dest_path, "existing")?;
+
+    let output = Command::new(env!("CARGO_BIN_EXE_lsb_hide"))
+        .args([
+            "hide",
+            "-i",
+            image_path.to_str().unwrap(),
+            "-m",
+            "secret",
+            "-d",
+            dest_path.to_str().unwrap(),
+        ])
+        .env_remove("NO_COLOR")
+        .env("CLICOLOR_FORCE", "1")
+        .env("LANG", "C")
+        .output()?;
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Output file already exists"), "{}", stderr);
+    assert_eq!(
+        stderr.contains('\x1b'),
+        cfg!(feature = "color"),
+        "{:?}",
+        stderr
+    );
+
+    Ok(())
+}
+
+/// 一个辅助函数，以子进程方式运行命令行程序并返回其标准输出
+fn run_cli(args: &[&str]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_lsb_hide"))
+        .args(args)
+        .env("NO_COLOR", "1")
+        .output()
+        .expect("Failed to run lsb_hide binary.");
+    assert!(
+        output.status.success(),
+        "lsb_hide exited with failure: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).expect("Output should be valid UTF-8.")
+}
+
+/// 验证 capacity 命令对 RGB 和 RGBA 图像分别报告正确的容量
+#[test]
+fn test_handle_capacity_rgb_and_rgba() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let rgba_path = dir.path().join("rgba.png");
+    let rgb_path = dir.path().join("rgb.png");
+
+    create_test_image(&rgba_path, 10, 10);
+    image::RgbImage::new(10, 10).save(&rgb_path)?;
+
+    // 处理函数本身应能正常完成
+    handle_capacity(CapacityArgs {
+        image: rgba_path.clone(),
+        ..Default::default()
+    })?;
+    handle_capacity(CapacityArgs {
+        image: rgb_path.clone(),
+        ..Default::default()
+    })?;
+
+    // 容量 = 扣除头部之后，默认位深度下像素字节能容纳的载荷字节数
+    let rgba_capacity = max_payload_bytes(10 * 10 * 4, DEFAULT_BITS as usize);
+    let rgb_capacity = max_payload_bytes(10 * 10 * 3, DEFAULT_BITS as usize);
+
+    let rgba_output = run_cli(&["capacity", "-i", rgba_path.to_str().unwrap()]);
+    assert!(rgba_output.contains("10x10, 4 channels"));
+    assert!(rgba_output.contains(&format!(
+        "Maximum payload size (RGBA channels): {} bytes",
+        rgba_capacity
+    )));
+    assert!(rgba_output.contains(&format!(
+        "Maximum payload size (RGB channels only): {} bytes",
+        rgb_capacity
+    )));
+
+    let rgb_output = run_cli(&["capacity", "-i", rgb_path.to_str().unwrap()]);
+    assert!(rgb_output.contains("10x10, 3 channels"));
+    assert!(rgb_output.contains(&format!("Maximum payload size: {} bytes", rgb_capacity)));
+    assert!(!rgb_output.contains("RGBA"));
+
+    Ok(())
+}
+
+/// 验证对未经隐写的普通图像执行恢复时，会报告明确的“无载荷”错误
+#[test]
+fn test_handle_recover_plain_image_reports_no_payload() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let image_path = dir.path().join("plain.png");
+    let text_path = dir.path().join("out.txt");
+
+    create_test_image(&image_path, 100, 100);
+
+    let recover_args = RecoverArgs {
+        image: image_path,
+        text: Some(text_path.clone()),
+        ..Default::default()
+    };
+    let err = handle_recover(recover_args).expect_err("Recover should fail on a plain image.");
+
+    assert_eq!(
+        err.downcast_ref::<StegError>(),
+        Some(&StegError::MagicMismatch)
+    );
+    assert_eq!(err.to_string(), NO_PAYLOAD_MESSAGE);
+    assert!(!text_path.exists(), "No output file should be written.");
+
+    Ok(())
+}
+
+/// 验证口令加密的载荷能够用正确口令恢复，而错误口令会明确地失败
+#[test]
+fn test_handle_hide_and_recover_with_password() -> anyhow::Result<()> {
+    // 1. 准备环境
+    let dir = tempdir()?;
+    let original_image_path = dir.path().join("original.png");
+    let hidden_image_path = dir.path().join("hidden.png");
+    let source_text_path = dir.path().join("source.txt");
+    let recovered_text_path = dir.path().join("recovered.txt");
+
+    create_test_image(&original_image_path, 100, 100);
+    let original_text = "Encrypted secret. 加密的秘密。";
+    fs::write(&source_text_path, original_text)?;
+
+    // 2. 使用口令隐藏
+    handle_hide(HideArgs {
+        image: Some(original_image_path),
+        text: vec![source_text_path],
+        dest: Some(hidden_image_path.clone()),
+        password: Some("hunter2".to_string()),
+        ..Default::default()
+    })?;
+
+    // 3. 错误口令应报告解密失败，且不写出任何文件
+    let err = handle_recover(RecoverArgs {
+        image: hidden_image_path.clone(),
+        text: Some(recovered_text_path.clone()),
+        password: Some("wrong".to_string()),
+        ..Default::default()
+    })
+    .expect_err("Recovery with a wrong password should fail.");
+    assert_eq!(err.to_string(), DECRYPTION_FAILED_MESSAGE);
+    assert!(!recovered_text_path.exists());
+
+    // 未提供口令时应提示载荷已加密
+    let err = handle_recover(RecoverArgs {
+        image: hidden_image_path.clone(),
+        text: Some(recovered_text_path.clone()),
+        ..Default::default()
+    })
+    .expect_err("Recovery without a password should fail.");
+    assert!(err.to_string().contains("--password"));
+
+    // 4. 正确口令应恢复出原文
+    handle_recover(RecoverArgs {
+        image: hidden_image_path,
+        text: Some(recovered_text_path.clone()),
+        password: Some("hunter2".to_string()),
+        ..Default::default()
+    })?;
+    assert_eq!(fs::read_to_string(&recovered_text_path)?, original_text);
+
+    Ok(())
+}
+
+/// 验证载荷可以从标准输入读取，并将恢复结果以二进制安全的方式写到标准输出
+#[test]
+fn test_hide_from_stdin_and_recover_to_stdout() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let image_path = dir.path().join("cover.png");
+    let hidden_path = dir.path().join("hidden.png");
+    create_test_image(&image_path, 100, 100);
+
+    // 包含 NUL 和非 UTF-8 字节的载荷，确保读写都是二进制安全的
+    let payload: &[u8] = b"secret\x00\xff\xfe piped payload\n";
+
+    // 1. 省略 --text，从标准输入读取载荷
+    let mut child = Command::new(env!("CARGO_BIN_EXE_lsb_hide"))
+        .args(["hide", "-i", image_path.to_str().unwrap()])
+        .args(["-d", hidden_path.to_str().unwrap()])
+        .env("NO_COLOR", "1")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    child.stdin.take().unwrap().write_all(payload)?;
+    let output = child.wait_with_output()?;
+    assert!(output.status.success(), "hide from stdin should succeed");
+
+    // 2. 使用 "-t -" 将恢复结果写到标准输出
+    let output = Command::new(env!("CARGO_BIN_EXE_lsb_hide"))
+        .args(["recover", "-i", hidden_path.to_str().unwrap(), "-t", "-"])
+        .env("NO_COLOR", "1")
+        .output()?;
+    assert!(output.status.success(), "recover to stdout should succeed");
+
+    // 标准输出中只能包含载荷本身，成功信息应写到标准错误
+    assert_eq!(output.stdout, payload);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("successfully recovered"));
+
+    Ok(())
+}
+
+/// 验证打散模式下，只有使用相同口令才能定位并恢复载荷
+#[test]
+fn test_handle_hide_and_recover_scattered() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let original_image_path = dir.path().join("original.png");
+    let hidden_image_path = dir.path().join("hidden.png");
+    let source_text_path = dir.path().join("source.txt");
+    let recovered_text_path = dir.path().join("recovered.txt");
+
+    create_test_image(&original_image_path, 100, 100);
+    let original_text = "Scattered secret. 打散的秘密。";
+    fs::write(&source_text_path, original_text)?;
+
+    handle_hide(HideArgs {
+        image: Some(original_image_path),
+        text: vec![source_text_path],
+        dest: Some(hidden_image_path.clone()),
+        password: Some("seed phrase".to_string()),
+        scatter: true,
+        ..Default::default()
+    })?;
+
+    // 错误的口令会得到错误的位置排列，恢复应失败
+    let result = handle_recover(RecoverArgs {
+        image: hidden_image_path.clone(),
+        text: Some(recovered_text_path.clone()),
+        password: Some("not the seed".to_string()),
+        ..Default::default()
+    });
+    assert!(result.is_err(), "Recovery with the wrong seed should fail.");
+    assert!(!recovered_text_path.exists());
+
+    // 正确的口令可以恢复原文
+    handle_recover(RecoverArgs {
+        image: hidden_image_path,
+        text: Some(recovered_text_path.clone()),
+        password: Some("seed phrase".to_string()),
+        ..Default::default()
+    })?;
+    assert_eq!(fs::read_to_string(&recovered_text_path)?, original_text);
+
+    Ok(())
+}
+
+/// 验证打散种子可以从文件读取：相同的种子文件可以恢复，不同的种子文件无法定位载荷
+#[test]
+fn test_handle_hide_and_recover_with_seed_file() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let original_image_path = dir.path().join("original.png");
+    let hidden_image_path = dir.path().join("hidden.png");
+    let source_text_path = dir.path().join("source.txt");
+    let recovered_text_path = dir.path().join("recovered.txt");
+    let seed_path = dir.path().join("seed.key");
+    let other_seed_path = dir.path().join("other.key");
+
+    create_test_image(&original_image_path, 100, 100);
+    let original_text = "Seeded from a keyfile.";
+    fs::write(&source_text_path, original_text)?;
+    fs::write(&seed_path, [0x5a, 0x00, 0xff, 0x13, 0x37])?;
+    fs::write(&other_seed_path, [0x5a, 0x00, 0xff, 0x13, 0x38])?;
+
+    handle_hide(HideArgs {
+        image: Some(original_image_path),
+        text: vec![source_text_path],
+        dest: Some(hidden_image_path.clone()),
+        seed_file: Some(seed_path.clone()),
+        scatter: true,
+        ..Default::default()
+    })?;
+
+    // 不同的种子文件会得到不同的位置排列，恢复应失败
+    let result = handle_recover(RecoverArgs {
+        image: hidden_image_path.clone(),
+        text: Some(recovered_text_path.clone()),
+        seed_file: Some(other_seed_path),
+        ..Default::default()
+    });
+    assert!(
+        result.is_err(),
+        "Recovery with a different seed file should fail."
+    );
+    assert!(!recovered_text_path.exists());
+
+    handle_recover(RecoverArgs {
+        image: hidden_image_path,
+        text: Some(recovered_text_path.clone()),
+        seed_file: Some(seed_path),
+        ..Default::default()
+    })?;
+    assert_eq!(fs::read_to_string(&recovered_text_path)?, original_text);
+
+    Ok(())
+}
+
+/// 验证高度可压缩的文本在压缩后可以放入原本容纳不下它的图像中
+#[test]
+fn test_handle_hide_and_recover_compressed() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let image_path = dir.path().join("small.png");
+    let text_path = dir.path().join("repetitive.txt");
+    create_test_image(&image_path, 20, 20);
+
+    // 20x20 的 RGBA 图像只能容纳不到 400 字节的未压缩载荷
+    let original_text = "abc".repeat(2000);
+    fs::write(&text_path, &original_text)?;
+
+    // 不压缩时空间不足
+    let err = handle_hide(HideArgs {
+        image: Some(image_path.clone()),
+        text: vec![text_path.clone()],
+        dest: Some(dir.path().join("uncompressed.png")),
+        ..Default::default()
+    })
+    .expect_err("Uncompressed payload should not fit.");
+    assert!(matches!(
+        err.downcast_ref::<StegError>(),
+        Some(StegError::NotEnoughSpace { .. })
+    ));
+
+    for algorithm in [Compression::Gzip, Compression::Zstd] {
+        let hidden_path = dir.path().join(format!("{:?}.png", algorithm));
+        let recovered_path = dir.path().join(format!("{:?}.txt", algorithm));
+
+        handle_hide(HideArgs {
+            image: Some(image_path.clone()),
+            text: vec![text_path.clone()],
+            dest: Some(hidden_path.clone()),
+            compress: algorithm,
+            ..Default::default()
+        })?;
+
+        handle_recover(RecoverArgs {
+            image: hidden_path,
+            text: Some(recovered_path.clone()),
+            ..Default::default()
+        })?;
+        assert_eq!(fs::read_to_string(&recovered_path)?, original_text);
+    }
+
+    Ok(())
+}
+
+/// 验证隐写图像中的一个最低有效位被翻转后，恢复会报告数据损坏
+#[test]
+fn test_handle_recover_detects_corruption() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let original_image_path = dir.path().join("original.png");
+    let hidden_image_path = dir.path().join("hidden.png");
+    let source_text_path = dir.path().join("source.txt");
+    let recovered_text_path = dir.path().join("recovered.txt");
+
+    create_test_image(&original_image_path, 50, 50);
+    fs::write(&source_text_path, "Do not tamper with me.")?;
+
+    handle_hide(HideArgs {
+        image: Some(original_image_path),
+        text: vec![source_text_path],
+        dest: Some(hidden_image_path.clone()),
+        ..Default::default()
+    })?;
+
+    // 翻转载荷区域中第一个像素字节的最低位 (头部包含原始文件名 "source.txt")
+    let mut img = image::open(&hidden_image_path)?.into_rgba8();
+    let raw: &mut [u8] = &mut img;
+    raw[header_len(22, "source.txt".len() + TYPE_TAG_LEN)] ^= 0b1;
+    img.save(&hidden_image_path)?;
+
+    let err = handle_recover(RecoverArgs {
+        image: hidden_image_path,
+        text: Some(recovered_text_path.clone()),
+        ..Default::default()
+    })
+    .expect_err("Recovery from a corrupted image should fail.");
+    assert!(format!("{:#}", err).contains(CHECKSUM_MISMATCH_MESSAGE));
+    assert!(!recovered_text_path.exists());
+
+    Ok(())
+}
+
+/// 验证 `--best-effort` 在载荷中间损坏时写出损坏之前恢复的部分，而默认模式放弃全部数据
+#[test]
+fn test_recover_best_effort_writes_prefix_before_corruption() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let original_image_path = dir.path().join("original.png");
+    let hidden_image_path = dir.path().join("hidden.png");
+    let source_text_path = dir.path().join("source.txt");
+    let recovered_text_path = dir.path().join("recovered.txt");
+
+    create_test_image(&original_image_path, 50, 50);
+    let original_text = "The first half survives. The second half is lost.";
+    fs::write(&source_text_path, original_text)?;
+
+    // 每个像素字节使用 3 位时，每个载荷字节占 3 个像素字节，共 9 位，可以写出超出 u8 范围的无效值
+    let bits = 3;
+    handle_hide(HideArgs {
+        image: Some(original_image_path),
+        text: vec![source_text_path],
+        dest: Some(hidden_image_path.clone()),
+        bits,
+        ..Default::default()
+    })?;
+
+    // 将第 `corrupt_at` 个载荷字节的全部数据位置 1，使其解码为 511
+    let corrupt_at = 25;
+    let mut img = image::open(&hidden_image_path)?.into_rgba8();
+    let payload_start = read_header(&img)?.payload_offset();
+    let raw: &mut [u8] = &mut img;
+    let char_size = 3;
+    for byte in &mut raw[payload_start + corrupt_at * char_size..][..char_size] {
+        *byte |= (1 << bits) - 1;
+    }
+    img.save(&hidden_image_path)?;
+
+    handle_recover(RecoverArgs {
+        image: hidden_image_path.clone(),
+        text: Some(recovered_text_path.clone()),
+        ..Default::default()
+    })
+    .expect_err("Recovery from a corrupted payload should fail by default.");
+    assert!(!recovered_text_path.exists());
+
+    handle_recover(RecoverArgs {
+        image: hidden_image_path,
+        text: Some(recovered_text_path.clone()),
+        best_effort: true,
+        ..Default::default()
+    })?;
+    assert_eq!(
+        fs::read_to_string(&recovered_text_path)?,
+        original_text[..corrupt_at]
+    );
+
+    Ok(())
+}
+
+/// 验证各类失败按约定映射为不同的进程退出码
+#[test]
+fn test_errors_map_to_exit_codes() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let image_path = dir.path().join("cover.png");
+    let hidden_path = dir.path().join("hidden.png");
+    let text_path = dir.path().join("source.txt");
+    create_test_image(&image_path, 20, 20);
+
+    // 空间不足
+    fs::write(&text_path, "x".repeat(1000))?;
+    let err = handle_hide(HideArgs {
+        image: Some(image_path.clone()),
+        text: vec![text_path.clone()],
+        dest: Some(hidden_path.clone()),
+        ..Default::default()
+    })
+    .expect_err("The payload should not fit.");
+    assert_eq!(exit_code(&err), EXIT_NOT_ENOUGH_SPACE);
+
+    // 图像中没有载荷
+    let err = handle_recover(RecoverArgs {
+        image: image_path.clone(),
+        text: Some(dir.path().join("none.txt")),
+        ..Default::default()
+    })
+    .expect_err("A plain image has no payload.");
+    assert_eq!(exit_code(&err), EXIT_NO_PAYLOAD);
+
+    // 输入图像不存在
+    let err = handle_recover(RecoverArgs {
+        image: dir.path().join("missing.png"),
+        text: Some(dir.path().join("missing.txt")),
+        ..Default::default()
+    })
+    .expect_err("A missing image cannot be read.");
+    assert_eq!(exit_code(&err), EXIT_IO_ERROR);
+
+    // 载荷已损坏
+    fs::write(&text_path, "Corrupt me.")?;
+    handle_hide(HideArgs {
+        image: Some(image_path.clone()),
+        text: vec![text_path.clone()],
+        dest: Some(hidden_path.clone()),
+        ..Default::default()
+    })?;
+    let mut img = image::open(&hidden_path)?.into_rgba8();
+    let payload_start = read_header(&img)?.payload_offset();
+    let raw: &mut [u8] = &mut img;
+    raw[payload_start] ^= 0b1;
+    img.save(&hidden_path)?;
+    let err = handle_recover(RecoverArgs {
+        image: hidden_path.clone(),
+        text: Some(dir.path().join("corrupt.txt")),
+        ..Default::default()
+    })
+    .expect_err("A corrupted payload should fail the integrity check.");
+    assert_eq!(exit_code(&err), EXIT_CORRUPTED);
+
+    // 其他失败：已加密的载荷缺少口令
+    handle_hide(HideArgs {
+        image: Some(image_path),
+        text: vec![text_path],
+        dest: Some(hidden_path.clone()),
+        password: Some("secret".to_string()),
+        force: true,
+        ..Default::default()
+    })?;
+    let err = handle_recover(RecoverArgs {
+        image: hidden_path.clone(),
+        text: Some(dir.path().join("encrypted.txt")),
+        ..Default::default()
+    })
+    .expect_err("An encrypted payload needs a password.");
+    assert_eq!(exit_code(&err), EXIT_FAILURE);
+
+    // 命令行程序以相同的状态码退出，参数错误不会与空间不足的状态码 2 混淆
+    let exit_status = |args: &[&str]| -> anyhow::Result<Option<i32>> {
+        let output = Command::new(env!("CARGO_BIN_EXE_lsb_hide"))
+            .args(args)
+            .env("NO_COLOR", "1")
+            .env("LANG", "C")
+            .output()?;
+        Ok(output.status.code())
+    };
+    let plain = dir.path().join("cover.png");
+    let plain = plain.to_str().unwrap();
+    assert_eq!(
+        exit_status(&["recover", "-i", plain, "-t", "-"])?,
+        Some(EXIT_NO_PAYLOAD)
+    );
+    assert_eq!(
+        exit_status(&["--json", "recover", "-i", plain, "-t", "-"])?,
+        Some(EXIT_NO_PAYLOAD)
+    );
+    assert_eq!(exit_status(&["recover", "--bogus"])?, Some(EXIT_FAILURE));
+
+    Ok(())
+}
+
+/// 验证只使用蓝色通道隐藏时，其余通道在头部之后保持不变且可以正确恢复
+#[test]
+fn test_handle_hide_and_recover_blue_channel_only() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let original_image_path = dir.path().join("original.png");
+    let hidden_image_path = dir.path().join("hidden.png");
+    let source_text_path = dir.path().join("source.txt");
+    let recovered_text_path = dir.path().join("recovered.txt");
+
+    create_test_image(&original_image_path, 50, 50);
+    let original_text = "Only the blue channel knows.";
+    fs::write(&source_text_path, original_text)?;
+
+    handle_hide(HideArgs {
+        image: Some(original_image_path.clone()),
+        text: vec![source_text_path],
+        dest: Some(hidden_image_path.clone()),
+        channels: "b".parse()?,
+        ..Default::default()
+    })?;
+
+    let original = image::open(&original_image_path)?.into_rgba8().into_raw();
+    let hidden = image::open(&hidden_image_path)?.into_rgba8().into_raw();
+    let header_end = header_len(
+        original_text.len() as u64,
+        "source.txt".len() + TYPE_TAG_LEN,
+    );
+    assert!(
+        (header_end..original.len())
+            .filter(|i| i % 4 != 2)
+            .all(|i| original[i] == hidden[i]),
+        "Only blue channel bytes should change after the header."
+    );
+
+    // 恢复时无需指定通道，通道选择记录在头部中
+    handle_recover(RecoverArgs {
+        image: hidden_image_path,
+        text: Some(recovered_text_path.clone()),
+        ..Default::default()
+    })?;
+    assert_eq!(fs::read_to_string(&recovered_text_path)?, original_text);
+
+    Ok(())
+}
+
+/// 验证写入位平面 2 时，头部之后每个像素字节的最低两位保持不变且可以正确恢复
+#[test]
+fn test_handle_hide_and_recover_plane_two() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let original_image_path = dir.path().join("original.png");
+    let hidden_image_path = dir.path().join("hidden.png");
+    let source_text_path = dir.path().join("source.txt");
+    let recovered_text_path = dir.path().join("recovered.txt");
+
+    create_test_image(&original_image_path, 50, 50);
+    let original_text = "Naive LSB detectors look one plane too low.";
+    fs::write(&source_text_path, original_text)?;
+
+    handle_hide(HideArgs {
+        image: Some(original_image_path.clone()),
+        text: vec![source_text_path],
+        dest: Some(hidden_image_path.clone()),
+        plane: 2,
+        ..Default::default()
+    })?;
+
+    let original = image::open(&original_image_path)?.into_rgba8().into_raw();
+    let hidden = image::open(&hidden_image_path)?.into_rgba8().into_raw();
+    let header_end = header_len(
+        original_text.len() as u64,
+        "source.txt".len() + TYPE_TAG_LEN,
+    );
+    assert!(
+        (header_end..original.len()).all(|i| original[i] & 0b11 == hidden[i] & 0b11),
+        "The lowest two bits must be untouched after the header."
+    );
+    assert_ne!(original, hidden, "Plane 2 bits should carry the payload.");
+
+    // 恢复时无需指定位平面，位平面记录在头部中
+    handle_recover(RecoverArgs {
+        image: hidden_image_path,
+        text: Some(recovered_text_path.clone()),
+        ..Default::default()
+    })?;
+    assert_eq!(fs::read_to_string(&recovered_text_path)?, original_text);
+
+    Ok(())
+}
+
+/// 验证 `--skip-alpha` 下 alpha 字节在输入与输出之间完全一致，且可以正确恢复
+#[test]
+fn test_handle_hide_and_recover_skip_alpha() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let original_image_path = dir.path().join("original.png");
+    let hidden_image_path = dir.path().join("hidden.png");
+    let source_text_path = dir.path().join("source.txt");
+    let recovered_text_path = dir.path().join("recovered.txt");
+
+    create_test_image(&original_image_path, 50, 50);
+    let original_text = "Alpha stays exactly as it was.";
+    fs::write(&source_text_path, original_text)?;
+
+    handle_hide(HideArgs {
+        image: Some(original_image_path.clone()),
+        text: vec![source_text_path],
+        dest: Some(hidden_image_path.clone()),
+        skip_alpha: true,
+        ..Default::default()
+    })?;
+
+    let original = image::open(&original_image_path)?.into_rgba8();
+    let hidden = image::open(&hidden_image_path)?.into_rgba8();
+    assert!(
+        original
+            .pixels()
+            .zip(hidden.pixels())
+            .all(|(before, after)| before[3] == after[3]),
+        "Alpha bytes should be byte-identical."
+    );
+    assert_ne!(original, hidden, "Color bytes should carry the payload.");
+
+    handle_recover(RecoverArgs {
+        image: hidden_image_path,
+        text: Some(recovered_text_path.clone()),
+        ..Default::default()
+    })?;
+    assert_eq!(fs::read_to_string(&recovered_text_path)?, original_text);
+
+    Ok(())
+}
+
+/// 验证 `--alpha-threshold` 跳过半透明像素：它们逐字节不变，恢复时由 alpha 通道重建相同的跳过模式
+#[test]
+fn test_handle_hide_and_recover_alpha_threshold() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let original_image_path = dir.path().join("original.png");
+    let hidden_image_path = dir.path().join("hidden.png");
+    let recovered_text_path = dir.path().join("recovered.txt");
+
+    // 每三个像素中有一个完全透明、一个几乎透明，第一个像素即为透明像素
+    let original = ImageBuffer::from_fn(40, 40, |x, y| {
+        let alpha = [0, 20, 255][((x + y * 40) % 3) as usize];
+        Rgba([(x * 6) as u8, (y * 6) as u8, ((x + y) * 3) as u8, alpha])
+    });
+    original.save(&original_image_path)?;
+    let original_text = "Only opaque pixels carry this.";
+
+    handle_hide(HideArgs {
+        image: Some(original_image_path.clone()),
+        message: Some(original_text.to_string()),
+        dest: Some(hidden_image_path.clone()),
+        alpha_threshold: Some(128),
+        ..Default::default()
+    })?;
+
+    let hidden = image::open(&hidden_image_path)?.into_rgba8();
+    assert!(
+        original
+            .pixels()
+            .zip(hidden.pixels())
+            .filter(|(before, _)| before[3] < 128)
+            .all(|(before, after)| before == after),
+        "Pixels below the alpha threshold should be byte-identical."
+    );
+    assert!(
+        original
+            .pixels()
+            .zip(hidden.pixels())
+            .all(|(before, after)| before[3] == after[3]),
+        "Alpha bytes should be byte-identical."
+    );
+    assert_ne!(original, hidden, "Opaque pixels should carry the payload.");
+
+    // 不指定相同的阈值时找不到头部
+    let err = handle_recover(RecoverArgs {
+        image: hidden_image_path.clone(),
+        text: Some(recovered_text_path.clone()),
+        ..Default::default()
+    })
+    .unwrap_err();
+    assert_eq!(err.to_string(), NO_PAYLOAD_MESSAGE);
+
+    handle_recover(RecoverArgs {
+        image: hidden_image_path.clone(),
+        text: Some(recovered_text_path.clone()),
+        alpha_threshold: Some(128),
+        ..Default::default()
+    })?;
+    assert_eq!(fs::read_to_string(&recovered_text_path)?, original_text);
+
+    let info: serde_json::Value = serde_json::from_str(&run_cli(&[
+        "--json",
+        "info",
+        "-i",
+        hidden_image_path.to_str().unwrap(),
+        "--alpha-threshold",
+        "128",
+    ]))?;
+    assert_eq!(info["payload_detected"], true);
+    assert_eq!(info["skip_alpha"], true);
+
+    // 没有 alpha 通道的图像不接受阈值
+    let rgb_path = dir.path().join("rgb.png");
+    image::RgbImage::new(10, 10).save(&rgb_path)?;
+    let err = handle_hide(HideArgs {
+        image: Some(rgb_path),
+        message: Some("x".to_string()),
+        dest: Some(dir.path().join("rgb_hidden.png")),
+        alpha_threshold: Some(128),
+        ..Default::default()
+    })
+    .unwrap_err();
+    assert!(err.to_string().starts_with("--alpha-threshold requires"));
+
+    Ok(())
+}
+
+/// 验证 `--frame` 只在 APNG 的指定帧中隐藏数据，其余帧保持不变，并可从同一帧恢复
+#[test]
+fn test_hide_and_recover_in_animation_frame() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let original_image_path = dir.path().join("animated.png");
+    let hidden_image_path = dir.path().join("hidden.png");
+    let recovered_text_path = dir.path().join("recovered.txt");
+
+    let frames = (0..2u8)
+        .map(|index| {
+            let buffer = ImageBuffer::from_fn(30, 30, |x, y| {
+                Rgba([(x * 8) as u8, (y * 8) as u8, index * 100, 255])
+            });
+            Frame::from_parts(buffer, 0, 0, Delay::from_numer_denom_ms(100, 1))
+        })
+        .collect();
+    let original = Animation::new(frames, 0);
+    fs::write(&original_image_path, original.encode()?)?;
+    let original_text = "Second frame only.";
+
+    handle_hide(HideArgs {
+        image: Some(original_image_path.clone()),
+        message: Some(original_text.to_string()),
+        dest: Some(hidden_image_path.clone()),
+        frame: Some(1),
+        ..Default::default()
+    })?;
+
+    let hidden = Animation::decode(&fs::read(&hidden_image_path)?)?;
+    assert_eq!(hidden.len(), 2);
+    assert_eq!(hidden.frame(0)?, original.frame(0)?);
+    assert_ne!(hidden.frame(1)?, original.frame(1)?);
+
+    handle_recover(RecoverArgs {
+        image: hidden_image_path.clone(),
+        text: Some(recovered_text_path.clone()),
+        frame: Some(1),
+        ..Default::default()
+    })?;
+    assert_eq!(fs::read_to_string(&recovered_text_path)?, original_text);
+
+    // 其他帧中没有载荷
+    for frame in [None, Some(0)] {
+        let err = handle_recover(RecoverArgs {
+            image: hidden_image_path.clone(),
+            text: Some(recovered_text_path.clone()),
+            force: true,
+            frame,
+            ..Default::default()
+        })
+        .unwrap_err();
+        assert_eq!(err.to_string(), NO_PAYLOAD_MESSAGE);
+    }
+
+    // 超出帧数或不是动画时报错
+    let err = handle_hide(HideArgs {
+        image: Some(original_image_path),
+        message: Some("x".to_string()),
+        dest: Some(dir.path().join("out_of_range.png")),
+        frame: Some(2),
+        ..Default::default()
+    })
+    .unwrap_err();
+    assert!(err.to_string().contains("out of range"), "{}", err);
+
+    let still_path = dir.path().join("still.png");
+    create_test_image(&still_path, 30, 30);
+    let err = handle_hide(HideArgs {
+        image: Some(still_path),
+        message: Some("x".to_string()),
+        dest: Some(dir.path().join("still_hidden.png")),
+        frame: Some(0),
+        ..Default::default()
+    })
+    .unwrap_err();
+    assert!(
+        err.to_string()
+            .starts_with("--frame requires an animated PNG (APNG) image")
+    );
+
+    Ok(())
+}
+
+/// 验证 `--verbose` 会在隐藏与恢复后打印统计信息
+#[test]
+fn test_verbose_prints_statistics() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let original_image_path = dir.path().join("original.png");
+    let hidden_image_path = dir.path().join("hidden.png");
+    let source_text_path = dir.path().join("source.txt");
+    let recovered_text_path = dir.path().join("recovered.txt");
+
+    create_test_image(&original_image_path, 10, 10);
+    fs::write(&source_text_path, "0123456789")?;
+
+    // 10 个载荷字节，容量为 400 个像素字节在默认位深度下的最大载荷，
+    // 减去头部中记录的原始文件名 "source.txt" 与类型标签所占的字节
+    let name_len = "source.txt".len() + TYPE_TAG_LEN;
+    let capacity = max_payload_bytes(10 * 10 * 4, DEFAULT_BITS as usize) - name_len;
+    let end_offset = header_len(10, name_len) + 10 * BYTES_PER_CHAR;
+
+    let hide_output = run_cli(&[
+        "hide",
+        "-i",
+        original_image_path.to_str().unwrap(),
+        "-t",
+        source_text_path.to_str().unwrap(),
+        "-d",
+        hidden_image_path.to_str().unwrap(),
+        "--verbose",
+    ]);
+    assert!(hide_output.contains("Payload size: 10 bytes"));
+    assert!(hide_output.contains(&format!("Total capacity: {} bytes", capacity)));
+    assert!(hide_output.contains(&format!(
+        "Capacity used: {:.2}%",
+        10.0 / capacity as f64 * 100.0
+    )));
+    assert!(hide_output.contains(&format!("Modified byte range: 0..{}", end_offset)));
+
+    let recover_output = run_cli(&[
+        "recover",
+        "-i",
+        hidden_image_path.to_str().unwrap(),
+        "-t",
+        recovered_text_path.to_str().unwrap(),
+        "--verbose",
+    ]);
+    assert!(recover_output.contains("Recovered length: 10 bytes"));
+    assert!(recover_output.contains(&format!(
+        "Extraction stopped at byte offset: {}",
+        end_offset
+    )));
+
+    // 未指定 --verbose 时不输出统计信息
+    let quiet_output = run_cli(&[
+        "hide",
+        "-i",
+        original_image_path.to_str().unwrap(),
+        "-t",
+        source_text_path.to_str().unwrap(),
+        "-d",
+        hidden_image_path.to_str().unwrap(),
+        "--force",
+    ]);
+    assert!(!quiet_output.contains("Capacity used"));
+
+    Ok(())
+}
+
+/// 验证输出路径为有损格式时，hide 会拒绝写入并说明原因
+#[test]
+fn test_handle_hide_rejects_lossy_destination() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let original_image_path = dir.path().join("original.png");
+    let source_text_path = dir.path().join("source.txt");
+    let lossy_path = dir.path().join("foo.JPG");
+
+    create_test_image(&original_image_path, 20, 20);
+    fs::write(&source_text_path, "JPEG would eat this.")?;
+
+    let err = handle_hide(HideArgs {
+        image: Some(original_image_path),
+        text: vec![source_text_path],
+        dest: Some(lossy_path.clone()),
+        ..Default::default()
+    })
+    .expect_err("Hiding into a lossy format should fail.");
+
+    assert!(err.to_string().contains("Output format is lossy"));
+    assert!(err.to_string().contains("--force-lossy"));
+    assert!(!lossy_path.exists());
+
+    Ok(())
+}
+
+/// 验证 `--message` 直接隐藏命令行中的字符串，且不能与 `--text` 同时使用
+#[test]
+fn test_hide_message_and_recover() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let original_image_path = dir.path().join("original.png");
+    let hidden_image_path = dir.path().join("hidden.png");
+    let recovered_text_path = dir.path().join("recovered.txt");
+
+    create_test_image(&original_image_path, 20, 20);
+
+    run_cli(&[
+        "hide",
+        "-i",
+        original_image_path.to_str().unwrap(),
+        "--message",
+        "hi",
+        "-d",
+        hidden_image_path.to_str().unwrap(),
+    ]);
+    run_cli(&[
+        "recover",
+        "-i",
+        hidden_image_path.to_str().unwrap(),
+        "-t",
+        recovered_text_path.to_str().unwrap(),
+    ]);
+    assert_eq!(fs::read_to_string(&recovered_text_path)?, "hi");
+
+    let conflict = Command::new(env!("CARGO_BIN_EXE_lsb_hide"))
+        .args([
+            "hide",
+            "-i",
+            original_image_path.to_str().unwrap(),
+            "-m",
+            "hi",
+            "-t",
+            "source.txt",
+        ])
+        .output()?;
+    assert!(!conflict.status.success());
+    assert!(String::from_utf8_lossy(&conflict.stderr).contains("cannot be used with"));
+
+    Ok(())
+}
+
+/// 验证 `--in-place` 需要 `--force` 确认，确认后载荷写入原始图像并可以恢复
+#[test]
+fn test_handle_hide_in_place() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let image_path = dir.path().join("original.png");
+    let source_text_path = dir.path().join("source.txt");
+    let recovered_text_path = dir.path().join("recovered.txt");
+
+    create_test_image(&image_path, 20, 20);
+    fs::write(&source_text_path, "Written over the source.")?;
+    let original_bytes = fs::read(&image_path)?;
+
+    let in_place = |force: bool| {
+        handle_hide(HideArgs {
+            image: Some(image_path.clone()),
+            text: vec![source_text_path.clone()],
+            in_place: true,
+            force,
+            ..Default::default()
+        })
+    };
+
+    let err = in_place(false).expect_err("In-place hiding without --force should fail.");
+    assert!(err.to_string().contains("--force"));
+    assert_eq!(fs::read(&image_path)?, original_bytes);
+
+    in_place(true)?;
+    assert_ne!(fs::read(&image_path)?, original_bytes);
+    assert!(!dir.path().join("doctored_original.png").exists());
+
+    handle_recover(RecoverArgs {
+        image: image_path,
+        text: Some(recovered_text_path.clone()),
+        ..Default::default()
+    })?;
+    assert_eq!(
+        fs::read_to_string(&recovered_text_path)?,
+        "Written over the source."
+    );
+
+    Ok(())
+}
+
+/// 验证 `--output-format` 按指定格式写出结果，与输出路径的扩展名无关
+#[test]
+fn test_handle_hide_with_output_format() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let original_image_path = dir.path().join("original.png");
+    let source_text_path = dir.path().join("source.txt");
+    let dest_path = dir.path().join("out.dat");
+    let recovered_text_path = dir.path().join("recovered.txt");
+
+    create_test_image(&original_image_path, 20, 20);
+    fs::write(&source_text_path, "PNG in disguise.")?;
+
+    handle_hide(HideArgs {
+        image: Some(original_image_path),
+        text: vec![source_text_path],
+        dest: Some(dest_path.clone()),
+        output_format: Some(OutputFormat::Png),
+        ..Default::default()
+    })?;
+
+    let written = fs::read(&dest_path)?;
+    assert_eq!(image::guess_format(&written)?, image::ImageFormat::Png);
+
+    // 恢复时根据文件内容识别格式
+    handle_recover(RecoverArgs {
+        image: dest_path,
+        text: Some(recovered_text_path.clone()),
+        ..Default::default()
+    })?;
+    assert_eq!(
+        fs::read_to_string(&recovered_text_path)?,
+        "PNG in disguise."
+    );
+
+    Ok(())
+}
+
+/// 验证 `--offset` 会保持前 N 个像素不变，且恢复时需要相同的偏移量
+#[test]
+fn test_handle_hide_and_recover_with_offset() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let original_image_path = dir.path().join("original.png");
+    let hidden_image_path = dir.path().join("hidden.png");
+    let source_text_path = dir.path().join("source.txt");
+    let recovered_text_path = dir.path().join("recovered.txt");
+
+    create_test_image(&original_image_path, 100, 100);
+    let original_text = "Thumbnail region preserved.";
+    fs::write(&source_text_path, original_text)?;
+
+    handle_hide(HideArgs {
+        image: Some(original_image_path.clone()),
+        text: vec![source_text_path],
+        dest: Some(hidden_image_path.clone()),
+        offset: 1000,
+        ..Default::default()
+    })?;
+
+    let original = image::open(&original_image_path)?.into_rgba8().into_raw();
+    let hidden = image::open(&hidden_image_path)?.into_rgba8().into_raw();
+    assert_eq!(original[..1000 * 4], hidden[..1000 * 4]);
+    assert_ne!(original, hidden);
+
+    // 不指定偏移量时找不到头部
+    let err = handle_recover(RecoverArgs {
+        image: hidden_image_path.clone(),
+        text: Some(recovered_text_path.clone()),
+        ..Default::default()
+    })
+    .expect_err("Recovery without the offset should fail.");
+    assert_eq!(
+        err.downcast_ref::<StegError>(),
+        Some(&StegError::MagicMismatch)
+    );
+
+    handle_recover(RecoverArgs {
+        image: hidden_image_path,
+        text: Some(recovered_text_path.clone()),
+        offset: 1000,
+        ..Default::default()
+    })?;
+    assert_eq!(fs::read_to_string(&recovered_text_path)?, original_text);
+
+    Ok(())
+}
+
+/// 验证 `--json` 会为 hide、recover 及错误输出可解析的 JSON 对象
+#[test]
+fn test_json_output() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let original_image_path = dir.path().join("original.png");
+    let hidden_image_path = dir.path().join("hidden.png");
+    let source_text_path = dir.path().join("source.txt");
+    let recovered_text_path = dir.path().join("recovered.txt");
+
+    create_test_image(&original_image_path, 10, 10);
+    fs::write(&source_text_path, "0123456789")?;
+
+    let hide_output = run_cli(&[
+        "--json",
+        "hide",
+        "-i",
+        original_image_path.to_str().unwrap(),
+        "-t",
+        source_text_path.to_str().unwrap(),
+        "-d",
+        hidden_image_path.to_str().unwrap(),
+    ]);
+    let hide_json: serde_json::Value = serde_json::from_str(&hide_output)?;
+    assert_eq!(hide_json["status"], "ok");
+    assert_eq!(hide_json["dest"], hidden_image_path.to_str().unwrap());
+    assert_eq!(hide_json["payload_bytes"], 10);
+    assert_eq!(
+        hide_json["capacity_bytes"],
+        max_payload_bytes(10 * 10 * 4, DEFAULT_BITS as usize) - "source.txt".len() - TYPE_TAG_LEN
+    );
+
+    // 全局标志也可以写在子命令之后
+    let recover_output = run_cli(&[
+        "recover",
+        "-i",
+        hidden_image_path.to_str().unwrap(),
+        "-t",
+        recovered_text_path.to_str().unwrap(),
+        "--json",
+    ]);
+    let recover_json: serde_json::Value = serde_json::from_str(&recover_output)?;
+    assert_eq!(recover_json["status"], "ok");
+    assert_eq!(
+        recover_json["output"],
+        recovered_text_path.to_str().unwrap()
+    );
+    assert_eq!(recover_json["recovered_bytes"], 10);
+
+    // 错误同样以 JSON 输出，并以非零状态码退出
+    let output = Command::new(env!("CARGO_BIN_EXE_lsb_hide"))
+        .args([
+            "--json",
+            "recover",
+            "-i",
+            original_image_path.to_str().unwrap(),
+        ])
+        .output()?;
+    assert!(!output.status.success());
+    let error_json: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(error_json["status"], "error");
+    assert_eq!(error_json["message"], NO_PAYLOAD_MESSAGE);
+
+    Ok(())
+}
+
+/// 验证 `--quiet` 成功时不向标准输出写入任何内容，与 `--json` 同时使用时仍输出 JSON，出错时仍报告错误
+#[test]
+fn test_quiet_suppresses_success_output() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let original_image_path = dir.path().join("original.png");
+    let hidden_image_path = dir.path().join("hidden.png");
+    let source_text_path = dir.path().join("source.txt");
+    let recovered_text_path = dir.path().join("recovered.txt");
+
+    create_test_image(&original_image_path, 10, 10);
+    fs::write(&source_text_path, "0123456789")?;
+
+    let hide_output = run_cli(&[
+        "--quiet",
+        "hide",
+        "-i",
+        original_image_path.to_str().unwrap(),
+        "-t",
+        source_text_path.to_str().unwrap(),
+        "-d",
+        hidden_image_path.to_str().unwrap(),
+        "--verbose",
+    ]);
+    assert_eq!(hide_output, "");
+
+    let recover_output = run_cli(&[
+        "recover",
+        "-i",
+        hidden_image_path.to_str().unwrap(),
+        "-t",
+        recovered_text_path.to_str().unwrap(),
+        "-q",
+    ]);
+    assert_eq!(recover_output, "");
+    assert_eq!(fs::read_to_string(&recovered_text_path)?, "0123456789");
+
+    let json_output = run_cli(&[
+        "--quiet",
+        "--json",
+        "recover",
+        "-i",
+        hidden_image_path.to_str().unwrap(),
+        "-t",
+        recovered_text_path.to_str().unwrap(),
+        "--force",
+    ]);
+    let recover_json: serde_json::Value = serde_json::from_str(&json_output)?;
+    assert_eq!(recover_json["status"], "ok");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_lsb_hide"))
+        .args(["-q", "recover", "-i", original_image_path.to_str().unwrap()])
+        .output()?;
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+    assert!(String::from_utf8_lossy(&output.stderr).contains(NO_PAYLOAD_MESSAGE));
+
+    Ok(())
+}
+
+/// 验证输出目录不存在时 hide 与 recover 都给出明确的错误，指定 `--mkdir` 时自动创建
+#[test]
+fn test_missing_output_directory_and_mkdir() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let original_image_path = dir.path().join("original.png");
+    let hidden_image_path = dir.path().join("images/nested/hidden.png");
+    let recovered_text_path = dir.path().join("texts/recovered.txt");
+
+    create_test_image(&original_image_path, 10, 10);
+
+    let hide_args = || HideArgs {
+        image: Some(original_image_path.clone()),
+        message: Some("mkdir".to_string()),
+        dest: Some(hidden_image_path.clone()),
+        ..Default::default()
+    };
+    let err = handle_hide(hide_args()).unwrap_err();
+    assert!(
+        err.to_string()
+            .starts_with("Destination directory does not exist:")
+    );
+    assert!(!hidden_image_path.parent().unwrap().exists());
+
+    // 试运行即使指定了 --mkdir 也不创建目录
+    handle_hide(HideArgs {
+        mkdir: true,
+        dry_run: true,
+        ..hide_args()
+    })?;
+    assert!(!hidden_image_path.parent().unwrap().exists());
+
+    handle_hide(HideArgs {
+        mkdir: true,
+        ..hide_args()
+    })?;
+    assert!(hidden_image_path.exists());
+
+    let recover_args = || RecoverArgs {
+        image: hidden_image_path.clone(),
+        text: Some(recovered_text_path.clone()),
+        ..Default::default()
+    };
+    let err = handle_recover(recover_args()).unwrap_err();
+    assert!(
+        err.to_string()
+            .starts_with("Destination directory does not exist:")
+    );
+
+    handle_recover(RecoverArgs {
+        mkdir: true,
+        ..recover_args()
+    })?;
+    assert_eq!(fs::read_to_string(&recovered_text_path)?, "mkdir");
+
+    Ok(())
+}
+
+/// 验证 clean 会销毁隐藏的载荷，且每个像素字节只有最低两位可能改变
+#[test]
+fn test_clean_destroys_hidden_payload() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let original_image_path = dir.path().join("original.png");
+    let hidden_image_path = dir.path().join("hidden.png");
+    let cleaned_image_path = dir.path().join("cleaned.png");
+
+    create_test_image(&original_image_path, 20, 20);
+    handle_hide(HideArgs {
+        image: Some(original_image_path.clone()),
+        message: Some("wipe me".to_string()),
+        dest: Some(hidden_image_path.clone()),
+        ..Default::default()
+    })?;
+
+    for mode in [CleanMode::Random, CleanMode::Zero] {
+        handle_clean(CleanArgs {
+            image: hidden_image_path.clone(),
+            dest: Some(cleaned_image_path.clone()),
+            force: true,
+            mode,
+            ..Default::default()
+        })?;
+
+        let hidden = image::open(&hidden_image_path)?.into_rgba8();
+        let cleaned = image::open(&cleaned_image_path)?.into_rgba8();
+        assert!(
+            hidden
+                .as_raw()
+                .iter()
+                .zip(cleaned.as_raw())
+                .all(|(before, after)| before >> 2 == after >> 2),
+            "Only the lowest two bits should change."
+        );
+        if mode == CleanMode::Zero {
+            assert!(cleaned.as_raw().iter().all(|byte| byte & 0b11 == 0));
+        }
+
+        let err = handle_recover(RecoverArgs {
+            image: cleaned_image_path.clone(),
+            text: Some(dir.path().join("recovered.txt")),
+            ..Default::default()
+        })
+        .unwrap_err();
+        assert_eq!(err.to_string(), NO_PAYLOAD_MESSAGE);
+    }
+
+    // 与 hide 一样拒绝覆盖已存在的文件和有损格式
+    let err = handle_clean(CleanArgs {
+        image: hidden_image_path.clone(),
+        dest: Some(cleaned_image_path.clone()),
+        ..Default::default()
+    })
+    .unwrap_err();
+    assert!(err.to_string().contains("Output file already exists"));
+    let err = handle_clean(CleanArgs {
+        image: hidden_image_path.clone(),
+        dest: Some(dir.path().join("cleaned.jpg")),
+        ..Default::default()
+    })
+    .unwrap_err();
+    assert!(err.to_string().contains("lossy"));
+
+    // `--quiet` 成功时不输出提示信息
+    let clean_output = run_cli(&[
+        "-q",
+        "clean",
+        "-i",
+        hidden_image_path.to_str().unwrap(),
+        "-d",
+        cleaned_image_path.to_str().unwrap(),
+        "--force",
+    ]);
+    assert_eq!(clean_output, "");
+
+    Ok(())
+}
+
+/// 验证多个文件会被打包隐藏，并在恢复时以原始文件名解包到目录中
+#[test]
+fn test_handle_hide_and_recover_multiple_files() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let original_image_path = dir.path().join("original.png");
+    let hidden_image_path = dir.path().join("hidden.png");
+    let first_path = dir.path().join("a.txt");
+    let second_path = dir.path().join("b.bin");
+    let output_dir = dir.path().join("extracted");
+
+    create_test_image(&original_image_path, 100, 100);
+    let mut binary = vec![0u8; 512];
+    rand::rng().fill_bytes(&mut binary);
+    fs::write(&first_path, "First file. 第一个文件。")?;
+    fs::write(&second_path, &binary)?;
+
+    handle_hide(HideArgs {
+        image: Some(original_image_path),
+        text: vec![first_path.clone(), second_path.clone()],
+        dest: Some(hidden_image_path.clone()),
+        ..Default::default()
+    })?;
+
+    let recover_args = || RecoverArgs {
+        image: hidden_image_path.clone(),
+        text: Some(output_dir.clone()),
+        ..Default::default()
+    };
+    handle_recover(recover_args())?;
+
+    assert_eq!(fs::read(output_dir.join("a.txt"))?, fs::read(&first_path)?);
+    assert_eq!(fs::read(output_dir.join("b.bin"))?, binary);
+    assert_eq!(fs::read_dir(&output_dir)?.count(), 2);
+
+    // 再次解包时，已存在的文件需要 --force 才能覆盖
+    assert!(handle_recover(recover_args()).is_err());
+    handle_recover(RecoverArgs {
+        force: true,
+        ..recover_args()
+    })?;
+
+    Ok(())
+}
+
+/// 验证 `--dry-run` 成功返回但不会创建输出文件，且不受已存在文件的影响
+#[test]
+fn test_handle_hide_dry_run() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let original_image_path = dir.path().join("original.png");
+    let source_text_path = dir.path().join("source.txt");
+    let dest_path = dir.path().join("dry.png");
+
+    create_test_image(&original_image_path, 20, 20);
+    fs::write(&source_text_path, "Just checking.")?;
+
+    let dry_run_args = || HideArgs {
+        image: Some(original_image_path.clone()),
+        text: vec![source_text_path.clone()],
+        dest: Some(dest_path.clone()),
+        dry_run: true,
+        ..Default::default()
+    };
+
+    handle_hide(dry_run_args())?;
+    assert!(
+        !dest_path.exists(),
+        "Dry run must not write an output file."
+    );
+
+    // 已存在的输出文件不会阻止试运行，也不会被修改
+    fs::write(&dest_path, "placeholder")?;
+    handle_hide(dry_run_args())?;
+    assert_eq!(fs::read_to_string(&dest_path)?, "placeholder");
+
+    // 容量不足等错误仍然会被报告
+    fs::write(&source_text_path, "x".repeat(1000))?;
+    assert!(handle_hide(dry_run_args()).is_err());
+
+    Ok(())
+}
+
+/// 验证 info 命令对普通图像和隐写图像分别给出不同的报告
+#[test]
+fn test_info_reports_plain_and_stego_images() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let original_image_path = dir.path().join("original.png");
+    let hidden_image_path = dir.path().join("hidden.png");
+    let source_text_path = dir.path().join("source.txt");
+
+    create_test_image(&original_image_path, 50, 50);
+    fs::write(&source_text_path, "Inspect me. ".repeat(20))?;
+
+    handle_hide(HideArgs {
+        image: Some(original_image_path.clone()),
+        text: vec![source_text_path.clone()],
+        dest: Some(hidden_image_path.clone()),
+        bits: 3,
+        password: Some("info password".to_string()),
+        compress: Compression::Gzip,
+        ..Default::default()
+    })?;
+
+    let plain_output = run_cli(&["info", "-i", original_image_path.to_str().unwrap()]);
+    assert!(plain_output.contains("No lsb_hide payload detected"));
+
+    let stego_output = run_cli(&["info", "-i", hidden_image_path.to_str().unwrap()]);
+    assert!(stego_output.contains("lsb_hide payload detected"));
+    assert!(stego_output.contains("Bit depth: 3 bits per byte"));
+    assert!(stego_output.contains("Encrypted: yes"));
+    assert!(stego_output.contains("Compression: gzip"));
+
+    let plain_json: serde_json::Value = serde_json::from_str(&run_cli(&[
+        "--json",
+        "info",
+        "-i",
+        original_image_path.to_str().unwrap(),
+    ]))?;
+    assert_eq!(plain_json["payload_detected"], false);
+
+    let stego_json: serde_json::Value = serde_json::from_str(&run_cli(&[
+        "--json",
+        "info",
+        "-i",
+        hidden_image_path.to_str().unwrap(),
+    ]))?;
+    assert_eq!(stego_json["payload_detected"], true);
+    assert_eq!(stego_json["bits"], 3);
+    assert_eq!(stego_json["encrypted"], true);
+    assert_eq!(stego_json["compression"], "gzip");
+    assert!(stego_json["payload_bytes"].as_u64().unwrap() > 0);
+
+    // info 只读，不会产生任何输出文件
+    assert_eq!(fs::read_dir(dir.path())?.count(), 3);
+
+    Ok(())
+}
+
+/// 验证命令行隐写与直接调用核心函数的结果逐字节一致：两者都从解码后像素的第 0 个字节开始写入，
+/// 不会为 BMP 等格式的文件头预留或跳过任何区域
+#[test]
+fn test_handler_and_core_embed_from_the_same_offset() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let original_image_path = dir.path().join("original.png");
+    let source_text_path = dir.path().join("source.txt");
+
+    create_test_image(&original_image_path, 40, 40);
+    let payload = b"Same bytes, same place.";
+    fs::write(&source_text_path, payload)?;
+
+    let mut expected = image::open(&original_image_path)?.into_rgba8().into_raw();
+    let options = HideOptions {
+        name: "source.txt".to_string(),
+        payload_type: Some(PayloadType::Text),
+        ..Default::default()
+    };
+    hide_bytes_with(&mut expected, payload, &options)?;
+
+    for extension in ["png", "bmp"] {
+        let hidden_image_path = dir.path().join(format!("hidden.{}", extension));
+        handle_hide(HideArgs {
+            image: Some(original_image_path.clone()),
+            text: vec![source_text_path.clone()],
+            dest: Some(hidden_image_path.clone()),
+            ..Default::default()
+        })?;
+
+        let hidden = image::open(&hidden_image_path)?.into_rgba8().into_raw();
+        assert_eq!(
+            hidden, expected,
+            "{} output differs from the core",
+            extension
+        );
+        assert!(has_magic(&hidden));
+        assert_eq!(recover_bytes(&hidden)?, payload);
+    }
+
+    Ok(())
+}
+
+/// 验证 recover --encoding 以 raw、base64 与 hex 写出的结果解码后都与原始载荷一致
+#[test]
+fn test_recover_with_each_encoding() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let original_image_path = dir.path().join("original.png");
+    let hidden_image_path = dir.path().join("hidden.png");
+    let source_path = dir.path().join("payload.bin");
+
+    create_test_image(&original_image_path, 60, 60);
+    let payload: Vec<u8> = (0..=255).collect();
+    fs::write(&source_path, &payload)?;
+
+    handle_hide(HideArgs {
+        image: Some(original_image_path.clone()),
+        text: vec![source_path.clone()],
+        dest: Some(hidden_image_path.clone()),
+        binary: true,
+        ..Default::default()
+    })?;
+
+    let recover = |encoding: Encoding| -> anyhow::Result<Vec<u8>> {
+        let output_path = dir.path().join(format!("recovered.{}", encoding));
+        handle_recover(RecoverArgs {
+            image: hidden_image_path.clone(),
+            text: Some(output_path.clone()),
+            binary: true,
+            encoding,
+            ..Default::default()
+        })?;
+        Ok(fs::read(output_path)?)
+    };
+
+    assert_eq!(recover(Encoding::Raw)?, payload);
+
+    let base64 = String::from_utf8(recover(Encoding::Base64)?)?;
+    assert!(base64.ends_with('\n'));
+    assert_eq!(BASE64_STANDARD.decode(base64.trim_end())?, payload);
+
+    let hex = String::from_utf8(recover(Encoding::Hex)?)?;
+    assert_eq!(hex.len(), payload.len() * 2 + 1);
+    let decoded = (0..hex.trim_end().len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16))
+        .collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(decoded, payload);
+
+    Ok(())
+}
+
+/// 验证载荷整体向后错开几个字节时，只有指定 --resync 才能找到头部并恢复
+#[test]
+fn test_recover_resync_finds_shifted_header() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let original_image_path = dir.path().join("original.png");
+    let hidden_image_path = dir.path().join("hidden.png");
+    let shifted_image_path = dir.path().join("shifted.png");
+    let source_text_path = dir.path().join("source.txt");
+    let recovered_text_path = dir.path().join("recovered.txt");
+
+    create_test_image(&original_image_path, 50, 50);
+    let original_text = "Found again after a small shift.";
+    fs::write(&source_text_path, original_text)?;
+
+    handle_hide(HideArgs {
+        image: Some(original_image_path.clone()),
+        text: vec![source_text_path.clone()],
+        dest: Some(hidden_image_path.clone()),
+        ..Default::default()
+    })?;
+
+    // 模拟重新保存时在像素数据前插入了 3 个字节 (不是整数个像素)
+    let hidden = image::open(&hidden_image_path)?.into_rgba8();
+    let mut shifted_bytes = vec![0x7Fu8; 3];
+    shifted_bytes.extend_from_slice(&hidden.as_raw()[..hidden.as_raw().len() - 3]);
+    ImageBuffer::<Rgba<u8>, _>::from_raw(50, 50, shifted_bytes)
+        .unwrap()
+        .save(&shifted_image_path)?;
+
+    let recover_args = |resync: bool| RecoverArgs {
+        image: shifted_image_path.clone(),
+        text: Some(recovered_text_path.clone()),
+        force: true,
+        resync,
+        ..Default::default()
+    };
+
+    let err = handle_recover(recover_args(false))
+        .expect_err("The shifted header should not be found without --resync.");
+    assert_eq!(err.to_string(), NO_PAYLOAD_MESSAGE);
+
+    handle_recover(recover_args(true))?;
+    assert_eq!(fs::read_to_string(&recovered_text_path)?, original_text);
+
+    Ok(())
+}
+
+/// 验证每种 --length-bits 设置都能通过命令行往返，并由 info 报告
+#[test]
+fn test_hide_and_recover_each_length_bits() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let original_image_path = dir.path().join("original.png");
+    let hidden_image_path = dir.path().join("hidden.png");
+    let source_text_path = dir.path().join("source.txt");
+    let recovered_text_path = dir.path().join("recovered.txt");
+
+    create_test_image(&original_image_path, 50, 50);
+    let original_text = "Length fields come in three sizes.";
+    fs::write(&source_text_path, original_text)?;
+
+    for length_bits in [16u8, 32, 64] {
+        handle_hide(HideArgs {
+            image: Some(original_image_path.clone()),
+            text: vec![source_text_path.clone()],
+            dest: Some(hidden_image_path.clone()),
+            force: true,
+            length_bits: Some(length_bits),
+            ..Default::default()
+        })?;
+
+        let info: serde_json::Value = serde_json::from_str(&run_cli(&[
+            "--json",
+            "info",
+            "-i",
+            hidden_image_path.to_str().unwrap(),
+        ]))?;
+        assert_eq!(info["length_bits"], length_bits);
+
+        handle_recover(RecoverArgs {
+            image: hidden_image_path.clone(),
+            text: Some(recovered_text_path.clone()),
+            force: true,
+            ..Default::default()
+        })?;
+        assert_eq!(fs::read_to_string(&recovered_text_path)?, original_text);
+    }
+
+    Ok(())
+}
+
+/// 验证 --redundancy 写入的图像在一份副本被破坏后仍能通过命令行恢复，并由 info 报告份数
+#[test]
+fn test_redundancy_recovers_after_corruption() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let original_image_path = dir.path().join("original.png");
+    let hidden_image_path = dir.path().join("hidden.png");
+    let source_text_path = dir.path().join("source.txt");
+    let recovered_text_path = dir.path().join("recovered.txt");
+
+    create_test_image(&original_image_path, 50, 50);
+    let original_text = "Three copies, one vote each.";
+    fs::write(&source_text_path, original_text)?;
+
+    handle_hide(HideArgs {
+        image: Some(original_image_path.clone()),
+        text: vec![source_text_path.clone()],
+        dest: Some(hidden_image_path.clone()),
+        redundancy: 3,
+        ..Default::default()
+    })?;
+
+    let info: serde_json::Value = serde_json::from_str(&run_cli(&[
+        "--json",
+        "info",
+        "-i",
+        hidden_image_path.to_str().unwrap(),
+    ]))?;
+    assert_eq!(info["redundancy"], 3);
+
+    // 破坏第一份副本所在的区域
+    let mut image = image::open(&hidden_image_path)?.to_rgba8();
+    let header = read_header(image.as_raw())?;
+    let copy_space = original_text.len() * BYTES_PER_CHAR;
+    let start = header.payload_offset();
+    for byte in &mut image.as_mut()[start..start + copy_space] {
+        *byte ^= 0b11;
+    }
+    image.save(&hidden_image_path)?;
+
+    handle_recover(RecoverArgs {
+        image: hidden_image_path.clone(),
+        text: Some(recovered_text_path.clone()),
+        ..Default::default()
+    })?;
+    assert_eq!(fs::read_to_string(&recovered_text_path)?, original_text);
+
+    Ok(())
+}
+
+/// 验证 --max-capacity-fraction 恰好达到阈值时允许隐藏，超过一个字节即报错并报告占用比例
+#[test]
+fn test_max_capacity_fraction_threshold() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let original_image_path = dir.path().join("original.png");
+    let hidden_image_path = dir.path().join("hidden.png");
+    let source_text_path = dir.path().join("source.txt");
+
+    create_test_image(&original_image_path, 50, 50);
+
+    // 长度前缀在 128 字节以内保持 1 个字节，因此 100 与 101 字节的载荷可用容量相同
+    let hide_args = |len: usize, fraction: f64| -> anyhow::Result<HideArgs> {
+        fs::write(&source_text_path, "x".repeat(len))?;
+        Ok(HideArgs {
+            image: Some(original_image_path.clone()),
+            text: vec![source_text_path.clone()],
+            dest: Some(hidden_image_path.clone()),
+            force: true,
+            max_capacity_fraction: fraction,
+            ..Default::default()
+        })
+    };
+    let capacity =
+        max_payload_bytes(50 * 50 * 4, DEFAULT_BITS as usize) - "source.txt".len() - TYPE_TAG_LEN;
+    let fraction = 100.0 / capacity as f64;
+
+    handle_hide(hide_args(100, fraction)?)?;
+    assert!(hidden_image_path.exists());
+    fs::remove_file(&hidden_image_path)?;
+
+    let err = handle_hide(hide_args(101, fraction)?)
+        .expect_err("A payload over the capacity fraction should be rejected.");
+    let message = err.to_string();
+    assert!(message.contains("--max-capacity-fraction"));
+    assert!(message.contains(&format!("{:.4}", 101.0 / capacity as f64)));
+    assert!(!hidden_image_path.exists());
+
+    Ok(())
+}
+
+/// 验证 --provenance 记录的隐藏时间与工具版本可以通过 info 读回，且时间戳落在隐藏前后的时间窗口内
+#[test]
+fn test_provenance_is_reported_by_info() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let original_image_path = dir.path().join("original.png");
+    let hidden_image_path = dir.path().join("hidden.png");
+    let plain_image_path = dir.path().join("plain.png");
+    let source_text_path = dir.path().join("source.txt");
+
+    create_test_image(&original_image_path, 50, 50);
+    fs::write(&source_text_path, "When was this hidden?")?;
+
+    let unix_now = || {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+    };
+    let before = unix_now();
+    handle_hide(HideArgs {
+        image: Some(original_image_path.clone()),
+        text: vec![source_text_path.clone()],
+        dest: Some(hidden_image_path.clone()),
+        provenance: true,
+        ..Default::default()
+    })?;
+    let after = unix_now();
+
+    let info: serde_json::Value = serde_json::from_str(&run_cli(&[
+        "--json",
+        "info",
+        "-i",
+        hidden_image_path.to_str().unwrap(),
+    ]))?;
+    let timestamp = info["timestamp"].as_u64().unwrap();
+    assert!((before..=after).contains(&timestamp));
+    assert_eq!(info["tool_version"], env!("CARGO_PKG_VERSION"));
+
+    let text_output = run_cli(&["info", "-i", hidden_image_path.to_str().unwrap()]);
+    assert!(text_output.contains(&format!("Unix time {}", timestamp)));
+
+    // 默认不记录来源信息
+    handle_hide(HideArgs {
+        image: Some(original_image_path.clone()),
+        text: vec![source_text_path.clone()],
+        dest: Some(plain_image_path.clone()),
+        ..Default::default()
+    })?;
+    let info: serde_json::Value = serde_json::from_str(&run_cli(&[
+        "--json",
+        "info",
+        "-i",
+        plain_image_path.to_str().unwrap(),
+    ]))?;
+    assert!(info["timestamp"].is_null());
+    assert!(info["tool_version"].is_null());
+
+    Ok(())
+}
+
+/// 验证灰度图像 (L 与 LA) 按原始颜色类型隐写并恢复，16 位图像会被明确拒绝
+#[test]
+fn test_handle_hide_and_recover_grayscale() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let source_text_path = dir.path().join("source.txt");
+    fs::write(&source_text_path, "Shades of grey. 灰色。")?;
+
+    let luma_path = dir.path().join("luma.png");
+    let luma_alpha_path = dir.path().join("luma_alpha.png");
+    image::GrayImage::from_fn(40, 40, |x, y| image::Luma([(x * 7 + y * 3) as u8]))
+        .save(&luma_path)?;
+    image::GrayAlphaImage::from_fn(40, 40, |x, y| image::LumaA([(x * 5 + y) as u8, 200]))
+        .save(&luma_alpha_path)?;
+
+    for (original_path, skip_alpha) in [(&luma_path, false), (&luma_alpha_path, true)] {
+        let original = image::open(original_path)?;
+        let hidden_path = dir.path().join("hidden.png");
+        let recovered_path = dir.path().join("recovered.txt");
+
+        handle_hide(HideArgs {
+            image: Some(original_path.clone()),
+            text: vec![source_text_path.clone()],
+            dest: Some(hidden_path.clone()),
+            skip_alpha,
+            force: true,
+            ..Default::default()
+        })?;
+
+        // 输出图像保持原始的颜色类型
+        let hidden = image::open(&hidden_path)?;
+        assert_eq!(hidden.color(), original.color());
+        if skip_alpha {
+            let alpha = |img: &image::DynamicImage| -> Vec<u8> {
+                img.to_luma_alpha8().pixels().map(|p| p[1]).collect()
+            };
+            assert_eq!(alpha(&hidden), alpha(&original));
+        }
+
+        handle_recover(RecoverArgs {
+            image: hidden_path,
+            text: Some(recovered_path.clone()),
+            force: true,
+            ..Default::default()
+        })?;
+        assert_eq!(
+            fs::read_to_string(&recovered_path)?,
+            fs::read_to_string(&source_text_path)?
+        );
+    }
+
+    Ok(())
+}
+
+/// 测试 16 位 RGB 与 RGBA 图像：数据只写入每个样本的低字节，输出保持 16 位且高字节不变
+#[test]
+fn test_hide_recover_16bit_image() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let source_text_path = dir.path().join("source.txt");
+    fs::write(&source_text_path, "Sixteen bits per channel.")?;
+
+    // 样本覆盖完整的 16 位范围，高字节与低字节都各不相同
+    let sample = |x: u32, y: u32, c: u32| ((x * 7919 + y * 104_729 + c * 31_337) % 65_536) as u16;
+    let rgb_path = dir.path().join("wide_rgb.png");
+    image::ImageBuffer::from_fn(40, 40, |x, y| {
+        image::Rgb([0, 1, 2].map(|c| sample(x, y, c)))
+    })
+    .save(&rgb_path)?;
+    let rgba_path = dir.path().join("wide_rgba.png");
+    image::ImageBuffer::from_fn(40, 40, |x, y| {
+        image::Rgba([sample(x, y, 0), sample(x, y, 1), sample(x, y, 2), u16::MAX])
+    })
+    .save(&rgba_path)?;
+
+    for original_path in [&rgb_path, &rgba_path] {
+        let original = image::open(original_path)?;
+        let hidden_path = dir.path().join("wide_hidden.png");
+        let recovered_path = dir.path().join("wide_recovered.txt");
+
+        handle_hide(HideArgs {
+            image: Some(original_path.clone()),
+            text: vec![source_text_path.clone()],
+            dest: Some(hidden_path.clone()),
+            force: true,
+            ..Default::default()
+        })?;
+
+        let hidden = image::open(&hidden_path)?;
+        assert_eq!(hidden.color(), original.color());
+        let (before, after) = (original.to_rgba16(), hidden.to_rgba16());
+        let mut changed = 0;
+        for (a, b) in before.as_raw().iter().zip(after.as_raw()) {
+            assert_eq!(a >> 8, b >> 8, "high bytes must be preserved");
+            assert!(a.abs_diff(*b) < 4, "only the lowest bits may change");
+            changed += usize::from(a != b);
+        }
+        assert!(changed > 0);
+
+        handle_recover(RecoverArgs {
+            image: hidden_path,
+            text: Some(recovered_path.clone()),
+            force: true,
+            ..Default::default()
+        })?;
+        assert_eq!(
+            fs::read_to_string(&recovered_path)?,
+            fs::read_to_string(&source_text_path)?
+        );
+    }
+
+    Ok(())
+}
+
+/// 测试 `--raw-bmp` 直接在 BMP 文件字节中隐写：文件头逐字节保持不变，载荷可从原始字节中恢复
+#[test]
+fn test_hide_recover_raw_bmp_preserves_header() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let original_path = dir.path().join("original.bmp");
+    let hidden_path = dir.path().join("hidden.bmp");
+    let source_text_path = dir.path().join("source.txt");
+    let recovered_path = dir.path().join("recovered.txt");
+
+    // 宽度为 13 像素的 RGB 行需要填充字节，原始字节中的行填充同样用于隐写
+    let mut rng = ChaCha20Rng::seed_from_u64(83);
+    image::RgbImage::from_fn(13, 20, |_, _| {
+        image::Rgb([
+            rng.next_u32() as u8,
+            rng.next_u32() as u8,
+            rng.next_u32() as u8,
+        ])
+    })
+    .save(&original_path)?;
+    let original_text = "Bit-exact header, bit-exact structure.";
+    fs::write(&source_text_path, original_text)?;
+
+    handle_hide(HideArgs {
+        image: Some(original_path.clone()),
+        text: vec![source_text_path],
+        dest: Some(hidden_path.clone()),
+        raw_bmp: true,
+        ..Default::default()
+    })?;
+
+    let original = fs::read(&original_path)?;
+    let hidden = fs::read(&hidden_path)?;
+    assert_eq!(hidden.len(), original.len());
+    assert_eq!(hidden[..BMP_HEADER_SIZE], original[..BMP_HEADER_SIZE]);
+    assert_ne!(hidden, original);
+
+    // 载荷直接位于文件头之后的原始字节中，每个字节视为一个单通道像素
+    let options = RecoverOptions {
+        stride: 1,
+        ..Default::default()
+    };
+    let (_, payload) = recover_payload(&hidden[BMP_HEADER_SIZE..], &options)?;
+    assert_eq!(payload, original_text.as_bytes());
+
+    handle_recover(RecoverArgs {
+        image: hidden_path.clone(),
+        text: Some(recovered_path.clone()),
+        raw_bmp: true,
+        ..Default::default()
+    })?;
+    assert_eq!(fs::read_to_string(&recovered_path)?, original_text);
+
+    // 输出必须仍是 BMP，PNG 等格式无法保留原始的文件结构
+    let err = handle_hide(HideArgs {
+        image: Some(original_path),
+        text: vec![recovered_path],
+        dest: Some(dir.path().join("hidden.png")),
+        raw_bmp: true,
+        ..Default::default()
+    })
+    .expect_err("--raw-bmp should only write BMP files.");
+    assert!(err.to_string().contains("must be a BMP file"));
+
+    // RGBA 的 BMP 使用更大的信息头，像素数据不紧跟在 54 字节之后，不能直接隐写
+    let rgba_path = dir.path().join("rgba.bmp");
+    create_test_image(&rgba_path, 20, 20);
+    let err = handle_hide(HideArgs {
+        image: Some(rgba_path),
+        message: Some("x".to_string()),
+        dest: Some(dir.path().join("rgba_hidden.bmp")),
+        raw_bmp: true,
+        ..Default::default()
+    })
+    .expect_err("BMPs with a larger header should be rejected.");
+    assert!(err.to_string().contains("--raw-bmp requires"));
+
+    Ok(())
+}
+
+/// 测试 `--chunk-size` 分块读取较大的载荷文件，输出与一次性读取时逐字节相同，且可以正确恢复
+#[test]
+fn test_hide_large_payload_in_chunks() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let image_path = dir.path().join("cover.png");
+    let payload_path = dir.path().join("large.bin");
+    let streamed_path = dir.path().join("streamed.png");
+    let buffered_path = dir.path().join("buffered.png");
+    let recovered_path = dir.path().join("recovered.bin");
+
+    // 每个像素字节使用 4 位时，1 MB 的载荷需要 2 MB 像素字节；长度不是块大小的整数倍
+    create_test_image(&image_path, 800, 800);
+    let mut payload = vec![0u8; 1_000_003];
+    ChaCha20Rng::seed_from_u64(84).fill_bytes(&mut payload);
+    fs::write(&payload_path, &payload)?;
+
+    for (dest, chunk_size) in [(&streamed_path, Some(64 * 1024)), (&buffered_path, None)] {
+        handle_hide(HideArgs {
+            image: Some(image_path.clone()),
+            text: vec![payload_path.clone()],
+            dest: Some(dest.clone()),
+            bits: 4,
+            chunk_size,
+            ..Default::default()
+        })?;
+    }
+    assert_eq!(
+        image::open(&streamed_path)?.into_bytes(),
+        image::open(&buffered_path)?.into_bytes()
+    );
+
+    handle_recover(RecoverArgs {
+        image: streamed_path,
+        text: Some(recovered_path.clone()),
+        ..Default::default()
+    })?;
+    assert_eq!(fs::read(&recovered_path)?, payload);
+
+    // 标准输入无法读取两遍，不能分块隐藏
+    let err = handle_hide(HideArgs {
+        image: Some(image_path),
+        text: vec!["-".into()],
+        dest: Some(dir.path().join("stdin.png")),
+        chunk_size: Some(1024),
+        ..Default::default()
+    })
+    .expect_err("Standard input cannot be streamed twice.");
+    assert!(err.to_string().contains("--chunk-size"));
+
+    Ok(())
+}
+
+/// 测试 hide 在取出的像素缓冲区上就地隐写后直接重建图像：各颜色类型的布局保持不变，
+/// 每个字节只有最低位被修改，载荷可以完整恢复
+#[test]
+fn test_hide_reuses_pixel_buffer_for_each_color_type() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let mut rng = ChaCha20Rng::seed_from_u64(88);
+    let mut noise = vec![0u8; 256 * 256 * 4];
+    rng.fill_bytes(&mut noise);
+    let rgba =
+        image::RgbaImage::from_raw(256, 256, noise).expect("The buffer matches the dimensions.");
+    let covers = [
+        image::DynamicImage::ImageRgba8(rgba.clone()),
+        image::DynamicImage::ImageRgb8(image::DynamicImage::ImageRgba8(rgba.clone()).into_rgb8()),
+        image::DynamicImage::ImageLuma8(image::DynamicImage::ImageRgba8(rgba).into_luma8()),
+    ];
+    let mut payload = vec![0u8; 4096];
+    rng.fill_bytes(&mut payload);
+    let payload_path = dir.path().join("payload.bin");
+    fs::write(&payload_path, &payload)?;
+
+    for (index, cover) in covers.into_iter().enumerate() {
+        let cover_path = dir.path().join(format!("cover_{}.png", index));
+        let stego_path = dir.path().join(format!("stego_{}.png", index));
+        let recovered_path = dir.path().join(format!("recovered_{}.bin", index));
+        cover.save(&cover_path)?;
+        handle_hide(HideArgs {
+            image: Some(cover_path),
+            text: vec![payload_path.clone()],
+            dest: Some(stego_path.clone()),
+            ..Default::default()
+        })?;
+
+        let stego = image::open(&stego_path)?;
+        assert_eq!(stego.color(), cover.color());
+        assert_eq!(stego.dimensions(), cover.dimensions());
+        assert!(
+            cover
+                .as_bytes()
+                .iter()
+                .zip(stego.as_bytes())
+                .all(|(cover, stego)| (cover ^ stego) >> DEFAULT_BITS == 0)
+        );
+
+        handle_recover(RecoverArgs {
+            image: stego_path,
+            text: Some(recovered_path.clone()),
+            ..Default::default()
+        })?;
+        assert_eq!(fs::read(&recovered_path)?, payload);
+    }
+
+    Ok(())
+}
+
+/// 测试 `--split` 将一张图像放不下的载荷拆分到两张图像中，`recover --split` 再拼接回原始载荷
+#[test]
+fn test_split_payload_across_two_images() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let covers = dir.path().join("covers");
+    let parts = dir.path().join("parts");
+    fs::create_dir(&covers)?;
+    create_test_image(&covers.join("a.png"), 40, 40);
+    create_test_image(&covers.join("b.png"), 40, 40);
+
+    // 每张 40x40 的 RGBA 图像只能容纳约 1500 字节
+    let payload_path = dir.path().join("payload.bin");
+    let recovered_path = dir.path().join("recovered.bin");
+    let mut payload = vec![0u8; 2500];
+    ChaCha20Rng::seed_from_u64(89).fill_bytes(&mut payload);
+    fs::write(&payload_path, &payload)?;
+    assert!(
+        handle_hide(HideArgs {
+            image: Some(covers.join("a.png")),
+            text: vec![payload_path.clone()],
+            dest: Some(dir.path().join("single.png")),
+            ..Default::default()
+        })
+        .is_err()
+    );
+
+    handle_hide(HideArgs {
+        image_dir: Some(covers.clone()),
+        split: true,
+        text: vec![payload_path.clone()],
+        dest: Some(parts.clone()),
+        password: Some("split secret".into()),
+        ..Default::default()
+    })?;
+    let manifest: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(parts.join(SPLIT_MANIFEST_NAME))?)?;
+    assert_eq!(manifest["segments"].as_array().unwrap().len(), 2);
+    assert!(parts.join("part_001.png").exists() && parts.join("part_002.png").exists());
+
+    // 单独一段无法恢复，提示使用 `--split`
+    let err = handle_recover(RecoverArgs {
+        image: parts.join("part_002.png"),
+        text: Some(recovered_path.clone()),
+        password: Some("split secret".into()),
+        ..Default::default()
+    })
+    .expect_err("A single segment cannot be recovered on its own.");
+    assert!(err.to_string().contains("segment 2 of 2"));
+
+    handle_recover(RecoverArgs {
+        image: parts.clone(),
+        split: true,
+        text: Some(recovered_path.clone()),
+        password: Some("split secret".into()),
+        ..Default::default()
+    })?;
+    assert_eq!(fs::read(&recovered_path)?, payload);
+
+    // 缺少一段时报告缺少的序号
+    fs::remove_file(parts.join("part_001.png"))?;
+    let err = handle_recover(RecoverArgs {
+        image: parts,
+        split: true,
+        text: Some(dir.path().join("partial.bin")),
+        password: Some("split secret".into()),
+        ..Default::default()
+    })
+    .expect_err("A missing segment cannot be reassembled.");
+    assert!(format!("{:#}", err).contains("Missing segments 1 of 2."));
+
+    Ok(())
+}
+
+/// 测试 `--image-dir` 在目录中挑选容量足够的最小封面图像，并忽略无法解码的文件
+#[test]
+fn test_hide_selects_cover_from_image_dir() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let covers = dir.path().join("covers");
+    fs::create_dir(&covers)?;
+    for (name, size) in [("small.png", 16), ("medium.png", 32), ("large.png", 64)] {
+        create_test_image(&covers.join(name), size, size);
+    }
+    fs::write(covers.join("notes.txt"), "not an image")?;
+
+    // 只有 64x64 的图像放得下 2000 字节的载荷
+    let payload_path = dir.path().join("payload.bin");
+    let mut payload = vec![0u8; 2000];
+    ChaCha20Rng::seed_from_u64(86).fill_bytes(&mut payload);
+    fs::write(&payload_path, &payload)?;
+
+    let stego_path = dir.path().join("stego.png");
+    let recovered_path = dir.path().join("recovered.bin");
+    handle_hide(HideArgs {
+        image_dir: Some(covers.clone()),
+        text: vec![payload_path.clone()],
+        dest: Some(stego_path.clone()),
+        ..Default::default()
+    })?;
+    assert_eq!(image::open(&stego_path)?.dimensions(), (64, 64));
+
+    handle_recover(RecoverArgs {
+        image: stego_path,
+        text: Some(recovered_path.clone()),
+        ..Default::default()
+    })?;
+    assert_eq!(fs::read(&recovered_path)?, payload);
+
+    // 短消息选择最小的图像
+    let short_path = dir.path().join("short.png");
+    handle_hide(HideArgs {
+        image_dir: Some(covers.clone()),
+        message: Some("hello".into()),
+        dest: Some(short_path.clone()),
+        ..Default::default()
+    })?;
+    assert_eq!(image::open(&short_path)?.dimensions(), (16, 16));
+
+    // 任何图像都放不下时报错，且不写出文件
+    fs::write(&payload_path, vec![0u8; 20_000])?;
+    let err = handle_hide(HideArgs {
+        image_dir: Some(covers),
+        text: vec![payload_path],
+        dest: Some(dir.path().join("none.png")),
+        ..Default::default()
+    })
+    .expect_err("No cover has room for the payload.");
+    assert!(
+        err.to_string()
+            .contains("has room for the 20000-byte payload")
+    );
+    assert!(!dir.path().join("none.png").exists());
+
+    Ok(())
+}
+
+/// 测试 `--image-dir` 与 `--region` 同时使用时，按区域的容量挑选封面，并跳过放不下区域的图像
+#[test]
+fn test_hide_selects_cover_by_region() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let covers = dir.path().join("covers");
+    fs::create_dir(&covers)?;
+    for (name, size) in [("small.png", 16), ("medium.png", 32), ("large.png", 64)] {
+        create_test_image(&covers.join(name), size, size);
+    }
+
+    // 区域只在 64x64 的图像之内，较小的图像即使容量足够也不会被选中
+    let stego_path = dir.path().join("stego.png");
+    let recovered_path = dir.path().join("recovered.txt");
+    handle_hide(HideArgs {
+        image_dir: Some(covers.clone()),
+        message: Some("hello".into()),
+        dest: Some(stego_path.clone()),
+        region: Some("24,24,16,16".parse()?),
+        ..Default::default()
+    })?;
+    assert_eq!(image::open(&stego_path)?.dimensions(), (64, 64));
+
+    handle_recover(RecoverArgs {
+        image: stego_path,
+        text: Some(recovered_path.clone()),
+        region: Some("24,24,16,16".parse()?),
+        ..Default::default()
+    })?;
+    assert_eq!(fs::read_to_string(&recovered_path)?, "hello");
+
+    // 每张图像都放得下区域，但区域本身的容量不足以容纳载荷时报错
+    let err = handle_hide(HideArgs {
+        image_dir: Some(covers),
+        message: Some("x".repeat(500)),
+        dest: Some(dir.path().join("none.png")),
+        region: Some("0,0,8,8".parse()?),
+        ..Default::default()
+    })
+    .expect_err("No region has room for the payload.");
+    assert!(
+        err.to_string()
+            .contains("has room for the 500-byte payload")
+    );
+
+    Ok(())
+}
+
+/// 写出一张 8 位索引颜色 (调色板) PNG，像素依次循环使用 4 种调色板颜色
+///
+/// `image` 库无法编码调色板图像，因此手工拼装 IHDR、PLTE、IDAT 与 IEND 数据块
+fn create_indexed_png(path: &Path, width: u32, height: u32) {
+    fn chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+        png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        png.extend_from_slice(kind);
+        png.extend_from_slice(data);
+        let mut hasher = crc32fast::Hasher::new();
+        hasher.update(kind);
+        hasher.update(data);
+        png.extend_from_slice(&hasher.finalize().to_be_bytes());
+    }
+
+    let mut ihdr = Vec::new();
+    ihdr.extend_from_slice(&width.to_be_bytes());
+    ihdr.extend_from_slice(&height.to_be_bytes());
+    // 位深度 8，颜色类型 3 (索引颜色)，默认压缩、过滤与非隔行扫描
+    ihdr.extend_from_slice(&[8, 3, 0, 0, 0]);
+
+    // 每行以过滤类型 0 开头，之后是每个像素的调色板索引
+    let scanlines: Vec<u8> = (0..height)
+        .flat_map(|y| std::iter::once(0).chain((0..width).map(move |x| ((x + y) % 4) as u8)))
+        .collect();
+    let mut encoder = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(&scanlines).unwrap();
+
+    let mut png = vec![0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n'];
+    chunk(&mut png, b"IHDR", &ihdr);
+    chunk(
+        &mut png,
+        b"PLTE",
+        &[0, 0, 0, 255, 0, 0, 0, 255, 0, 0, 0, 255],
+    );
+    chunk(&mut png, b"IDAT", &encoder.finish().unwrap());
+    chunk(&mut png, b"IEND", &[]);
+    fs::write(path, png).expect("Failed to create indexed test image.");
+}
+
+/// 验证把非图像文件误传给 --image 时，hide 与 recover 都报告友好的错误并列出支持的格式
+#[test]
+fn test_non_image_input_reports_supported_formats() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let notes_path = dir.path().join("notes.txt");
+    fs::write(&notes_path, "This is not an image.")?;
+
+    let hide_err = handle_hide(HideArgs {
+        image: Some(notes_path.clone()),
+        message: Some("secret".to_string()),
+        ..Default::default()
+    })
+    .expect_err("A text file should not be accepted as an image.");
+    let recover_err = handle_recover(RecoverArgs {
+        image: notes_path.clone(),
+        ..Default::default()
+    })
+    .expect_err("A text file should not be accepted as an image.");
+
+    for err in [hide_err, recover_err] {
+        let message = err.to_string();
+        assert!(message.contains("Unrecognized image format"), "{}", message);
+        assert!(message.contains("png, bmp"), "{}", message);
+    }
+    assert_eq!(fs::read_dir(dir.path())?.count(), 1);
+
+    Ok(())
+}
+
+/// 验证调色板 PNG 会被明确拒绝而不是被静默展开为真彩色，但仍然可以被只读的子命令检查
+#[test]
+fn test_indexed_png_is_rejected_with_advice() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let indexed_image_path = dir.path().join("indexed.png");
+    let hidden_image_path = dir.path().join("hidden.png");
+    let source_text_path = dir.path().join("source.txt");
+
+    create_indexed_png(&indexed_image_path, 40, 40);
+    fs::write(&source_text_path, "Palette images are not supported.")?;
+
+    // 确认测试图像本身可以被正常解码 (展开为 RGB)
+    let decoded = image::open(&indexed_image_path)?;
+    assert_eq!(decoded.color(), image::ColorType::Rgb8);
+
+    let err = handle_hide(HideArgs {
+        image: Some(indexed_image_path.clone()),
+        text: vec![source_text_path.clone()],
+        dest: Some(hidden_image_path.clone()),
+        ..Default::default()
+    })
+    .expect_err("Indexed PNG images should be rejected.");
+    let message = err.to_string();
+    assert!(message.contains("Indexed-color (palette) PNG"));
+    assert!(message.contains("Convert the image to RGB or RGBA first"));
+    assert!(!hidden_image_path.exists());
+
+    // 读取类的子命令不会修改图像，照常工作
+    let output = run_cli(&["info", "-i", indexed_image_path.to_str().unwrap()]);
+    assert!(output.contains("No lsb_hide payload detected"));
+
+    Ok(())
+}
+
+/// 验证启用进度条时，大载荷的隐藏与恢复仍然正常完成
+#[test]
+fn test_hide_and_recover_large_payload_with_progress() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let original_image_path = dir.path().join("original.png");
+    let hidden_image_path = dir.path().join("hidden.png");
+    let source_path = dir.path().join("large.bin");
+    let recovered_path = dir.path().join("large_recovered.bin");
+
+    // 4 bits/字节时每个载荷字节占用 2 个像素字节
+    image::RgbaImage::new(730, 730).save(&original_image_path)?;
+    let mut payload = vec![0u8; PROGRESS_THRESHOLD_BYTES];
+    rand::rng().fill_bytes(&mut payload);
+    fs::write(&source_path, &payload)?;
+
+    let run_with_progress = |args: &[&str]| -> anyhow::Result<()> {
+        let output = Command::new(env!("CARGO_BIN_EXE_lsb_hide"))
+            .args(args)
+            .env(PROGRESS_ENV_VAR, "1")
+            .output()?;
+        assert!(
+            output.status.success(),
+            "lsb_hide exited with failure: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        Ok(())
+    };
+
+    run_with_progress(&[
+        "hide",
+        "-i",
+        original_image_path.to_str().unwrap(),
+        "-t",
+        source_path.to_str().unwrap(),
+        "-d",
+        hidden_image_path.to_str().unwrap(),
+        "--binary",
+        "--bits",
+        "4",
+    ])?;
+    run_with_progress(&[
+        "recover",
+        "-i",
+        hidden_image_path.to_str().unwrap(),
+        "-t",
+        recovered_path.to_str().unwrap(),
+        "--binary",
+    ])?;
+
+    assert_eq!(fs::read(&recovered_path)?, payload);
+
+    Ok(())
+}
+
+/// 验证 `file://` URL 形式的图像路径与本地路径一样可以隐藏和恢复
+#[test]
+fn test_handle_hide_and_recover_file_url() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let original_image_path = dir.path().join("original.png");
+    let source_text_path = dir.path().join("source.txt");
+    let recovered_text_path = dir.path().join("recovered.txt");
+
+    create_test_image(&original_image_path, 20, 20);
+    fs::write(&source_text_path, "Loaded through a file URL.")?;
+
+    let image_url = format!("file://{}", original_image_path.display());
+    handle_hide(HideArgs {
+        image: Some(image_url.into()),
+        text: vec![source_text_path],
+        ..Default::default()
+    })?;
+
+    // 默认输出路径与本地路径相同，位于原始图像所在的目录
+    let hidden_image_path = dir.path().join("doctored_original.png");
+    assert!(hidden_image_path.exists());
+
+    handle_recover(RecoverArgs {
+        image: format!("file://{}", hidden_image_path.display()).into(),
+        text: Some(recovered_text_path.clone()),
+        ..Default::default()
+    })?;
+    assert_eq!(
+        fs::read_to_string(&recovered_text_path)?,
+        "Loaded through a file URL."
+    );
+
+    Ok(())
+}
+
+/// 验证未启用 `remote` 特性时，http(s) URL 会被明确拒绝
+#[cfg(not(feature = "remote"))]
+#[test]
+fn test_remote_image_requires_feature() {
+    let err = handle_capacity(CapacityArgs {
+        image: "https://example.com/cover.png".into(),
+        ..Default::default()
+    })
+    .expect_err("Remote images need the remote feature.");
+    assert!(format!("{:#}", err).contains("--features remote"));
+}
+
+/// 验证启用 `remote` 特性时，可以从本地模拟的 HTTP 服务器下载图像并恢复载荷，网络错误会被报告
+#[cfg(feature = "remote")]
+#[test]
+fn test_handle_recover_remote_image() -> anyhow::Result<()> {
+    use std::io::{BufRead, BufReader};
+    use std::net::TcpListener;
+
+    let dir = tempdir()?;
+    let original_image_path = dir.path().join("original.png");
+    let hidden_image_path = dir.path().join("hidden.png");
+    let source_text_path = dir.path().join("source.txt");
+    let recovered_text_path = dir.path().join("recovered.txt");
+
+    create_test_image(&original_image_path, 20, 20);
+    fs::write(&source_text_path, "Fetched over HTTP.")?;
+    handle_hide(HideArgs {
+        image: Some(original_image_path),
+        text: vec![source_text_path],
+        dest: Some(hidden_image_path.clone()),
+        ..Default::default()
+    })?;
+
+    // 只响应一次请求的模拟服务器
+    let body = fs::read(&hidden_image_path)?;
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let address = listener.local_addr()?;
+    let server = std::thread::spawn(move || -> std::io::Result<()> {
+        let (mut stream, _) = listener.accept()?;
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let mut line = String::new();
+        while reader.read_line(&mut line)? > 2 {
+            line.clear();
+        }
+        write!(
+            stream,
+            "HTTP/1.1 200 OK\r\nContent-Type: image/png\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            body.len()
+        )?;
+        stream.write_all(&body)
+    });
+
+    handle_recover(RecoverArgs {
+        image: format!("http://{}/hidden.png", address).into(),
+        text: Some(recovered_text_path.clone()),
+        ..Default::default()
+    })?;
+    server.join().expect("Mock server panicked.")?;
+    assert_eq!(
+        fs::read_to_string(&recovered_text_path)?,
+        "Fetched over HTTP."
+    );
+
+    // 服务器已关闭，再次请求会失败
+    let err = handle_capacity(CapacityArgs {
+        image: format!("http://{}/hidden.png", address).into(),
+        ..Default::default()
+    })
+    .expect_err("Downloading from a closed server should fail.");
+    assert!(format!("{:#}", err).contains("Failed to download image"));
+
+    Ok(())
+}
+
+/// 验证不加密时隐藏过程是确定性的：相同的载荷与载体图像两次隐藏得到逐字节相同的输出文件
+#[test]
+fn test_hide_is_deterministic() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let original_image_path = dir.path().join("original.png");
+    let source_text_path = dir.path().join("source.txt");
+
+    create_test_image(&original_image_path, 40, 40);
+    fs::write(&source_text_path, "Same input, same output.".repeat(4))?;
+
+    let hide_into = |dest: &Path| {
+        handle_hide(HideArgs {
+            image: Some(original_image_path.clone()),
+            text: vec![source_text_path.clone()],
+            dest: Some(dest.to_path_buf()),
+            compress: Compression::Zstd,
+            channels: "rg".parse()?,
+            ..Default::default()
+        })
+    };
+    let first_path = dir.path().join("first.png");
+    let second_path = dir.path().join("second.png");
+    hide_into(&first_path)?;
+    hide_into(&second_path)?;
+
+    assert_eq!(
+        fs::read(&first_path)?,
+        fs::read(&second_path)?,
+        "Hiding the same payload twice must produce identical files."
+    );
+
+    Ok(())
+}
+
+/// 验证 analyze 命令对嵌入了大量顺序载荷的图像给出比干净图像更高的分数
+#[test]
+fn test_analyze_scores_stego_image_higher() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let clean_image_path = dir.path().join("clean.png");
+    let hidden_image_path = dir.path().join("hidden.png");
+    let payload_path = dir.path().join("payload.bin");
+
+    // 步长为 2 的渐变只包含偶数值，与常见的自然图像一样，值对的频数相差悬殊
+    let (width, height) = (64, 64);
+    ImageBuffer::from_fn(width, height, |x, y| {
+        let value = ((x + y) * 2 % 256) as u8;
+        Rgba([value, value, value, 255])
+    })
+    .save(&clean_image_path)?;
+
+    // 几乎占满容量的随机载荷
+    let capacity = max_payload_bytes((width * height * 4) as usize, DEFAULT_BITS as usize);
+    let mut payload = vec![0u8; capacity * 9 / 10];
+    ChaCha20Rng::seed_from_u64(33).fill_bytes(&mut payload);
+    fs::write(&payload_path, &payload)?;
+    handle_hide(HideArgs {
+        image: Some(clean_image_path.clone()),
+        text: vec![payload_path],
+        dest: Some(hidden_image_path.clone()),
+        binary: true,
+        ..Default::default()
+    })?;
+
+    let probability = |image: &Path| -> anyhow::Result<f64> {
+        let output = run_cli(&["--json", "analyze", "-i", image.to_str().unwrap()]);
+        let report: serde_json::Value = serde_json::from_str(&output)?;
+        assert_eq!(report["status"], "ok");
+        Ok(report["probability"].as_f64().expect("probability"))
+    };
+    let clean = probability(&clean_image_path)?;
+    let hidden = probability(&hidden_image_path)?;
+    assert!(
+        hidden > clean,
+        "Stego image ({}) should score higher than the clean image ({}).",
+        hidden,
+        clean
+    );
+
+    Ok(())
+}
+
+/// 测试顺序嵌入的载荷在最低有效位平面的预览中形成规则的区域，而封面图像的预览是随机噪声
+#[test]
+fn test_preview_shows_sequential_embedding() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let cover_path = dir.path().join("cover.png");
+    let stego_path = dir.path().join("stego.png");
+    let cover_preview_path = dir.path().join("cover_lsb.png");
+    let stego_preview_path = dir.path().join("stego_lsb.png");
+    create_test_image(&cover_path, 64, 64);
+
+    let message = "A".repeat(512);
+    run_cli(&[
+        "hide",
+        "-i",
+        cover_path.to_str().unwrap(),
+        "-m",
+        &message,
+        "-d",
+        stego_path.to_str().unwrap(),
+    ]);
+    for (image, preview) in [
+        (&cover_path, &cover_preview_path),
+        (&stego_path, &stego_preview_path),
+    ] {
+        let output: serde_json::Value = serde_json::from_str(&run_cli(&[
+            "--json",
+            "preview",
+            "-i",
+            image.to_str().unwrap(),
+            "-d",
+            preview.to_str().unwrap(),
+        ]))?;
+        assert_eq!(output["plane"], 0);
+    }
+    let quiet_output = run_cli(&[
+        "-q",
+        "preview",
+        "-i",
+        stego_path.to_str().unwrap(),
+        "-d",
+        stego_preview_path.to_str().unwrap(),
+        "--force",
+    ]);
+    assert_eq!(quiet_output, "");
+
+    let cover_preview = image::open(&cover_preview_path)?.into_rgba8();
+    let stego_preview = image::open(&stego_preview_path)?.into_rgba8();
+    assert!(stego_preview.iter().all(|&byte| byte == 0 || byte == 255));
+
+    // 同一个字节重复嵌入，载荷区域中每个像素的最低位都与下一个像素相同；随机的封面图像中约有一半相同
+    let start = read_header(&image::open(&stego_path)?.into_rgba8())?.payload_offset();
+    let region = start..start + (message.len() - 1) * BYTES_PER_CHAR;
+    let repeating = |preview: &[u8]| {
+        let color: Vec<usize> = region.clone().filter(|index| index % 4 != 3).collect();
+        let same = color
+            .iter()
+            .filter(|&&index| preview[index] == preview[index + 4])
+            .count();
+        same as f64 / color.len() as f64
+    };
+    assert_eq!(repeating(&stego_preview), 1.0);
+    assert!(repeating(&cover_preview) < 0.75);
+
+    // 载荷之后的像素字节没有被修改，两者的预览完全相同
+    let end = start + message.len() * BYTES_PER_CHAR;
+    assert_eq!(
+        &stego_preview.as_raw()[end..],
+        &cover_preview.as_raw()[end..]
+    );
+
+    Ok(())
+}
+
+/// 测试 diff 命令：图像与自身比较时没有差异，与隐写后的图像比较时差值不超过位深度
+#[test]
+fn test_diff_cover_and_stego() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let cover_path = dir.path().join("cover.png");
+    let stego_path = dir.path().join("stego.png");
+    let same_path = dir.path().join("same_diff.png");
+    let diff_path = dir.path().join("diff.png");
+    create_test_image(&cover_path, 32, 32);
+    handle_hide(HideArgs {
+        image: Some(cover_path.clone()),
+        message: Some("compare me".repeat(20)),
+        dest: Some(stego_path.clone()),
+        ..Default::default()
+    })?;
+
+    let report = |stego: &Path| -> anyhow::Result<serde_json::Value> {
+        let output = run_cli(&[
+            "--json",
+            "diff",
+            "-c",
+            cover_path.to_str().unwrap(),
+            "-s",
+            stego.to_str().unwrap(),
+        ]);
+        Ok(serde_json::from_str(&output)?)
+    };
+    let same = report(&cover_path)?;
+    assert_eq!(same["bytes"], 32 * 32 * 4);
+    assert_eq!(same["changed_bytes"], 0);
+    assert!(same["psnr"].is_null());
+
+    let changed = report(&stego_path)?;
+    assert!(changed["changed_bytes"].as_u64().unwrap() > 0);
+    assert!(changed["max_delta"].as_u64().unwrap() < 1 << DEFAULT_BITS);
+    assert!(changed["psnr"].as_f64().unwrap() > 40.0);
+
+    // 差异图像中 alpha 保持不透明，颜色字节为差值的放大倍数
+    for (stego, dest) in [(&cover_path, &same_path), (&stego_path, &diff_path)] {
+        handle_diff(DiffArgs {
+            cover: cover_path.clone(),
+            stego: stego.clone(),
+            dest: Some(dest.clone()),
+            ..Default::default()
+        })?;
+    }
+    let same_image = image::open(&same_path)?.into_rgba8();
+    assert!(same_image.pixels().all(|pixel| pixel.0 == [0, 0, 0, 255]));
+    let diff_image = image::open(&diff_path)?.into_rgba8();
+    let amplified = diff_image
+        .pixels()
+        .flat_map(|pixel| pixel.0[..3].to_vec())
+        .filter(|&byte| byte != 0)
+        .count();
+    assert!(amplified > 0);
+    assert!(diff_image.pixels().all(|pixel| {
+        pixel.0[..3]
+            .iter()
+            .all(|byte| byte % DEFAULT_DIFF_AMPLIFY == 0)
+    }));
+
+    // `--quiet` 成功时不输出报告，差异图像照常写出
+    let quiet_path = dir.path().join("quiet_diff.png");
+    let quiet_output = run_cli(&[
+        "-q",
+        "diff",
+        "-c",
+        cover_path.to_str().unwrap(),
+        "-s",
+        stego_path.to_str().unwrap(),
+        "-d",
+        quiet_path.to_str().unwrap(),
+    ]);
+    assert_eq!(quiet_output, "");
+    assert!(quiet_path.exists());
+
+    // 尺寸不同的图像无法比较
+    let other_path = dir.path().join("other.png");
+    create_test_image(&other_path, 16, 16);
+    let err = handle_diff(DiffArgs {
+        cover: cover_path,
+        stego: other_path,
+        ..Default::default()
+    })
+    .expect_err("Images of different sizes cannot be compared.");
+    assert!(err.to_string().contains("same size and color type"));
+
+    Ok(())
+}
+
+/// 测试 `--region` 只修改区域内的像素，恢复时必须指定相同的区域
+#[test]
+fn test_hide_and_recover_in_region() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let cover_path = dir.path().join("cover.png");
+    let stego_path = dir.path().join("stego.png");
+    let recovered_path = dir.path().join("recovered.txt");
+    create_test_image(&cover_path, 32, 32);
+
+    let message = "Hidden in the middle of the picture.";
+    run_cli(&[
+        "hide",
+        "-i",
+        cover_path.to_str().unwrap(),
+        "-m",
+        message,
+        "-d",
+        stego_path.to_str().unwrap(),
+        "--region",
+        "8,8,16,16",
+    ]);
+
+    // 区域外的像素与原始图像完全相同，区域内的像素确实承载了数据
+    let cover = image::open(&cover_path)?.into_rgba8();
+    let stego = image::open(&stego_path)?.into_rgba8();
+    let inside = |x: u32, y: u32| (8..24).contains(&x) && (8..24).contains(&y);
+    assert!(
+        stego
+            .enumerate_pixels()
+            .filter(|(x, y, _)| !inside(*x, *y))
+            .all(|(x, y, pixel)| pixel == cover.get_pixel(x, y))
+    );
+    assert_ne!(stego, cover);
+
+    // 不指定区域时找不到头部
+    let err = handle_recover(RecoverArgs {
+        image: stego_path.clone(),
+        text: Some(recovered_path.clone()),
+        ..Default::default()
+    })
+    .unwrap_err();
+    assert_eq!(exit_code(&err), EXIT_NO_PAYLOAD);
+
+    run_cli(&[
+        "recover",
+        "-i",
+        stego_path.to_str().unwrap(),
+        "-t",
+        recovered_path.to_str().unwrap(),
+        "--region",
+        "8,8,16,16",
+    ]);
+    assert_eq!(fs::read_to_string(&recovered_path)?, message);
+
+    // 超出图像边界的区域在隐写前就被拒绝
+    let err = handle_hide(HideArgs {
+        image: Some(cover_path.clone()),
+        text: vec![recovered_path.clone()],
+        dest: Some(dir.path().join("out.png")),
+        region: Some("24,24,16,16".parse()?),
+        ..Default::default()
+    })
+    .unwrap_err();
+    assert!(err.to_string().contains("does not fit"));
 
     Ok(())
 }